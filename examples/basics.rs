@@ -21,7 +21,7 @@ use bevy_ggf::mapping::terrain::{TerrainClass, TerrainType};
 use bevy_ggf::mapping::tiles::{
     ObjectStackingClass, StackingClass, Tile, TileObjectStacks, TileObjectStacksCount, TileObjects,
 };
-use bevy_ggf::mapping::{GameBuilderMappingExt, MapCommandsExt, MapId};
+use bevy_ggf::mapping::{GameBuilderMappingExt, MapCommandsExt, MapId, MapIdProvider};
 use bevy_ggf::movement::defaults::SquareMovementCalculator;
 use bevy_ggf::movement::{
     GameBuilderMovementExt, MoveCommandsExt, MovementType, ObjectMovement,
@@ -53,6 +53,7 @@ fn main() {
 #[derive(Resource)]
 pub struct PlayerPos {
     pub object_grid_position: ObjectGridPosition,
+    pub map_id: MapId,
 }
 
 #[derive(Default, Clone, Component, Reflect)]
@@ -79,7 +80,7 @@ fn handle_input(
         if input.just_pressed(KeyCode::A) {
             let _ = game_commands.move_object(
                 ObjectId { id: 1 },
-                MapId { id: 1 },
+                player_pos.map_id,
                 player_pos.object_grid_position.tile_position,
                 TilePos {
                     x: player_pos
@@ -95,7 +96,7 @@ fn handle_input(
         if input.just_pressed(KeyCode::S) {
             let _ = game_commands.move_object(
                 ObjectId { id: 1 },
-                MapId { id: 1 },
+                player_pos.map_id,
                 player_pos.object_grid_position.tile_position,
                 TilePos {
                     x: player_pos.object_grid_position.tile_position.x,
@@ -111,7 +112,7 @@ fn handle_input(
         if input.just_pressed(KeyCode::D) {
             let _ = game_commands.move_object(
                 ObjectId { id: 1 },
-                MapId { id: 1 },
+                player_pos.map_id,
                 player_pos.object_grid_position.tile_position,
                 TilePos {
                     x: player_pos
@@ -127,7 +128,7 @@ fn handle_input(
         if input.just_pressed(KeyCode::W) {
             let _ = game_commands.move_object(
                 ObjectId { id: 1 },
-                MapId { id: 1 },
+                player_pos.map_id,
                 player_pos.object_grid_position.tile_position,
                 TilePos {
                     x: player_pos.object_grid_position.tile_position.x,
@@ -255,19 +256,26 @@ fn setup(mut world: &mut World) {
         ),
     ]);
 
+    // Allocate the map id from the games own MapIdProvider so that it can't collide with any other
+    // map spawned in the game
+    let mut game = GameBuilder::<TestRunner>::new_game(TestRunner::default());
     let mut game_commands = GameCommands::new();
 
-    let spawn_map_command = game_commands.generate_random_map(
+    let spawn_map_command = game_commands.generate_random_map_with_id(
+        &mut game.game_world.resource_mut::<MapIdProvider>(),
         tilemap_size,
         tilemap_type,
         tilemap_tile_size,
         terrain_extension_types,
         tile_stack_rules,
     );
+    let map_id = spawn_map_command
+        .map_id()
+        .expect("generate_random_map_with_id pre-allocates the map id");
 
     let player_spawn_pos = TilePos { x: 3, y: 3 };
 
-    let spawn_object = game_commands.spawn_object(
+    game_commands.spawn_object(
         (
             ObjectGridPosition {
                 tile_position: player_spawn_pos,
@@ -287,20 +295,19 @@ fn setup(mut world: &mut World) {
             PlayerMarker,
         ),
         player_spawn_pos,
-        MapId { id: 1 },
+        map_id,
         0,
     );
     world.insert_resource(PlayerPos {
         object_grid_position: ObjectGridPosition {
             tile_position: player_spawn_pos,
         },
+        map_id,
     });
 
-    let mut game = GameBuilder::<TestRunner>::new_game_with_commands(
-        vec![Box::new(spawn_map_command), Box::new(spawn_object)],
-        TestRunner::default(),
-    );
+    game.insert_commands(game_commands);
 
+    game.setup_movement_types(MOVEMENT_TYPES);
     game.setup_movement(vec![(
         TerrainType {
             name: String::from("Grassland"),
@@ -322,7 +329,7 @@ fn setup(mut world: &mut World) {
 
     game.register_component::<PlayerMarker>();
 
-    game.build(&mut world);
+    game.build(&mut world).expect("Game setup is invalid");
 
     let mut term =
         Terminal::new([tilemap_size.x, tilemap_size.y]).with_border(Border::single_line());
@@ -333,9 +340,11 @@ fn setup(mut world: &mut World) {
 fn simulate_game(world: &mut World) {
     world.resource_scope(|mut world, mut game: Mut<Game>| {
         world.resource_scope(|world, mut game_runtime: Mut<GameRuntime<TestRunner>>| {
-            game_runtime.game_runner.simulate_game(&mut game.game_world);
-            world.resource_scope(|world, mut game_commands: Mut<GameCommands>| {
-                game_commands.execute_buffer(&mut game.game_world);
+            if !game_runtime.is_paused() {
+                game_runtime.game_runner.simulate_game(&mut game.game_world);
+            }
+            world.resource_scope(|_, mut game_commands: Mut<GameCommands>| {
+                game_runtime.execute_commands(&mut game_commands, &mut game.game_world);
             });
         });
     });
@@ -10,7 +10,8 @@ use bevy_ggf::game_core::runner::{GameRunner, GameRuntime};
 use bevy_ggf::game_core::{Game, GameBuilder};
 use bevy_ggf::mapping::terrain::{TerrainClass, TerrainType};
 use bevy_ggf::mapping::tiles::{StackingClass, TileObjectStacks, TileObjectStacksCount};
-use bevy_ggf::mapping::{MapCommandsExt, MapId};
+use bevy_ggf::mapping::{MapCommandsExt, MapIdProvider};
+use bevy_ggf::movement::defaults::SquareMovementCalculator;
 use bevy_ggf::movement::{GameBuilderMovementExt, MovementType, TileMovementCosts};
 use bevy_ggf::object::{ObjectClass, ObjectGroup, ObjectType};
 use bevy_ggf::BggfDefaultPlugins;
@@ -149,28 +150,31 @@ fn setup(mut world: &mut World) {
         ),
     ]);
 
+    let mut game = GameBuilder::<TestRunner>::new_game(TestRunner::default());
     let mut game_commands = GameCommands::new();
 
-    let spawn_map_command = game_commands.generate_random_map(
+    let spawn_map_command = game_commands.generate_random_map_with_id(
+        &mut game.game_world.resource_mut::<MapIdProvider>(),
         tilemap_size,
         tilemap_type,
         tilemap_tile_size,
         terrain_extension_types,
         tile_stack_rules,
     );
+    let map_id = spawn_map_command
+        .map_id()
+        .expect("generate_random_map_with_id pre-allocates the map id");
 
-    let spawn_object_command = game_commands.spawn_object(
+    game_commands.spawn_object(
         (TilePos { x: 50, y: 50 }),
         TilePos { x: 50, y: 50 },
-        MapId { id: 1 },
+        map_id,
         0,
     );
 
-    let mut game = GameBuilder::<TestRunner>::new_game_with_commands(
-        vec![Box::new(spawn_object_command)],
-        TestRunner::default(),
-    );
+    game.insert_commands(game_commands);
 
+    game.setup_movement_types(MOVEMENT_TYPES);
     game.setup_movement(vec![(
         TerrainType {
             name: String::from("Grassland"),
@@ -180,8 +184,16 @@ fn setup(mut world: &mut World) {
             movement_type_cost: Default::default(),
         },
     )]);
+    game.with_movement_calculator(
+        SquareMovementCalculator {
+            diagonal_movement: Default::default(),
+        },
+        vec![],
+        tilemap_type,
+    );
+    game.add_player(false);
 
-    game.build(&mut world);
+    game.build(&mut world).expect("Game setup is invalid");
 }
 
 fn simulate(mut world: &mut World) {
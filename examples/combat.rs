@@ -0,0 +1,319 @@
+//! Combat example. Two players each have a few units on a small map.
+//!
+//! - Arrow keys move the cursor
+//! - Space selects one of player 0's units under the cursor, or attacks the enemy under the cursor if
+//!   it is highlighted as attackable by the selected unit
+//! - Escape clears the selection
+//!
+//! Player 1's riflemen are destroyed when killed while their tank is captured instead.
+
+use bevy::input::Input;
+use bevy::prelude::{
+    info, App, ClearColor, Color, Entity, KeyCode, Mut, Query, Res, ResMut, Resource, Schedule,
+    With, World,
+};
+use bevy::DefaultPlugins;
+use bevy_ascii_terminal::{
+    AutoCamera, Border, Terminal, TerminalBundle, TerminalPlugin, TileFormatter,
+};
+use bevy_ecs_tilemap::prelude::{TilemapSize, TilemapTileSize, TilemapType};
+use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_ggf::combat::commands::GameCommandsExt;
+use bevy_ggf::combat::defaults::UniversalAP;
+use bevy_ggf::combat::{attackable_objects, AttackPower, AttackRange, Health, OnDeath};
+use bevy_ggf::game_core::command::GameCommands;
+use bevy_ggf::game_core::runner::{GameRunner, GameRuntime};
+use bevy_ggf::game_core::{Game, GameBuilder};
+use bevy_ggf::mapping::terrain::{TerrainClass, TerrainType};
+use bevy_ggf::mapping::tiles::{
+    ObjectStackingClass, StackingClass, Tile, TileObjectStacks, TileObjectStacksCount,
+};
+use bevy_ggf::mapping::{GameBuilderMappingExt, MapCommandsExt, MapId, MapIdProvider};
+use bevy_ggf::object::{Object, ObjectGridPosition, ObjectId};
+use bevy_ggf::player::PlayerMarker;
+use bevy_ggf::selection::{select_object_at_tile_pos, CurrentSelectedObject};
+use bevy_ggf::BggfDefaultPlugins;
+
+fn main() {
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins);
+    app.add_plugins(BggfDefaultPlugins);
+    app.add_plugin(TerminalPlugin)
+        .insert_resource(ClearColor(Color::BLACK));
+    app.add_startup_system(setup);
+    app.add_system(simulate_game);
+    app.add_system(handle_input);
+    app.add_system(draw_world);
+
+    app.run();
+}
+
+#[derive(Resource)]
+pub struct Cursor {
+    pub tile_pos: TilePos,
+    pub map_id: MapId,
+    pub map_size: TilemapSize,
+}
+
+#[derive(Default)]
+pub struct TestRunner {
+    schedule: Schedule,
+}
+impl GameRunner for TestRunner {
+    fn simulate_game(&mut self, world: &mut World) {
+        self.schedule.run(world);
+    }
+}
+
+fn setup(world: &mut World) {
+    let stacking_class_ground: StackingClass = StackingClass {
+        name: String::from("Ground"),
+    };
+
+    let terrain_class_ground: TerrainClass = TerrainClass {
+        name: String::from("Ground"),
+    };
+    let terrain_types: Vec<TerrainType> = vec![TerrainType {
+        name: String::from("Grassland"),
+        terrain_class: terrain_class_ground,
+    }];
+
+    let tilemap_size = TilemapSize { x: 12, y: 12 };
+    let tilemap_tile_size = TilemapTileSize { x: 16.0, y: 16.0 };
+    let tilemap_type = TilemapType::Square;
+
+    let tile_stack_rules = TileObjectStacks::new(vec![(
+        stacking_class_ground.clone(),
+        TileObjectStacksCount {
+            current_count: 0,
+            max_count: 1,
+        },
+    )]);
+
+    let mut game = GameBuilder::<TestRunner>::new_game(TestRunner::default());
+    let mut game_commands = GameCommands::new();
+
+    let spawn_map_command = game_commands.generate_random_map_with_id(
+        &mut game.game_world.resource_mut::<MapIdProvider>(),
+        tilemap_size,
+        tilemap_type,
+        tilemap_tile_size,
+        terrain_types,
+        tile_stack_rules,
+    );
+    let map_id = spawn_map_command
+        .map_id()
+        .expect("generate_random_map_with_id pre-allocates the map id");
+
+    let rifleman_health = Health {
+        current_health: 10,
+        max_health: 10,
+        on_death: OnDeath::Destroy,
+    };
+    let tank_health = Health {
+        current_health: 10,
+        max_health: 10,
+        on_death: OnDeath::Capture {
+            restore_at_health: 5,
+        },
+    };
+
+    let units = vec![
+        (TilePos { x: 4, y: 4 }, 0, rifleman_health),
+        (TilePos { x: 4, y: 6 }, 0, rifleman_health),
+        (TilePos { x: 5, y: 4 }, 1, rifleman_health),
+        (TilePos { x: 5, y: 7 }, 1, tank_health),
+    ];
+
+    for (tile_pos, player, health) in units {
+        game_commands.spawn_object(
+            (
+                ObjectGridPosition {
+                    tile_position: tile_pos.into(),
+                },
+                ObjectStackingClass {
+                    stack_class: stacking_class_ground.clone(),
+                },
+                Object,
+                health,
+                AttackRange {
+                    min_range: 1,
+                    max_range: 1,
+                },
+            ),
+            tile_pos,
+            map_id,
+            player,
+        );
+    }
+
+    game.insert_commands(game_commands);
+    game.setup_mapping();
+    game.add_player(false);
+    game.add_player(false);
+    game.build(world).expect("Game setup is invalid");
+
+    // AttackPower holds a trait object so it can't be part of a spawn command. Add it to every unit
+    // now that they have been spawned
+    world.resource_scope(|_, mut game: Mut<Game>| {
+        let mut unit_query = game
+            .game_world
+            .query_filtered::<Entity, (With<AttackRange>, With<Object>)>();
+        let units: Vec<Entity> = unit_query.iter(&game.game_world).collect();
+        for entity in units {
+            game.game_world
+                .entity_mut(entity)
+                .insert(AttackPower::new(UniversalAP::new(4)));
+        }
+    });
+
+    world.insert_resource(Cursor {
+        tile_pos: TilePos { x: 4, y: 4 },
+        map_id,
+        map_size: tilemap_size,
+    });
+    world.insert_resource(CurrentSelectedObject::default());
+
+    let term = Terminal::new([tilemap_size.x, tilemap_size.y]).with_border(Border::single_line());
+    world.spawn((TerminalBundle::from(term), AutoCamera));
+}
+
+fn simulate_game(world: &mut World) {
+    world.resource_scope(|world, mut game: Mut<Game>| {
+        world.resource_scope(|world, mut game_runtime: Mut<GameRuntime<TestRunner>>| {
+            if !game_runtime.is_paused() {
+                game_runtime.game_runner.simulate_game(&mut game.game_world);
+            }
+            world.resource_scope(|_, mut game_commands: Mut<GameCommands>| {
+                game_runtime.execute_commands(&mut game_commands, &mut game.game_world);
+            });
+        });
+    });
+}
+
+fn handle_input(
+    mut game: ResMut<Game>,
+    mut game_commands: ResMut<GameCommands>,
+    input: Res<Input<KeyCode>>,
+    mut cursor: ResMut<Cursor>,
+    mut selected: ResMut<CurrentSelectedObject>,
+) {
+    let map_size = cursor.map_size;
+    let tile_pos = &mut cursor.tile_pos;
+    if input.just_pressed(KeyCode::Left) {
+        tile_pos.x = tile_pos.x.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::Right) {
+        tile_pos.x = (tile_pos.x + 1).min(map_size.x - 1);
+    }
+    if input.just_pressed(KeyCode::Down) {
+        tile_pos.y = tile_pos.y.saturating_sub(1);
+    }
+    if input.just_pressed(KeyCode::Up) {
+        tile_pos.y = (tile_pos.y + 1).min(map_size.y - 1);
+    }
+    if input.just_pressed(KeyCode::Escape) {
+        selected.object = None;
+    }
+
+    if !input.just_pressed(KeyCode::Space) {
+        return;
+    }
+
+    if let Some(attacking_object) = selected.object {
+        if let Some(attacking_entity) = object_entity(&mut game.game_world, attacking_object) {
+            let targets = attackable_objects(&mut game.game_world, cursor.map_id, attacking_entity);
+            if let Some(target) = targets
+                .iter()
+                .find(|target| target.target_tile_position == cursor.tile_pos)
+            {
+                if let Some(defending_object) =
+                    game.game_world.get::<ObjectId>(target.target_entity)
+                {
+                    info!("{:?} attacks {:?}", attacking_object, defending_object);
+                    game_commands.attack_object(attacking_object, *defending_object, cursor.map_id);
+                    selected.object = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    selected.object = select_object_at_tile_pos(
+        &mut game.game_world,
+        cursor.map_id,
+        cursor.tile_pos,
+        selected.object,
+        Some(0),
+        Some(0),
+    );
+}
+
+fn draw_world(
+    mut term_query: Query<&mut Terminal>,
+    mut game: ResMut<Game>,
+    cursor: Res<Cursor>,
+    selected: Res<CurrentSelectedObject>,
+) {
+    let mut term = term_query.single_mut();
+    term.clear();
+
+    let mut tile_query = game.game_world.query_filtered::<&TilePos, With<Tile>>();
+    for tile_pos in tile_query.iter(&game.game_world) {
+        term.put_char(
+            [tile_pos.x, tile_pos.y],
+            '.'.fg(Color::GREEN).bg(Color::BLACK),
+        );
+    }
+
+    let mut attackable: Vec<TilePos> = vec![];
+    if let Some(selected_object) = selected.object {
+        if let Some(entity) = object_entity(&mut game.game_world, selected_object) {
+            attackable = attackable_objects(&mut game.game_world, cursor.map_id, entity)
+                .iter()
+                .map(|target| target.target_tile_position)
+                .collect();
+        }
+    }
+
+    let mut object_query = game
+        .game_world
+        .query::<(&ObjectId, &ObjectGridPosition, &PlayerMarker, &Health)>();
+    for (object_id, object_grid_position, player_marker, health) in
+        object_query.iter(&game.game_world)
+    {
+        let tile_pos: TilePos = object_grid_position.tile_position.into();
+        let glyph = char::from_digit(health.current_health.min(9), 10).unwrap_or('?');
+        let foreground = if player_marker.id() == 0 {
+            Color::CYAN
+        } else {
+            Color::RED
+        };
+        let background = if Some(*object_id) == selected.object {
+            Color::WHITE
+        } else if attackable.contains(&tile_pos) {
+            Color::YELLOW
+        } else {
+            Color::BLACK
+        };
+        term.put_char(
+            [tile_pos.x, tile_pos.y],
+            glyph.fg(foreground).bg(background),
+        );
+    }
+
+    term.put_char(
+        [cursor.tile_pos.x, cursor.tile_pos.y],
+        'X'.fg(Color::WHITE).bg(Color::DARK_GRAY),
+    );
+}
+
+/// Returns the entity of the given object in the game world
+fn object_entity(game_world: &mut World, object_id: ObjectId) -> Option<Entity> {
+    let mut object_query = game_world.query::<(Entity, &ObjectId)>();
+    object_query
+        .iter(game_world)
+        .find(|(_, id)| id == &&object_id)
+        .map(|(entity, _)| entity)
+}
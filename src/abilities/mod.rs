@@ -0,0 +1,259 @@
+//! Abilities are special actions that an object can use which are then unavailable for a set number
+//! of turns. The effect of an ability is entirely defined by the game through the [`Ability`] trait,
+//! bevy_ggf only keeps track of the cooldowns and makes sure that using and ticking them is
+//! rollback-able.
+//!
+//! Send a [`TickCooldowns`] command at the start of a players turn to count down the cooldowns of
+//! every object that player owns.
+
+use crate::game_core::command::{GameCommand, GameCommands};
+use crate::game_core::state::Changed;
+use crate::object::ObjectId;
+use crate::player::PlayerMarker;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Component, Entity, FromReflect, Query, Reflect, ReflectComponent, World};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Component that tracks the remaining turns until each named ability of an object can be used again.
+/// An ability that has no entry or an entry of zero is ready to use.
+///
+/// The entries are kept sorted by name so that the component always serializes to the same bytes.
+/// bevy_reflect doesn't support [`BTreeMap`] so they aren't reflected.
+#[derive(
+    Default, Clone, Eq, PartialEq, Debug, Component, Reflect, FromReflect, Serialize, Deserialize,
+)]
+#[reflect(Component)]
+pub struct Cooldowns {
+    #[reflect(ignore)]
+    pub entries: BTreeMap<String, u32>,
+}
+
+impl Cooldowns {
+    /// Returns the remaining turns on the given ability. Returns 0 if the ability has no cooldown
+    pub fn remaining(&self, ability_name: &str) -> u32 {
+        *self.entries.get(ability_name).unwrap_or(&0)
+    }
+
+    /// Returns true if the given ability has no remaining cooldown
+    pub fn is_ready(&self, ability_name: &str) -> bool {
+        self.remaining(ability_name) == 0
+    }
+
+    /// Sets the remaining turns on the given ability
+    pub fn set(&mut self, ability_name: &str, turns: u32) {
+        self.entries.insert(String::from(ability_name), turns);
+    }
+
+    /// Reduces every cooldown by one turn, down to a minimum of 0
+    pub fn tick(&mut self) {
+        for (_, turns) in self.entries.iter_mut() {
+            *turns = turns.saturating_sub(1);
+        }
+    }
+}
+
+/// A trait defining a special ability that an object can use. Implement this for your own structs to
+/// define what the ability actually does. The ability is executed through the [`UseAbility`] command
+/// which handles checking and setting the objects [`Cooldowns`].
+pub trait Ability: Send + Sync + Clone + Reflect + 'static {
+    /// The name of the ability. Used as the key into the objects [`Cooldowns`]
+    fn name(&self) -> String;
+
+    /// How many turns the ability is unavailable for after being used
+    fn cooldown(&self) -> u32;
+
+    /// Perform the effect of the ability for the given object
+    fn execute(&mut self, object_id: ObjectId, world: &mut World) -> Result<(), String>;
+
+    /// Reverse the effect of the ability. Must undo exactly what execute did.
+    ///
+    /// NOTE: Like [`GameCommand::rollback`] this has a default implementation that does nothing
+    fn rollback(&mut self, _object_id: ObjectId, _world: &mut World) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// An extension trait for [GameCommands] with ability related commands.
+pub trait AbilityCommandsExt {
+    fn use_ability<A>(&mut self, object_id: ObjectId, ability: A) -> UseAbility<A>
+    where
+        A: Ability;
+
+    fn tick_cooldowns(&mut self, player_id: usize) -> TickCooldowns;
+}
+
+impl AbilityCommandsExt for GameCommands {
+    /// Uses the given ability for the given object if the abilities cooldown is ready
+    fn use_ability<A>(&mut self, object_id: ObjectId, ability: A) -> UseAbility<A>
+    where
+        A: Ability,
+    {
+        self.queue.push(UseAbility {
+            object_id,
+            ability: ability.clone(),
+            previous_cooldown: None,
+        });
+        UseAbility {
+            object_id,
+            ability,
+            previous_cooldown: None,
+        }
+    }
+
+    /// Counts down the cooldowns of every object owned by the given player
+    fn tick_cooldowns(&mut self, player_id: usize) -> TickCooldowns {
+        self.queue.push(TickCooldowns {
+            player_id,
+            previous_cooldowns: vec![],
+        });
+        TickCooldowns {
+            player_id,
+            previous_cooldowns: vec![],
+        }
+    }
+}
+
+/// Uses the given [`Ability`] for the given object. Fails if the object doesn't have a [`Cooldowns`]
+/// component or if the abilities cooldown is not ready. Sets the abilities cooldown after the ability
+/// executes successfully. If the ability removes the objects [`Cooldowns`] it is rolled back and the
+/// command fails.
+#[derive(Clone, Debug, Reflect)]
+pub struct UseAbility<A>
+where
+    A: Ability,
+{
+    pub object_id: ObjectId,
+    pub ability: A,
+    previous_cooldown: Option<u32>,
+}
+
+impl<A> GameCommand for UseAbility<A>
+where
+    A: Ability,
+{
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<Query<(Entity, &ObjectId, &Cooldowns)>> =
+            SystemState::new(world);
+        let object_query = system_state.get(world);
+
+        let Some((entity, _, cooldowns)) = object_query
+            .iter()
+            .find(|(_, id, _)| id == &&self.object_id)
+        else {
+            return Err(format!(
+                "No Cooldowns component found for ObjectId: {:?}",
+                self.object_id
+            ));
+        };
+
+        let ability_name = self.ability.name();
+        if !cooldowns.is_ready(&ability_name) {
+            return Err(format!(
+                "Ability {} is on cooldown for {} more turns",
+                ability_name,
+                cooldowns.remaining(&ability_name)
+            ));
+        }
+        let previous_cooldown = cooldowns.entries.get(&ability_name).copied();
+
+        self.ability.execute(self.object_id, world)?;
+
+        let Some(mut cooldowns) = world.get_mut::<Cooldowns>(entity) else {
+            self.ability.rollback(self.object_id, world)?;
+            return Err(String::from("Object lost its Cooldowns component"));
+        };
+        cooldowns.set(&ability_name, self.ability.cooldown());
+        self.previous_cooldown = previous_cooldown;
+
+        world.entity_mut(entity).insert(Changed::default());
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        self.ability.rollback(self.object_id, world)?;
+
+        let mut system_state: SystemState<Query<(Entity, &ObjectId, &mut Cooldowns)>> =
+            SystemState::new(world);
+        let mut object_query = system_state.get_mut(world);
+
+        let Some((entity, _, mut cooldowns)) = object_query
+            .iter_mut()
+            .find(|(_, id, _)| id == &&self.object_id)
+        else {
+            return Err(String::from("No object components found"));
+        };
+
+        match self.previous_cooldown {
+            Some(turns) => cooldowns.set(&self.ability.name(), turns),
+            None => {
+                cooldowns.entries.remove(&self.ability.name());
+            }
+        }
+
+        world.entity_mut(entity).insert(Changed::default());
+        Ok(())
+    }
+}
+
+/// Counts down the [`Cooldowns`] of every object owned by the given player by one turn. Intended to
+/// be sent at the start of that players turn. Rollback restores the cooldowns to exactly what they
+/// were before.
+#[derive(Clone, Debug, Reflect)]
+pub struct TickCooldowns {
+    pub player_id: usize,
+    previous_cooldowns: Vec<(ObjectId, Cooldowns)>,
+}
+
+impl GameCommand for TickCooldowns {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<
+            Query<(Entity, &ObjectId, &PlayerMarker, &mut Cooldowns)>,
+        > = SystemState::new(world);
+        let mut object_query = system_state.get_mut(world);
+
+        let mut previous_cooldowns: Vec<(ObjectId, Cooldowns)> = vec![];
+        let mut changed_entities: Vec<Entity> = vec![];
+
+        for (entity, object_id, player_marker, mut cooldowns) in object_query.iter_mut() {
+            if player_marker.id() != self.player_id {
+                continue;
+            }
+            previous_cooldowns.push((*object_id, cooldowns.clone()));
+            cooldowns.tick();
+            changed_entities.push(entity);
+        }
+
+        for entity in changed_entities {
+            world.entity_mut(entity).insert(Changed::default());
+        }
+
+        self.previous_cooldowns = previous_cooldowns;
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<Query<(Entity, &ObjectId, &mut Cooldowns)>> =
+            SystemState::new(world);
+        let mut object_query = system_state.get_mut(world);
+
+        let mut changed_entities: Vec<Entity> = vec![];
+
+        for (entity, object_id, mut cooldowns) in object_query.iter_mut() {
+            if let Some((_, previous)) = self
+                .previous_cooldowns
+                .iter()
+                .find(|(id, _)| id == object_id)
+            {
+                *cooldowns = previous.clone();
+                changed_entities.push(entity);
+            }
+        }
+
+        for entity in changed_entities {
+            world.entity_mut(entity).insert(Changed::default());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Resources tracking where the cursor is in the world and which tile it is over, updated every
+//! frame by [`update_cursor_world_pos`] and [`update_cursor_tile_pos`]. Hover effects, path
+//! previews, and tooltips can read [`CursorTilePos`] instead of converting the cursor position
+//! themselves.
+
+use crate::camera::GgfGameCamera;
+use crate::mapping::{world_pos_to_tile_pos, MapId};
+use bevy::prelude::{Camera, GlobalTransform, Query, Res, ResMut, Resource, Transform, Vec2, With};
+use bevy::window::{PrimaryWindow, Window};
+use bevy_ecs_tilemap::prelude::{TilePos, TilemapGridSize, TilemapSize, TilemapType};
+
+/// The world position of the cursor in the primary window. Is None when the cursor is outside the
+/// window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct CursorWorldPos {
+    pub world_pos: Option<Vec2>,
+}
+
+/// The tile the cursor is over and the map that tile is on. Both are None when the cursor isn't over
+/// any map
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct CursorTilePos {
+    pub tile_pos: Option<TilePos>,
+    pub map_id: Option<MapId>,
+}
+
+/// The map that [`CursorTilePos`] is calculated against. If None the topmost map under the cursor,
+/// by its z translation, is used
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct ActiveMap {
+    pub map_id: Option<MapId>,
+}
+
+/// Updates [`CursorWorldPos`] from the cursor position in the primary window using the first active
+/// [`GgfGameCamera`]
+pub fn update_cursor_world_pos(
+    mut cursor_world_pos: ResMut<CursorWorldPos>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<GgfGameCamera>>,
+) {
+    let world_pos = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor_pos| {
+            camera_query
+                .iter()
+                .filter(|(camera, _)| camera.is_active)
+                .find_map(|(camera, camera_transform)| {
+                    camera.viewport_to_world(camera_transform, cursor_pos)
+                })
+        })
+        .map(|ray| ray.origin.truncate());
+
+    if cursor_world_pos.world_pos != world_pos {
+        cursor_world_pos.world_pos = world_pos;
+    }
+}
+
+/// Updates [`CursorTilePos`] from the [`CursorWorldPos`] against the [`ActiveMap`], or the topmost
+/// map under the cursor if there is no active map
+pub fn update_cursor_tile_pos(
+    cursor_world_pos: Res<CursorWorldPos>,
+    active_map: Res<ActiveMap>,
+    mut cursor_tile_pos: ResMut<CursorTilePos>,
+    map_query: Query<(
+        &MapId,
+        &TilemapSize,
+        &TilemapGridSize,
+        &TilemapType,
+        &Transform,
+    )>,
+) {
+    let mut new_cursor_tile_pos = CursorTilePos::default();
+
+    if let Some(world_pos) = cursor_world_pos.world_pos {
+        let mut topmost_z = f32::MIN;
+        for (map_id, map_size, grid_size, map_type, map_transform) in map_query.iter() {
+            if matches!(active_map.map_id, Some(active) if active != *map_id) {
+                continue;
+            }
+            if map_transform.translation.z < topmost_z {
+                continue;
+            }
+            let Some(tile_pos) =
+                world_pos_to_tile_pos(&world_pos, map_transform, map_size, grid_size, map_type)
+            else {
+                continue;
+            };
+            topmost_z = map_transform.translation.z;
+            new_cursor_tile_pos = CursorTilePos {
+                tile_pos: Some(tile_pos),
+                map_id: Some(*map_id),
+            };
+        }
+    }
+
+    if *cursor_tile_pos != new_cursor_tile_pos {
+        *cursor_tile_pos = new_cursor_tile_pos;
+    }
+}
@@ -0,0 +1,216 @@
+//! Controls for the 2d cameras that view the game. Only cameras marked with [`GgfGameCamera`] are
+//! controlled so other cameras, like a minimap or split screen views, can coexist with them.
+//!
+//! The user can drag the camera with the [`CameraSettings::drag_button`] and zoom it with the mouse
+//! wheel. The camera can also be controlled programmatically by sending [`CameraCommand`] events, eg
+//! for cutscenes, jumping to the next unit, or centering on combat.
+//!
+//! Programmatic pans move the camera smoothly towards their target over a few frames. If the user
+//! grabs the camera while a pan is in progress the pan is cancelled so that the two never fight.
+//!
+//! ```rust
+//! use bevy::prelude::EventWriter;
+//! use bevy_ecs_tilemap::prelude::TilePos;
+//! use bevy_ggf::camera::CameraCommand;
+//! use bevy_ggf::mapping::MapId;
+//!
+//! fn focus_on_unit(mut camera_commands: EventWriter<CameraCommand>) {
+//!     camera_commands.send(CameraCommand::FocusOnTile(TilePos::new(4, 2), MapId { id: 1 }));
+//!     camera_commands.send(CameraCommand::SetZoom(0.5));
+//! }
+//! ```
+//!
+//! The plugin also keeps the [`CursorWorldPos`](cursor::CursorWorldPos) and
+//! [`CursorTilePos`](cursor::CursorTilePos) resources up to date with where the cursor is.
+
+pub mod cursor;
+
+use crate::camera::cursor::{
+    update_cursor_tile_pos, update_cursor_world_pos, ActiveMap, CursorTilePos, CursorWorldPos,
+};
+use crate::mapping::MapId;
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::{
+    App, Component, EventReader, GlobalTransform, Input, IntoSystemConfigs, MouseButton,
+    OrthographicProjection, Plugin, Query, Res, ResMut, Resource, Time, Transform, Vec2, With,
+};
+use bevy_ecs_tilemap::prelude::{TilePos, TilemapGridSize, TilemapType};
+
+/// Plugin that adds the camera controls. Only cameras marked with [`GgfGameCamera`] are controlled,
+/// other cameras such as a minimap are left alone. Game cameras need an [`OrthographicProjection`]
+pub struct BggfCameraPlugin;
+
+impl Plugin for BggfCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraCommand>()
+            .init_resource::<CameraSettings>()
+            .init_resource::<CameraState>()
+            .init_resource::<CursorWorldPos>()
+            .init_resource::<CursorTilePos>()
+            .init_resource::<ActiveMap>()
+            .add_systems(
+                (
+                    handle_camera_commands,
+                    drag_camera,
+                    zoom_camera,
+                    pan_camera_to_focus,
+                    update_cursor_world_pos,
+                    update_cursor_tile_pos,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Marks a camera as a game camera controlled by the [`BggfCameraPlugin`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Component)]
+pub struct GgfGameCamera;
+
+/// Settings for the camera controls. Zoom is the scale of the cameras [`OrthographicProjection`] so
+/// larger values are zoomed further out
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct CameraSettings {
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    /// How much the zoom changes for each line scrolled with the mouse wheel
+    pub zoom_speed: f32,
+    /// How quickly programmatic pans move towards their target. Higher is faster
+    pub pan_speed: f32,
+    /// The mouse button that is held to drag the camera
+    pub drag_button: MouseButton,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            min_zoom: 0.25,
+            max_zoom: 4.0,
+            zoom_speed: 0.1,
+            pan_speed: 8.0,
+            drag_button: MouseButton::Right,
+        }
+    }
+}
+
+/// The current state of the camera controls
+#[derive(Clone, Copy, Debug, Default, PartialEq, Resource)]
+pub struct CameraState {
+    /// True while the user is dragging the camera
+    pub dragging: bool,
+    /// The world position the camera is panning to from a [`CameraCommand`]. Is None when no pan
+    /// is in progress
+    pub focus_target: Option<Vec2>,
+}
+
+/// Events that control the camera. Send these with an `EventWriter<CameraCommand>`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraCommand {
+    /// Pans the camera to center on the given world position
+    FocusOn(Vec2),
+    /// Pans the camera to center on the given tile of the given map
+    FocusOnTile(TilePos, MapId),
+    /// Sets the zoom of the camera, clamped to the [`CameraSettings`] min and max zoom
+    SetZoom(f32),
+}
+
+/// Applies every [`CameraCommand`] sent since the last frame
+pub fn handle_camera_commands(
+    mut camera_commands: EventReader<CameraCommand>,
+    camera_settings: Res<CameraSettings>,
+    mut camera_state: ResMut<CameraState>,
+    mut camera_query: Query<&mut OrthographicProjection, With<GgfGameCamera>>,
+    map_query: Query<(&MapId, &TilemapGridSize, &TilemapType, &GlobalTransform)>,
+) {
+    for camera_command in camera_commands.iter() {
+        match camera_command {
+            CameraCommand::FocusOn(world_pos) => {
+                camera_state.focus_target = Some(*world_pos);
+            }
+            CameraCommand::FocusOnTile(tile_pos, map_id) => {
+                let Some((_, grid_size, map_type, map_transform)) =
+                    map_query.iter().find(|(id, _, _, _)| id == &map_id)
+                else {
+                    continue;
+                };
+                let tile_world_pos = tile_pos.center_in_world(grid_size, map_type).extend(0.0);
+                camera_state.focus_target =
+                    Some(map_transform.transform_point(tile_world_pos).truncate());
+            }
+            CameraCommand::SetZoom(zoom) => {
+                for mut projection in camera_query.iter_mut() {
+                    projection.scale =
+                        zoom.clamp(camera_settings.min_zoom, camera_settings.max_zoom);
+                }
+            }
+        }
+    }
+}
+
+/// Moves the camera with the mouse while the [`CameraSettings::drag_button`] is held. Dragging
+/// cancels any programmatic pan that is in progress
+pub fn drag_camera(
+    camera_settings: Res<CameraSettings>,
+    mut camera_state: ResMut<CameraState>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<GgfGameCamera>>,
+) {
+    let delta: Vec2 = mouse_motion.iter().map(|motion| motion.delta).sum();
+    if !mouse_buttons.pressed(camera_settings.drag_button) {
+        camera_state.dragging = false;
+        return;
+    }
+    camera_state.dragging = true;
+    camera_state.focus_target = None;
+
+    for (mut transform, projection) in camera_query.iter_mut() {
+        transform.translation.x -= delta.x * projection.scale;
+        transform.translation.y += delta.y * projection.scale;
+    }
+}
+
+/// Zooms the camera with the mouse wheel, clamped to the [`CameraSettings`] min and max zoom
+pub fn zoom_camera(
+    camera_settings: Res<CameraSettings>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut camera_query: Query<&mut OrthographicProjection, With<GgfGameCamera>>,
+) {
+    let scroll: f32 = mouse_wheel.iter().map(|wheel| wheel.y).sum();
+    if scroll == 0.0 {
+        return;
+    }
+    for mut projection in camera_query.iter_mut() {
+        projection.scale = (projection.scale * (1.0 - scroll * camera_settings.zoom_speed))
+            .clamp(camera_settings.min_zoom, camera_settings.max_zoom);
+    }
+}
+
+/// Smoothly moves the camera towards the [`CameraState::focus_target`], clearing it once the camera
+/// arrives
+pub fn pan_camera_to_focus(
+    time: Res<Time>,
+    camera_settings: Res<CameraSettings>,
+    mut camera_state: ResMut<CameraState>,
+    mut camera_query: Query<&mut Transform, With<GgfGameCamera>>,
+) {
+    let Some(focus_target) = camera_state.focus_target else {
+        return;
+    };
+    let step = (camera_settings.pan_speed * time.delta_seconds()).min(1.0);
+    let mut arrived = true;
+    for mut transform in camera_query.iter_mut() {
+        let current = transform.translation.truncate();
+        if current.distance(focus_target) < 0.5 {
+            transform.translation = focus_target.extend(transform.translation.z);
+            continue;
+        }
+        arrived = false;
+        transform.translation = current
+            .lerp(focus_target, step)
+            .extend(transform.translation.z);
+    }
+
+    if arrived {
+        camera_state.focus_target = None;
+    }
+}
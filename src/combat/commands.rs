@@ -1,23 +1,860 @@
-use crate::game_core::command::GameCommands;
+use crate::combat::{
+    apply_terrain_defense, check_attack, check_counter_attack, has_attack_power,
+    object_attack_power, object_tile_on_map, objects_affected_by_aoe, AttackRange, Health,
+    Invulnerable, ObjectAttacked, OnDeath,
+};
+use crate::game_core::command::{
+    despawn_recorded_object, object_entity, respawn_recorded_object, GameCommand, GameCommands,
+    RecordedObject,
+};
+use crate::game_core::rules::GameRules;
+use crate::mapping::tiles::tile_distance;
 use crate::mapping::MapId;
-use crate::object::ObjectId;
+use crate::object::{ObjectId, ObjectInfo};
+use crate::player::PlayerMarker;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Entity, Query, Reflect, World};
+use bevy_ecs_tilemap::prelude::{TilePos, TilemapType};
+use std::sync::Arc;
 
+/// An extension trait for [GameCommands] with combat related commands.
 pub trait GameCommandsExt {
     fn attack_object(
+        &mut self,
         attacking_object: ObjectId,
         defending_object: ObjectId,
         on_map: MapId,
     ) -> AttackObject;
+
+    fn attack_area(
+        &mut self,
+        attacking_object: ObjectId,
+        on_map: MapId,
+        center: TilePos,
+        radius: u32,
+        map_type: TilemapType,
+    ) -> AttackArea;
+
+    fn join_objects(&mut self, source: ObjectId, target: ObjectId, on_map: MapId) -> JoinObjects;
+
+    fn join_objects_with_overflow(
+        &mut self,
+        source: ObjectId,
+        target: ObjectId,
+        on_map: MapId,
+        on_overflow: impl Fn(u32) -> Box<dyn GameCommand> + Send + Sync + 'static,
+    ) -> JoinObjects;
 }
 
 impl GameCommandsExt for GameCommands {
+    /// Attacks the defending object with the attacking object. See [`AttackObject`]
     fn attack_object(
+        &mut self,
         attacking_object: ObjectId,
         defending_object: ObjectId,
         on_map: MapId,
     ) -> AttackObject {
-        todo!()
+        self.queue.push(AttackObject {
+            attacking_object,
+            defending_object,
+            on_map,
+            defending_health: None,
+            attacking_health: None,
+            defender_death: None,
+            attacker_death: None,
+        });
+        AttackObject {
+            attacking_object,
+            defending_object,
+            on_map,
+            defending_health: None,
+            attacking_health: None,
+            defender_death: None,
+            attacker_death: None,
+        }
+    }
+
+    /// Attacks every object in the area around the center tile with the attacking object. See
+    /// [`AttackArea`]
+    fn attack_area(
+        &mut self,
+        attacking_object: ObjectId,
+        on_map: MapId,
+        center: TilePos,
+        radius: u32,
+        map_type: TilemapType,
+    ) -> AttackArea {
+        self.queue.push(AttackArea {
+            attacking_object,
+            on_map,
+            center,
+            radius,
+            map_type,
+            hit_objects: vec![],
+        });
+        AttackArea {
+            attacking_object,
+            on_map,
+            center,
+            radius,
+            map_type,
+            hit_objects: vec![],
+        }
+    }
+
+    /// Joins the source object into the target object. See [`JoinObjects`]
+    fn join_objects(&mut self, source: ObjectId, target: ObjectId, on_map: MapId) -> JoinObjects {
+        self.queue
+            .push(JoinObjects::new(source, target, on_map, None));
+        JoinObjects::new(source, target, on_map, None)
+    }
+
+    /// Joins the source object into the target object, executing the command returned by
+    /// `on_overflow` with any health over the targets max health. See [`JoinObjects`]
+    fn join_objects_with_overflow(
+        &mut self,
+        source: ObjectId,
+        target: ObjectId,
+        on_map: MapId,
+        on_overflow: impl Fn(u32) -> Box<dyn GameCommand> + Send + Sync + 'static,
+    ) -> JoinObjects {
+        let on_overflow: Arc<dyn Fn(u32) -> Box<dyn GameCommand> + Send + Sync> =
+            Arc::new(on_overflow);
+        self.queue.push(JoinObjects::new(
+            source,
+            target,
+            on_map,
+            Some(on_overflow.clone()),
+        ));
+        JoinObjects::new(source, target, on_map, Some(on_overflow))
+    }
+}
+
+/// Attacks the defending object with the attacking object. Fails if [`check_attack`] fails.
+///
+/// The defender takes damage equal to the attackers [`object_attack_power`], reduced by the defenders
+/// [`TerrainDefenseBonuses`](crate::combat::TerrainDefenseBonuses) if there are any, or none if it is
+/// [`Invulnerable`], and the attacker is marked with [`ObjectAttacked`]. If the defenders health reaches 0 its
+/// [`OnDeath`] is applied - it is either removed from its tile and despawned or captured by the
+/// attackers player and restored to the given health. Capturing is replaced by destroying when
+/// [`GameRules::capture_enabled`] is false.
+///
+/// If [`GameRules::counter_attacks`] is enabled and the defender survives it strikes back at the
+/// attacker when [`check_counter_attack`] passes, dealing damage the same way.
+///
+/// Every component of a destroyed object with a [`SaveId`](crate::game_core::saving::SaveId) is recorded before despawning. Rollback
+/// respawns destroyed objects from them in their previous tile and restores both objects health and
+/// owner. Components without a [`SaveId`](crate::game_core::saving::SaveId) are not restored.
+#[derive(Clone, Debug, Reflect)]
+pub struct AttackObject {
+    pub attacking_object: ObjectId,
+    pub defending_object: ObjectId,
+    pub on_map: MapId,
+    defending_health: Option<u32>,
+    attacking_health: Option<u32>,
+    #[reflect(ignore)]
+    defender_death: Option<DeathOutcome>,
+    #[reflect(ignore)]
+    attacker_death: Option<DeathOutcome>,
+}
+
+/// What happened to an object that was killed in an [`AttackObject`]
+#[derive(Clone, Debug)]
+enum DeathOutcome {
+    Destroyed {
+        health: Health,
+        recorded_object: RecordedObject,
+    },
+    Captured {
+        captured_from: Option<usize>,
+    },
+}
+
+impl GameCommand for AttackObject {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<Query<(Entity, &ObjectId)>> = SystemState::new(world);
+        let object_query = system_state.get(world);
+
+        let Some((attacking_entity, _)) = object_query
+            .iter()
+            .find(|(_, id)| id == &&self.attacking_object)
+        else {
+            return Err(String::from("Attacking object not found"));
+        };
+        let Some((defending_entity, _)) = object_query
+            .iter()
+            .find(|(_, id)| id == &&self.defending_object)
+        else {
+            return Err(String::from("Defending object not found"));
+        };
+
+        check_attack(world, self.on_map, attacking_entity, defending_entity)?;
+        let game_rules = GameRules::get(world);
+
+        let damage = attack_damage(world, attacking_entity, defending_entity);
+        let Some(mut health) = world.get_mut::<Health>(defending_entity) else {
+            return Err(String::from(
+                "Defending object does not have a Health component",
+            ));
+        };
+        self.defending_health = Some(health.current_health);
+        health.damage(damage);
+        let health = *health;
+
+        world
+            .entity_mut(attacking_entity)
+            .insert((ObjectAttacked, crate::game_core::state::Changed::default()));
+        world
+            .entity_mut(defending_entity)
+            .insert(crate::game_core::state::Changed::default());
+
+        if health.current_health == 0 {
+            self.defender_death = Some(kill_object(
+                world,
+                self.on_map,
+                defending_entity,
+                attacking_entity,
+                &game_rules,
+            )?);
+            return Ok(());
+        }
+
+        if !game_rules.counter_attacks
+            || check_counter_attack(world, self.on_map, attacking_entity, defending_entity).is_err()
+        {
+            return Ok(());
+        }
+
+        let counter_damage = attack_damage(world, defending_entity, attacking_entity);
+        let Some(mut attacker_health) = world.get_mut::<Health>(attacking_entity) else {
+            return Ok(());
+        };
+        self.attacking_health = Some(attacker_health.current_health);
+        attacker_health.damage(counter_damage);
+        let attacker_health = *attacker_health;
+
+        if attacker_health.current_health == 0 {
+            self.attacker_death = Some(kill_object(
+                world,
+                self.on_map,
+                attacking_entity,
+                defending_entity,
+                &game_rules,
+            )?);
+        }
+
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let attacker_death = self.attacker_death.take();
+        let defender_death = self.defender_death.take();
+        for death in [&attacker_death, &defender_death] {
+            if let Some(DeathOutcome::Destroyed {
+                recorded_object, ..
+            }) = death
+            {
+                respawn_recorded_object(world, self.on_map, recorded_object.clone())?;
+            }
+        }
+
+        let mut system_state: SystemState<Query<(Entity, &ObjectId)>> = SystemState::new(world);
+        let object_query = system_state.get(world);
+
+        let Some((attacking_entity, _)) = object_query
+            .iter()
+            .find(|(_, id)| id == &&self.attacking_object)
+        else {
+            return Err(String::from("Attacking object not found"));
+        };
+        let Some((defending_entity, _)) = object_query
+            .iter()
+            .find(|(_, id)| id == &&self.defending_object)
+        else {
+            return Err(String::from("Defending object not found"));
+        };
+
+        world
+            .entity_mut(attacking_entity)
+            .remove::<ObjectAttacked>()
+            .insert(crate::game_core::state::Changed::default());
+
+        restore_object(
+            world,
+            attacking_entity,
+            self.attacking_health,
+            attacker_death.as_ref(),
+        );
+        restore_object(
+            world,
+            defending_entity,
+            self.defending_health,
+            defender_death.as_ref(),
+        );
+
+        Ok(())
     }
 }
 
-pub struct AttackObject {}
+/// Attacks every object returned by [`objects_affected_by_aoe`] for the given area, apart from the
+/// attacking object itself. The attacker must have an attack power and an [`AttackRange`], must not
+/// have attacked already, and the center tile must be within its [`AttackRange`].
+///
+/// Each object hit takes damage the same way as the defender in an [`AttackObject`] and has its
+/// [`OnDeath`] applied if its health reaches 0. There are no counter attacks. The attacker is marked
+/// with [`ObjectAttacked`].
+///
+/// Destroyed objects are recorded and respawned on rollback the same as in [`AttackObject`]
+#[derive(Clone, Debug, Reflect)]
+pub struct AttackArea {
+    pub attacking_object: ObjectId,
+    pub on_map: MapId,
+    pub center: TilePos,
+    pub radius: u32,
+    pub map_type: TilemapType,
+    #[reflect(ignore)]
+    hit_objects: Vec<(ObjectId, u32, Option<DeathOutcome>)>,
+}
+
+impl GameCommand for AttackArea {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let attacking_entity = object_entity(world, self.attacking_object)?;
+
+        if world.get::<ObjectAttacked>(attacking_entity).is_some() {
+            return Err(String::from("Attacking object has already attacked"));
+        }
+        if !has_attack_power(world, attacking_entity) {
+            return Err(String::from(
+                "Attacking object does not have an AttackPower or BaseAttackPower component",
+            ));
+        }
+        let Some(attack_range) = world.get::<AttackRange>(attacking_entity).copied() else {
+            return Err(String::from(
+                "Attacking object does not have an AttackRange component",
+            ));
+        };
+        let Some(attacking_pos) = object_tile_on_map(world, self.on_map, attacking_entity) else {
+            return Err(String::from("Attacking object is not on the given map"));
+        };
+        if !attack_range.in_range(tile_distance(attacking_pos, self.center, self.map_type)) {
+            return Err(String::from(
+                "Center tile is not within the attackers AttackRange",
+            ));
+        }
+
+        let game_rules = GameRules::get(world);
+        let affected =
+            objects_affected_by_aoe(world, self.on_map, self.center, self.radius, self.map_type);
+
+        world
+            .entity_mut(attacking_entity)
+            .insert((ObjectAttacked, crate::game_core::state::Changed::default()));
+
+        self.hit_objects.clear();
+        for object_id in affected {
+            if object_id == self.attacking_object {
+                continue;
+            }
+            let Ok(defending_entity) = object_entity(world, object_id) else {
+                continue;
+            };
+
+            let damage = attack_damage(world, attacking_entity, defending_entity);
+            let Some(mut health) = world.get_mut::<Health>(defending_entity) else {
+                continue;
+            };
+            let previous_health = health.current_health;
+            health.damage(damage);
+            let health = *health;
+            world
+                .entity_mut(defending_entity)
+                .insert(crate::game_core::state::Changed::default());
+
+            let death = match health.current_health == 0 {
+                true => Some(kill_object(
+                    world,
+                    self.on_map,
+                    defending_entity,
+                    attacking_entity,
+                    &game_rules,
+                )?),
+                false => None,
+            };
+            self.hit_objects.push((object_id, previous_health, death));
+        }
+
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let attacking_entity = object_entity(world, self.attacking_object)?;
+        world
+            .entity_mut(attacking_entity)
+            .remove::<ObjectAttacked>()
+            .insert(crate::game_core::state::Changed::default());
+
+        for (object_id, previous_health, death) in self.hit_objects.drain(..).rev() {
+            if let Some(DeathOutcome::Destroyed {
+                recorded_object, ..
+            }) = &death
+            {
+                respawn_recorded_object(world, self.on_map, recorded_object.clone())?;
+            }
+            let defending_entity = object_entity(world, object_id)?;
+            restore_object(
+                world,
+                defending_entity,
+                Some(previous_health),
+                death.as_ref(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Joins two objects of the same type into one, eg two damaged units in Advance Wars. The sources
+/// [`Health`] is added to the targets, capped at the targets max health, and the source is removed from
+/// its tile and despawned.
+///
+/// Fails if the objects are the same, either has no [`ObjectInfo`] or [`Health`], their
+/// [`ObjectType`](crate::object::ObjectType)s differ, they belong to different players, or the source
+/// isn't on the given map. Neutral objects can only join other neutral objects.
+///
+/// If `on_overflow` is set and the combined health is over the targets max health it is called with
+/// the health that was lost and the command it returns is executed, eg a
+/// [`ModifyResource`](crate::game_core::command::ModifyResource) refunding gold to the player.
+///
+/// Every component of the source with a [`SaveId`](crate::game_core::saving::SaveId) is recorded before despawning. Rollback respawns the
+/// source from them with its previous [`Health`] and tile, restores the targets health, and rolls back
+/// the overflow command. Components of the source without a [`SaveId`](crate::game_core::saving::SaveId) are not restored.
+#[derive(Clone, Reflect)]
+pub struct JoinObjects {
+    pub source: ObjectId,
+    pub target: ObjectId,
+    pub on_map: MapId,
+    #[reflect(ignore)]
+    pub on_overflow: Option<Arc<dyn Fn(u32) -> Box<dyn GameCommand> + Send + Sync>>,
+    target_health: Option<u32>,
+    #[reflect(ignore)]
+    despawned_source: Option<DespawnedSource>,
+    #[reflect(ignore)]
+    overflow_command: Option<Box<dyn GameCommand>>,
+}
+
+/// Everything needed to respawn the source of a [`JoinObjects`]
+#[derive(Clone)]
+struct DespawnedSource {
+    health: Health,
+    recorded_object: RecordedObject,
+}
+
+impl JoinObjects {
+    pub fn new(
+        source: ObjectId,
+        target: ObjectId,
+        on_map: MapId,
+        on_overflow: Option<Arc<dyn Fn(u32) -> Box<dyn GameCommand> + Send + Sync>>,
+    ) -> JoinObjects {
+        JoinObjects {
+            source,
+            target,
+            on_map,
+            on_overflow,
+            target_health: None,
+            despawned_source: None,
+            overflow_command: None,
+        }
+    }
+}
+
+impl GameCommand for JoinObjects {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        if self.source == self.target {
+            return Err(String::from("Can't join an object with itself"));
+        }
+        let Ok(source_entity) = object_entity(world, self.source) else {
+            return Err(String::from("Source object not found"));
+        };
+        let Ok(target_entity) = object_entity(world, self.target) else {
+            return Err(String::from("Target object not found"));
+        };
+
+        let (Some(source_info), Some(target_info)) = (
+            world.get::<ObjectInfo>(source_entity),
+            world.get::<ObjectInfo>(target_entity),
+        ) else {
+            return Err(String::from(
+                "Both objects need an ObjectInfo component to be joined",
+            ));
+        };
+        if source_info.object_type != target_info.object_type {
+            return Err(String::from("Only objects of the same type can be joined"));
+        }
+        let source_owner = world
+            .get::<PlayerMarker>(source_entity)
+            .map(|player_marker| player_marker.id());
+        let target_owner = world
+            .get::<PlayerMarker>(target_entity)
+            .map(|player_marker| player_marker.id());
+        if source_owner != target_owner {
+            return Err(String::from(
+                "Only objects belonging to the same player can be joined",
+            ));
+        }
+        let (Some(source_health), Some(target_health)) = (
+            world.get::<Health>(source_entity).copied(),
+            world.get::<Health>(target_entity).copied(),
+        ) else {
+            return Err(String::from(
+                "Both objects need a Health component to be joined",
+            ));
+        };
+        if object_tile_on_map(world, self.on_map, source_entity).is_none() {
+            return Err(String::from("Source object is not on the given map"));
+        }
+
+        let combined_health = target_health
+            .current_health
+            .saturating_add(source_health.current_health);
+        let joined_health = combined_health.min(target_health.max_health);
+        let overflow = combined_health - joined_health;
+        if let (true, Some(on_overflow)) = (overflow > 0, &self.on_overflow) {
+            let mut overflow_command = on_overflow(overflow);
+            overflow_command.execute(world)?;
+            self.overflow_command = Some(overflow_command);
+        }
+
+        world.entity_mut(target_entity).insert((
+            Health {
+                current_health: joined_health,
+                ..target_health
+            },
+            crate::game_core::state::Changed::default(),
+        ));
+        self.target_health = Some(target_health.current_health);
+
+        let recorded_object = despawn_recorded_object(world, self.on_map, source_entity)?;
+        self.despawned_source = Some(DespawnedSource {
+            health: source_health,
+            recorded_object,
+        });
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let (Some(despawned_source), Some(target_health)) =
+            (self.despawned_source.take(), self.target_health.take())
+        else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let source_entity =
+            respawn_recorded_object(world, self.on_map, despawned_source.recorded_object)?;
+        world
+            .entity_mut(source_entity)
+            .insert(despawned_source.health);
+
+        if let Ok(target_entity) = object_entity(world, self.target) {
+            restore_object(world, target_entity, Some(target_health), None);
+        }
+
+        if let Some(mut overflow_command) = self.overflow_command.take() {
+            overflow_command.rollback(world)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the damage the attacker deals to the defender. Zero if the defender is [`Invulnerable`],
+/// otherwise the attackers [`object_attack_power`] reduced by the defenders terrain defense
+fn attack_damage(world: &mut World, attacking_entity: Entity, defending_entity: Entity) -> u32 {
+    let damage = match world.get::<Invulnerable>(defending_entity) {
+        Some(_) => 0,
+        None => object_attack_power(world, attacking_entity, defending_entity),
+    };
+    apply_terrain_defense(world, defending_entity, damage)
+}
+
+/// Applies the [`OnDeath`] of an object whose health has reached 0, either destroying it or capturing
+/// it for the player that owns the killing object. A destroyed object is despawned with
+/// [`despawn_recorded_object`] so that it can be respawned by [`respawn_recorded_object`]
+fn kill_object(
+    world: &mut World,
+    on_map: MapId,
+    entity: Entity,
+    killer_entity: Entity,
+    game_rules: &GameRules,
+) -> Result<DeathOutcome, String> {
+    let Some(health) = world.get::<Health>(entity).copied() else {
+        return Err(String::from(
+            "Killed object does not have a Health component",
+        ));
+    };
+
+    match health.on_death {
+        OnDeath::Capture { restore_at_health } if game_rules.capture_enabled => {
+            let Some(killing_player) = world.get::<PlayerMarker>(killer_entity).copied() else {
+                return Err(String::from(
+                    "Killing object does not have a PlayerMarker to capture with",
+                ));
+            };
+            let captured_from = world
+                .get::<PlayerMarker>(entity)
+                .map(|player_marker| player_marker.id());
+
+            let mut killed = world.entity_mut(entity);
+            killed.insert(killing_player);
+            if let Some(mut health) = killed.get_mut::<Health>() {
+                health.current_health = restore_at_health.min(health.max_health);
+            }
+            Ok(DeathOutcome::Captured { captured_from })
+        }
+        _ => {
+            let recorded_object = despawn_recorded_object(world, on_map, entity)?;
+            Ok(DeathOutcome::Destroyed {
+                health,
+                recorded_object,
+            })
+        }
+    }
+}
+
+/// Restores the health and, if it was captured, the owner of an object that was damaged in an attack.
+/// A destroyed object is given back its [`Health`] since it isn't restored from its save
+fn restore_object(
+    world: &mut World,
+    entity: Entity,
+    previous_health: Option<u32>,
+    death_outcome: Option<&DeathOutcome>,
+) {
+    let mut object = world.entity_mut(entity);
+    if let Some(DeathOutcome::Destroyed { health, .. }) = death_outcome {
+        object.insert(*health);
+    }
+    if let Some(current_health) = previous_health {
+        if let Some(mut health) = object.get_mut::<Health>() {
+            health.current_health = current_health;
+        }
+    }
+    if let Some(DeathOutcome::Captured { captured_from }) = death_outcome {
+        match *captured_from {
+            Some(player_id) => {
+                object.insert(PlayerMarker::new(player_id));
+            }
+            None => {
+                object.remove::<PlayerMarker>();
+            }
+        }
+    }
+    object.insert(crate::game_core::state::Changed::default());
+}
+
+#[test]
+fn test_attack_object_outcomes_rollback() {
+    use crate::combat::defaults::UniversalAP;
+    use crate::combat::AttackPower;
+    use crate::game_core::command::AddObjectToTile;
+    use crate::game_core::runner::TurnBasedGameRunner;
+    use crate::game_core::{Game, GameBuilder};
+    use crate::mapping::tiles::{
+        ObjectStackingClass, StackingClass, Tile, TileObjectStacks, TileObjectStacksCount,
+        TileObjects,
+    };
+    use crate::object::{Object, ObjectGridPosition};
+    use crate::testing::assert_rollback_consistent;
+    use bevy::prelude::Schedule;
+    use bevy_ecs_tilemap::prelude::{TileStorage, TilemapSize};
+
+    let map_id = MapId { id: 0 };
+    let attacker_id = ObjectId { id: 0 };
+    let defender_id = ObjectId { id: 1 };
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+
+    // The attacker deals 4 damage and the defender 3, both from the neighboring tile
+    let new_game = |attacker_health: u32, defender_health: Health, game_rules: GameRules| {
+        let mut game_builder = GameBuilder::new_game(TurnBasedGameRunner {
+            turn_schedule: Schedule::default(),
+        });
+        game_builder.add_default_registrations();
+        game_builder.set_game_rules(game_rules);
+        let map_size = TilemapSize { x: 2, y: 1 };
+        let mut tile_storage = TileStorage::empty(map_size);
+        for x in 0..map_size.x {
+            let tile_pos = TilePos { x, y: 0 };
+            let tile_entity = game_builder
+                .game_world
+                .spawn((
+                    Tile,
+                    tile_pos,
+                    TileObjectStacks::new(vec![(
+                        stacking_class.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    )]),
+                    TileObjects::default(),
+                ))
+                .id();
+            tile_storage.set(&tile_pos, tile_entity);
+        }
+        game_builder.game_world.spawn((map_id, tile_storage));
+        game_builder.add_player(false);
+        game_builder.add_player(false);
+
+        let mut main_world = World::new();
+        game_builder.build(&mut main_world).unwrap();
+        let mut game = main_world.remove_resource::<Game>().unwrap();
+        let world = &mut game.game_world;
+
+        for (object_id, player_id, health, attack_power) in [
+            (
+                attacker_id,
+                0,
+                Health {
+                    current_health: attacker_health,
+                    max_health: 10,
+                    on_death: OnDeath::Destroy,
+                },
+                4,
+            ),
+            (defender_id, 1, defender_health, 3),
+        ] {
+            let tile_pos = TilePos::new(object_id.id as u32, 0);
+            world.spawn((
+                Object,
+                object_id,
+                ObjectGridPosition {
+                    tile_position: tile_pos.into(),
+                },
+                ObjectStackingClass {
+                    stack_class: stacking_class.clone(),
+                },
+                PlayerMarker::new(player_id),
+                health,
+                AttackPower::new(UniversalAP::new(attack_power)),
+                AttackRange {
+                    min_range: 1,
+                    max_range: 1,
+                },
+            ));
+            AddObjectToTile {
+                object_game_id: object_id,
+                on_map: map_id,
+                tile_pos,
+                strict: false,
+            }
+            .execute(world)
+            .unwrap();
+        }
+        game
+    };
+    let health_of = |world: &mut World, object_id: ObjectId| {
+        let entity = object_entity(world, object_id).ok()?;
+        world
+            .get::<Health>(entity)
+            .map(|health| health.current_health)
+    };
+    let owner_of = |world: &mut World, object_id: ObjectId| {
+        let entity = object_entity(world, object_id).unwrap();
+        world
+            .get::<PlayerMarker>(entity)
+            .map(|player_marker| player_marker.id())
+    };
+    let attack = || AttackObject {
+        attacking_object: attacker_id,
+        defending_object: defender_id,
+        on_map: map_id,
+        defending_health: None,
+        attacking_health: None,
+        defender_death: None,
+        attacker_death: None,
+    };
+    let health = |current_health: u32, on_death: OnDeath| Health {
+        current_health,
+        max_health: 10,
+        on_death,
+    };
+
+    // Damage
+    let mut game = new_game(10, health(10, OnDeath::Destroy), GameRules::default());
+    let world = &mut game.game_world;
+    let mut command = attack();
+    command.execute(world).unwrap();
+    assert_eq!(health_of(world, defender_id), Some(6));
+    assert_eq!(health_of(world, attacker_id), Some(10));
+    let attacker = object_entity(world, attacker_id).unwrap();
+    assert!(world.get::<ObjectAttacked>(attacker).is_some());
+    command.rollback(world).unwrap();
+    assert_eq!(health_of(world, defender_id), Some(10));
+    let attacker = object_entity(world, attacker_id).unwrap();
+    assert!(world.get::<ObjectAttacked>(attacker).is_none());
+    assert_rollback_consistent(&mut attack(), world);
+
+    // Destroy
+    let mut game = new_game(10, health(4, OnDeath::Destroy), GameRules::default());
+    let world = &mut game.game_world;
+    let mut command = attack();
+    command.execute(world).unwrap();
+    assert!(object_entity(world, defender_id).is_err());
+    assert!(world
+        .resource::<crate::game_core::state::DespawnedObjects>()
+        .despawned_objects
+        .contains_key(&defender_id));
+    command.rollback(world).unwrap();
+    assert_eq!(health_of(world, defender_id), Some(4));
+    assert_eq!(owner_of(world, defender_id), Some(1));
+    assert_eq!(
+        crate::object::objects_sorted(world, map_id),
+        vec![
+            (attacker_id, TilePos::new(0, 0)),
+            (defender_id, TilePos::new(1, 0))
+        ]
+    );
+    assert_rollback_consistent(&mut attack(), world);
+
+    // Capture
+    let capture = OnDeath::Capture {
+        restore_at_health: 5,
+    };
+    let mut game = new_game(10, health(4, capture), GameRules::default());
+    let world = &mut game.game_world;
+    let mut command = attack();
+    command.execute(world).unwrap();
+    assert_eq!(owner_of(world, defender_id), Some(0));
+    assert_eq!(health_of(world, defender_id), Some(5));
+    command.rollback(world).unwrap();
+    assert_eq!(owner_of(world, defender_id), Some(1));
+    assert_eq!(health_of(world, defender_id), Some(4));
+    assert_rollback_consistent(&mut attack(), world);
+
+    // Counter attack that the attacker survives, then one that destroys it
+    let counter_attacks = GameRules {
+        counter_attacks: true,
+        ..Default::default()
+    };
+    let mut game = new_game(10, health(10, OnDeath::Destroy), counter_attacks);
+    let world = &mut game.game_world;
+    let mut command = attack();
+    command.execute(world).unwrap();
+    assert_eq!(health_of(world, defender_id), Some(6));
+    assert_eq!(health_of(world, attacker_id), Some(7));
+    command.rollback(world).unwrap();
+    assert_eq!(health_of(world, defender_id), Some(10));
+    assert_eq!(health_of(world, attacker_id), Some(10));
+    assert_rollback_consistent(&mut attack(), world);
+
+    // The respawned attacker loses its AttackPower, which has no SaveId, so each check gets a new game
+    let mut game = new_game(3, health(10, OnDeath::Destroy), counter_attacks);
+    assert_rollback_consistent(&mut attack(), &mut game.game_world);
+    let mut game = new_game(3, health(10, OnDeath::Destroy), counter_attacks);
+    let world = &mut game.game_world;
+    let mut command = attack();
+    command.execute(world).unwrap();
+    assert_eq!(health_of(world, attacker_id), None);
+    command.rollback(world).unwrap();
+    assert_eq!(health_of(world, attacker_id), Some(3));
+    assert_eq!(health_of(world, defender_id), Some(10));
+}
@@ -2,7 +2,7 @@ use crate::combat::battle_resolver::{
     AttackPowerCalculator, BattleCalculator, BattleError, BattleResult, Combat,
 };
 use crate::combat::commands::GameCommandsExt;
-use crate::combat::{AttackPower, BaseAttackPower, Health, OnDeath};
+use crate::combat::{apply_terrain_defense, object_attack_power, BaseAttackPower, Health, OnDeath};
 use crate::game_core::command::GameCommands;
 use crate::object::{ObjectId, ObjectInfo, ObjectType};
 use bevy::ecs::system::SystemState;
@@ -53,6 +53,12 @@ pub struct UniversalAP {
     attack_power: u32,
 }
 
+impl UniversalAP {
+    pub fn new(attack_power: u32) -> UniversalAP {
+        UniversalAP { attack_power }
+    }
+}
+
 impl BaseAttackPower for UniversalAP {
     fn get_base_attack_power(&self, _: &World, _: Entity, _: Entity) -> u32 {
         self.attack_power
@@ -76,25 +82,52 @@ impl AttackPowerCalculator for BasicObjectAPCalculator {
         opponent_object: ObjectId,
         world: &mut World,
     ) -> u32 {
-        let mut system_state: SystemState<Query<(Entity, &ObjectId, &AttackPower)>> =
-            SystemState::new(world);
+        let mut system_state: SystemState<Query<(Entity, &ObjectId)>> = SystemState::new(world);
         let object_query = system_state.get(world);
 
-        let Some((main_entity, _, main_ap)) = object_query.iter().find(|(_, id, _)| {
-            id == &&object_to_calculate
-        })else {
+        let Some((main_entity, _)) = object_query
+            .iter()
+            .find(|(_, id)| id == &&object_to_calculate)
+        else {
             return 0;
         };
 
-        let Some((opponent_entity, _, _)) = object_query.iter().find(|(_, id, _)| {
-            id == &&opponent_object
-        })else {
+        let Some((opponent_entity, _)) =
+            object_query.iter().find(|(_, id)| id == &&opponent_object)
+        else {
             return 0;
         };
 
-        return main_ap
-            .attack_power
-            .get_base_attack_power(&world, main_entity, opponent_entity);
+        return object_attack_power(world, main_entity, opponent_entity);
+    }
+}
+
+/// An [`AttackPowerCalculator`] that calculates the attack power the same as [`BasicObjectAPCalculator`]
+/// and then reduces it by the opponents [`TerrainDefenseBonuses`](crate::combat::TerrainDefenseBonuses)
+pub struct TerrainDefenseAPCalculator;
+
+impl AttackPowerCalculator for TerrainDefenseAPCalculator {
+    fn calculate_object_attack_power(
+        &self,
+        object_to_calculate: ObjectId,
+        opponent_object: ObjectId,
+        world: &mut World,
+    ) -> u32 {
+        let attack_power = BasicObjectAPCalculator.calculate_object_attack_power(
+            object_to_calculate,
+            opponent_object,
+            world,
+        );
+
+        let mut system_state: SystemState<Query<(Entity, &ObjectId)>> = SystemState::new(world);
+        let object_query = system_state.get(world);
+        let Some((opponent_entity, _)) =
+            object_query.iter().find(|(_, id)| id == &&opponent_object)
+        else {
+            return attack_power;
+        };
+
+        apply_terrain_defense(world, opponent_entity, attack_power)
     }
 }
 
@@ -127,10 +160,13 @@ impl BattleCalculator for BasicBattleCalculator {
         )> = SystemState::new(world);
         let (mut object_query, mut game_commands) = system_state.get_mut(world);
 
-        let Some((attacking_entity, _, mut attacking_health)) = object_query.iter_mut().find(|(_, id, _)| {
-            id == &&attacking_id
-        })else {
-            return Err(BattleError::Message(String::from("Attacking Object not found in query")));
+        let Some((attacking_entity, _, mut attacking_health)) = object_query
+            .iter_mut()
+            .find(|(_, id, _)| id == &&attacking_id)
+        else {
+            return Err(BattleError::Message(String::from(
+                "Attacking Object not found in query",
+            )));
         };
 
         attacking_health.damage(attacking_ap);
@@ -144,10 +180,13 @@ impl BattleCalculator for BasicBattleCalculator {
             }
         }
 
-        let Some((defending_entity, _, mut defending_health)) = object_query.iter_mut().find(|(_, id, _)| {
-            id == &&defending_id
-        })else {
-            return Err(BattleError::Message(String::from("Defending Object not found in query")));
+        let Some((defending_entity, _, mut defending_health)) = object_query
+            .iter_mut()
+            .find(|(_, id, _)| id == &&defending_id)
+        else {
+            return Err(BattleError::Message(String::from(
+                "Defending Object not found in query",
+            )));
         };
 
         defending_health.damage(attacking_ap);
@@ -1,13 +1,30 @@
 //!
 
+use crate::game_core::rules::GameRules;
+use crate::game_core::runner::GameRunner;
+use crate::game_core::GameBuilder;
+use crate::mapping::terrain::{TerrainType, TileTerrainInfo};
+use crate::mapping::tiles::{tile_distance, tile_neighbors, TileObjects};
+use crate::mapping::vision::visible_to_player;
+use crate::mapping::{Map, MapId};
+use crate::movement::{calculate_available_moves, ObjectMoved, PartialMove};
+use crate::object::{ObjectGridPosition, ObjectId};
+use crate::player::PlayerMarker;
 use bevy::app::App;
-use bevy::prelude::{Component, Entity, Plugin, World};
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{
+    Component, Entity, FromReflect, Plugin, Query, Reflect, Res, Resource, With, World,
+};
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_tilemap::prelude::{TileStorage, TilemapType};
 use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_trait_query::RegisterExt;
 
 pub mod backend;
 pub mod battle_resolver;
 pub mod commands;
 pub mod defaults;
+pub mod threat;
 
 pub struct BggfCombatPlugin {}
 
@@ -41,7 +58,7 @@ pub struct ValidAttack {
 /// The health of an object. Without a Health component an object is not able to be attacked or killed.
 /// Objects with a health component can be attacked and will be returned as valid targets by relevant
 /// systems
-#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component)]
+#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component, Reflect, FromReflect)]
 pub struct Health {
     pub current_health: u32,
     pub max_health: u32,
@@ -65,25 +82,57 @@ impl Health {
 }
 
 /// Specifies what will happen to the object when it is killed in battle
-#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Reflect, FromReflect)]
 pub enum OnDeath {
     /// Destroys the object when killed
     Destroy,
     /// Captures the object when killed, converting it to the killing team and restoring it to the
-    /// specified health
+    /// specified health. Neutral objects without a [`PlayerMarker`] are given the killing teams marker
     Capture { restore_at_health: u32 },
 }
 
+/// Implement on anything that provides attack power to an object. The [`AttackPower`] component
+/// holds a boxed BaseAttackPower, or a component can implement it directly and be registered with
+/// [`GameBuilderCombatExt::register_attack_power_component`] so that it is found by
+/// [`object_attack_power`] through a trait query.
+#[bevy_trait_query::queryable]
 pub trait BaseAttackPower {
     /// Returns the *base* attack power of the unit. This should be the base power, unmodified by any
     /// buffs, nerfs, or other modifiers.
     fn get_base_attack_power(&self, world: &World, entity: Entity, opponent_entity: Entity) -> u32;
 }
 
+/// The range in tiles that an object can attack at, measured from the tile the object is in. An object
+/// with a min_range of 0 can attack objects in its own tile.
+#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component, Reflect, FromReflect)]
+pub struct AttackRange {
+    pub min_range: u32,
+    pub max_range: u32,
+}
+
+impl AttackRange {
+    /// Returns true if the given distance is within this range
+    pub fn in_range(&self, distance: u32) -> bool {
+        distance >= self.min_range && distance <= self.max_range
+    }
+}
+
 /// Marker component denoting this unit as having attacked.
 #[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component)]
 pub struct ObjectAttacked;
 
+/// Component that controls whether an object can move before attacking in the same turn. Read by
+/// [`attackable_targets`]. Objects without this component use [`CombatMovePolicy::MoveThenAttack`]
+#[derive(Default, Clone, Copy, Eq, Hash, Debug, PartialEq, Component, Reflect, FromReflect)]
+pub enum CombatMovePolicy {
+    /// The object can move and then attack from the tile it moved to
+    #[default]
+    MoveThenAttack,
+    /// The object can only attack from the tile it started its turn in and can't attack at all once
+    /// it has moved, eg artillery
+    AttackWithoutMoving,
+}
+
 /// Component that holds an [`BaseAttackPower`] trait object. Attach this to objects that should deal damage
 /// in combat
 #[derive(Component)]
@@ -91,6 +140,77 @@ pub struct AttackPower {
     attack_power: Box<dyn BaseAttackPower + Send + Sync>,
 }
 
+impl AttackPower {
+    pub fn new<T>(attack_power: T) -> AttackPower
+    where
+        T: BaseAttackPower + Send + Sync + 'static,
+    {
+        AttackPower {
+            attack_power: Box::new(attack_power),
+        }
+    }
+}
+
+/// Extension trait for the [`GameBuilder`] with combat related registrations
+pub trait GameBuilderCombatExt {
+    /// Registers a component that implements [`BaseAttackPower`] so that it is included in
+    /// [`object_attack_power`]. Use this to give objects attack power with your own components
+    /// rather than the [`AttackPower`] component. Must be called before the game is built.
+    fn register_attack_power_component<C>(&mut self)
+    where
+        C: Component + BaseAttackPower;
+}
+
+impl<T> GameBuilderCombatExt for GameBuilder<T>
+where
+    T: GameRunner + 'static,
+{
+    fn register_attack_power_component<C>(&mut self)
+    where
+        C: Component + BaseAttackPower,
+    {
+        self.game_world
+            .register_component_as::<dyn BaseAttackPower, C>();
+    }
+}
+
+/// Returns the total base attack power the object has against the opponent. This is the sum of its
+/// [`AttackPower`] component and every component registered with
+/// [`GameBuilderCombatExt::register_attack_power_component`]. Returns 0 if it has neither.
+pub fn object_attack_power(world: &mut World, entity: Entity, opponent_entity: Entity) -> u32 {
+    let mut attack_power = world
+        .get::<AttackPower>(entity)
+        .map(|attack_power| {
+            attack_power
+                .attack_power
+                .get_base_attack_power(world, entity, opponent_entity)
+        })
+        .unwrap_or(0);
+
+    let mut attack_power_query = world.query::<&dyn BaseAttackPower>();
+    if let Ok(attack_powers) = attack_power_query.get(world, entity) {
+        for base_attack_power in attack_powers.iter() {
+            attack_power = attack_power.saturating_add(base_attack_power.get_base_attack_power(
+                world,
+                entity,
+                opponent_entity,
+            ));
+        }
+    }
+
+    attack_power
+}
+
+/// Returns true if the object has an [`AttackPower`] component or any component registered with
+/// [`GameBuilderCombatExt::register_attack_power_component`]
+pub fn has_attack_power(world: &mut World, entity: Entity) -> bool {
+    if world.get::<AttackPower>(entity).is_some() {
+        return true;
+    }
+    let mut attack_power_query = world.query::<&dyn BaseAttackPower>();
+    attack_power_query.get(world, entity).is_ok()
+}
+
 /// Marks this object as NOT being attackable, can not be targeted or attacked
 #[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component)]
 pub struct NonAttackable;
@@ -98,3 +218,487 @@ pub struct NonAttackable;
 /// Marks this object as being invulnerable. Will not take damage during combat but can be attacked
 #[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component)]
 pub struct Invulnerable;
+
+/// Checks whether the attacking object is able to attack the defending object on the given map.
+/// Returns an error describing the first check that failed.
+///
+/// An attack is valid if:
+/// - the attacker has an [`AttackPower`], or a registered [`BaseAttackPower`] component, and an [`AttackRange`] and hasn't already attacked
+/// - the defender has [`Health`] and isn't [`NonAttackable`]
+/// - the objects belong to different players, unless [`GameRules::friendly_fire`] is enabled. Neutral
+///   objects without a [`PlayerMarker`] can attack and be attacked by anyone
+/// - both objects are in a tile on the given map and the defender is within the attackers [`AttackRange`]
+/// - when [`GameRules::fog_of_war`] is enabled, the defenders tile is [`visible_to_player`] for the
+///   attackers player
+pub fn check_attack(
+    world: &mut World,
+    on_map: MapId,
+    attacking_entity: Entity,
+    defending_entity: Entity,
+) -> Result<(), String> {
+    if world.get::<ObjectAttacked>(attacking_entity).is_some() {
+        return Err(String::from("Attacking object has already attacked"));
+    }
+    check_strike(world, on_map, attacking_entity, defending_entity)
+}
+
+/// Checks whether the defending object can strike back at the attacking object after being attacked,
+/// used for [`GameRules::counter_attacks`]. The same as [`check_attack`] with the objects swapped
+/// except that it doesn't matter if the defender has already attacked this turn.
+pub fn check_counter_attack(
+    world: &mut World,
+    on_map: MapId,
+    attacking_entity: Entity,
+    defending_entity: Entity,
+) -> Result<(), String> {
+    check_strike(world, on_map, defending_entity, attacking_entity)
+}
+
+/// Every check in [`check_attack`] apart from whether the attacker has already attacked
+fn check_strike(
+    world: &mut World,
+    on_map: MapId,
+    attacking_entity: Entity,
+    defending_entity: Entity,
+) -> Result<(), String> {
+    let attack_range = check_target(world, attacking_entity, defending_entity)?;
+
+    let Some(attacking_pos) = object_tile_on_map(world, on_map, attacking_entity) else {
+        return Err(String::from("Attacking object is not on the given map"));
+    };
+    let Some(defending_pos) = object_tile_on_map(world, on_map, defending_entity) else {
+        return Err(String::from("Defending object is not on the given map"));
+    };
+
+    let map_type = map_type(world, on_map);
+    if !attack_range.in_range(tile_distance(attacking_pos, defending_pos, map_type)) {
+        return Err(String::from(
+            "Defending object is not within the attackers AttackRange",
+        ));
+    }
+    if hidden_by_fog(world, on_map, attacking_entity, defending_pos) {
+        return Err(String::from(
+            "Defending object is hidden from the attacking player by fog of war",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns true if [`GameRules::fog_of_war`] is enabled and the given tile isn't
+/// [`visible_to_player`] for the player that owns the attacking object. Neutral attackers can always
+/// see every tile
+fn hidden_by_fog(
+    world: &mut World,
+    on_map: MapId,
+    attacking_entity: Entity,
+    tile_pos: TilePos,
+) -> bool {
+    if !GameRules::get(world).fog_of_war {
+        return false;
+    }
+    let Some(player_id) = world
+        .get::<PlayerMarker>(attacking_entity)
+        .map(|player_marker| player_marker.id())
+    else {
+        return false;
+    };
+    !visible_to_player(world, on_map, tile_pos, player_id)
+}
+
+/// Every check in [`check_strike`] that doesn't depend on where the objects are. Returns the attackers
+/// [`AttackRange`]
+fn check_target(
+    world: &mut World,
+    attacking_entity: Entity,
+    defending_entity: Entity,
+) -> Result<AttackRange, String> {
+    if !has_attack_power(world, attacking_entity) {
+        return Err(String::from(
+            "Attacking object does not have an AttackPower or BaseAttackPower component",
+        ));
+    }
+    let Some(attack_range) = world.get::<AttackRange>(attacking_entity).copied() else {
+        return Err(String::from(
+            "Attacking object does not have an AttackRange component",
+        ));
+    };
+    if world.get::<Health>(defending_entity).is_none() {
+        return Err(String::from(
+            "Defending object does not have a Health component",
+        ));
+    }
+    if world.get::<NonAttackable>(defending_entity).is_some() {
+        return Err(String::from("Defending object is NonAttackable"));
+    }
+    if let (Some(attacking_player), Some(defending_player)) = (
+        world.get::<PlayerMarker>(attacking_entity),
+        world.get::<PlayerMarker>(defending_entity),
+    ) {
+        if attacking_player.id() == defending_player.id() && !GameRules::get(world).friendly_fire {
+            return Err(String::from(
+                "Objects belonging to the same player can't attack each other",
+            ));
+        }
+    }
+
+    Ok(attack_range)
+}
+
+/// Returns the [`TilemapType`] of the given map, used to measure the distance for [`AttackRange`]s.
+/// Reads the maps [`Map`] component, falling back to a [`TilemapType`] component on the map entity,
+/// and defaults to a square map if the map can't be found
+fn map_type(world: &mut World, on_map: MapId) -> TilemapType {
+    let mut map_query = world.query::<(&MapId, Option<&Map>, Option<&TilemapType>)>();
+    map_query
+        .iter(world)
+        .filter(|(id, _, _)| id == &&on_map)
+        .find_map(|(_, map, map_type)| map.map(|map| map.tilemap_type).or(map_type.copied()))
+        .unwrap_or_default()
+}
+
+/// Returns every object on the given map that the attacking object could attack from the tile it is
+/// currently in. See [`check_attack`] for the checks that are done.
+pub fn attackable_objects(
+    world: &mut World,
+    on_map: MapId,
+    attacking_entity: Entity,
+) -> Vec<ValidAttack> {
+    let mut system_state: SystemState<Query<(Entity, &ObjectGridPosition), With<Health>>> =
+        SystemState::new(world);
+    let object_query = system_state.get(world);
+    let defenders: Vec<(Entity, TilePos)> = object_query
+        .iter()
+        .filter(|(entity, _)| *entity != attacking_entity)
+        .map(|(entity, object_grid_position)| (entity, object_grid_position.tile_position.into()))
+        .collect();
+
+    defenders
+        .into_iter()
+        .filter(|(defending_entity, _)| {
+            check_attack(world, on_map, attacking_entity, *defending_entity).is_ok()
+        })
+        .map(|(target_entity, target_tile_position)| ValidAttack {
+            target_entity,
+            target_tile_position,
+        })
+        .collect()
+}
+
+/// Returns every object on the given map that the attacking object could attack this turn, either from
+/// the tile it is in or after moving, along with the tile to attack each one from. Each target is only
+/// returned once, from the reachable tile with the lowest move cost. The current tile is preferred
+/// over moving.
+///
+/// Where the object can attack from depends on its [`CombatMovePolicy`]:
+/// - [`CombatMovePolicy::MoveThenAttack`] - the tile it is in and, if it hasn't finished moving
+///   this turn, every tile returned by [`calculate_available_moves`]
+/// - [`CombatMovePolicy::AttackWithoutMoving`] - only the tile it is in, and nothing if it has
+///   already moved this turn
+///
+/// Every target passes the same checks as [`check_attack`] from the tile returned with it. Returns
+/// an empty list if the object has already attacked.
+pub fn attackable_targets(
+    world: &mut World,
+    on_map: MapId,
+    attacking_entity: Entity,
+) -> Vec<(ObjectId, TilePos)> {
+    if world.get::<ObjectAttacked>(attacking_entity).is_some() {
+        return vec![];
+    }
+    let Some(current_pos) = object_tile_on_map(world, on_map, attacking_entity) else {
+        return vec![];
+    };
+
+    let policy = world
+        .get::<CombatMovePolicy>(attacking_entity)
+        .copied()
+        .unwrap_or_default();
+    let finished_moving = world.get::<ObjectMoved>(attacking_entity).is_some();
+    let started_moving = world
+        .get::<PartialMove>(attacking_entity)
+        .is_some_and(|partial_move| partial_move.spent > 0);
+
+    let mut attack_from: Vec<(TilePos, i32)> = vec![];
+    match policy {
+        CombatMovePolicy::MoveThenAttack => {
+            attack_from.push((current_pos, 0));
+            if !finished_moving {
+                if let Some(moves) = calculate_available_moves(world, on_map, attacking_entity) {
+                    attack_from.extend(
+                        moves
+                            .values()
+                            .filter(|available_move| available_move.tile_pos != current_pos)
+                            .map(|available_move| {
+                                (available_move.tile_pos, available_move.move_cost)
+                            }),
+                    );
+                }
+            }
+        }
+        CombatMovePolicy::AttackWithoutMoving => {
+            if !finished_moving && !started_moving {
+                attack_from.push((current_pos, 0));
+            }
+        }
+    }
+    // Sort so that the first tile in range of a target is the cheapest, ties broken by position so
+    // the result doesn't depend on HashMap ordering
+    attack_from.sort_by_key(|(tile_pos, move_cost)| (*move_cost, tile_pos.y, tile_pos.x));
+
+    let mut system_state: SystemState<Query<(Entity, &ObjectId), With<Health>>> =
+        SystemState::new(world);
+    let object_query = system_state.get(world);
+    let defenders: Vec<(Entity, ObjectId)> = object_query
+        .iter()
+        .filter(|(entity, _)| *entity != attacking_entity)
+        .map(|(entity, object_id)| (entity, *object_id))
+        .collect();
+
+    let map_type = map_type(world, on_map);
+    let mut targets: Vec<(ObjectId, TilePos)> = vec![];
+    for (defending_entity, object_id) in defenders {
+        let Ok(attack_range) = check_target(world, attacking_entity, defending_entity) else {
+            continue;
+        };
+        let Some(defending_pos) = object_tile_on_map(world, on_map, defending_entity) else {
+            continue;
+        };
+        if hidden_by_fog(world, on_map, attacking_entity, defending_pos) {
+            continue;
+        }
+        if let Some((tile_pos, _)) = attack_from.iter().find(|(tile_pos, _)| {
+            attack_range.in_range(tile_distance(*tile_pos, defending_pos, map_type))
+        }) {
+            targets.push((object_id, *tile_pos));
+        }
+    }
+    targets
+}
+
+/// Returns every object on the given map that would be hit by an area of effect centered on the given
+/// tile, without changing anything. Used by [`AttackArea`](commands::AttackArea) to pick its targets
+/// so it can also be used to preview a blast before committing to it.
+///
+/// The area is every tile within `radius` neighbor steps of the center using [`tile_neighbors`] for the
+/// given map type - a square on square and isometric maps and a hexagon on hexagon maps. A radius of
+/// 0 only covers the center tile. Objects in the area are hit if they have [`Health`] and aren't
+/// [`NonAttackable`]. Ownership isn't checked, an area of effect hits every object in it.
+pub fn objects_affected_by_aoe(
+    world: &mut World,
+    on_map: MapId,
+    center: TilePos,
+    radius: u32,
+    map_type: TilemapType,
+) -> Vec<ObjectId> {
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage)>,
+        Query<&TileObjects>,
+        Query<(&ObjectId, Option<&NonAttackable>), With<Health>>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&on_map) else {
+        return vec![];
+    };
+    if tile_storage.checked_get(&center).is_none() {
+        return vec![];
+    }
+
+    let mut area: Vec<TilePos> = vec![center];
+    let mut visited: HashSet<TilePos> = HashSet::from([center]);
+    let mut frontier: Vec<TilePos> = vec![center];
+    for _ in 0..radius {
+        let mut next_frontier: Vec<TilePos> = vec![];
+        for tile_pos in frontier.iter() {
+            for neighbor in tile_neighbors(tile_pos, &tile_storage.size, &map_type)
+                .into_iter()
+                .flatten()
+            {
+                if visited.insert(neighbor) {
+                    area.push(neighbor);
+                    next_frontier.push(neighbor);
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    let mut affected: Vec<ObjectId> = vec![];
+    for tile_pos in area.iter() {
+        let Some(tile_objects) = tile_storage
+            .checked_get(tile_pos)
+            .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+        else {
+            continue;
+        };
+        for object_id in tile_objects.entities_in_tile.iter() {
+            if object_query
+                .iter()
+                .any(|(id, non_attackable)| id == object_id && non_attackable.is_none())
+            {
+                affected.push(*object_id);
+            }
+        }
+    }
+    affected
+}
+
+/// Resource mapping [`TerrainType`]s to the defense bonus an object gets while in a tile of that
+/// terrain, eg plains 0, forest 2, mountain 4. Insert into the game world to have the bonuses
+/// applied by [`AttackObject`](commands::AttackObject) and [`TerrainDefenseAPCalculator`](defaults::TerrainDefenseAPCalculator).
+///
+/// The bonus is subtracted from the damage the object takes. A negative bonus increases the damage taken.
+#[derive(Resource, Default, Debug)]
+pub struct TerrainDefenseBonuses {
+    pub defense_bonuses: HashMap<TerrainType, i32>,
+}
+
+impl TerrainDefenseBonuses {
+    /// Creates a new [`TerrainDefenseBonuses`] struct from a vec of [`TerrainType`] and defense bonus
+    pub fn from_vec(terrain_defense_bonuses: Vec<(TerrainType, i32)>) -> TerrainDefenseBonuses {
+        let mut hashmap: HashMap<TerrainType, i32> = HashMap::new();
+        for (terrain_type, defense_bonus) in terrain_defense_bonuses {
+            hashmap.insert(terrain_type, defense_bonus);
+        }
+
+        Self {
+            defense_bonuses: hashmap,
+        }
+    }
+
+    /// Returns the defense bonus for the given terrain type, or 0 if there isn't one
+    pub fn defense_bonus(&self, terrain_type: &TerrainType) -> i32 {
+        self.defense_bonuses.get(terrain_type).copied().unwrap_or(0)
+    }
+}
+
+/// Returns the [`TerrainDefenseBonuses`] bonus for the terrain of the tile the given object is in.
+/// Returns 0 if there is no [`TerrainDefenseBonuses`] resource or the objects tile can't be found.
+pub fn terrain_defense_bonus(world: &mut World, entity: Entity) -> i32 {
+    let mut system_state: SystemState<(
+        Option<Res<TerrainDefenseBonuses>>,
+        Query<&TileStorage>,
+        Query<(&TileObjects, &TileTerrainInfo)>,
+        Query<(&ObjectId, &ObjectGridPosition)>,
+    )> = SystemState::new(world);
+    let (terrain_defense_bonuses, tile_storage_query, tile_query, object_query) =
+        system_state.get(world);
+
+    let Some(terrain_defense_bonuses) = terrain_defense_bonuses else {
+        return 0;
+    };
+    let Ok((object_id, object_grid_position)) = object_query.get(entity) else {
+        return 0;
+    };
+    let tile_pos: TilePos = object_grid_position.tile_position.into();
+
+    // Find the map whose tile actually holds the object
+    tile_storage_query
+        .iter()
+        .find_map(|tile_storage| {
+            let (tile_objects, tile_terrain_info) =
+                tile_query.get(tile_storage.checked_get(&tile_pos)?).ok()?;
+            tile_objects
+                .contains_object(*object_id)
+                .then(|| terrain_defense_bonuses.defense_bonus(&tile_terrain_info.terrain_type))
+        })
+        .unwrap_or(0)
+}
+
+/// Reduces the given damage by the [`terrain_defense_bonus`] of the defending object, down to a
+/// minimum of 0
+pub fn apply_terrain_defense(world: &mut World, defending_entity: Entity, damage: u32) -> u32 {
+    let defense_bonus = terrain_defense_bonus(world, defending_entity);
+    (damage as i64 - defense_bonus as i64).clamp(0, u32::MAX as i64) as u32
+}
+
+/// Returns the tile the given object is in if that tile is on the given map and holds the object
+pub fn object_tile_on_map(world: &mut World, on_map: MapId, entity: Entity) -> Option<TilePos> {
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage)>,
+        Query<&TileObjects>,
+        Query<(&ObjectId, &ObjectGridPosition)>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let (object_id, object_grid_position) = object_query.get(entity).ok()?;
+    let tile_pos: TilePos = object_grid_position.tile_position.into();
+    let (_, tile_storage) = tile_storage_query.iter().find(|(id, _)| id == &&on_map)?;
+    let tile_objects = tile_query.get(tile_storage.checked_get(&tile_pos)?).ok()?;
+
+    tile_objects.contains_object(*object_id).then_some(tile_pos)
+}
+
+#[test]
+fn test_check_attack_hex_range() {
+    use crate::combat::defaults::UniversalAP;
+    use crate::combat::threat::calculate_threat_map;
+    use bevy_ecs_tilemap::prelude::{HexCoordSystem, TilemapSize};
+
+    let mut world = World::new();
+    let map_id = MapId { id: 0 };
+    let attacker_pos = TilePos::new(0, 1);
+    let defender_pos = TilePos::new(1, 0);
+
+    let map_size = TilemapSize { x: 2, y: 2 };
+    let mut tile_storage = TileStorage::empty(map_size);
+    for x in 0..map_size.x {
+        for y in 0..map_size.y {
+            let tile_pos = TilePos::new(x, y);
+            let mut tile_objects = TileObjects::default();
+            if tile_pos == attacker_pos {
+                tile_objects.add_object(ObjectId { id: 0 });
+            } else if tile_pos == defender_pos {
+                tile_objects.add_object(ObjectId { id: 1 });
+            }
+            let tile_entity = world.spawn((tile_pos, tile_objects)).id();
+            tile_storage.set(&tile_pos, tile_entity);
+        }
+    }
+    let map_entity = world.spawn((map_id, tile_storage, map_size)).id();
+    world.entity_mut(map_entity).insert(Map {
+        tilemap_type: TilemapType::Hexagon(HexCoordSystem::Row),
+        map_size,
+        tilemap_entity: map_entity,
+    });
+
+    let attacker = world
+        .spawn((
+            ObjectId { id: 0 },
+            ObjectGridPosition {
+                tile_position: attacker_pos.into(),
+            },
+            PlayerMarker::new(0),
+            AttackPower::new(UniversalAP::new(5)),
+            AttackRange {
+                min_range: 1,
+                max_range: 1,
+            },
+        ))
+        .id();
+    let defender = world
+        .spawn((
+            ObjectId { id: 1 },
+            ObjectGridPosition {
+                tile_position: defender_pos.into(),
+            },
+            PlayerMarker::new(1),
+            Health {
+                current_health: 10,
+                max_health: 10,
+                on_death: OnDeath::Destroy,
+            },
+        ))
+        .id();
+
+    // The tiles are neighbors on a hex map but two steps apart on a square map
+    assert!(check_attack(&mut world, map_id, attacker, defender).is_ok());
+    let threats = calculate_threat_map(&mut world, 1, map_id, |_, _| 1);
+    assert_eq!(threats.get(&defender_pos), Some(&1));
+
+    world.get_mut::<Map>(map_entity).unwrap().tilemap_type = TilemapType::Square;
+    assert!(check_attack(&mut world, map_id, attacker, defender).is_err());
+    let threats = calculate_threat_map(&mut world, 1, map_id, |_, _| 1);
+    assert_eq!(threats.get(&defender_pos), None);
+}
@@ -0,0 +1,176 @@
+//! Threat maps for AI. A threat map holds, for every tile on a map, the total threat of every enemy
+//! object that could attack that tile this turn by moving and then attacking.
+//!
+//! Calculating a threat map runs the movement calculator for every enemy object so it is only done on
+//! demand. Use [`update_threat_map`] to calculate it at most once per turn.
+
+use crate::combat::{map_type, AttackRange};
+use crate::mapping::tiles::{tile_distance, TileObjects};
+use crate::mapping::MapId;
+use crate::movement::calculate_available_moves;
+use crate::object::{ObjectGridPosition, ObjectId};
+use crate::player::PlayerMarker;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Entity, Query, Resource, World};
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapSize, TilemapType};
+
+/// Resource holding the most recently calculated threat map along with what it was calculated for
+#[derive(Clone, Debug, Default, Resource)]
+pub struct ThreatMap {
+    pub player_id: usize,
+    pub on_map: Option<MapId>,
+    pub turn: u32,
+    pub threats: HashMap<TilePos, u32>,
+}
+
+impl ThreatMap {
+    /// Returns the threat on the given tile. Tiles that no enemy can reach have a threat of 0
+    pub fn threat_at(&self, tile_pos: &TilePos) -> u32 {
+        *self.threats.get(tile_pos).unwrap_or(&0)
+    }
+}
+
+/// Calculates the [`ThreatMap`] for the given player on the given map and inserts it into the world.
+/// Does nothing if the world already has a [`ThreatMap`] for the same player, map, and turn.
+///
+/// See [`calculate_threat_map`] for how the threat is calculated.
+pub fn update_threat_map<F>(
+    world: &mut World,
+    player_id: usize,
+    on_map: MapId,
+    turn: u32,
+    threat_value: F,
+) where
+    F: Fn(&World, Entity) -> u32,
+{
+    if let Some(threat_map) = world.get_resource::<ThreatMap>() {
+        if threat_map.player_id == player_id
+            && threat_map.on_map == Some(on_map)
+            && threat_map.turn == turn
+        {
+            return;
+        }
+    }
+
+    let threats = calculate_threat_map(world, player_id, on_map, threat_value);
+    world.insert_resource(ThreatMap {
+        player_id,
+        on_map: Some(on_map),
+        turn,
+        threats,
+    });
+}
+
+/// Calculates the threat on every tile of the given map for the given player.
+///
+/// Every object on the map that has an [`AttackRange`] and a [`PlayerMarker`] belonging to another
+/// player, or no [`PlayerMarker`] at all as neutral objects are hostile to everyone, is an enemy.
+/// Each enemy adds its `threat_value` to every tile that is within its [`AttackRange`] of any tile it
+/// can move to, including the tile it is currently in. Range is measured the same way as in
+/// [`check_attack`](crate::combat::check_attack), using [`tile_distance`] for the maps
+/// [`TilemapType`]. An enemy only adds its threat to each tile once. `threat_value` is given the enemy
+/// objects entity, usually returning its attack power.
+pub fn calculate_threat_map<F>(
+    world: &mut World,
+    player_id: usize,
+    on_map: MapId,
+    threat_value: F,
+) -> HashMap<TilePos, u32>
+where
+    F: Fn(&World, Entity) -> u32,
+{
+    let map_type = map_type(world, on_map);
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage, &TilemapSize)>,
+        Query<&TileObjects>,
+        Query<(
+            Entity,
+            &ObjectId,
+            &ObjectGridPosition,
+            Option<&PlayerMarker>,
+            &AttackRange,
+        )>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let Some((_, tile_storage, tilemap_size)) =
+        tile_storage_query.iter().find(|(id, _, _)| id == &&on_map)
+    else {
+        return HashMap::new();
+    };
+    let tilemap_size = *tilemap_size;
+
+    let mut enemies: Vec<(Entity, TilePos, AttackRange)> = vec![];
+    for (entity, object_id, object_grid_position, player_marker, attack_range) in
+        object_query.iter()
+    {
+        if matches!(player_marker, Some(player_marker) if player_marker.id() == player_id) {
+            continue;
+        }
+        let tile_pos: TilePos = object_grid_position.tile_position.into();
+        let Some(tile_objects) = tile_storage
+            .checked_get(&tile_pos)
+            .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+        else {
+            continue;
+        };
+        if tile_objects.contains_object(*object_id) {
+            enemies.push((entity, tile_pos, *attack_range));
+        }
+    }
+
+    let mut threats: HashMap<TilePos, u32> = HashMap::new();
+
+    for (entity, tile_pos, attack_range) in enemies {
+        let mut attack_from: Vec<TilePos> = vec![tile_pos];
+        if let Some(moves) = calculate_available_moves(world, on_map, entity) {
+            attack_from.extend(moves.keys());
+        }
+
+        let mut threatened: HashSet<TilePos> = HashSet::new();
+        for from in attack_from.iter() {
+            threatened.extend(tiles_in_attack_range(
+                from,
+                &attack_range,
+                &tilemap_size,
+                map_type,
+            ));
+        }
+
+        let threat = threat_value(world, entity);
+        for tile_pos in threatened {
+            *threats.entry(tile_pos).or_insert(0) += threat;
+        }
+    }
+
+    threats
+}
+
+/// Returns every tile on the map within the given [`AttackRange`] of the given tile. Each step on any
+/// map type changes a tiles x and y by at most one so only tiles within max_range on both axes are
+/// checked
+fn tiles_in_attack_range(
+    from: &TilePos,
+    attack_range: &AttackRange,
+    tilemap_size: &TilemapSize,
+    map_type: TilemapType,
+) -> Vec<TilePos> {
+    let mut tiles: Vec<TilePos> = vec![];
+    let max_range = attack_range.max_range as i32;
+    for x_offset in -max_range..=max_range {
+        for y_offset in -max_range..=max_range {
+            let Some(tile_pos) = TilePos::from_i32_pair(
+                from.x as i32 + x_offset,
+                from.y as i32 + y_offset,
+                tilemap_size,
+            ) else {
+                continue;
+            };
+            if attack_range.in_range(tile_distance(*from, tile_pos, map_type)) {
+                tiles.push(tile_pos);
+            }
+        }
+    }
+    tiles
+}
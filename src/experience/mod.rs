@@ -0,0 +1,262 @@
+//! Experience and leveling for objects. An object with an [`Experience`] component gains experience
+//! through the [`GrantExperience`] command and levels up whenever its current experience reaches the
+//! amount needed for the next level. What a level up actually does is entirely defined by the game
+//! through the [`LevelProgression`] trait, bevy_ggf only keeps track of the experience and makes sure
+//! that granting it is rollback-able.
+//!
+//! [`HealthProgression`] is a built in progression that raises an objects [`Health`] each level.
+
+use crate::combat::Health;
+use crate::game_core::command::{object_entity, GameCommand, GameCommands};
+use crate::game_core::state::Changed;
+use crate::object::ObjectId;
+use bevy::prelude::{Component, FromReflect, Reflect, ReflectComponent, World};
+use serde::{Deserialize, Serialize};
+
+/// Component that tracks an objects experience and level. `current` is the experience earned toward
+/// the next level and `to_next` is the experience needed to reach it.
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Eq,
+    Hash,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Component)]
+pub struct Experience {
+    pub current: u32,
+    pub level: u32,
+    pub to_next: u32,
+}
+
+impl Experience {
+    /// Creates a new level 1 [`Experience`] with no experience that needs `to_next` experience to reach
+    /// level 2
+    pub fn new(to_next: u32) -> Experience {
+        Experience {
+            current: 0,
+            level: 1,
+            to_next,
+        }
+    }
+}
+
+/// A trait defining how objects progress when they level up. Implement this for your own structs to
+/// define what stats increase and how much experience each level needs. Used by the
+/// [`GrantExperience`] command.
+pub trait LevelProgression: Send + Sync + Clone + Reflect + 'static {
+    /// The experience needed to go from the given level to the next one. Returning 0 stops the object
+    /// from leveling past the given level
+    fn experience_to_next(&self, level: u32) -> u32;
+
+    /// Apply the effects of the object reaching the given level
+    fn level_up(
+        &mut self,
+        object_id: ObjectId,
+        new_level: u32,
+        world: &mut World,
+    ) -> Result<(), String>;
+
+    /// Reverse the effects of the object reaching the given level. Must undo exactly what level_up
+    /// did. Called once for every level gained, starting with the highest.
+    ///
+    /// NOTE: Like [`GameCommand::rollback`] this has a default implementation that does nothing
+    fn rollback_level_up(
+        &mut self,
+        _object_id: ObjectId,
+        _new_level: u32,
+        _world: &mut World,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// A built in [`LevelProgression`] that raises the objects max health, and current health by the same
+/// amount, every level. Every level needs the same amount of experience.
+#[derive(Clone, Debug, Reflect)]
+pub struct HealthProgression {
+    pub health_per_level: u32,
+    pub experience_per_level: u32,
+    previous_health: Vec<Health>,
+}
+
+impl HealthProgression {
+    pub fn new(health_per_level: u32, experience_per_level: u32) -> HealthProgression {
+        HealthProgression {
+            health_per_level,
+            experience_per_level,
+            previous_health: vec![],
+        }
+    }
+}
+
+impl LevelProgression for HealthProgression {
+    fn experience_to_next(&self, _level: u32) -> u32 {
+        self.experience_per_level
+    }
+
+    fn level_up(
+        &mut self,
+        object_id: ObjectId,
+        _new_level: u32,
+        world: &mut World,
+    ) -> Result<(), String> {
+        let entity = object_entity(world, object_id)?;
+        let Some(mut health) = world.get_mut::<Health>(entity) else {
+            return Err(format!(
+                "No Health component found for ObjectId: {:?}",
+                object_id
+            ));
+        };
+        self.previous_health.push(*health);
+        health.max_health = health.max_health.saturating_add(self.health_per_level);
+        health.current_health = health.current_health.saturating_add(self.health_per_level);
+        Ok(())
+    }
+
+    fn rollback_level_up(
+        &mut self,
+        object_id: ObjectId,
+        _new_level: u32,
+        world: &mut World,
+    ) -> Result<(), String> {
+        let Some(previous_health) = self.previous_health.pop() else {
+            return Err(String::from("Rollback can only be called after level_up"));
+        };
+        let entity = object_entity(world, object_id)?;
+        world.entity_mut(entity).insert(previous_health);
+        Ok(())
+    }
+}
+
+/// An extension trait for [GameCommands] with experience related commands.
+pub trait ExperienceCommandsExt {
+    fn grant_experience<P>(
+        &mut self,
+        object: ObjectId,
+        amount: u32,
+        progression: P,
+    ) -> GrantExperience<P>
+    where
+        P: LevelProgression;
+}
+
+impl ExperienceCommandsExt for GameCommands {
+    /// Grants the given object experience, leveling it up with the given progression. See
+    /// [`GrantExperience`]
+    fn grant_experience<P>(
+        &mut self,
+        object: ObjectId,
+        amount: u32,
+        progression: P,
+    ) -> GrantExperience<P>
+    where
+        P: LevelProgression,
+    {
+        self.queue.push(GrantExperience {
+            object,
+            amount,
+            progression: progression.clone(),
+            previous_experience: None,
+        });
+        GrantExperience {
+            object,
+            amount,
+            progression,
+            previous_experience: None,
+        }
+    }
+}
+
+/// Adds the given amount of experience to the objects [`Experience`]. Fails if the object has no
+/// [`Experience`] component.
+///
+/// Each time the current experience reaches `to_next` the object levels up - the experience needed is
+/// taken from the current experience, the level is increased, [`LevelProgression::level_up`] is
+/// called, and `to_next` is set from [`LevelProgression::experience_to_next`]. An object can gain
+/// several levels from one grant.
+///
+/// Rollback calls [`LevelProgression::rollback_level_up`] for every level gained, highest first, and
+/// restores the objects previous [`Experience`].
+#[derive(Clone, Debug, Reflect)]
+pub struct GrantExperience<P>
+where
+    P: LevelProgression,
+{
+    pub object: ObjectId,
+    pub amount: u32,
+    pub progression: P,
+    #[reflect(ignore)]
+    previous_experience: Option<Experience>,
+}
+
+impl<P> GameCommand for GrantExperience<P>
+where
+    P: LevelProgression,
+{
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let entity = object_entity(world, self.object)?;
+        let Some(previous_experience) = world.get::<Experience>(entity).copied() else {
+            return Err(format!(
+                "No Experience component found for ObjectId: {:?}",
+                self.object
+            ));
+        };
+
+        let mut experience = previous_experience;
+        experience.current = experience.current.saturating_add(self.amount);
+        while experience.to_next > 0 && experience.current >= experience.to_next {
+            experience.current -= experience.to_next;
+            experience.level += 1;
+            if let Err(error) = self
+                .progression
+                .level_up(self.object, experience.level, world)
+            {
+                // Undo the levels already gained so a failed grant leaves the object untouched
+                for level in (previous_experience.level + 1..experience.level).rev() {
+                    let _ = self
+                        .progression
+                        .rollback_level_up(self.object, level, world);
+                }
+                return Err(error);
+            }
+            experience.to_next = self.progression.experience_to_next(experience.level);
+        }
+
+        world
+            .entity_mut(entity)
+            .insert((experience, Changed::default()));
+        self.previous_experience = Some(previous_experience);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(previous_experience) = self.previous_experience.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let entity = object_entity(world, self.object)?;
+        let Some(experience) = world.get::<Experience>(entity).copied() else {
+            return Err(format!(
+                "No Experience component found for ObjectId: {:?}",
+                self.object
+            ));
+        };
+
+        for level in (previous_experience.level + 1..=experience.level).rev() {
+            self.progression
+                .rollback_level_up(self.object, level, world)?;
+        }
+
+        world
+            .entity_mut(entity)
+            .insert((previous_experience, Changed::default()));
+        Ok(())
+    }
+}
@@ -71,7 +71,8 @@ fn test_component_change_tracking() {
         turn_schedule: Default::default(),
     });
     game.register_component::<TestComponent>();
-    game.build(&mut world);
+    game.add_player(false);
+    game.build(&mut world).unwrap();
 
     let mut game = world.remove_resource::<Game>().unwrap();
     let mut game_runtime = world
@@ -152,7 +153,8 @@ fn test_resource_change_tracking() {
         turn_schedule: Default::default(),
     });
     game.register_resource::<TestResource>();
-    game.build(&mut world);
+    game.add_player(false);
+    game.build(&mut world).unwrap();
 
     let mut game = world.remove_resource::<Game>().unwrap();
     let mut game_runtime = world
@@ -56,20 +56,32 @@
 //!
 //! ```
 
-use crate::game_core::state::DespawnedObjects;
+use crate::combat::ObjectAttacked;
+use crate::game_core::saving::{
+    BinaryComponentId, ComponentBinaryState, GameSerDeRegistry, SaveId,
+};
+use crate::game_core::state::{DespawnedObjects, PendingReveal};
 use crate::game_core::{Game, ObjectIdProvider};
-use crate::mapping::tiles::{ObjectStackingClass, TileObjectStacks, TileObjects};
+use crate::mapping::tiles::{
+    IgnoresStacking, ObjectStackingClass, TileContents, TileObjectStacks, TileObjects,
+};
 use crate::mapping::MapId;
-use crate::object::{Object, ObjectGridPosition, ObjectId};
-use crate::player::PlayerMarker;
+use crate::movement::{MoveError, ObjectMoved, PartialMove};
+use crate::object::{
+    index_object_tags, unindex_object_tags, CarriedByParent, Object, ObjectDespawned,
+    ObjectGridPosition, ObjectId, ObjectInfo, ObjectSpawned, ObjectType, TagIndex, Tags,
+};
+use crate::player::{player_entity, Player, PlayerList, PlayerMarker};
 use bevy::ecs::system::SystemState;
 use bevy::log::info;
 use bevy::prelude::{
-    Bundle, DespawnRecursiveExt, Entity, Mut, Query, Reflect, Resource, With, Without, World,
+    AppTypeRegistry, BuildWorldChildren, Bundle, DespawnRecursiveExt, Entity, Mut, Parent, Query,
+    Reflect, ReflectComponent, Resource, With, World,
 };
 use bevy_ecs_tilemap::tiles::{TilePos, TileStorage};
 use chrono::{DateTime, Utc};
 use std::fmt::Debug;
+use std::sync::Arc;
 
 /// Executes all stored game commands by calling the command queue execute buffer function
 pub fn execute_game_commands_buffer(world: &mut World) {
@@ -85,7 +97,11 @@ pub fn execute_game_rollbacks_buffer(world: &mut World) {
     world.resource_scope(|world, mut game: Mut<GameCommands>| {
         while game.history.rollbacks != 0 {
             if let Some(mut command) = game.history.pop() {
-                command.command.rollback(world).expect("Rollback failed");
+                let result = command.command.rollback(world);
+                for observer in game.observers.iter() {
+                    observer.on_rolledback(command.command.as_ref(), &result);
+                }
+                result.expect("Rollback failed");
                 game.history.rolledback_history.push(command);
                 info!("Rollbacked command");
             }
@@ -99,7 +115,11 @@ pub fn execute_game_rollforward_buffer(world: &mut World) {
     world.resource_scope(|world, mut game: Mut<GameCommands>| {
         while game.history.rollforwards != 0 {
             if let Some(mut command) = game.history.rolledback_history.pop() {
-                if let Ok(_) = command.command.execute(world) {
+                let result = command.command.execute(world);
+                for observer in game.observers.iter() {
+                    observer.on_executed(command.command.as_ref(), &result);
+                }
+                if let Ok(_) = result {
                     game.history.push(command.clone());
                 } else {
                     info!("Rolledforward failed");
@@ -119,6 +139,8 @@ pub enum CommandType {
 pub struct GameCommandMeta {
     pub command: Box<dyn GameCommand>,
     pub command_time: DateTime<Utc>,
+    /// Commands with a higher priority are executed first. See [`GameCommandQueue`]
+    pub priority: i32,
     //command_type: CommandType,
 }
 
@@ -157,6 +179,27 @@ pub trait GameCommand: Send + GameCommandClone + Sync + Reflect + 'static {
     fn rollback(&mut self, _world: &mut World) -> Result<(), String> {
         Ok(())
     }
+
+    /// The name of the command, used when inspecting the command history. Defaults to the commands
+    /// type name from its [`Reflect`] implementation
+    fn name(&self) -> &str {
+        self.type_name()
+    }
+}
+
+/// Observes every [`GameCommand`] that [`GameCommands`] executes or rolls back, eg for analytics,
+/// logging, or driving a UI log, without having to modify each command. Add observers with
+/// [`GameCommands::add_observer`].
+///
+/// Observers are called after the command has run, with the result it returned, and can't modify the
+/// command or the world
+pub trait CommandObserver: Send + Sync + 'static {
+    /// Called after a command is executed, including when it is executed by a rollforward or as an
+    /// order in a [`ResolutionPhase`]
+    fn on_executed(&self, command: &dyn GameCommand, result: &Result<(), String>);
+
+    /// Called after a command is rolled back. Does nothing by default
+    fn on_rolledback(&self, _command: &dyn GameCommand, _result: &Result<(), String>) {}
 }
 
 /* TODO: Figure out if a closure is possible. Probably not since we have two functions, but either way
@@ -196,6 +239,12 @@ where
 }
 
 /// The queue of pending [`GameCommand`]s. Doesn't do anything until executed
+///
+/// When executed, commands are ordered by their priority first, highest first, and then by the order
+/// they were pushed in. The `command_time` of a command is only a record of when it was queued and is
+/// never used for ordering within the queue, so a command with a higher priority always executes
+/// before one with a lower priority even if it was queued later. Commands pushed with
+/// [`push`](Self::push) have a priority of 0.
 #[derive(Default)]
 pub struct GameCommandQueue {
     pub queue: Vec<GameCommandMeta>,
@@ -204,6 +253,15 @@ pub struct GameCommandQueue {
 impl GameCommandQueue {
     /// Push a new command to the end of the queue
     pub fn push<C>(&mut self, command: C)
+    where
+        C: GameCommand,
+    {
+        self.push_with_priority(command, 0);
+    }
+
+    /// Push a new command to the end of the queue with the given priority. Commands with a higher
+    /// priority are executed before those with a lower priority
+    pub fn push_with_priority<C>(&mut self, command: C, priority: i32)
     where
         C: GameCommand,
     {
@@ -211,14 +269,53 @@ impl GameCommandQueue {
         let command_meta = GameCommandMeta {
             command: Box::from(command),
             command_time: utc,
+            priority,
         };
         self.queue.push(command_meta);
     }
 
+    /// Sorts the queue into execution order - highest priority first and then in the order the
+    /// commands were pushed
+    pub fn sort_by_priority(&mut self) {
+        // sort_by_key is stable so insertion order is kept within a priority
+        self.queue
+            .sort_by_key(|command| std::cmp::Reverse(command.priority));
+    }
+
     /// Take the last command in the queue. Returns None if queue is empty
     pub fn pop(&mut self) -> Option<GameCommandMeta> {
         self.queue.pop()
     }
+
+    /// Returns an iterator over the pending commands in the order they were pushed. Use
+    /// [`GameCommand::name`] to display them
+    pub fn iter(&self) -> impl Iterator<Item = &GameCommandMeta> {
+        self.queue.iter()
+    }
+
+    /// Returns the number of pending commands
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns true if there are no pending commands
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Removes and returns the pending command at the given index, cancelling it. Returns None if the
+    /// index is out of bounds
+    pub fn remove(&mut self, index: usize) -> Option<GameCommandMeta> {
+        if index >= self.queue.len() {
+            return None;
+        }
+        Some(self.queue.remove(index))
+    }
+
+    /// Cancels every pending command
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
 }
 
 /// The history of all commands sent for this [`Game`] instance - if a command rollback occurs the
@@ -243,6 +340,24 @@ impl GameCommandsHistory {
         self.history.pop()
     }
 
+    /// Returns the time and name of every command in the history, oldest first. Use this to debug
+    /// the history, eg to print it when a rollback fails
+    pub fn dump(&self) -> Vec<(DateTime<Utc>, String)> {
+        self.history
+            .iter()
+            .map(|command| (command.command_time, String::from(command.command.name())))
+            .collect()
+    }
+
+    /// Returns the time and name of every command that has been rolled back and not yet rolled
+    /// forward. The last entry is the next command to be rolled forward
+    pub fn dump_rolledback(&self) -> Vec<(DateTime<Utc>, String)> {
+        self.rolledback_history
+            .iter()
+            .map(|command| (command.command_time, String::from(command.command.name())))
+            .collect()
+    }
+
     /// Push a command to the end of the history vec
     pub fn push_rollback_history(&mut self, command: GameCommandMeta) {
         self.rolledback_history.push(command);
@@ -258,12 +373,92 @@ impl GameCommandsHistory {
     }
 }
 
+/// An order submitted by a player for one of their objects during a simultaneous turn. See
+/// [`ResolutionPhase`]
+#[derive(Clone)]
+pub struct SubmittedOrder {
+    pub player_id: usize,
+    pub object_id: ObjectId,
+    pub command: GameCommandMeta,
+}
+
+/// Collects the orders every player submits during a simultaneous resolution (WeGo) turn so that they
+/// can be resolved together with [`GameCommands::resolve_orders`].
+///
+/// Orders are resolved in a deterministic order - by their `command_time`, then by their
+/// [`ObjectId`], and then by the id of the player that submitted them - so every client resolving the
+/// same orders ends in the same state. Orders are executed against the world as it is at that point
+/// in the resolution, so an order that conflicts with an earlier one, eg a move into a tile that an
+/// earlier move filled, fails without affecting the other orders.
+#[derive(Default, Clone)]
+pub struct ResolutionPhase {
+    pub orders: Vec<SubmittedOrder>,
+}
+
+impl ResolutionPhase {
+    /// Submits an order for the given object using the current time as its `command_time`
+    pub fn submit<C>(&mut self, player_id: usize, object_id: ObjectId, command: C)
+    where
+        C: GameCommand,
+    {
+        self.push(SubmittedOrder {
+            player_id,
+            object_id,
+            command: GameCommandMeta {
+                command: Box::from(command),
+                command_time: Utc::now(),
+                priority: 0,
+            },
+        });
+    }
+
+    /// Pushes an already timestamped order, eg one received from another client
+    pub fn push(&mut self, order: SubmittedOrder) {
+        self.orders.push(order);
+    }
+
+    /// Sorts the orders into resolution order. See [`ResolutionPhase`]
+    pub fn sort(&mut self) {
+        self.orders.sort_by(|a, b| {
+            a.command
+                .command_time
+                .cmp(&b.command.command_time)
+                .then(a.object_id.id.cmp(&b.object_id.id))
+                .then(a.player_id.cmp(&b.player_id))
+        });
+    }
+
+    /// Returns true if the given player has submitted at least one order
+    pub fn has_submitted(&self, player_id: usize) -> bool {
+        self.orders.iter().any(|order| order.player_id == player_id)
+    }
+
+    /// Returns the number of submitted orders
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Returns true if there are no submitted orders
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Cancels every submitted order
+    pub fn clear(&mut self) {
+        self.orders.clear();
+    }
+}
+
 /// A struct to hold, execute, and rollback [`GameCommand`]s. Use associated actions to access and
 /// modify the game
 #[derive(Default, Resource)]
 pub struct GameCommands {
     pub queue: GameCommandQueue,
     pub history: GameCommandsHistory,
+    /// Orders submitted for the current simultaneous turn. See [`ResolutionPhase`]
+    pub resolution: ResolutionPhase,
+    /// Observers called after every command is executed or rolled back. See [`CommandObserver`]
+    pub observers: Vec<Box<dyn CommandObserver>>,
 }
 
 impl GameCommands {
@@ -271,14 +466,32 @@ impl GameCommands {
         GameCommands {
             queue: Default::default(),
             history: Default::default(),
+            resolution: Default::default(),
+            observers: vec![],
         }
     }
 
+    /// Adds an observer that is called after every command is executed or rolled back. See
+    /// [`CommandObserver`]
+    pub fn add_observer<O>(&mut self, observer: O)
+    where
+        O: CommandObserver,
+    {
+        self.observers.push(Box::new(observer));
+    }
+
     /// Drains the command buffer and attempts to execute each command. Will only push commands that
     /// succeed to the history. If commands dont succeed they are silently failed.
+    ///
+    /// Commands are executed in priority order, see [`GameCommandQueue`]
     pub fn execute_buffer(&mut self, world: &mut World) {
+        self.queue.sort_by_priority();
         for mut command in self.queue.queue.drain(..).into_iter() {
-            match command.command.execute(world) {
+            let result = command.command.execute(world);
+            for observer in self.observers.iter() {
+                observer.on_executed(command.command.as_ref(), &result);
+            }
+            match result {
                 Ok(_) => {
                     self.history.push(command);
                 }
@@ -290,6 +503,44 @@ impl GameCommands {
         }
     }
 
+    /// Submits an order from the given player for the given object to the current simultaneous turn.
+    /// The order isn't executed until [`resolve_orders`](Self::resolve_orders) is called. See
+    /// [`ResolutionPhase`]
+    pub fn submit_order<T>(&mut self, player_id: usize, object_id: ObjectId, command: T) -> T
+    where
+        T: GameCommand + Clone,
+    {
+        self.resolution
+            .submit(player_id, object_id, command.clone());
+        command
+    }
+
+    /// Resolves every submitted order in the deterministic order described in [`ResolutionPhase`].
+    /// Orders that succeed are pushed to the history the same as commands executed by
+    /// [`execute_buffer`](Self::execute_buffer). Orders that fail are skipped and returned along with
+    /// their errors so that the game can inform the players
+    pub fn resolve_orders(&mut self, world: &mut World) -> Vec<(SubmittedOrder, String)> {
+        self.resolution.sort();
+        let mut failed = vec![];
+        for mut order in self.resolution.orders.drain(..) {
+            let result = order.command.command.execute(world);
+            for observer in self.observers.iter() {
+                observer.on_executed(order.command.command.as_ref(), &result);
+            }
+            match result {
+                Ok(_) => {
+                    self.history.push(order.command);
+                }
+                Err(error) => {
+                    info!("order resolution failed with: {:?}", error);
+                    failed.push((order, error));
+                }
+            }
+            self.history.clear_rollback_history();
+        }
+        failed
+    }
+
     /// Request a single rollback - The game will attempt to rollback the next time
     /// [`execute_game_rollbacks_buffer`] is called
     pub fn rollback_one(&mut self) {
@@ -315,6 +566,16 @@ impl GameCommands {
         command
     }
 
+    /// Add a custom command to the queue with the given priority. Commands with a higher priority
+    /// are executed first when the buffer is executed
+    pub fn add_with_priority<T>(&mut self, command: T, priority: i32) -> T
+    where
+        T: GameCommand + Clone,
+    {
+        self.queue.push_with_priority(command.clone(), priority);
+        command
+    }
+
     /// Adds the given entity to the given tile if the tile exists and the entity has the required components.
     /// Will silently fail if either of the above are invalid.
     /// Rollback will *not* set the objects grid position
@@ -331,11 +592,36 @@ impl GameCommands {
             object_game_id: object_entity,
             on_map,
             tile_pos,
+            strict: false,
+        });
+        AddObjectToTile {
+            object_game_id: object_entity,
+            on_map,
+            tile_pos,
+            strict: false,
+        }
+    }
+
+    /// Adds the given entity to the given tile the same as [`GameCommands::add_object_to_tile`]
+    /// except that it checks the tiles [`TileObjectStacks`] first and fails with a TileFull error
+    /// rather than overfilling the tile
+    pub fn add_object_to_tile_strict(
+        &mut self,
+        object_entity: ObjectId,
+        on_map: MapId,
+        tile_pos: TilePos,
+    ) -> AddObjectToTile {
+        self.queue.push(AddObjectToTile {
+            object_game_id: object_entity,
+            on_map,
+            tile_pos,
+            strict: true,
         });
         AddObjectToTile {
             object_game_id: object_entity,
             on_map,
             tile_pos,
+            strict: true,
         }
     }
 
@@ -374,17 +660,133 @@ impl GameCommands {
             bundle: bundle.clone(),
             tile_pos: tile_pos,
             on_map,
-            player_team,
+            player_team: Some(player_team),
             object_game_id: None,
         });
         SpawnObject {
             bundle,
             tile_pos: tile_pos,
             on_map,
-            player_team,
+            player_team: Some(player_team),
+            object_game_id: None,
+        }
+    }
+
+    /// Spawns a neutral object that isn't owned by any player - it is spawned without a
+    /// [`PlayerMarker`]. See [`SpawnObject`]
+    pub fn spawn_neutral_object<T>(
+        &mut self,
+        bundle: T,
+        tile_pos: TilePos,
+        on_map: MapId,
+    ) -> SpawnObject<T>
+    where
+        T: Bundle + Clone + Reflect,
+    {
+        self.queue.push(SpawnObject {
+            bundle: bundle.clone(),
+            tile_pos,
+            on_map,
+            player_team: None,
             object_game_id: None,
+        });
+        SpawnObject {
+            bundle,
+            tile_pos,
+            on_map,
+            player_team: None,
+            object_game_id: None,
+        }
+    }
+
+    /// Reveals an existing object to the given player without respawning it. See [`RevealObject`]
+    pub fn reveal_object(&mut self, object_id: ObjectId, player_id: usize) -> RevealObject {
+        self.queue.push(RevealObject {
+            object_id,
+            player_id,
+            previous_state: None,
+        });
+        RevealObject {
+            object_id,
+            player_id,
+            previous_state: None,
+        }
+    }
+
+    /// Sets the owner of the given object, or makes it neutral if `player_id` is None. See
+    /// [`SetObjectOwner`]
+    pub fn set_object_owner(
+        &mut self,
+        object_id: ObjectId,
+        player_id: Option<usize>,
+    ) -> SetObjectOwner {
+        self.queue.push(SetObjectOwner {
+            object_id,
+            player_id,
+            previous_owner: None,
+        });
+        SetObjectOwner {
+            object_id,
+            player_id,
+            previous_owner: None,
+        }
+    }
+
+    /// Adds a new player to the running game. See [`AddPlayer`]
+    pub fn add_player(&mut self, needs_state: bool) -> AddPlayer {
+        self.queue.push(AddPlayer {
+            needs_state,
+            player_id: None,
+        });
+        AddPlayer {
+            needs_state,
+            player_id: None,
+        }
+    }
+
+    /// Modifies the given resource with the given function. See [`ModifyResource`]
+    pub fn modify_resource<R>(
+        &mut self,
+        modify: impl Fn(&mut R) + Send + Sync + 'static,
+    ) -> ModifyResource<R>
+    where
+        R: Resource + Clone,
+    {
+        let command = ModifyResource::new(modify);
+        self.queue.push(command.clone());
+        command
+    }
+
+    /// Adds the label to the given objects [`Tags`]. See [`AddTag`]
+    pub fn add_tag(&mut self, object_id: ObjectId, label: impl Into<String>) -> AddTag {
+        let label = label.into();
+        self.queue.push(AddTag {
+            object_id,
+            label: label.clone(),
+            added: None,
+        });
+        AddTag {
+            object_id,
+            label,
+            added: None,
+        }
+    }
+
+    /// Removes the label from the given objects [`Tags`]. See [`RemoveTag`]
+    pub fn remove_tag(&mut self, object_id: ObjectId, label: impl Into<String>) -> RemoveTag {
+        let label = label.into();
+        self.queue.push(RemoveTag {
+            object_id,
+            label: label.clone(),
+            removed: None,
+        });
+        RemoveTag {
+            object_id,
+            label,
+            removed: None,
         }
     }
+
     pub fn despawn_object(&mut self, on_map: MapId, object_game_id: ObjectId) -> DespawnObject {
         self.queue.push(DespawnObject {
             on_map,
@@ -397,10 +799,149 @@ impl GameCommands {
             tile_pos: None,
         }
     }
+
+    /// Despawns every object belonging to the given player on the given map. See [`DespawnPlayerObjects`]
+    pub fn despawn_player_objects(
+        &mut self,
+        player_id: usize,
+        on_map: MapId,
+    ) -> DespawnPlayerObjects {
+        self.queue.push(DespawnPlayerObjects {
+            player_id,
+            on_map,
+            despawned_objects: vec![],
+        });
+        DespawnPlayerObjects {
+            player_id,
+            on_map,
+            despawned_objects: vec![],
+        }
+    }
+
+    /// Swaps the tiles of the two given objects. See [`SwapObjects`]
+    pub fn swap_objects(&mut self, a: ObjectId, b: ObjectId) -> SwapObjects {
+        self.queue.push(SwapObjects {
+            a,
+            b,
+            on_map: None,
+            a_tile_pos: None,
+            b_tile_pos: None,
+            a_carried: vec![],
+            b_carried: vec![],
+        });
+        SwapObjects {
+            a,
+            b,
+            on_map: None,
+            a_tile_pos: None,
+            b_tile_pos: None,
+            a_carried: vec![],
+            b_carried: vec![],
+        }
+    }
+
+    /// Clears the action markers of every object for a new round. See [`ResetActionsAll`]
+    pub fn reset_actions_all(&mut self) -> ResetActionsAll {
+        self.queue.push(ResetActionsAll { reset: vec![] });
+        ResetActionsAll { reset: vec![] }
+    }
+
+    /// Clears the action markers of every object owned by the given player. See
+    /// [`ResetActionsForPlayer`]
+    pub fn reset_actions_for_player(&mut self, player_id: usize) -> ResetActionsForPlayer {
+        self.queue.push(ResetActionsForPlayer {
+            player_id,
+            reset: vec![],
+        });
+        ResetActionsForPlayer {
+            player_id,
+            reset: vec![],
+        }
+    }
+
+    /// Attaches the child object to the parent object. See [`AttachObject`]
+    pub fn attach_object(
+        &mut self,
+        child: ObjectId,
+        parent: ObjectId,
+        carried: bool,
+    ) -> AttachObject {
+        self.queue.push(AttachObject {
+            child,
+            parent,
+            carried,
+            previous_hierarchy: None,
+        });
+        AttachObject {
+            child,
+            parent,
+            carried,
+            previous_hierarchy: None,
+        }
+    }
+
+    /// Detaches the child object from its parent. See [`DetachObject`]
+    pub fn detach_object(&mut self, child: ObjectId) -> DetachObject {
+        self.queue.push(DetachObject {
+            child,
+            previous_hierarchy: None,
+        });
+        DetachObject {
+            child,
+            previous_hierarchy: None,
+        }
+    }
+
+    /// Inserts the given reflected component onto the given object, replacing it if the object
+    /// already has one. See [`SetComponent`]
+    pub fn set_component(
+        &mut self,
+        object_id: ObjectId,
+        component: Box<dyn Reflect>,
+    ) -> SetComponent {
+        self.queue.push(SetComponent {
+            object_id,
+            component: component.clone_value(),
+            previous_component: None,
+        });
+        SetComponent {
+            object_id,
+            component,
+            previous_component: None,
+        }
+    }
+
+    /// Removes the component with the given type name from the given object. See [`RemoveComponent`]
+    pub fn remove_component(&mut self, object_id: ObjectId, type_name: String) -> RemoveComponent {
+        self.queue.push(RemoveComponent {
+            object_id,
+            type_name: type_name.clone(),
+            removed_component: None,
+        });
+        RemoveComponent {
+            object_id,
+            type_name,
+            removed_component: None,
+        }
+    }
+
+    /// Changes the given objects [`ObjectType`] and replaces the given reflected components on it.
+    /// See [`TransformObject`]
+    pub fn transform_object(
+        &mut self,
+        object_id: ObjectId,
+        new_type: ObjectType,
+        components: Vec<Box<dyn Reflect>>,
+    ) -> TransformObject {
+        let transform_object = TransformObject::new(object_id, new_type, components);
+        self.queue.push(transform_object.clone());
+        transform_object
+    }
 }
 
 /// Removes the given entity from the given tile if the tile exists and the entity has the required components.
-/// Will silently fail if either of the above are invalid.
+/// Fails if either of the above are invalid or if the object isn't in the tile. The tiles [`TileObjects`]
+/// and [`TileObjectStacks`] are updated together through its [`TileContents`].
 /// Execute will *not* set the objects grid position - Rollback will.
 /// This should be used with [AddObjectToTile] command to enable true reversing as needed. Look
 ///  at [SpawnObject] as an example of how to do this.
@@ -412,95 +953,24 @@ pub struct RemoveObjectFromTile {
 }
 
 impl GameCommand for RemoveObjectFromTile {
-    fn execute(&mut self, mut world: &mut World) -> Result<(), String> {
-        let mut system_state: SystemState<(
-            Query<(Entity, &ObjectId, &ObjectStackingClass)>,
-            Query<(&mut TileObjectStacks, &mut TileObjects)>,
-            Query<(&MapId, &TileStorage)>,
-        )> = SystemState::new(&mut world);
-        let (mut object_query, mut tile_query, mut tile_storage_query) =
-            system_state.get_mut(&mut world);
-
-        let Some((entity, _, object_stacking_class)) = object_query
-            .iter_mut()
-            .find(|(_, id, _)| id == &&self.object_game_id)
-        else {
-            return Err(String::from("No object components found"));
-        };
-        let Some((_, tile_storage)) = tile_storage_query
-            .iter_mut()
-            .find(|(id, _)| id == &&self.on_map)
-        else {
-            return Err(String::from("No tile components found"));
-        };
-
-        let tile_entity = tile_storage.get(&self.tile_pos.into()).unwrap();
-        let Ok((mut tile_stack_rules, mut tile_objects)) = tile_query.get_mut(tile_entity) else {
-            return Err(String::from("No tile stack rules found"));
-        };
-
-        tile_objects.remove_object(self.object_game_id);
-        tile_stack_rules.decrement_object_class_count(object_stacking_class);
-
-        world
-            .entity_mut(tile_entity)
-            .insert(crate::game_core::state::Changed::default());
-        world
-            .entity_mut(entity)
-            .insert(crate::game_core::state::Changed::default());
-        return Ok(());
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        remove_from_tile(world, self.object_game_id, self.on_map, self.tile_pos)
     }
 
-    fn rollback(&mut self, mut world: &mut World) -> Result<(), String> {
-        let mut system_state: SystemState<(
-            Query<(
-                Entity,
-                &ObjectId,
-                &mut ObjectGridPosition,
-                &ObjectStackingClass,
-            )>,
-            Query<(&mut TileObjectStacks, &mut TileObjects)>,
-            Query<(&MapId, &TileStorage)>,
-        )> = SystemState::new(&mut world);
-
-        let (mut object_query, mut tile_query, mut tile_storage_query) =
-            system_state.get_mut(&mut world);
-
-        let Some((entity, _, mut object_grid_position, object_stacking_class)) = object_query
-            .iter_mut()
-            .find(|(_, id, _, _)| id == &&self.object_game_id)
-        else {
-            return Err(String::from("No object components found"));
-        };
-        let Some((_, tile_storage)) = tile_storage_query
-            .iter_mut()
-            .find(|(id, _)| id == &&self.on_map)
-        else {
-            return Err(String::from("No tile components found found"));
-        };
-
-        let tile_entity = tile_storage.get(&self.tile_pos.into()).unwrap();
-
-        let Ok((mut tile_stack_rules, mut tile_objects)) = tile_query.get_mut(tile_entity) else {
-            return Err(String::from("No tile stack rules found"));
-        };
-
-        tile_objects.add_object(self.object_game_id);
-        object_grid_position.tile_position = self.tile_pos.into();
-        tile_stack_rules.increment_object_class_count(object_stacking_class);
-
-        world
-            .entity_mut(tile_entity)
-            .insert(crate::game_core::state::Changed::default());
-        world
-            .entity_mut(entity)
-            .insert(crate::game_core::state::Changed::default());
-        Ok(())
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        add_to_tile(
+            world,
+            self.object_game_id,
+            self.on_map,
+            self.tile_pos,
+            false,
+        )
     }
 }
 
 /// Adds the given entity to the given tile if the tile exists and the entity has the required components.
-/// Will silently fail if either of the above are invalid.
+/// Fails if either of the above are invalid or if the object is already in the tile. The tiles
+/// [`TileObjects`] and [`TileObjectStacks`] are updated together through its [`TileContents`].
 /// Rollback will *not* set the objects grid position or change the position of the objects transform
 /// This should be used with [RemoveObjectFromTile] command to enable true reversing as needed. Look
 /// at [SpawnObject] as an example of how to do this.
@@ -509,108 +979,119 @@ pub struct AddObjectToTile {
     pub object_game_id: ObjectId,
     pub on_map: MapId,
     pub tile_pos: TilePos,
+    /// If true the command checks that the tile has space for the objects [`ObjectStackingClass`]
+    /// and fails rather than overfilling the tile. If false the object is always added, which is
+    /// used internally by commands like [`MoveObject`](crate::movement::MoveObject) that have already validated the move
+    pub strict: bool,
 }
 
 impl GameCommand for AddObjectToTile {
-    fn execute(&mut self, mut world: &mut World) -> Result<(), String> {
-        let mut system_state: SystemState<(
-            Query<
-                (
-                    Entity,
-                    &ObjectId,
-                    &mut ObjectGridPosition,
-                    &ObjectStackingClass,
-                ),
-                With<Object>,
-            >,
-            Query<(&mut TileObjectStacks, &mut TileObjects)>,
-            Query<(Entity, &MapId, &TileStorage, Without<Object>)>,
-        )> = SystemState::new(&mut world);
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        add_to_tile(
+            world,
+            self.object_game_id,
+            self.on_map,
+            self.tile_pos,
+            self.strict,
+        )
+    }
 
-        let (mut object_query, mut tile_query, mut tile_storage_query) =
-            system_state.get_mut(&mut world);
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        remove_from_tile(world, self.object_game_id, self.on_map, self.tile_pos)
+    }
+}
 
-        let Some((_entity, _, mut object_grid_position, object_stacking_class)) = object_query
-            .iter_mut()
-            .find(|(_, id, _, _)| id == &&self.object_game_id)
-        else {
-            return Err(String::from(format!(
-                "No Object Components found for ObjectId: {:?}",
-                self.object_game_id
-            )));
-        };
-        let Some((entity, _, tile_storage, _)) = tile_storage_query
-            .iter_mut()
-            .find(|(_, id, _, _)| id == &&self.on_map)
-        else {
-            return Err(String::from(format!(
-                "No Map Components found for ObjectId: {:?}",
-                self.on_map
-            )));
-        };
-
-        let tile_entity = tile_storage.get(&self.tile_pos.into()).unwrap();
-
-        let Ok((mut tile_stack_rules, mut tile_objects)) = tile_query.get_mut(tile_entity) else {
-            return Err(String::from("No tile components found"));
-        };
-
-        tile_objects.add_object(self.object_game_id);
-        object_grid_position.tile_position = self.tile_pos.into();
-        tile_stack_rules.increment_object_class_count(object_stacking_class);
-
-        world
-            .entity_mut(tile_entity)
-            .insert(crate::game_core::state::Changed::default());
-        world
-            .entity_mut(entity)
-            .insert(crate::game_core::state::Changed::default());
+/// Returns the entity, [`ObjectStackingClass`], and whether the object has [`IgnoresStacking`] for
+/// the object with the given id
+fn object_stacking(
+    world: &mut World,
+    object_id: ObjectId,
+) -> Result<(Entity, ObjectStackingClass, bool), String> {
+    let mut object_query = world.query::<(
+        Entity,
+        &ObjectId,
+        &ObjectStackingClass,
+        Option<&IgnoresStacking>,
+    )>();
+    object_query
+        .iter(world)
+        .find(|(_, id, _, _)| id == &&object_id)
+        .map(|(entity, _, object_stacking_class, ignores_stacking)| {
+            (
+                entity,
+                object_stacking_class.clone(),
+                ignores_stacking.is_some(),
+            )
+        })
+        .ok_or(format!(
+            "No object components found for ObjectId: {:?}",
+            object_id
+        ))
+}
 
-        Ok(())
+/// Adds the object to the tile through the tiles [`TileContents`] and sets the objects
+/// [`ObjectGridPosition`] to the tile. Both the tile and the object get
+/// [`Changed`](crate::game_core::state::Changed) inserted
+fn add_to_tile(
+    world: &mut World,
+    object_id: ObjectId,
+    on_map: MapId,
+    tile_pos: TilePos,
+    strict: bool,
+) -> Result<(), String> {
+    let (entity, object_stacking_class, ignores_stacking) = object_stacking(world, object_id)?;
+    let tile_entity = tile_entity(world, on_map, tile_pos)?;
+    let Some(mut tile_contents) = TileContents::get(world, tile_entity) else {
+        return Err(String::from("No tile components found"));
+    };
+    tile_contents
+        .add_object(object_id, &object_stacking_class, ignores_stacking, strict)
+        .map_err(|error| format!("{} at TilePos: {:?}", error, tile_pos))?;
+
+    if let Some(mut object_grid_position) = world.get_mut::<ObjectGridPosition>(entity) {
+        object_grid_position.tile_position = tile_pos.into();
     }
+    world
+        .entity_mut(tile_entity)
+        .insert(crate::game_core::state::Changed::default());
+    world
+        .entity_mut(entity)
+        .insert(crate::game_core::state::Changed::default());
+    Ok(())
+}
 
-    fn rollback(&mut self, mut world: &mut World) -> Result<(), String> {
-        let mut system_state: SystemState<(
-            Query<(Entity, &ObjectId, &ObjectStackingClass)>,
-            Query<(&mut TileObjectStacks, &mut TileObjects)>,
-            Query<(&MapId, &TileStorage)>,
-        )> = SystemState::new(&mut world);
-
-        let (mut object_query, mut tile_query, mut tile_storage_query) =
-            system_state.get_mut(&mut world);
-
-        let Some((entity, _, object_stacking_class)) = object_query
-            .iter_mut()
-            .find(|(_, id, _)| id == &&self.object_game_id)
-        else {
-            return Err(String::from("No object components found found"));
-        };
-        let Some((_, tile_storage)) = tile_storage_query
-            .iter_mut()
-            .find(|(id, _)| id == &&self.on_map)
-        else {
-            return Err(String::from("No tile components found"));
-        };
-
-        let tile_entity = tile_storage.get(&self.tile_pos.into()).unwrap();
-
-        let Ok((mut tile_stack_rules, mut tile_objects)) = tile_query.get_mut(tile_entity) else {
-            return Err(String::from("No tile components found"));
-        };
-
-        tile_objects.remove_object(self.object_game_id);
-        tile_stack_rules.decrement_object_class_count(object_stacking_class);
-
-        world
-            .entity_mut(tile_entity)
-            .insert(crate::game_core::state::Changed::default());
-        world
-            .entity_mut(entity)
-            .insert(crate::game_core::state::Changed::default());
-        Ok(())
+/// Removes the object from the tile through the tiles [`TileContents`]. Fails if the object isn't in
+/// the tile. Both the tile and the object get [`Changed`](crate::game_core::state::Changed) inserted
+fn remove_from_tile(
+    world: &mut World,
+    object_id: ObjectId,
+    on_map: MapId,
+    tile_pos: TilePos,
+) -> Result<(), String> {
+    let (entity, object_stacking_class, ignores_stacking) = object_stacking(world, object_id)?;
+    let tile_entity = tile_entity(world, on_map, tile_pos)?;
+    let Some(mut tile_contents) = TileContents::get(world, tile_entity) else {
+        return Err(String::from("No tile components found"));
+    };
+    if !tile_contents.remove_object(object_id, &object_stacking_class, ignores_stacking) {
+        return Err(format!(
+            "ObjectId: {:?} is not in the tile at TilePos: {:?}",
+            object_id, tile_pos
+        ));
     }
+
+    world
+        .entity_mut(tile_entity)
+        .insert(crate::game_core::state::Changed::default());
+    world
+        .entity_mut(entity)
+        .insert(crate::game_core::state::Changed::default());
+    Ok(())
 }
 
+/// Spawns the given bundle as an object on the given tile. The object is given the next [`ObjectId`]
+/// and a [`PlayerMarker`] for `player_team`. If `player_team` is None the object is neutral and is
+/// spawned without a [`PlayerMarker`].
 #[derive(Clone, Debug, Reflect)]
 pub struct SpawnObject<T>
 where
@@ -619,7 +1100,7 @@ where
     pub bundle: T,
     pub tile_pos: TilePos,
     pub on_map: MapId,
-    pub player_team: usize,
+    pub player_team: Option<usize>,
     pub object_game_id: Option<ObjectId>,
 }
 
@@ -628,19 +1109,28 @@ where
     T: Bundle + Clone + Reflect,
 {
     fn execute(&mut self, mut world: &mut World) -> Result<(), String> {
-        // Assign a new id as we un assign the id when we rollback
-        let id = world.resource_mut::<ObjectIdProvider>().next_id_component();
+        // Reuse the id if this spawn is being rolled forward so that later commands referencing the
+        // object still find it. Ids are never handed out twice so it can't collide
+        let mut object_id_provider = world.resource_mut::<ObjectIdProvider>();
+        let id = match self.object_game_id {
+            Some(id) => {
+                object_id_provider.reserve_id(id.id);
+                id
+            }
+            None => object_id_provider.next_id_component(),
+        };
         let entity = world
             .spawn(self.bundle.clone())
-            .insert((
-                id,
-                crate::game_core::state::Changed::default(),
-                PlayerMarker::new(self.player_team),
-            ))
+            .insert((id, crate::game_core::state::Changed::default()))
             .id();
+        if let Some(player_team) = self.player_team {
+            world
+                .entity_mut(entity)
+                .insert(PlayerMarker::new(player_team));
+        }
 
         let mut system_state: SystemState<(
-            Query<&ObjectStackingClass>,
+            Query<(&ObjectStackingClass, Option<&IgnoresStacking>)>,
             Query<&TileObjectStacks>,
             Query<(&MapId, &TileStorage)>,
         )> = SystemState::new(&mut world);
@@ -648,7 +1138,7 @@ where
         let (object_query, mut tile_query, mut tile_storage_query) =
             system_state.get_mut(&mut world);
 
-        let Ok(object_stacking_class) = object_query.get(entity) else {
+        let Ok((object_stacking_class, ignores_stacking)) = object_query.get(entity) else {
             return Err(String::from(
                 "Object does not have required ObjectStackingClass component",
             ));
@@ -660,19 +1150,28 @@ where
         else {
             return Err(String::from("TileStorage not found"));
         };
-        let tile_entity = tile_storage.get(&self.tile_pos).unwrap();
+        let Some(tile_entity) = tile_storage.checked_get(&self.tile_pos) else {
+            return Err(format!("TileNotFound({:?})", self.tile_pos));
+        };
         let Ok(tile_stack_rules) = tile_query.get_mut(tile_entity) else {
             return Err(String::from("No tile components found"));
         };
 
-        return if tile_stack_rules.has_space(&object_stacking_class) {
+        return if ignores_stacking.is_some() || tile_stack_rules.has_space(&object_stacking_class) {
             let mut add = AddObjectToTile {
                 object_game_id: id,
                 on_map: self.on_map,
                 tile_pos: self.tile_pos,
+                strict: false,
             };
             let _ = add.execute(world);
+            index_object_tags(world, entity);
             self.object_game_id = Some(id);
+            world.send_event(ObjectSpawned {
+                object_id: id,
+                tile_pos: self.tile_pos,
+                map_id: self.on_map,
+            });
             Ok(())
         } else {
             Err(format!(
@@ -695,16 +1194,17 @@ where
             return Err(String::from("No object components found"));
         };
 
+        let object_game_id = self
+            .object_game_id
+            .expect("Rollback can only be called after execute which returns an entity id");
         let mut remove = RemoveObjectFromTile {
-            object_game_id: self
-                .object_game_id
-                .expect("Rollback can only be called after execute which returns an entity id"),
+            object_game_id,
             on_map: self.on_map,
             tile_pos: self.tile_pos.into(),
         };
         let _ = remove.execute(world);
         world.entity_mut(entity).despawn_recursive();
-        world.resource_mut::<ObjectIdProvider>().remove_last_id();
+        unindex_object_tags(world, object_game_id);
 
         world
             .resource_mut::<DespawnedObjects>()
@@ -744,6 +1244,10 @@ impl GameCommand for DespawnObject {
         let tile_pos = *tile_pos;
 
         world.despawn(entity);
+        unindex_object_tags(world, self.object_game_id);
+        world.send_event(ObjectDespawned {
+            object_id: self.object_game_id,
+        });
 
         let mut remove = RemoveObjectFromTile {
             object_game_id: self.object_game_id,
@@ -784,8 +1288,1922 @@ impl GameCommand for DespawnObject {
         };
         let _ = remove.execute(world);
         world.entity_mut(entity).despawn_recursive();
-        world.resource_mut::<ObjectIdProvider>().remove_last_id();
 
         return Ok(());
     }
 }
+
+/// Despawns every object on the given map with a [`PlayerMarker`] matching the given player id,
+/// removing each from its tile the same as [`DespawnObject`]. Used when a player is eliminated or
+/// surrenders so that all of their objects are removed in a single history entry.
+///
+/// Every component with a [`SaveId`] is recorded before despawning so that rollback can respawn the
+/// objects using the [`GameSerDeRegistry`], adding their [`Tags`](crate::object::Tags) back to the
+/// [`TagIndex`](crate::object::TagIndex). Components without a [`SaveId`] are not restored.
+#[derive(Clone, Debug, Reflect)]
+pub struct DespawnPlayerObjects {
+    pub player_id: usize,
+    pub on_map: MapId,
+    #[reflect(ignore)]
+    despawned_objects: Vec<RecordedObject>,
+}
+
+impl GameCommand for DespawnPlayerObjects {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<(
+            Query<(&MapId, &TileStorage)>,
+            Query<&TileObjects>,
+            Query<(Entity, &ObjectId, &ObjectGridPosition, &PlayerMarker)>,
+        )> = SystemState::new(world);
+        let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+        let Some((_, tile_storage)) = tile_storage_query
+            .iter()
+            .find(|(id, _)| id == &&self.on_map)
+        else {
+            return Err(String::from("No tile storage found for the given map"));
+        };
+
+        let mut to_despawn: Vec<Entity> = vec![];
+        for (entity, object_id, object_grid_position, player_marker) in object_query.iter() {
+            if player_marker.id() != self.player_id {
+                continue;
+            }
+            let tile_pos: TilePos = object_grid_position.tile_position.into();
+            let Some(tile_objects) = tile_storage
+                .checked_get(&tile_pos)
+                .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+            else {
+                continue;
+            };
+            if !tile_objects.contains_object(*object_id) {
+                continue;
+            }
+            to_despawn.push(entity);
+        }
+
+        for entity in to_despawn {
+            let recorded_object = despawn_recorded_object(world, self.on_map, entity)?;
+            self.despawned_objects.push(recorded_object);
+        }
+
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        for recorded_object in self.despawned_objects.drain(..).rev() {
+            respawn_recorded_object(world, self.on_map, recorded_object)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Exchanges the tiles of the two given objects, updating both objects [`ObjectGridPosition`] and
+/// both tiles [`TileObjects`]. Fails if the objects are in the same tile, are on different maps, or if
+/// either object wouldn't fit in the others tile according to the tiles [`TileObjectStacks`].
+///
+/// Objects carried by either object, see [`CarriedByParent`], swap tiles along with it and also have
+/// to fit in the other tile. If moving any of them fails every object is put back in its original
+/// tile.
+#[derive(Clone, Debug, Reflect)]
+pub struct SwapObjects {
+    pub a: ObjectId,
+    pub b: ObjectId,
+    on_map: Option<MapId>,
+    a_tile_pos: Option<TilePos>,
+    b_tile_pos: Option<TilePos>,
+    #[reflect(ignore)]
+    a_carried: Vec<ObjectId>,
+    #[reflect(ignore)]
+    b_carried: Vec<ObjectId>,
+}
+
+impl GameCommand for SwapObjects {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let (a_entity, a_stacking_class, a_ignores_stacking) = object_stacking(world, self.a)?;
+        let (b_entity, b_stacking_class, b_ignores_stacking) = object_stacking(world, self.b)?;
+        let tile_pos = |world: &World, entity: Entity| -> Option<TilePos> {
+            world
+                .get::<ObjectGridPosition>(entity)
+                .map(|object_grid_position| object_grid_position.tile_position.into())
+        };
+        let (Some(a_tile_pos), Some(b_tile_pos)) =
+            (tile_pos(world, a_entity), tile_pos(world, b_entity))
+        else {
+            return Err(String::from("No ObjectGridPosition found"));
+        };
+
+        // Find the map whose tile actually contains each object
+        let mut map_query = world.query::<&MapId>();
+        let map_ids: Vec<MapId> = map_query.iter(world).copied().collect();
+        let mut find_map = |object_id: ObjectId, tile_pos: TilePos| {
+            map_ids.iter().find_map(|map_id| {
+                let tile_contents = TileContents::at(world, *map_id, tile_pos)?;
+                tile_contents
+                    .contains_object(object_id)
+                    .then(|| (*map_id, tile_contents.stacks().clone()))
+            })
+        };
+
+        let Some((a_map, a_tile_stacks)) = find_map(self.a, a_tile_pos) else {
+            return Err(format!("ObjectId: {:?} is not in a tile", self.a));
+        };
+        let Some((b_map, b_tile_stacks)) = find_map(self.b, b_tile_pos) else {
+            return Err(format!("ObjectId: {:?} is not in a tile", self.b));
+        };
+
+        if a_map != b_map {
+            return Err(String::from("Objects are on different maps"));
+        }
+        if a_tile_pos == b_tile_pos {
+            return Err(String::from("Objects are in the same tile"));
+        }
+
+        // Each object has to fit in the others tile once the other object has left it. Objects that
+        // ignore stacking always fit and don't free up any space when they leave
+        if a_stacking_class != b_stacking_class || a_ignores_stacking != b_ignores_stacking {
+            if !objects_fit(world, b_tile_stacks.clone(), &[self.b], &[self.a]) {
+                return Err(format!("ObjectId: {:?} does not fit in the tile", self.a));
+            }
+            if !objects_fit(world, a_tile_stacks.clone(), &[self.a], &[self.b]) {
+                return Err(format!("ObjectId: {:?} does not fit in the tile", self.b));
+            }
+        }
+
+        // Carried objects have to fit in the other tile once both objects have swapped and the
+        // objects carried out of it have left
+        let a_carried = carried_objects(world, self.a, a_tile_pos);
+        let b_carried = carried_objects(world, self.b, b_tile_pos);
+        if !a_carried.is_empty() || !b_carried.is_empty() {
+            let swapped_stacks =
+                |world: &mut World, mut tile_object_stacks: TileObjectStacks, leaving, entering| {
+                    if let Some(stacking_class) = counted_stacking_class(world, leaving) {
+                        tile_object_stacks.decrement_object_class_count(&stacking_class);
+                    }
+                    if let Some(stacking_class) = counted_stacking_class(world, entering) {
+                        tile_object_stacks.increment_object_class_count(&stacking_class);
+                    }
+                    tile_object_stacks
+                };
+            let b_tile_stacks = swapped_stacks(world, b_tile_stacks, self.b, self.a);
+            if !objects_fit(world, b_tile_stacks, &b_carried, &a_carried) {
+                return Err(format!(
+                    "Objects carried by ObjectId: {:?} do not fit in the tile",
+                    self.a
+                ));
+            }
+            let a_tile_stacks = swapped_stacks(world, a_tile_stacks, self.a, self.b);
+            if !objects_fit(world, a_tile_stacks, &a_carried, &b_carried) {
+                return Err(format!(
+                    "Objects carried by ObjectId: {:?} do not fit in the tile",
+                    self.b
+                ));
+            }
+        }
+
+        swap_objects(world, a_map, self.a, a_tile_pos, self.b, b_tile_pos)?;
+        if let Err(error) = move_all_between_tiles(world, &a_carried, a_map, a_tile_pos, b_tile_pos)
+        {
+            let _ = swap_objects(world, a_map, self.a, b_tile_pos, self.b, a_tile_pos);
+            return Err(error);
+        }
+        if let Err(error) = move_all_between_tiles(world, &b_carried, a_map, b_tile_pos, a_tile_pos)
+        {
+            let _ = move_all_between_tiles(world, &a_carried, a_map, b_tile_pos, a_tile_pos);
+            let _ = swap_objects(world, a_map, self.a, b_tile_pos, self.b, a_tile_pos);
+            return Err(error);
+        }
+
+        self.on_map = Some(a_map);
+        self.a_tile_pos = Some(a_tile_pos);
+        self.b_tile_pos = Some(b_tile_pos);
+        self.a_carried = a_carried;
+        self.b_carried = b_carried;
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let (Some(on_map), Some(a_tile_pos), Some(b_tile_pos)) =
+            (self.on_map, self.a_tile_pos, self.b_tile_pos)
+        else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+
+        // a is now in b's old tile and b is in a's old tile
+        move_all_between_tiles(
+            world,
+            &std::mem::take(&mut self.b_carried),
+            on_map,
+            a_tile_pos,
+            b_tile_pos,
+        )?;
+        move_all_between_tiles(
+            world,
+            &std::mem::take(&mut self.a_carried),
+            on_map,
+            b_tile_pos,
+            a_tile_pos,
+        )?;
+        swap_objects(world, on_map, self.a, b_tile_pos, self.b, a_tile_pos)
+    }
+}
+
+/// Moves object a from a_tile_pos to b_tile_pos and object b from b_tile_pos to a_tile_pos
+fn swap_objects(
+    world: &mut World,
+    on_map: MapId,
+    a: ObjectId,
+    a_tile_pos: TilePos,
+    b: ObjectId,
+    b_tile_pos: TilePos,
+) -> Result<(), String> {
+    RemoveObjectFromTile {
+        object_game_id: a,
+        on_map,
+        tile_pos: a_tile_pos,
+    }
+    .execute(world)?;
+    RemoveObjectFromTile {
+        object_game_id: b,
+        on_map,
+        tile_pos: b_tile_pos,
+    }
+    .execute(world)?;
+    AddObjectToTile {
+        object_game_id: a,
+        on_map,
+        tile_pos: b_tile_pos,
+        strict: false,
+    }
+    .execute(world)?;
+    AddObjectToTile {
+        object_game_id: b,
+        on_map,
+        tile_pos: a_tile_pos,
+        strict: false,
+    }
+    .execute(world)
+}
+
+/// Returns every object with [`CarriedByParent`] whose parent is the given object and that is in the
+/// given tile, sorted by id
+pub(crate) fn carried_objects(
+    world: &mut World,
+    parent: ObjectId,
+    tile_pos: TilePos,
+) -> Vec<ObjectId> {
+    let mut system_state: SystemState<(
+        Query<(Entity, &ObjectId)>,
+        Query<(&ObjectId, &Parent, &ObjectGridPosition), With<CarriedByParent>>,
+    )> = SystemState::new(world);
+    let (object_query, carried_query) = system_state.get(world);
+
+    let Some((parent_entity, _)) = object_query.iter().find(|(_, id)| id == &&parent) else {
+        return vec![];
+    };
+    let mut carried: Vec<ObjectId> = carried_query
+        .iter()
+        .filter(|(_, child_parent, object_grid_position)| {
+            child_parent.get() == parent_entity
+                && object_grid_position.tile_position == tile_pos.into()
+        })
+        .map(|(object_id, _, _)| *object_id)
+        .collect();
+    carried.sort_by_key(|object_id| object_id.id);
+    carried
+}
+
+/// Returns the [`ObjectStackingClass`] the object counts as in a tiles [`TileObjectStacks`]. Returns
+/// None if the object has [`IgnoresStacking`] or no [`ObjectStackingClass`]
+fn counted_stacking_class(world: &mut World, object_id: ObjectId) -> Option<ObjectStackingClass> {
+    let entity = object_entity(world, object_id).ok()?;
+    if world.get::<IgnoresStacking>(entity).is_some() {
+        return None;
+    }
+    world.get::<ObjectStackingClass>(entity).cloned()
+}
+
+/// Returns true if every one of the entering objects fits in the given stacks once the leaving
+/// objects have left them, counting each entering object as it is added, the same as
+/// [`AddObjectToTile`] with strict set. Objects that don't count towards stacking always fit, see
+/// [`counted_stacking_class`]
+fn objects_fit(
+    world: &mut World,
+    mut tile_object_stacks: TileObjectStacks,
+    leaving: &[ObjectId],
+    entering: &[ObjectId],
+) -> bool {
+    for object_id in leaving {
+        if let Some(stacking_class) = counted_stacking_class(world, *object_id) {
+            tile_object_stacks.decrement_object_class_count(&stacking_class);
+        }
+    }
+    for object_id in entering {
+        let Some(stacking_class) = counted_stacking_class(world, *object_id) else {
+            continue;
+        };
+        if !tile_object_stacks.has_space(&stacking_class) {
+            return false;
+        }
+        tile_object_stacks.increment_object_class_count(&stacking_class);
+    }
+    true
+}
+
+/// Returns the [`TileObjectStacks`] of the tile at the given position from its [`TileContents`]
+fn tile_object_stacks(
+    world: &mut World,
+    on_map: MapId,
+    tile_pos: TilePos,
+) -> Option<TileObjectStacks> {
+    TileContents::at(world, on_map, tile_pos).map(|tile_contents| tile_contents.stacks().clone())
+}
+
+/// Moves the object from one tile to another without any checks. If the object can't be added to
+/// the new tile it is put back in its original tile
+fn move_between_tiles(
+    world: &mut World,
+    object_id: ObjectId,
+    on_map: MapId,
+    from: TilePos,
+    to: TilePos,
+) -> Result<(), String> {
+    let mut remove = RemoveObjectFromTile {
+        object_game_id: object_id,
+        on_map,
+        tile_pos: from,
+    };
+    remove.execute(world)?;
+    let result = AddObjectToTile {
+        object_game_id: object_id,
+        on_map,
+        tile_pos: to,
+        strict: false,
+    }
+    .execute(world);
+    if result.is_err() {
+        let _ = remove.rollback(world);
+    }
+    result
+}
+
+/// Moves each of the given objects from one tile to another without any checks. If any of them
+/// fails the objects that were already moved are put back before returning the error
+fn move_all_between_tiles(
+    world: &mut World,
+    objects: &[ObjectId],
+    on_map: MapId,
+    from: TilePos,
+    to: TilePos,
+) -> Result<(), String> {
+    for (index, object_id) in objects.iter().enumerate() {
+        if let Err(error) = move_between_tiles(world, *object_id, on_map, from, to) {
+            for moved in objects[..index].iter().rev() {
+                let _ = move_between_tiles(world, *moved, on_map, to, from);
+            }
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Moves an object and every object it carries, see [`carried_objects`], from one tile to another.
+/// Used by every built in command that moves an object so that carried objects always stay with
+/// their parent.
+///
+/// The object itself is moved without checking the new tiles stacking, the calling command is
+/// expected to have validated the move. Fails with [`MoveError::TileOccupied`] before anything is
+/// moved if the new tile doesn't have space for the carried objects once the object is in it, and
+/// with [`MoveError::Other`] if any object fails to move, in which case every object is put back in
+/// its original tile. Returns the carried objects, pass them to [`undo_move_with_carried`] to
+/// reverse the move.
+pub(crate) fn move_with_carried(
+    world: &mut World,
+    on_map: MapId,
+    object_id: ObjectId,
+    from: TilePos,
+    to: TilePos,
+) -> Result<Vec<ObjectId>, MoveError> {
+    let carried = carried_objects(world, object_id, from);
+    if from != to && !carried.is_empty() {
+        if let Some(mut tile_object_stacks) = tile_object_stacks(world, on_map, to) {
+            if let Some(stacking_class) = counted_stacking_class(world, object_id) {
+                tile_object_stacks.increment_object_class_count(&stacking_class);
+            }
+            if !objects_fit(world, tile_object_stacks, &[], &carried) {
+                return Err(MoveError::TileOccupied(to));
+            }
+        }
+    }
+
+    move_between_tiles(world, object_id, on_map, from, to).map_err(MoveError::Other)?;
+    if let Err(error) = move_all_between_tiles(world, &carried, on_map, from, to) {
+        let _ = move_between_tiles(world, object_id, on_map, to, from);
+        return Err(MoveError::Other(error));
+    }
+    Ok(carried)
+}
+
+/// Reverses a [`move_with_carried`] from one tile to another, moving the object and the carried
+/// objects it returned back to the original tile
+pub(crate) fn undo_move_with_carried(
+    world: &mut World,
+    on_map: MapId,
+    object_id: ObjectId,
+    carried: &[ObjectId],
+    from: TilePos,
+    to: TilePos,
+) -> Result<(), String> {
+    move_all_between_tiles(world, carried, on_map, to, from)?;
+    move_between_tiles(world, object_id, on_map, to, from)
+}
+
+/// Makes the child object a child of the parent object using Bevys [`Parent`] and
+/// [`Children`](bevy::prelude::Children) components, eg a turret on a tank or a rider on a mount. If
+/// the child already has a parent it is moved to the new one. Fails if the objects are the same or if
+/// the parent is a descendant of the child.
+///
+/// If `carried` is true the child gets [`CarriedByParent`] and is moved along with the parent by
+/// every built in move command, eg [`MoveObject`](crate::movement::MoveObject) and [`SwapObjects`],
+/// otherwise any [`CarriedByParent`] is removed. The
+/// child, the parent, and any previous parent get [`Changed`](crate::game_core::state::Changed)
+/// inserted.
+///
+/// Rollback restores the childs previous parent, or removes its parent if it had none, and whether it
+/// was carried.
+#[derive(Clone, Debug, Reflect)]
+pub struct AttachObject {
+    pub child: ObjectId,
+    pub parent: ObjectId,
+    pub carried: bool,
+    #[reflect(ignore)]
+    previous_hierarchy: Option<PreviousHierarchy>,
+}
+
+impl GameCommand for AttachObject {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        if self.child == self.parent {
+            return Err(String::from("Can't attach an object to itself"));
+        }
+        let child_entity = object_entity(world, self.child)?;
+        let parent_entity = object_entity(world, self.parent)?;
+
+        let mut ancestor = Some(parent_entity);
+        while let Some(entity) = ancestor {
+            if entity == child_entity {
+                return Err(String::from(
+                    "Can't attach an object to one of its own descendants",
+                ));
+            }
+            ancestor = world.get::<Parent>(entity).map(|parent| parent.get());
+        }
+
+        let previous_hierarchy = PreviousHierarchy::from_world(world, child_entity);
+        let mut child_entity_mut = world.entity_mut(child_entity);
+        child_entity_mut.set_parent(parent_entity);
+        if self.carried {
+            child_entity_mut.insert(CarriedByParent);
+        } else {
+            child_entity_mut.remove::<CarriedByParent>();
+        }
+        mark_hierarchy_changed(
+            world,
+            &[
+                Some(child_entity),
+                Some(parent_entity),
+                previous_hierarchy.parent,
+            ],
+        );
+        self.previous_hierarchy = Some(previous_hierarchy);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(previous_hierarchy) = self.previous_hierarchy.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let child_entity = object_entity(world, self.child)?;
+        let parent_entity = object_entity(world, self.parent)?;
+        previous_hierarchy.restore(world, child_entity);
+        mark_hierarchy_changed(
+            world,
+            &[
+                Some(child_entity),
+                Some(parent_entity),
+                previous_hierarchy.parent,
+            ],
+        );
+        Ok(())
+    }
+}
+
+/// Removes the child object from its parent, undoing an [`AttachObject`]. Removes any
+/// [`CarriedByParent`] from the child. Fails if the child has no parent. The child and its parent get
+/// [`Changed`](crate::game_core::state::Changed) inserted.
+///
+/// Rollback attaches the child to its parent again and restores whether it was carried.
+#[derive(Clone, Debug, Reflect)]
+pub struct DetachObject {
+    pub child: ObjectId,
+    #[reflect(ignore)]
+    previous_hierarchy: Option<PreviousHierarchy>,
+}
+
+impl GameCommand for DetachObject {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let child_entity = object_entity(world, self.child)?;
+        let previous_hierarchy = PreviousHierarchy::from_world(world, child_entity);
+        if previous_hierarchy.parent.is_none() {
+            return Err(format!("ObjectId: {:?} does not have a parent", self.child));
+        }
+
+        world
+            .entity_mut(child_entity)
+            .remove_parent()
+            .remove::<CarriedByParent>();
+        mark_hierarchy_changed(world, &[Some(child_entity), previous_hierarchy.parent]);
+        self.previous_hierarchy = Some(previous_hierarchy);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(previous_hierarchy) = self.previous_hierarchy.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let child_entity = object_entity(world, self.child)?;
+        previous_hierarchy.restore(world, child_entity);
+        mark_hierarchy_changed(world, &[Some(child_entity), previous_hierarchy.parent]);
+        Ok(())
+    }
+}
+
+/// The parent a child object had and whether it was carried, saved by [`AttachObject`] and
+/// [`DetachObject`] for rollback
+#[derive(Clone, Copy, Debug)]
+struct PreviousHierarchy {
+    parent: Option<Entity>,
+    carried: bool,
+}
+
+impl PreviousHierarchy {
+    fn from_world(world: &World, child_entity: Entity) -> PreviousHierarchy {
+        PreviousHierarchy {
+            parent: world.get::<Parent>(child_entity).map(|parent| parent.get()),
+            carried: world.get::<CarriedByParent>(child_entity).is_some(),
+        }
+    }
+
+    fn restore(&self, world: &mut World, child_entity: Entity) {
+        let mut child_entity_mut = world.entity_mut(child_entity);
+        match self.parent {
+            Some(parent_entity) => child_entity_mut.set_parent(parent_entity),
+            None => child_entity_mut.remove_parent(),
+        };
+        if self.carried {
+            child_entity_mut.insert(CarriedByParent);
+        } else {
+            child_entity_mut.remove::<CarriedByParent>();
+        }
+    }
+}
+
+/// Inserts [`Changed`](crate::game_core::state::Changed) on every given entity that still exists
+fn mark_hierarchy_changed(world: &mut World, entities: &[Option<Entity>]) {
+    for entity in entities.iter().flatten() {
+        if let Some(mut entity_mut) = world.get_entity_mut(*entity) {
+            entity_mut.insert(crate::game_core::state::Changed::default());
+        }
+    }
+}
+
+/// Inserts a component onto an object using the [`AppTypeRegistry`] in the game world. The
+/// components type must be registered with
+/// [`GameBuilder::register_reflect_component`](crate::game_core::GameBuilder::register_reflect_component)
+/// and reflect `Component`.
+///
+/// If the object already has the component it is replaced and rollback restores the previous value,
+/// otherwise rollback removes the component.
+#[derive(Debug, Reflect)]
+pub struct SetComponent {
+    pub object_id: ObjectId,
+    #[reflect(ignore)]
+    pub component: Box<dyn Reflect>,
+    #[reflect(ignore)]
+    previous_component: Option<Box<dyn Reflect>>,
+}
+
+impl Clone for SetComponent {
+    fn clone(&self) -> Self {
+        SetComponent {
+            object_id: self.object_id,
+            component: self.component.clone_value(),
+            previous_component: self
+                .previous_component
+                .as_ref()
+                .map(|component| component.clone_value()),
+        }
+    }
+}
+
+impl GameCommand for SetComponent {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let reflect_component = reflect_component(world, self.component.type_name())?;
+        let entity = object_entity(world, self.object_id)?;
+
+        self.previous_component = reflect_component
+            .reflect(world.entity(entity))
+            .map(|component| component.clone_value());
+
+        let mut entity_mut = world.entity_mut(entity);
+        reflect_component.remove(&mut entity_mut);
+        reflect_component.insert(&mut entity_mut, &*self.component);
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let reflect_component = reflect_component(world, self.component.type_name())?;
+        let entity = object_entity(world, self.object_id)?;
+
+        let mut entity_mut = world.entity_mut(entity);
+        reflect_component.remove(&mut entity_mut);
+        if let Some(previous_component) = self.previous_component.take() {
+            reflect_component.insert(&mut entity_mut, &*previous_component);
+        }
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+}
+
+/// Removes the component with the given type name from an object using the [`AppTypeRegistry`] in
+/// the game world. Fails if the object doesn't have the component. Rollback reinserts the removed
+/// component.
+#[derive(Debug, Reflect)]
+pub struct RemoveComponent {
+    pub object_id: ObjectId,
+    pub type_name: String,
+    #[reflect(ignore)]
+    removed_component: Option<Box<dyn Reflect>>,
+}
+
+impl Clone for RemoveComponent {
+    fn clone(&self) -> Self {
+        RemoveComponent {
+            object_id: self.object_id,
+            type_name: self.type_name.clone(),
+            removed_component: self
+                .removed_component
+                .as_ref()
+                .map(|component| component.clone_value()),
+        }
+    }
+}
+
+impl GameCommand for RemoveComponent {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let reflect_component = reflect_component(world, &self.type_name)?;
+        let entity = object_entity(world, self.object_id)?;
+
+        let Some(component) = reflect_component.reflect(world.entity(entity)) else {
+            return Err(format!(
+                "ObjectId: {:?} does not have component: {}",
+                self.object_id, self.type_name
+            ));
+        };
+        self.removed_component = Some(component.clone_value());
+
+        let mut entity_mut = world.entity_mut(entity);
+        reflect_component.remove(&mut entity_mut);
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(removed_component) = self.removed_component.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let reflect_component = reflect_component(world, &self.type_name)?;
+        let entity = object_entity(world, self.object_id)?;
+
+        let mut entity_mut = world.entity_mut(entity);
+        reflect_component.insert(&mut entity_mut, &*removed_component);
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+}
+
+/// Changes the [`ObjectType`] in an objects [`ObjectInfo`], eg when a unit is promoted or a building
+/// is upgraded. Each reflected component in `components` is inserted onto the object, replacing the
+/// objects current component of that type, so that stats can be swapped along with the type. The
+/// components types must be registered the same as for [`SetComponent`].
+///
+/// The object keeps its [`ObjectId`]. Rollback restores the previous type and components, removing
+/// any components the object didn't have before.
+#[derive(Debug, Reflect)]
+pub struct TransformObject {
+    pub object_id: ObjectId,
+    pub new_type: ObjectType,
+    #[reflect(ignore)]
+    pub components: Vec<Box<dyn Reflect>>,
+    previous_type: Option<ObjectType>,
+    #[reflect(ignore)]
+    previous_components: Vec<Option<Box<dyn Reflect>>>,
+}
+
+impl TransformObject {
+    pub fn new(
+        object_id: ObjectId,
+        new_type: ObjectType,
+        components: Vec<Box<dyn Reflect>>,
+    ) -> TransformObject {
+        TransformObject {
+            object_id,
+            new_type,
+            components,
+            previous_type: None,
+            previous_components: vec![],
+        }
+    }
+}
+
+impl Clone for TransformObject {
+    fn clone(&self) -> Self {
+        TransformObject {
+            object_id: self.object_id,
+            new_type: self.new_type.clone(),
+            components: self
+                .components
+                .iter()
+                .map(|component| component.clone_value())
+                .collect(),
+            previous_type: self.previous_type.clone(),
+            previous_components: self
+                .previous_components
+                .iter()
+                .map(|component| component.as_ref().map(|component| component.clone_value()))
+                .collect(),
+        }
+    }
+}
+
+impl GameCommand for TransformObject {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let entity = object_entity(world, self.object_id)?;
+        // Resolve every component first so that a missing registration fails before the object is changed
+        let reflect_components = self
+            .components
+            .iter()
+            .map(|component| reflect_component(world, component.type_name()))
+            .collect::<Result<Vec<ReflectComponent>, String>>()?;
+
+        let Some(mut object_info) = world.get_mut::<ObjectInfo>(entity) else {
+            return Err(format!(
+                "ObjectId: {:?} does not have an ObjectInfo",
+                self.object_id
+            ));
+        };
+        self.previous_type = Some(std::mem::replace(
+            &mut object_info.object_type,
+            self.new_type.clone(),
+        ));
+
+        self.previous_components = reflect_components
+            .iter()
+            .map(|reflect_component| {
+                reflect_component
+                    .reflect(world.entity(entity))
+                    .map(|component| component.clone_value())
+            })
+            .collect();
+
+        let mut entity_mut = world.entity_mut(entity);
+        for (reflect_component, component) in reflect_components.iter().zip(self.components.iter())
+        {
+            reflect_component.remove(&mut entity_mut);
+            reflect_component.insert(&mut entity_mut, &**component);
+        }
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(previous_type) = self.previous_type.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let entity = object_entity(world, self.object_id)?;
+        let reflect_components = self
+            .components
+            .iter()
+            .map(|component| reflect_component(world, component.type_name()))
+            .collect::<Result<Vec<ReflectComponent>, String>>()?;
+
+        let previous_components = std::mem::take(&mut self.previous_components);
+        let mut entity_mut = world.entity_mut(entity);
+        for (reflect_component, previous_component) in
+            reflect_components.iter().zip(previous_components).rev()
+        {
+            reflect_component.remove(&mut entity_mut);
+            if let Some(previous_component) = previous_component {
+                reflect_component.insert(&mut entity_mut, &*previous_component);
+            }
+        }
+        entity_mut.insert(ObjectInfo {
+            object_type: previous_type,
+        });
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+}
+
+/// Reveals an existing object that was hidden from the given player, eg by fog of war. The object
+/// keeps its [`ObjectId`] and the next state diff for the player contains its full state with
+/// `revealed` set in its [`ObjectState`](crate::game_core::state::ObjectState) so that clients don't
+/// treat it as a newly spawned object.
+///
+/// Rollback restores the objects [`Changed`](crate::game_core::state::Changed) and
+/// [`PendingReveal`] components to what they were before.
+#[derive(Clone, Debug, Reflect)]
+pub struct RevealObject {
+    pub object_id: ObjectId,
+    pub player_id: usize,
+    #[reflect(ignore)]
+    previous_state: Option<(
+        Option<crate::game_core::state::Changed>,
+        Option<PendingReveal>,
+    )>,
+}
+
+impl GameCommand for RevealObject {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let entity = object_entity(world, self.object_id)?;
+        let mut entity_mut = world.entity_mut(entity);
+
+        let previous_changed = entity_mut
+            .get::<crate::game_core::state::Changed>()
+            .cloned();
+        let previous_pending_reveal = entity_mut.get::<PendingReveal>().cloned();
+
+        // Clear the player from the seen list so that the next diff for them includes the object
+        let mut changed = previous_changed.clone().unwrap_or_default();
+        changed.players_seen.retain(|id| id != &self.player_id);
+        let mut pending_reveal = previous_pending_reveal.clone().unwrap_or_default();
+        if !pending_reveal.players.contains(&self.player_id) {
+            pending_reveal.players.push(self.player_id);
+        }
+        entity_mut.insert((changed, pending_reveal));
+
+        self.previous_state = Some((previous_changed, previous_pending_reveal));
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some((previous_changed, previous_pending_reveal)) = self.previous_state.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let entity = object_entity(world, self.object_id)?;
+        let mut entity_mut = world.entity_mut(entity);
+
+        match previous_changed {
+            Some(changed) => {
+                entity_mut.insert(changed);
+            }
+            None => {
+                entity_mut.remove::<crate::game_core::state::Changed>();
+            }
+        }
+        match previous_pending_reveal {
+            Some(pending_reveal) => {
+                entity_mut.insert(pending_reveal);
+            }
+            None => {
+                entity_mut.remove::<PendingReveal>();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets the [`PlayerMarker`] of the given object to `player_id`. If `player_id` is None the marker is
+/// removed and the object becomes neutral. Objects that were neutral are given a new marker.
+///
+/// Rollback restores the previous owner, removing the marker again if the object was neutral.
+#[derive(Clone, Debug, Reflect)]
+pub struct SetObjectOwner {
+    pub object_id: ObjectId,
+    pub player_id: Option<usize>,
+    previous_owner: Option<Option<usize>>,
+}
+
+impl GameCommand for SetObjectOwner {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let entity = object_entity(world, self.object_id)?;
+        self.previous_owner = Some(
+            world
+                .get::<PlayerMarker>(entity)
+                .map(|player_marker| player_marker.id()),
+        );
+        set_object_owner(world, entity, self.player_id);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(previous_owner) = self.previous_owner.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        let entity = object_entity(world, self.object_id)?;
+        set_object_owner(world, entity, previous_owner);
+        Ok(())
+    }
+}
+
+/// Adds a new player to a game that is already running. The new players id is one higher than the
+/// highest id in the [`PlayerList`] resource. A [`Player`] entity is spawned with a fresh
+/// [`Changed`](crate::game_core::state::Changed) component and the player is pushed into the
+/// [`PlayerList`], marking the resource as changed so existing players receive the updated roster in
+/// their next state diff. The new player has not seen anything yet - send them
+/// [`Game::full_state_for`] to catch them up.
+///
+/// Rollback despawns the player entity and removes the player from the [`PlayerList`].
+#[derive(Clone, Debug, Reflect)]
+pub struct AddPlayer {
+    pub needs_state: bool,
+    player_id: Option<usize>,
+}
+
+impl AddPlayer {
+    /// The id given to the new player. None until the command has been executed
+    pub fn player_id(&self) -> Option<usize> {
+        self.player_id
+    }
+}
+
+impl GameCommand for AddPlayer {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(mut player_list) = world.get_resource_mut::<PlayerList>() else {
+            return Err(String::from("PlayerList resource not found"));
+        };
+        let player_id = player_list
+            .players
+            .iter()
+            .map(|player| player.id() + 1)
+            .max()
+            .unwrap_or(0);
+        player_list
+            .players
+            .push(Player::new(player_id, self.needs_state));
+
+        world.spawn((
+            Player::new(player_id, self.needs_state),
+            crate::game_core::state::Changed::default(),
+        ));
+        self.player_id = Some(player_id);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(player_id) = self.player_id.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        if let Some(entity) = player_entity(world, player_id) {
+            world.despawn(entity);
+        }
+        if let Some(mut player_list) = world.get_resource_mut::<PlayerList>() {
+            player_list
+                .players
+                .retain(|player| player.id() != player_id);
+        }
+        Ok(())
+    }
+}
+
+/// Modifies a resource in the game world with the given function, eg adding gold to a players
+/// treasury. The change is picked up by
+/// [`GameBuilder::register_resource_track_changes`](crate::game_core::GameBuilder::register_resource_track_changes)
+/// like any other change to the resource. Fails if the resource doesn't exist.
+///
+/// Execute saves a copy of the resource before modifying it and rollback puts that copy back, so
+/// rollback restores the exact previous value no matter what the function did.
+#[derive(Reflect)]
+pub struct ModifyResource<R>
+where
+    R: Resource + Clone,
+{
+    #[reflect(ignore)]
+    pub modify: Arc<dyn Fn(&mut R) + Send + Sync>,
+    #[reflect(ignore)]
+    previous_resource: Option<R>,
+}
+
+impl<R> ModifyResource<R>
+where
+    R: Resource + Clone,
+{
+    pub fn new(modify: impl Fn(&mut R) + Send + Sync + 'static) -> ModifyResource<R> {
+        ModifyResource {
+            modify: Arc::new(modify),
+            previous_resource: None,
+        }
+    }
+}
+
+impl<R> Clone for ModifyResource<R>
+where
+    R: Resource + Clone,
+{
+    fn clone(&self) -> Self {
+        ModifyResource {
+            modify: self.modify.clone(),
+            previous_resource: self.previous_resource.clone(),
+        }
+    }
+}
+
+impl<R> GameCommand for ModifyResource<R>
+where
+    R: Resource + Clone,
+{
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(mut resource) = world.get_resource_mut::<R>() else {
+            return Err(format!(
+                "Resource not found: {}",
+                std::any::type_name::<R>()
+            ));
+        };
+        self.previous_resource = Some(resource.clone());
+        (self.modify)(&mut resource);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(previous_resource) = self.previous_resource.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        world.insert_resource(previous_resource);
+        Ok(())
+    }
+}
+
+/// Removes the [`ObjectMoved`], [`ObjectAttacked`], and [`PartialMove`] markers from every object so
+/// they can act again, eg at the start of a round in a simultaneous turn game. Every object that had a
+/// marker removed gets [`Changed`](crate::game_core::state::Changed) inserted.
+///
+/// Rollback puts back the markers each object had.
+#[derive(Clone, Debug, Reflect)]
+pub struct ResetActionsAll {
+    #[reflect(ignore)]
+    reset: Vec<ActionMarkers>,
+}
+
+impl GameCommand for ResetActionsAll {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        self.reset = reset_object_actions(world, None);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        restore_object_actions(world, self.reset.drain(..))
+    }
+}
+
+/// Removes the [`ObjectMoved`], [`ObjectAttacked`], and [`PartialMove`] markers from every object
+/// with a [`PlayerMarker`] for the given player, eg at the start of that players turn. Every object
+/// that had a marker removed gets [`Changed`](crate::game_core::state::Changed) inserted.
+///
+/// Rollback puts back the markers each object had.
+#[derive(Clone, Debug, Reflect)]
+pub struct ResetActionsForPlayer {
+    pub player_id: usize,
+    #[reflect(ignore)]
+    reset: Vec<ActionMarkers>,
+}
+
+impl GameCommand for ResetActionsForPlayer {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        self.reset = reset_object_actions(world, Some(self.player_id));
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        restore_object_actions(world, self.reset.drain(..))
+    }
+}
+
+/// The action markers an object had before they were reset
+#[derive(Clone, Debug)]
+struct ActionMarkers {
+    object_id: ObjectId,
+    moved: bool,
+    attacked: bool,
+    partial_move: Option<PartialMove>,
+}
+
+/// Removes the action markers from every object, or only those owned by the given player, and returns
+/// the markers each changed object had
+fn reset_object_actions(world: &mut World, player_id: Option<usize>) -> Vec<ActionMarkers> {
+    let mut object_query = world.query_filtered::<(
+        Entity,
+        &ObjectId,
+        Option<&PlayerMarker>,
+        Option<&ObjectMoved>,
+        Option<&ObjectAttacked>,
+        Option<&PartialMove>,
+    ), With<Object>>();
+    let objects: Vec<(Entity, ActionMarkers)> = object_query
+        .iter(world)
+        .filter(|(_, _, player_marker, _, _, _)| match player_id {
+            Some(player_id) => {
+                matches!(player_marker, Some(player_marker) if player_marker.id() == player_id)
+            }
+            None => true,
+        })
+        .filter(|(_, _, _, moved, attacked, partial_move)| {
+            moved.is_some() || attacked.is_some() || partial_move.is_some()
+        })
+        .map(|(entity, object_id, _, moved, attacked, partial_move)| {
+            (
+                entity,
+                ActionMarkers {
+                    object_id: *object_id,
+                    moved: moved.is_some(),
+                    attacked: attacked.is_some(),
+                    partial_move: partial_move.copied(),
+                },
+            )
+        })
+        .collect();
+
+    objects
+        .into_iter()
+        .map(|(entity, action_markers)| {
+            world
+                .entity_mut(entity)
+                .remove::<(ObjectMoved, ObjectAttacked, PartialMove)>()
+                .insert(crate::game_core::state::Changed::default());
+            action_markers
+        })
+        .collect()
+}
+
+/// Puts back the action markers removed by [`reset_object_actions`]
+fn restore_object_actions(
+    world: &mut World,
+    reset: impl Iterator<Item = ActionMarkers>,
+) -> Result<(), String> {
+    for action_markers in reset {
+        let entity = object_entity(world, action_markers.object_id)?;
+        let mut entity_mut = world.entity_mut(entity);
+        if action_markers.moved {
+            entity_mut.insert(ObjectMoved);
+        }
+        if action_markers.attacked {
+            entity_mut.insert(ObjectAttacked);
+        }
+        if let Some(partial_move) = action_markers.partial_move {
+            entity_mut.insert(partial_move);
+        }
+        entity_mut.insert(crate::game_core::state::Changed::default());
+    }
+    Ok(())
+}
+
+/// Adds the label to the given objects [`Tags`], inserting the component if the object has none,
+/// and adds the object to the [`TagIndex`] under the label.
+///
+/// Rollback removes the label again unless the object already had it.
+#[derive(Clone, Debug, Reflect)]
+pub struct AddTag {
+    pub object_id: ObjectId,
+    pub label: String,
+    added: Option<bool>,
+}
+
+impl GameCommand for AddTag {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let entity = object_entity(world, self.object_id)?;
+        self.added = Some(set_object_tag(
+            world,
+            entity,
+            self.object_id,
+            &self.label,
+            true,
+        ));
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(added) = self.added.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        if added {
+            let entity = object_entity(world, self.object_id)?;
+            set_object_tag(world, entity, self.object_id, &self.label, false);
+        }
+        Ok(())
+    }
+}
+
+/// Removes the label from the given objects [`Tags`] and removes the object from the [`TagIndex`]
+/// under the label.
+///
+/// Rollback adds the label back if the object had it.
+#[derive(Clone, Debug, Reflect)]
+pub struct RemoveTag {
+    pub object_id: ObjectId,
+    pub label: String,
+    removed: Option<bool>,
+}
+
+impl GameCommand for RemoveTag {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let entity = object_entity(world, self.object_id)?;
+        self.removed = Some(set_object_tag(
+            world,
+            entity,
+            self.object_id,
+            &self.label,
+            false,
+        ));
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some(removed) = self.removed.take() else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+        if removed {
+            let entity = object_entity(world, self.object_id)?;
+            set_object_tag(world, entity, self.object_id, &self.label, true);
+        }
+        Ok(())
+    }
+}
+
+/// Adds or removes the label from the entities [`Tags`] and the [`TagIndex`]. Returns true if the
+/// tags were changed
+fn set_object_tag(
+    world: &mut World,
+    entity: Entity,
+    object_id: ObjectId,
+    label: &str,
+    tagged: bool,
+) -> bool {
+    let mut entity_mut = world.entity_mut(entity);
+    let mut tags = entity_mut.get::<Tags>().cloned().unwrap_or_default();
+    let changed = match tagged {
+        true => tags.labels.insert(label.to_string()),
+        false => tags.labels.remove(label),
+    };
+    if !changed {
+        return false;
+    }
+    entity_mut.insert((tags, crate::game_core::state::Changed::default()));
+
+    if let Some(mut tag_index) = world.get_resource_mut::<TagIndex>() {
+        match tagged {
+            true => tag_index.insert(label, object_id),
+            false => tag_index.remove(label, object_id),
+        }
+    }
+    true
+}
+
+/// Inserts a [`PlayerMarker`] for the given player on the entity, or removes it if `player_id` is None
+fn set_object_owner(world: &mut World, entity: Entity, player_id: Option<usize>) {
+    let mut entity_mut = world.entity_mut(entity);
+    match player_id {
+        Some(player_id) => {
+            entity_mut.insert(PlayerMarker::new(player_id));
+        }
+        None => {
+            entity_mut.remove::<PlayerMarker>();
+        }
+    }
+    entity_mut.insert(crate::game_core::state::Changed::default());
+}
+
+/// Returns the [`ReflectComponent`] for the given type name from the worlds [`AppTypeRegistry`]
+fn reflect_component(world: &World, type_name: &str) -> Result<ReflectComponent, String> {
+    let Some(type_registry) = world.get_resource::<AppTypeRegistry>() else {
+        return Err(String::from("No AppTypeRegistry found in the game world"));
+    };
+    let type_registry = type_registry.read();
+    let Some(registration) = type_registry.get_with_name(type_name) else {
+        return Err(format!("Type: {} is not registered", type_name));
+    };
+    let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+        return Err(format!("Type: {} does not reflect Component", type_name));
+    };
+    Ok(reflect_component.clone())
+}
+
+/// An object despawned by [`despawn_recorded_object`] with everything needed to respawn it with
+/// [`respawn_recorded_object`]
+#[derive(Clone, Debug)]
+pub(crate) struct RecordedObject {
+    pub(crate) object_id: ObjectId,
+    pub(crate) tile_pos: TilePos,
+    pub(crate) components: Vec<(BinaryComponentId, Vec<u8>)>,
+}
+
+/// Despawns the given object so that it can be respawned on rollback. Every component with a
+/// [`SaveId`] is recorded, then the object is removed from its tile, despawned, and removed from the
+/// [`TagIndex`], an [`ObjectDespawned`] event is sent, and it is added to the [`DespawnedObjects`].
+/// Used by every built in command that despawns objects with a rollback, eg
+/// [`DespawnPlayerObjects`] and [`AttackObject`](crate::combat::commands::AttackObject)
+pub(crate) fn despawn_recorded_object(
+    world: &mut World,
+    on_map: MapId,
+    entity: Entity,
+) -> Result<RecordedObject, String> {
+    let mut system_state: SystemState<Query<(&ObjectId, &ObjectGridPosition, &dyn SaveId)>> =
+        SystemState::new(world);
+    let object_query = system_state.get(world);
+    let Ok((object_id, object_grid_position, saveable_components)) = object_query.get(entity)
+    else {
+        return Err(String::from(
+            "Despawned object needs an ObjectId and ObjectGridPosition",
+        ));
+    };
+    let object_id = *object_id;
+    let tile_pos: TilePos = object_grid_position.tile_position.into();
+    let components: Vec<(BinaryComponentId, Vec<u8>)> = saveable_components
+        .iter()
+        .filter_map(|component| component.save())
+        .collect();
+
+    let _ = remove_from_tile(world, object_id, on_map, tile_pos);
+    world.entity_mut(entity).despawn_recursive();
+    unindex_object_tags(world, object_id);
+    world.send_event(ObjectDespawned { object_id });
+    world
+        .resource_mut::<DespawnedObjects>()
+        .despawned_objects
+        .insert(object_id, crate::game_core::state::Changed::default());
+
+    Ok(RecordedObject {
+        object_id,
+        tile_pos,
+        components,
+    })
+}
+
+/// Respawns an object despawned by [`despawn_recorded_object`] from its recorded components using the
+/// [`GameSerDeRegistry`], adds its [`Tags`] back to the [`TagIndex`], removes it from the
+/// [`DespawnedObjects`], and adds it back to its tile. Returns the new entity
+pub(crate) fn respawn_recorded_object(
+    world: &mut World,
+    on_map: MapId,
+    recorded_object: RecordedObject,
+) -> Result<Entity, String> {
+    let Some(registry) = world.get_resource::<GameSerDeRegistry>().cloned() else {
+        return Err(String::from(
+            "No GameSerDeRegistry found to restore the despawned object with",
+        ));
+    };
+
+    let mut entity = world.spawn(crate::game_core::state::Changed::default());
+    for (id, component) in recorded_object.components {
+        registry.deserialize_component_onto(&ComponentBinaryState { id, component }, &mut entity);
+    }
+    let entity = entity.id();
+    index_object_tags(world, entity);
+    world
+        .resource_mut::<DespawnedObjects>()
+        .despawned_objects
+        .remove(&recorded_object.object_id);
+
+    add_to_tile(
+        world,
+        recorded_object.object_id,
+        on_map,
+        recorded_object.tile_pos,
+        false,
+    )?;
+    Ok(entity)
+}
+
+/// Returns the entity of the tile at the given position on the given map
+pub(crate) fn tile_entity(
+    world: &mut World,
+    on_map: MapId,
+    tile_pos: TilePos,
+) -> Result<Entity, String> {
+    let mut system_state: SystemState<Query<(&MapId, &TileStorage)>> = SystemState::new(world);
+    let tile_storage_query = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&on_map) else {
+        return Err(String::from("TileStorage not found"));
+    };
+    tile_storage
+        .checked_get(&tile_pos)
+        .ok_or(format!("TileNotFound({:?})", tile_pos))
+}
+
+/// Returns the entity of the object with the given id
+pub(crate) fn object_entity(world: &mut World, object_id: ObjectId) -> Result<Entity, String> {
+    let mut object_query = world.query_filtered::<(Entity, &ObjectId), With<Object>>();
+    object_query
+        .iter(world)
+        .find(|(_, id)| id == &&object_id)
+        .map(|(entity, _)| entity)
+        .ok_or(format!(
+            "No object components found for ObjectId: {:?}",
+            object_id
+        ))
+}
+
+#[test]
+fn test_add_object_to_tile_strict_rejects_full_tile() {
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount};
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let map_id = MapId { id: 0 };
+    let tile_pos = TilePos::new(0, 0);
+
+    let tile_entity = world
+        .spawn((
+            TileObjectStacks::new(vec![(
+                stacking_class.clone(),
+                TileObjectStacksCount {
+                    current_count: 1,
+                    max_count: 1,
+                },
+            )]),
+            TileObjects {
+                entities_in_tile: vec![ObjectId { id: 0 }],
+            },
+        ))
+        .id();
+    let mut tile_storage = TileStorage::empty(TilemapSize { x: 1, y: 1 });
+    tile_storage.set(&tile_pos, tile_entity);
+    world.spawn((map_id, tile_storage));
+
+    world.spawn((
+        Object,
+        ObjectId { id: 1 },
+        ObjectGridPosition {
+            tile_position: tile_pos.into(),
+        },
+        ObjectStackingClass {
+            stack_class: stacking_class.clone(),
+        },
+    ));
+
+    let mut add = AddObjectToTile {
+        object_game_id: ObjectId { id: 1 },
+        on_map: map_id,
+        tile_pos,
+        strict: true,
+    };
+    assert!(add.execute(&mut world).is_err());
+
+    let tile_objects = world.get::<TileObjects>(tile_entity).unwrap();
+    assert_eq!(tile_objects.entities_in_tile.len(), 1);
+    let tile_stacks = world.get::<TileObjectStacks>(tile_entity).unwrap();
+    assert_eq!(
+        tile_stacks
+            .tile_object_stacks
+            .get(&stacking_class)
+            .unwrap()
+            .current_count,
+        1
+    );
+
+    // Force mode keeps the old behavior and overfills the tile
+    add.strict = false;
+    assert!(add.execute(&mut world).is_ok());
+    let tile_objects = world.get::<TileObjects>(tile_entity).unwrap();
+    assert_eq!(tile_objects.entities_in_tile.len(), 2);
+
+    // The object list and stacking counts are only changed together, so adding an object twice or
+    // removing one that isn't in the tile fails without touching either
+    let count = |world: &World| {
+        world
+            .get::<TileObjectStacks>(tile_entity)
+            .unwrap()
+            .tile_object_stacks
+            .get(&stacking_class)
+            .unwrap()
+            .current_count
+    };
+    assert!(add.execute(&mut world).is_err());
+    assert_eq!(count(&world), 2);
+    let mut remove = RemoveObjectFromTile {
+        object_game_id: ObjectId { id: 1 },
+        on_map: map_id,
+        tile_pos,
+    };
+    assert!(remove.execute(&mut world).is_ok());
+    assert!(remove.execute(&mut world).is_err());
+    assert_eq!(count(&world), 1);
+    let tile_objects = world.get::<TileObjects>(tile_entity).unwrap();
+    assert_eq!(tile_objects.entities_in_tile, vec![ObjectId { id: 0 }]);
+}
+
+#[test]
+fn test_object_ids_never_collide_after_rollback() {
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount};
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    #[derive(Bundle, Clone, Reflect)]
+    struct TestObjectBundle {
+        object: Object,
+        object_stacking_class: ObjectStackingClass,
+    }
+
+    let mut world = World::new();
+    world.insert_resource(ObjectIdProvider::default());
+    world.insert_resource(DespawnedObjects {
+        despawned_objects: Default::default(),
+    });
+
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let map_id = MapId { id: 0 };
+    let tile_pos = TilePos::new(0, 0);
+    let tile_entity = world
+        .spawn((
+            TileObjectStacks::new(vec![(
+                stacking_class.clone(),
+                TileObjectStacksCount {
+                    current_count: 0,
+                    max_count: 10,
+                },
+            )]),
+            TileObjects::default(),
+        ))
+        .id();
+    let mut tile_storage = TileStorage::empty(TilemapSize { x: 1, y: 1 });
+    tile_storage.set(&tile_pos, tile_entity);
+    world.spawn((map_id, tile_storage));
+
+    let bundle = TestObjectBundle {
+        object: Object,
+        object_stacking_class: ObjectStackingClass {
+            stack_class: stacking_class,
+        },
+    };
+    let mut game_commands = GameCommands::new();
+    let mut first = game_commands.spawn_object(bundle.clone(), tile_pos, map_id, 0);
+    let mut second = game_commands.spawn_object(bundle.clone(), tile_pos, map_id, 0);
+
+    assert!(first.execute(&mut world).is_ok());
+    assert!(second.execute(&mut world).is_ok());
+    let first_id = first.object_game_id.unwrap();
+    let second_id = second.object_game_id.unwrap();
+
+    // Roll back the first spawn and spawn a new object, it must not reuse the rolled back id
+    assert!(first.rollback(&mut world).is_ok());
+    let mut third = game_commands.spawn_object(bundle, tile_pos, map_id, 0);
+    assert!(third.execute(&mut world).is_ok());
+    let third_id = third.object_game_id.unwrap();
+    assert_ne!(third_id, first_id);
+    assert_ne!(third_id, second_id);
+
+    // Rolling the first spawn forward again gives back its original id
+    assert!(first.execute(&mut world).is_ok());
+    assert_eq!(first.object_game_id.unwrap(), first_id);
+
+    let mut object_query = world.query::<&ObjectId>();
+    let mut ids: Vec<ObjectId> = object_query.iter(&world).copied().collect();
+    ids.sort_by_key(|id| id.id);
+    ids.dedup();
+    assert_eq!(ids.len(), 3);
+}
+
+#[test]
+fn test_resolve_orders_is_deterministic() {
+    #[derive(Default, Resource)]
+    struct ClaimedTile {
+        claimed_by: Option<ObjectId>,
+    }
+
+    #[derive(Clone, Debug, Reflect)]
+    struct ClaimTile {
+        object_id: ObjectId,
+    }
+
+    impl GameCommand for ClaimTile {
+        fn execute(&mut self, world: &mut World) -> Result<(), String> {
+            let mut claimed_tile = world.resource_mut::<ClaimedTile>();
+            if claimed_tile.claimed_by.is_some() {
+                return Err(String::from("Tile is full"));
+            }
+            claimed_tile.claimed_by = Some(self.object_id);
+            Ok(())
+        }
+    }
+
+    let command_time = Utc::now();
+    let order = |player_id: usize, id: usize| SubmittedOrder {
+        player_id,
+        object_id: ObjectId { id },
+        command: GameCommandMeta {
+            command: Box::new(ClaimTile {
+                object_id: ObjectId { id },
+            }),
+            command_time,
+            priority: 0,
+        },
+    };
+
+    let mut world = World::new();
+    world.init_resource::<ClaimedTile>();
+    let mut game_commands = GameCommands::new();
+    game_commands.resolution.push(order(1, 5));
+    game_commands.resolution.push(order(0, 2));
+    assert!(game_commands.resolution.has_submitted(0));
+    assert!(game_commands.resolution.has_submitted(1));
+
+    let failed = game_commands.resolve_orders(&mut world);
+
+    assert_eq!(
+        world.resource::<ClaimedTile>().claimed_by,
+        Some(ObjectId { id: 2 })
+    );
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0.object_id, ObjectId { id: 5 });
+    assert_eq!(game_commands.history.history.len(), 1);
+    assert!(game_commands.resolution.is_empty());
+}
+
+#[test]
+fn test_swap_objects_with_carried_objects() {
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount};
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    let ground = StackingClass {
+        name: String::from("Ground"),
+    };
+    let passenger = StackingClass {
+        name: String::from("Passenger"),
+    };
+    let tilemap_size = TilemapSize { x: 2, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    let mut tile_entities = vec![];
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileObjectStacks::new(vec![
+                    (
+                        ground.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                    (
+                        passenger.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                ]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+        tile_entities.push(tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage));
+
+    let spawn_object = |world: &mut World, id: usize, x: u32, stacking_class: &StackingClass| {
+        let entity = world
+            .spawn((
+                Object,
+                ObjectId { id },
+                ObjectGridPosition {
+                    tile_position: TilePos::new(x, 0).into(),
+                },
+                ObjectStackingClass {
+                    stack_class: stacking_class.clone(),
+                },
+            ))
+            .id();
+        AddObjectToTile {
+            object_game_id: ObjectId { id },
+            on_map: map_id,
+            tile_pos: TilePos::new(x, 0),
+            strict: false,
+        }
+        .execute(world)
+        .unwrap();
+        entity
+    };
+    let transport = spawn_object(&mut world, 0, 0, &ground);
+    let carried = spawn_object(&mut world, 1, 0, &passenger);
+    spawn_object(&mut world, 2, 1, &ground);
+    world
+        .entity_mut(carried)
+        .set_parent(transport)
+        .insert(CarriedByParent);
+    let objects_in = |world: &World, x: usize| -> Vec<ObjectId> {
+        let mut objects = world
+            .get::<TileObjects>(tile_entities[x])
+            .unwrap()
+            .entities_in_tile
+            .clone();
+        objects.sort_by_key(|object_id| object_id.id);
+        objects
+    };
+
+    // The passenger doesn't fit in the other tile so nothing is swapped
+    world
+        .get_mut::<TileObjectStacks>(tile_entities[1])
+        .unwrap()
+        .tile_object_stacks
+        .get_mut(&passenger)
+        .unwrap()
+        .max_count = 0;
+    let mut swap = SwapObjects {
+        a: ObjectId { id: 0 },
+        b: ObjectId { id: 2 },
+        on_map: None,
+        a_tile_pos: None,
+        b_tile_pos: None,
+        a_carried: vec![],
+        b_carried: vec![],
+    };
+    assert!(swap.execute(&mut world).is_err());
+    assert_eq!(
+        objects_in(&world, 0),
+        vec![ObjectId { id: 0 }, ObjectId { id: 1 }]
+    );
+
+    world
+        .get_mut::<TileObjectStacks>(tile_entities[1])
+        .unwrap()
+        .tile_object_stacks
+        .get_mut(&passenger)
+        .unwrap()
+        .max_count = 1;
+    swap.execute(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 0), vec![ObjectId { id: 2 }]);
+    assert_eq!(
+        objects_in(&world, 1),
+        vec![ObjectId { id: 0 }, ObjectId { id: 1 }]
+    );
+    assert_eq!(
+        world
+            .get::<ObjectGridPosition>(carried)
+            .unwrap()
+            .tile_position,
+        TilePos::new(1, 0).into()
+    );
+
+    swap.rollback(&mut world).unwrap();
+    assert_eq!(
+        objects_in(&world, 0),
+        vec![ObjectId { id: 0 }, ObjectId { id: 1 }]
+    );
+    assert_eq!(objects_in(&world, 1), vec![ObjectId { id: 2 }]);
+    assert_eq!(
+        world
+            .get::<TileObjectStacks>(tile_entities[1])
+            .unwrap()
+            .tile_object_stacks
+            .get(&passenger)
+            .unwrap()
+            .current_count,
+        0
+    );
+}
+
+#[test]
+fn test_despawn_player_objects_rollback() {
+    use crate::game_core::saving::GameSerDeRegistry;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount};
+    use crate::object::{ObjectDespawned, TagIndex, Tags};
+    use bevy::ecs::event::Events;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.insert_resource(GameSerDeRegistry::default_registry());
+    world.insert_resource(DespawnedObjects {
+        despawned_objects: Default::default(),
+    });
+    world.init_resource::<TagIndex>();
+    world.init_resource::<Events<ObjectDespawned>>();
+
+    let ground = StackingClass {
+        name: String::from("Ground"),
+    };
+    let tilemap_size = TilemapSize { x: 3, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    let mut tile_entities = vec![];
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileObjectStacks::new(vec![(
+                    ground.clone(),
+                    TileObjectStacksCount {
+                        current_count: 0,
+                        max_count: 1,
+                    },
+                )]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+        tile_entities.push(tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage));
+
+    for (id, player_id, tag) in [(0, 0, "scout"), (1, 0, "boss"), (2, 1, "guard")] {
+        let entity = world
+            .spawn((
+                Object,
+                ObjectId { id },
+                ObjectGridPosition {
+                    tile_position: TilePos::new(id as u32, 0).into(),
+                },
+                ObjectStackingClass {
+                    stack_class: ground.clone(),
+                },
+                PlayerMarker::new(player_id),
+                Tags::new([tag]),
+            ))
+            .id();
+        crate::object::index_object_tags(&mut world, entity);
+        AddObjectToTile {
+            object_game_id: ObjectId { id },
+            on_map: map_id,
+            tile_pos: TilePos::new(id as u32, 0),
+            strict: false,
+        }
+        .execute(&mut world)
+        .unwrap();
+    }
+    let ground_count = |world: &World, x: usize| {
+        world
+            .get::<TileObjectStacks>(tile_entities[x])
+            .unwrap()
+            .tile_object_stacks
+            .get(&ground)
+            .unwrap()
+            .current_count
+    };
+    let objects_in = |world: &World, x: usize| {
+        world
+            .get::<TileObjects>(tile_entities[x])
+            .unwrap()
+            .entities_in_tile
+            .clone()
+    };
+
+    let mut despawn = DespawnPlayerObjects {
+        player_id: 0,
+        on_map: map_id,
+        despawned_objects: vec![],
+    };
+    despawn.execute(&mut world).unwrap();
+
+    for x in 0..2 {
+        assert!(objects_in(&world, x).is_empty());
+        assert_eq!(ground_count(&world, x), 0);
+    }
+    assert_eq!(objects_in(&world, 2), vec![ObjectId { id: 2 }]);
+    let tag_index = world.resource::<TagIndex>();
+    assert_eq!(tag_index.object_with("scout"), None);
+    assert_eq!(tag_index.object_with("boss"), None);
+    assert_eq!(tag_index.object_with("guard"), Some(ObjectId { id: 2 }));
+    assert_eq!(
+        world.resource::<DespawnedObjects>().despawned_objects.len(),
+        2
+    );
+
+    despawn.rollback(&mut world).unwrap();
+
+    for x in 0..3 {
+        assert_eq!(objects_in(&world, x), vec![ObjectId { id: x }]);
+        assert_eq!(ground_count(&world, x), 1);
+    }
+    let tag_index = world.resource::<TagIndex>();
+    assert_eq!(tag_index.object_with("scout"), Some(ObjectId { id: 0 }));
+    assert_eq!(tag_index.object_with("boss"), Some(ObjectId { id: 1 }));
+    assert_eq!(tag_index.object_with("guard"), Some(ObjectId { id: 2 }));
+    assert!(world
+        .resource::<DespawnedObjects>()
+        .despawned_objects
+        .is_empty());
+    let mut object_query = world.query::<(&ObjectId, &PlayerMarker, &ObjectGridPosition)>();
+    assert_eq!(object_query.iter(&world).count(), 3);
+    assert!(object_query
+        .iter(&world)
+        .all(|(object_id, _, grid_position)| grid_position.tile_position
+            == TilePos::new(object_id.id as u32, 0).into()));
+}
@@ -1,21 +1,35 @@
-﻿//!
+//!
 
+use crate::abilities::Cooldowns;
+use crate::experience::Experience;
 use crate::game_core::change_detection::{
     despawn_objects, track_component_changes, track_resource_changes,
 };
 use crate::game_core::command::{GameCommand, GameCommandMeta, GameCommandQueue, GameCommands};
+use crate::game_core::rules::GameRules;
 use crate::game_core::runner::{GameRunner, GameRuntime, PostBaseSets, PreBaseSets};
 use crate::game_core::state::{
-    DespawnedObjects, GameStateHandler, ResourceChangeTracking, StateEvents,
+    DespawnedObjects, GameStateHandler, ObjectState, ResourceChangeTracking, ResourceState,
+    StateEvents,
+};
+use crate::mapping::terrain::{TerrainType, TileTerrainInfo};
+use crate::mapping::tiles::{
+    IgnoresStacking, ObjectStackingClass, Tile, TileObjectStacks, TileObjects, TilePosition,
+};
+use crate::mapping::{MapIdProvider, SpawnRandomMap};
+use crate::movement::{
+    MovementSystem, MovementType, MovementTypes, ObjectMovement, ObjectMovementCostModifiers,
+    ObjectTypeMovementRules, PendingMovementCallback, TerrainMovementCosts, TileEdgeCosts,
+    TileMovementCosts,
+};
+use crate::object::{
+    Object, ObjectDespawned, ObjectGridPosition, ObjectId, ObjectIdProvider, ObjectInfo,
+    ObjectSpawned, TagIndex, Tags,
 };
-use crate::mapping::terrain::TileTerrainInfo;
-use crate::mapping::tiles::{ObjectStackingClass, Tile, TileObjects, TilePosition};
-use crate::mapping::MapIdProvider;
-use crate::movement::TileMovementCosts;
-use crate::object::{Object, ObjectGridPosition, ObjectId, ObjectIdProvider, ObjectInfo};
 use crate::player::{Player, PlayerList, PlayerMarker};
 use bevy::ecs::world::EntityMut;
 use bevy::prelude::*;
+use bevy::reflect::GetTypeRegistration;
 use bevy_ecs_tilemap::tiles::TilePos;
 use bevy_trait_query::RegisterExt;
 use chrono::{DateTime, Utc};
@@ -23,11 +37,12 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::default::Default;
 
-use self::saving::{GameSerDeRegistry, SaveId};
+use self::saving::{CommandId, GameCommandRegistry, GameSerDeRegistry, SaveId};
 
 pub mod change_detection;
 pub mod command;
 pub mod requests;
+pub mod rules;
 pub mod runner;
 pub mod save_id_implementations;
 pub mod saving;
@@ -57,11 +72,90 @@ impl Game {
             .get_state_diff(&mut self.game_world, for_player_id)
     }
 
+    /// Returns the entire game state for the given player to resync from. See
+    /// [`GameStateHandler::full_state_for`]
+    pub fn full_state_for(&mut self, player_id: usize) -> StateEvents {
+        self.game_state_handler
+            .full_state_for(&mut self.game_world, player_id)
+    }
+
+    /// Returns the current state of a single object. See [`GameStateHandler::object_state`]
+    pub fn object_state(&mut self, object_id: ObjectId) -> Option<ObjectState> {
+        self.game_state_handler
+            .object_state(&mut self.game_world, object_id)
+    }
+
+    /// Returns every state change regardless of player for spectators. See
+    /// [`GameStateHandler::get_state_diff_full`]
+    pub fn get_state_diff_full(&mut self) -> StateEvents {
+        self.game_state_handler
+            .get_state_diff_full(&mut self.game_world)
+    }
+
     pub fn clear_changed(&mut self) {
+        self.sync_player_list();
         self.game_state_handler
             .clear_changed(&mut self.game_world, &self.player_list);
     }
 
+    /// Copies the [`PlayerList`] resource in the game world into the games player_list so that players
+    /// added mid-game with [`AddPlayer`](command::AddPlayer) are included. Called automatically by
+    /// [`clear_changed`](Self::clear_changed) and [`set_player_needs_state`](Self::set_player_needs_state)
+    pub fn sync_player_list(&mut self) {
+        if let Some(player_list) = self.game_world.get_resource::<PlayerList>() {
+            self.player_list = player_list.clone();
+        }
+    }
+
+    /// Sets whether the given player needs state, updating the games player_list, the [`PlayerList`]
+    /// resource, and the players [`Player`] component.
+    ///
+    /// Setting it to false drops the player from the set of players that must see a change before it
+    /// is cleared - use this when a player disconnects. Pending [`Changed`](state::Changed)
+    /// components, [`DespawnedObjects`], and [`ResourceChangeTracking`] entries are re-evaluated
+    /// immediately so that anything only waiting on that player is cleared.
+    pub fn set_player_needs_state(
+        &mut self,
+        player_id: usize,
+        needs_state: bool,
+    ) -> Result<(), String> {
+        self.sync_player_list();
+        if !self
+            .player_list
+            .set_player_needs_state(player_id, needs_state)
+        {
+            return Err(format!("Player: {} not found", player_id));
+        }
+        if let Some(mut player_list) = self.game_world.get_resource_mut::<PlayerList>() {
+            player_list.set_player_needs_state(player_id, needs_state);
+        }
+        let mut player_query = self.game_world.query::<&mut Player>();
+        for mut player in player_query.iter_mut(&mut self.game_world) {
+            if player.id() == player_id {
+                player.needs_state = needs_state;
+            }
+        }
+
+        if !needs_state {
+            self.clear_changed();
+        }
+        Ok(())
+    }
+
+    /// Deserializes the given resource state into the game world using the games
+    /// [`GameSerDeRegistry`]. If the resource is the [`PlayerList`] then the games player_list is
+    /// updated to match so that a loaded save restores the full player roster
+    pub fn apply_resource_state(&mut self, resource_state: &ResourceState) {
+        self.component_registry
+            .deserialize_resource_into_world(resource_state, &mut self.game_world);
+
+        if resource_state.resource_id == PlayerList::save_id_const() {
+            if let Some(player_list) = self.game_world.get_resource::<PlayerList>() {
+                self.player_list = player_list.clone();
+            }
+        }
+    }
+
     pub fn execute_game_commands(&mut self) {}
 }
 
@@ -77,10 +171,12 @@ where
     /// A schedule that is run after the GameRunner::simulate_game function
     pub game_post_schedule: Schedule,
     pub game_world: World,
-    /// A schedule that is run as the last item before inserting the Game Resource during setup. Use
-    /// this for systems that must be run once when the game is setup and only then
+    /// A schedule that is run once as the last item before inserting the Game Resource during setup,
+    /// after the game resources are inserted and the initial commands are executed. Use this for
+    /// systems that must be run once when the game is setup and only then
     pub setup_schedule: Schedule,
     pub game_serde_registry: GameSerDeRegistry,
+    pub game_command_registry: GameCommandRegistry,
     pub commands: Option<GameCommands>,
     pub next_player_id: usize,
     pub player_list: PlayerList,
@@ -93,8 +189,12 @@ where
     pub fn new_game(game_runner: GR) -> GameBuilder<GR> {
         let mut game_world = World::new();
 
+        game_world.init_resource::<AppTypeRegistry>();
         game_world.insert_resource(GameCommands::default());
         game_world.insert_resource(ObjectIdProvider::default());
+        game_world.insert_resource(MapIdProvider::default());
+        game_world.init_resource::<Events<ObjectSpawned>>();
+        game_world.init_resource::<Events<ObjectDespawned>>();
 
         GameBuilder {
             game_runner,
@@ -103,9 +203,10 @@ where
             game_world,
             setup_schedule: GameBuilder::<GR>::default_setup_schedule(),
             game_serde_registry: GameSerDeRegistry::default_registry(),
+            game_command_registry: GameCommandRegistry::default(),
             commands: Default::default(),
             next_player_id: 0,
-            player_list: PlayerList { players: vec![] },
+            player_list: PlayerList::default(),
         }
     }
     pub fn new_game_with_commands(
@@ -119,13 +220,17 @@ where
             game_command_queue.push(GameCommandMeta {
                 command,
                 command_time: utc,
+                priority: 0,
             })
         }
 
         let mut game_world = World::new();
 
+        game_world.init_resource::<AppTypeRegistry>();
         game_world.insert_resource(ObjectIdProvider::default());
         game_world.insert_resource(MapIdProvider::default());
+        game_world.init_resource::<Events<ObjectSpawned>>();
+        game_world.init_resource::<Events<ObjectDespawned>>();
 
         GameBuilder {
             game_runner,
@@ -134,14 +239,17 @@ where
             game_world,
             setup_schedule: GameBuilder::<GR>::default_setup_schedule(),
             game_serde_registry: GameSerDeRegistry::default_registry(),
+            game_command_registry: GameCommandRegistry::default(),
             commands: Some(GameCommands {
                 queue: GameCommandQueue {
                     queue: game_command_queue,
                 },
                 history: Default::default(),
+                resolution: Default::default(),
+                observers: vec![],
             }),
             next_player_id: 0,
-            player_list: PlayerList { players: vec![] },
+            player_list: PlayerList::default(),
         }
     }
 
@@ -165,8 +273,12 @@ where
             .register_component_as::<dyn SaveId, TileTerrainInfo>();
         self.game_world
             .register_component_as::<dyn SaveId, TileObjects>();
+        self.game_world
+            .register_component_as::<dyn SaveId, TileObjectStacks>();
         self.game_world
             .register_component_as::<dyn SaveId, TileMovementCosts>();
+        self.game_world
+            .register_component_as::<dyn SaveId, TileEdgeCosts>();
         self.game_world
             .register_component_as::<dyn SaveId, ObjectId>();
         self.game_world
@@ -179,6 +291,19 @@ where
             .register_component_as::<dyn SaveId, Player>();
         self.game_world
             .register_component_as::<dyn SaveId, ObjectStackingClass>();
+        self.game_world
+            .register_component_as::<dyn SaveId, IgnoresStacking>();
+        self.game_world
+            .register_component_as::<dyn SaveId, ObjectMovement>();
+        self.game_world
+            .register_component_as::<dyn SaveId, ObjectTypeMovementRules>();
+        self.game_world
+            .register_component_as::<dyn SaveId, ObjectMovementCostModifiers>();
+        self.game_world.register_component_as::<dyn SaveId, Tags>();
+        self.game_world
+            .register_component_as::<dyn SaveId, Cooldowns>();
+        self.game_world
+            .register_component_as::<dyn SaveId, Experience>();
         self.game_world
             .register_component_as::<dyn SaveId, PlayerMarker>();
     }
@@ -191,16 +316,25 @@ where
         self.register_component_track_changes::<Tile>();
         self.register_component_track_changes::<TileTerrainInfo>();
         self.register_component_track_changes::<TileObjects>();
-        //self.register_component_track_changes::<TileObjectStacks>();
+        self.register_component_track_changes::<TileObjectStacks>();
         self.register_component_track_changes::<TileMovementCosts>();
+        self.register_component_track_changes::<TileEdgeCosts>();
 
         self.register_component_track_changes::<ObjectId>();
         self.register_component_track_changes::<ObjectGridPosition>();
         self.register_component_track_changes::<Object>();
         self.register_component_track_changes::<ObjectStackingClass>();
+        self.register_component_track_changes::<IgnoresStacking>();
         self.register_component_track_changes::<ObjectInfo>();
+        self.register_component_track_changes::<ObjectMovement>();
+        self.register_component_track_changes::<ObjectTypeMovementRules>();
+        self.register_component_track_changes::<ObjectMovementCostModifiers>();
+        self.register_component_track_changes::<Tags>();
+        self.register_component_track_changes::<Cooldowns>();
+        self.register_component_track_changes::<Experience>();
 
         self.register_component_track_changes::<PlayerMarker>();
+        self.register_component_track_changes::<Player>();
     }
 
     /// Inserts a system into GameRunner::game_post_schedule that will track the specified Component
@@ -233,6 +367,28 @@ where
         self.register_component_track_changes::<Type>();
     }
 
+    /// Registers a command with the given id in the [`GameCommandRegistry`] so that it can be
+    /// serialized and deserialized, eg to save the command history or send commands to other clients
+    pub fn register_command<Type>(&mut self, id: CommandId)
+    where
+        Type: GameCommand + Serialize + DeserializeOwned,
+    {
+        self.game_command_registry.register_command::<Type>(id);
+    }
+
+    /// Registers a component in the game worlds [`AppTypeRegistry`] so that it can be inserted and
+    /// removed generically with the [`SetComponent`](command::SetComponent) and
+    /// [`RemoveComponent`](command::RemoveComponent) commands. The component must reflect Component
+    pub fn register_reflect_component<Type>(&mut self)
+    where
+        Type: Component + GetTypeRegistration,
+    {
+        self.game_world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Type>();
+    }
+
     pub fn default_setup_schedule() -> Schedule {
         let schedule = Schedule::default();
 
@@ -281,34 +437,147 @@ where
         schedule
     }
 
+    /// Adds a player during setup. Use [`GameCommands::add_player`] to add players once the game is
+    /// running
     pub fn add_player(&mut self, needs_state: bool) -> (usize, EntityMut) {
         let new_player_id = self.next_player_id;
         self.next_player_id += 1;
-        let player_entity = self
-            .game_world
-            .spawn(Player::new(new_player_id, needs_state));
+        let player_entity = self.game_world.spawn((
+            Player::new(new_player_id, needs_state),
+            state::Changed::default(),
+        ));
         self.player_list
             .players
             .push(Player::new(new_player_id, needs_state));
         (new_player_id, player_entity)
     }
 
-    pub fn build(mut self, main_world: &mut World) {
-        self.setup_schedule.run(&mut self.game_world);
+    /// Sets the [`GameRules`] for the game. The default rules are used if this isn't called
+    pub fn set_game_rules(&mut self, game_rules: GameRules) {
+        self.game_world.insert_resource(game_rules);
+    }
+
+    /// Checks the builder for common misconfigurations that would otherwise panic or misbehave once
+    /// the game is running. Returns every problem found. Called automatically by
+    /// [`build`](Self::build). See [`SetupError`] for the checks that are done.
+    pub fn validate(&self) -> Result<(), Vec<SetupError>> {
+        let mut errors: Vec<SetupError> = vec![];
+
+        if self.player_list.players.is_empty() {
+            errors.push(SetupError::NoPlayers);
+        }
+
+        let terrain_movement_costs = self.game_world.get_resource::<TerrainMovementCosts>();
+        if (terrain_movement_costs.is_some()
+            || self
+                .game_world
+                .contains_resource::<PendingMovementCallback>())
+            && !self.game_world.contains_resource::<MovementSystem>()
+        {
+            errors.push(SetupError::MissingMovementSystem);
+        }
+
+        if let Some(commands) = self.commands.as_ref() {
+            for command_meta in commands.queue.iter() {
+                let Some(spawn_map) = command_meta
+                    .command
+                    .as_reflect()
+                    .downcast_ref::<SpawnRandomMap>()
+                else {
+                    continue;
+                };
+                for terrain_type in spawn_map.terrain_types() {
+                    let has_costs = matches!(terrain_movement_costs, Some(terrain_movement_costs)
+                        if terrain_movement_costs.movement_cost_rules.contains_key(terrain_type));
+                    let error = SetupError::TerrainWithoutMovementCosts(terrain_type.clone());
+                    if !has_costs && !errors.contains(&error) {
+                        errors.push(error);
+                    }
+                }
+            }
+        }
+
+        if let Some(movement_types) = self.game_world.get_resource::<MovementTypes>() {
+            let mut used_movement_types: Vec<&MovementType> = vec![];
+            if let Some(terrain_movement_costs) = terrain_movement_costs {
+                for tile_movement_costs in terrain_movement_costs.movement_cost_rules.values() {
+                    used_movement_types.extend(tile_movement_costs.movement_type_cost.keys());
+                }
+            }
+            for entity in self.game_world.iter_entities() {
+                if let Some(object_movement) = entity.get::<ObjectMovement>() {
+                    used_movement_types.push(&object_movement.movement_type);
+                }
+            }
+            for movement_type in used_movement_types {
+                let error = SetupError::UnregisteredMovementType(movement_type.clone());
+                if !movement_types.contains(movement_type) && !errors.contains(&error) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Builds the game, inserting the [`Game`], [`GameRuntime`], and [`GameCommands`] resources into
+    /// the main world. Fails without changing the main world if [`validate`](Self::validate) finds
+    /// any problems.
+    pub fn build(mut self, main_world: &mut World) -> Result<(), Vec<SetupError>> {
+        self.validate()?;
+
+        self.game_world.insert_resource(self.player_list.clone());
+        if !self
+            .game_serde_registry
+            .is_resource_registered::<PlayerList>()
+        {
+            let player_list_id = self
+                .game_world
+                .components()
+                .resource_id::<PlayerList>()
+                .expect("PlayerList was inserted above");
+            self.game_serde_registry
+                .register_resource::<PlayerList>(player_list_id);
+        }
+        self.register_resource_track_changes::<PlayerList>();
+
+        if !self.game_world.contains_resource::<GameRules>() {
+            self.game_world.insert_resource(GameRules::default());
+        }
+        if !self
+            .game_serde_registry
+            .is_resource_registered::<GameRules>()
+        {
+            let game_rules_id = self
+                .game_world
+                .components()
+                .resource_id::<GameRules>()
+                .expect("GameRules was inserted above");
+            self.game_serde_registry
+                .register_resource::<GameRules>(game_rules_id);
+        }
+        self.register_resource_track_changes::<GameRules>();
+
         main_world.insert_resource::<GameRuntime<GR>>(GameRuntime {
             game_runner: self.game_runner,
             game_pre_schedule: self.game_pre_schedule,
             game_post_schedule: self.game_post_schedule,
+            paused: false,
         });
         self.game_world
             .insert_resource(self.game_serde_registry.clone());
+        self.game_world
+            .insert_resource(self.game_command_registry.clone());
         self.game_world.insert_resource(DespawnedObjects {
             despawned_objects: Default::default(),
         });
         self.game_world.insert_resource(ResourceChangeTracking {
             resources: Default::default(),
         });
-        self.game_world.insert_resource(self.player_list.clone());
+        self.game_world.insert_resource(TagIndex::default());
 
         if let Some(commands) = self.commands.as_mut() {
             commands.execute_buffer(&mut self.game_world);
@@ -326,5 +595,158 @@ where
             game_state_handler: Default::default(),
             player_list: self.player_list,
         });
+        Ok(())
     }
 }
+
+/// A misconfiguration found by [`GameBuilder::validate`]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SetupError {
+    /// No players were added with [`GameBuilder::add_player`]
+    NoPlayers,
+    /// Movement was setup with [`setup_movement`](crate::movement::GameBuilderMovementExt::setup_movement)
+    /// or a callback was given to
+    /// [`with_movement_callback`](crate::movement::GameBuilderMovementExt::with_movement_callback)
+    /// but no [`MovementSystem`] was added with
+    /// [`with_movement_calculator`](crate::movement::GameBuilderMovementExt::with_movement_calculator)
+    MissingMovementSystem,
+    /// A [`SpawnRandomMap`] command uses a [`TerrainType`] that has no entry in the
+    /// [`TerrainMovementCosts`]
+    TerrainWithoutMovementCosts(TerrainType),
+    /// A [`MovementType`] is used in the [`TerrainMovementCosts`] or by an object but isn't in the
+    /// [`MovementTypes`]. Only checked if the [`MovementTypes`] were setup
+    UnregisteredMovementType(MovementType),
+}
+
+impl std::fmt::Display for SetupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetupError::NoPlayers => write!(f, "No players were added to the game"),
+            SetupError::MissingMovementSystem => write!(
+                f,
+                "Movement was setup but no MovementSystem was added with with_movement_calculator"
+            ),
+            SetupError::TerrainWithoutMovementCosts(terrain_type) => write!(
+                f,
+                "TerrainType {} is used to spawn a map but has no TerrainMovementCosts",
+                terrain_type.name
+            ),
+            SetupError::UnregisteredMovementType(movement_type) => write!(
+                f,
+                "MovementType {} is used but is not registered in MovementTypes",
+                movement_type.name
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_setup_schedule_runs_once_after_commands() {
+    use crate::game_core::runner::TurnBasedGameRunner;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacks, TileObjectStacksCount};
+    use crate::mapping::MapId;
+    use bevy_ecs_tilemap::prelude::{TileStorage, TilemapSize};
+
+    #[derive(Bundle, Clone, Reflect)]
+    struct TestObjectBundle {
+        object: Object,
+        object_stacking_class: ObjectStackingClass,
+    }
+
+    #[derive(Default, Resource)]
+    struct SetupRuns {
+        objects_seen: Vec<usize>,
+    }
+
+    fn count_objects(mut setup_runs: ResMut<SetupRuns>, object_query: Query<&ObjectId>) {
+        setup_runs.objects_seen.push(object_query.iter().count());
+    }
+
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let map_id = MapId { id: 0 };
+    let tile_pos = TilePos::new(0, 0);
+
+    let mut game_commands = GameCommands::new();
+    let spawn = game_commands.spawn_object(
+        TestObjectBundle {
+            object: Object,
+            object_stacking_class: ObjectStackingClass {
+                stack_class: stacking_class.clone(),
+            },
+        },
+        tile_pos,
+        map_id,
+        0,
+    );
+
+    let mut game = GameBuilder::new_game_with_commands(
+        vec![Box::new(spawn)],
+        TurnBasedGameRunner {
+            turn_schedule: Schedule::default(),
+        },
+    );
+    let tile_entity = game
+        .game_world
+        .spawn((
+            TileObjectStacks::new(vec![(
+                stacking_class,
+                TileObjectStacksCount {
+                    current_count: 0,
+                    max_count: 1,
+                },
+            )]),
+            TileObjects::default(),
+        ))
+        .id();
+    let mut tile_storage = TileStorage::empty(TilemapSize { x: 1, y: 1 });
+    tile_storage.set(&tile_pos, tile_entity);
+    game.game_world.spawn((map_id, tile_storage));
+
+    game.game_world.init_resource::<SetupRuns>();
+    game.setup_schedule.add_system(count_objects);
+
+    game.add_player(false);
+
+    let mut main_world = World::new();
+    game.build(&mut main_world).unwrap();
+
+    let game = main_world.resource::<Game>();
+    assert_eq!(
+        game.game_world.resource::<SetupRuns>().objects_seen,
+        vec![1]
+    );
+}
+
+#[test]
+fn test_unregistered_movement_type_reported_by_validate() {
+    use crate::game_core::runner::TurnBasedGameRunner;
+    use crate::mapping::terrain::TerrainClass;
+    use crate::movement::GameBuilderMovementExt;
+
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let wheel = MovementType {
+        name: String::from("Wheel"),
+    };
+    let grassland = TerrainType {
+        name: String::from("Grassland"),
+        terrain_class: TerrainClass {
+            name: String::from("Ground"),
+        },
+    };
+
+    let mut game_builder = GameBuilder::new_game(TurnBasedGameRunner {
+        turn_schedule: Schedule::default(),
+    });
+    game_builder.setup_movement_types(vec![foot.clone()]);
+    game_builder.setup_movement(vec![(
+        grassland,
+        TileMovementCosts::new(vec![(foot, 1), (wheel.clone(), 2)]),
+    )]);
+
+    let errors = game_builder.validate().unwrap_err();
+    assert!(errors.contains(&SetupError::UnregisteredMovementType(wheel)));
+}
@@ -0,0 +1,55 @@
+//! Game wide rule settings. The [`GameRules`] resource is inserted into the game world by
+//! [`GameBuilder::build`](crate::game_core::GameBuilder::build) and is registered as a tracked resource
+//! so that changes to it are saved and sent to every client in state events.
+//!
+//! Set the rules for a game with [`GameBuilder::set_game_rules`](crate::game_core::GameBuilder::set_game_rules).
+
+use bevy::prelude::{Resource, World};
+use serde::{Deserialize, Serialize};
+
+/// Toggles for rules that several built in systems read. Each field lists the systems that read it.
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug, Resource, Serialize, Deserialize)]
+pub struct GameRules {
+    /// Whether players can only see what their objects can see. Read by
+    /// [`check_attack`](crate::combat::check_attack) and
+    /// [`select_object_at_tile_pos`](crate::selection::select_object_at_tile_pos), which hide objects
+    /// on tiles that aren't [`visible_to_player`](crate::mapping::vision::visible_to_player). Games
+    /// implementing fog of war with [`RevealObject`](crate::game_core::command::RevealObject) should
+    /// check it too
+    pub fog_of_war: bool,
+    /// Read by [`check_attack`](crate::combat::check_attack). When true objects can attack objects
+    /// belonging to the same player
+    pub friendly_fire: bool,
+    /// Read by [`AttackObject`](crate::combat::commands::AttackObject). When true a defender that
+    /// survives an attack strikes back if the attacker is within its range
+    pub counter_attacks: bool,
+    /// Read by [`AttackObject`](crate::combat::commands::AttackObject). When false objects with
+    /// [`OnDeath::Capture`](crate::combat::OnDeath::Capture) are destroyed instead
+    pub capture_enabled: bool,
+    /// Read by [`SquareMovementCalculator`](crate::movement::defaults::SquareMovementCalculator) and
+    /// [`path_cost`](crate::movement::path_cost). Overrides the calculators own
+    /// [`DiagonalMovement`](crate::movement::DiagonalMovement) setting when Some
+    pub diagonal_movement: Option<bool>,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        GameRules {
+            fog_of_war: false,
+            friendly_fire: false,
+            counter_attacks: false,
+            capture_enabled: true,
+            diagonal_movement: None,
+        }
+    }
+}
+
+impl GameRules {
+    /// Returns the [`GameRules`] in the world or the default rules if there are none
+    pub fn get(world: &World) -> GameRules {
+        world
+            .get_resource::<GameRules>()
+            .copied()
+            .unwrap_or_default()
+    }
+}
@@ -1,7 +1,14 @@
+use crate::game_core::command::GameCommands;
 use bevy::prelude::{Resource, Schedule, SystemSet, World};
+use std::time::Duration;
 
 /// Runtime that is used to drive the game. Users can implement whatever the want onto the GameRunner
 /// and then call [GameRuntime::simulate()] in order to drive their game forward.
+///
+/// The runtime can be paused with [`GameRuntime::pause`] for menus, cutscenes, or while waiting on
+/// network input. While paused [`GameRuntime::simulate`] and [`GameRuntime::execute_commands`] do
+/// nothing, commands submitted to the [`GameCommands`] stay queued until the runtime is resumed, and
+/// the game state can still be read.
 #[derive(Resource)]
 pub struct GameRuntime<T>
 where
@@ -10,6 +17,7 @@ where
     pub game_runner: T,
     pub game_pre_schedule: Schedule,
     pub game_post_schedule: Schedule,
+    pub paused: bool,
 }
 
 impl<T> GameRuntime<T>
@@ -17,10 +25,37 @@ where
     T: GameRunner,
 {
     pub fn simulate(&mut self, mut world: &mut World) {
+        if self.paused {
+            return;
+        }
         self.game_pre_schedule.run(&mut world);
         self.game_runner.simulate_game(&mut world);
         self.game_post_schedule.run(&mut world);
     }
+
+    /// Executes the commands buffered in the given [`GameCommands`] on the game world unless the
+    /// runtime is paused, in which case they stay queued
+    pub fn execute_commands(&self, game_commands: &mut GameCommands, world: &mut World) {
+        if self.paused {
+            return;
+        }
+        game_commands.execute_buffer(world);
+    }
+
+    /// Pauses the runtime. See [`GameRuntime`]
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes the runtime. Commands queued while it was paused are executed the next time
+    /// [`GameRuntime::execute_commands`] is called
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 }
 
 // SystemSet for the GameRunner FrameworkPostSchedule
@@ -77,3 +112,86 @@ impl GameRunner for RealTimeGameRunner {
         self.tick_schedule.run(world);
     }
 }
+
+/// A game runner for real time games that need game logic to advance at a fixed rate regardless of
+/// frame rate, eg continuous movement or physics. Time is added with
+/// [`accumulate`](FixedTimestepGameRunner::accumulate) and every full `timestep` of accumulated time
+/// runs the tick schedule once. Leftover time carries over to the next call.
+///
+/// At most `max_steps` ticks are run per call so a long frame can't stall the game, any time past that
+/// is kept and caught up on in later calls.
+///
+/// Use [`GameRuntime::simulate_fixed`] to run the runtimes pre and post schedules around every tick.
+/// [`GameRuntime::simulate`] also works but only runs them once around all of the ticks.
+pub struct FixedTimestepGameRunner {
+    pub timestep: Duration,
+    pub max_steps: u32,
+    pub accumulator: Duration,
+    pub ticks: usize,
+    pub tick_schedule: Schedule,
+}
+
+impl FixedTimestepGameRunner {
+    /// Creates a new runner that ticks once every `timestep` and runs at most 8 ticks per call
+    pub fn new(timestep: Duration, tick_schedule: Schedule) -> FixedTimestepGameRunner {
+        FixedTimestepGameRunner {
+            timestep,
+            max_steps: 8,
+            accumulator: Duration::ZERO,
+            ticks: 0,
+            tick_schedule,
+        }
+    }
+
+    /// Adds the given time, usually the frames delta time, to the accumulator
+    pub fn accumulate(&mut self, delta: Duration) {
+        self.accumulator = self.accumulator.saturating_add(delta);
+    }
+
+    /// Removes one timestep from the accumulator and returns true if there was a full timestep
+    /// accumulated. Always false if the timestep is zero
+    pub fn take_step(&mut self) -> bool {
+        if self.timestep.is_zero() || self.accumulator < self.timestep {
+            return false;
+        }
+        self.accumulator -= self.timestep;
+        true
+    }
+
+    /// Runs a single tick of the tick schedule
+    pub fn tick(&mut self, world: &mut World) {
+        self.ticks = self.ticks.saturating_add(1);
+        self.tick_schedule.run(world);
+    }
+}
+
+impl GameRunner for FixedTimestepGameRunner {
+    fn simulate_game(&mut self, world: &mut World) {
+        let mut steps = 0;
+        while steps < self.max_steps && self.take_step() {
+            self.tick(world);
+            steps += 1;
+        }
+    }
+}
+
+impl GameRuntime<FixedTimestepGameRunner> {
+    /// Adds the given delta time to the runners accumulator and runs every full timestep, up to the
+    /// runners `max_steps`, with the pre and post schedules run around each tick so that every tick is
+    /// change detected on its own. Does nothing while the runtime is paused, time passed while paused
+    /// isn't accumulated.
+    pub fn simulate_fixed(&mut self, world: &mut World, delta: Duration) {
+        if self.paused {
+            return;
+        }
+        self.game_runner.accumulate(delta);
+
+        let mut steps = 0;
+        while steps < self.game_runner.max_steps && self.game_runner.take_step() {
+            self.game_pre_schedule.run(world);
+            self.game_runner.tick(world);
+            self.game_post_schedule.run(world);
+            steps += 1;
+        }
+    }
+}
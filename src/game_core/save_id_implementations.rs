@@ -1,12 +1,21 @@
 use crate::{
+    abilities::Cooldowns,
+    experience::Experience,
     mapping::{
         terrain::TileTerrainInfo,
-        tiles::{ObjectStackingClass, Tile, TileObjects, TilePosition},
+        tiles::{
+            IgnoresStacking, ObjectStackingClass, Tile, TileObjectStacks, TileObjects, TilePosition,
+        },
     },
-    movement::TileMovementCosts,
-    object::{Object, ObjectGridPosition, ObjectId, ObjectInfo}, player::{Player, PlayerMarker},
+    movement::{
+        ObjectMovement, ObjectMovementCostModifiers, ObjectTypeMovementRules, TileEdgeCosts,
+        TileMovementCosts,
+    },
+    object::{Object, ObjectGridPosition, ObjectId, ObjectInfo, Tags},
+    player::{Player, PlayerList, PlayerMarker},
 };
 
+use super::rules::GameRules;
 use super::saving::{BinaryComponentId, SaveId};
 
 impl SaveId for TilePosition {
@@ -224,3 +233,201 @@ impl SaveId for ObjectInfo {
         bincode::serialize(self).ok()
     }
 }
+
+impl SaveId for Cooldowns {
+    fn save_id(&self) -> BinaryComponentId {
+        12
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        12
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for TileEdgeCosts {
+    fn save_id(&self) -> BinaryComponentId {
+        13
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        13
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for PlayerList {
+    fn save_id(&self) -> BinaryComponentId {
+        14
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        14
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for GameRules {
+    fn save_id(&self) -> BinaryComponentId {
+        15
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        15
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for ObjectMovement {
+    fn save_id(&self) -> BinaryComponentId {
+        16
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        16
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for ObjectTypeMovementRules {
+    fn save_id(&self) -> BinaryComponentId {
+        17
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        17
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for Experience {
+    fn save_id(&self) -> BinaryComponentId {
+        18
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        18
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for TileObjectStacks {
+    fn save_id(&self) -> BinaryComponentId {
+        19
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        19
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for IgnoresStacking {
+    fn save_id(&self) -> BinaryComponentId {
+        20
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        20
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for ObjectMovementCostModifiers {
+    fn save_id(&self) -> BinaryComponentId {
+        21
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        21
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
+
+impl SaveId for Tags {
+    fn save_id(&self) -> BinaryComponentId {
+        22
+    }
+
+    fn save_id_const() -> BinaryComponentId
+    where
+        Self: Sized,
+    {
+        22
+    }
+
+    #[doc = r" Serializes the state of the object at the given tick into binary. Only saves the keyframe and not the curve itself"]
+    fn to_binary(&self) -> Option<Vec<u8>> {
+        bincode::serialize(self).ok()
+    }
+}
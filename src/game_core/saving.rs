@@ -6,19 +6,28 @@ use bevy::{
     },
     utils::HashMap,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
+    abilities::Cooldowns,
+    experience::Experience,
     mapping::{
         terrain::TileTerrainInfo,
-        tiles::{ObjectStackingClass, Tile, TileObjects, TilePosition},
+        tiles::{
+            IgnoresStacking, ObjectStackingClass, Tile, TileObjectStacks, TileObjects, TilePosition,
+        },
     },
-    movement::TileMovementCosts,
-    object::{Object, ObjectGridPosition, ObjectId},
-    player::PlayerMarker,
+    movement::{
+        ObjectMovement, ObjectMovementCostModifiers, ObjectTypeMovementRules, TileEdgeCosts,
+        TileMovementCosts,
+    },
+    object::{Object, ObjectGridPosition, ObjectId, Tags},
+    player::{Player, PlayerMarker},
 };
 
+use super::command::GameCommand;
 use super::state::ResourceState;
+use std::any::TypeId;
 
 /// An id hand assigned to components using the [`SaveId`] trait that identifies each component
 ///
@@ -42,6 +51,8 @@ pub struct GameSerDeRegistry {
     pub component_de_map: HashMap<BinaryComponentId, ComponentDeserializeFn>,
     pub resource_de_map: HashMap<ResourceId, ResourceDeserializeFn>,
     pub resource_se_map: HashMap<ComponentId, ResourceSerializeFn>,
+    pub component_names: HashMap<BinaryComponentId, String>,
+    pub resource_names: HashMap<ResourceId, String>,
 }
 
 impl GameSerDeRegistry {
@@ -62,6 +73,8 @@ impl GameSerDeRegistry {
         }
         self.component_de_map
             .insert(C::save_id_const(), component_deserialize_onto::<C>);
+        self.component_names
+            .insert(C::save_id_const(), String::from(std::any::type_name::<C>()));
     }
 
     /// Registers a component into the [`GameSerDeRegistry`] for automatic serialization and deserialization
@@ -79,6 +92,8 @@ impl GameSerDeRegistry {
             .insert(R::save_id_const(), resource_deserialize_into_world::<R>);
         self.resource_se_map
             .insert(resource_component_id, serialize_resource_from_world::<R>);
+        self.resource_names
+            .insert(R::save_id_const(), String::from(std::any::type_name::<R>()));
     }
 
     pub fn deserialize_component_onto(&self, data: &ComponentBinaryState, entity: &mut EntityMut) {
@@ -87,6 +102,148 @@ impl GameSerDeRegistry {
         }
     }
 
+    /// Deserializes the given resource state and inserts it into the world, replacing the current
+    /// resource. Does nothing if the resource isn't registered
+    pub fn deserialize_resource_into_world(&self, data: &ResourceState, world: &mut World) {
+        if let Some(deserialize_fn) = self.resource_de_map.get(&data.resource_id) {
+            deserialize_fn(&data.resource, world);
+        }
+    }
+
+    /// Serializes the resource with the given [`ComponentId`] if it is registered and exists in the
+    /// world
+    pub fn serialize_resource(
+        &self,
+        resource_component_id: &ComponentId,
+        world: &mut World,
+    ) -> Option<ResourceState> {
+        let serialize_fn = self.resource_se_map.get(resource_component_id)?;
+        serialize_fn(world)
+    }
+
+    /// Returns true if the given component has been registered in the [`GameSerDeRegistry`]
+    pub fn is_component_registered<C>(&self) -> bool
+    where
+        C: Component + SaveId,
+    {
+        self.component_de_map.contains_key(&C::save_id_const())
+    }
+
+    /// Returns true if the given resource has been registered in the [`GameSerDeRegistry`]
+    pub fn is_resource_registered<R>(&self) -> bool
+    where
+        R: Resource + SaveId,
+    {
+        self.resource_de_map.contains_key(&R::save_id_const())
+    }
+
+    /// Returns the [`BinaryComponentId`] of every registered component, sorted in ascending order
+    pub fn registered_component_ids(&self) -> Vec<BinaryComponentId> {
+        let mut ids: Vec<BinaryComponentId> = self.component_de_map.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Returns the [`ResourceId`] of every registered resource, sorted in ascending order
+    pub fn registered_resource_ids(&self) -> Vec<ResourceId> {
+        let mut ids: Vec<ResourceId> = self.resource_de_map.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Returns a [`SchemaEntry`] for every registered component and resource, sorted by kind and then
+    /// id. Can be sent to the other side of a connection and compared using [`Self::schema_mismatches`]
+    pub fn schema_entries(&self) -> Vec<SchemaEntry> {
+        let mut entries: Vec<SchemaEntry> = vec![];
+        for id in self.registered_component_ids() {
+            entries.push(SchemaEntry {
+                kind: SchemaEntryKind::Component,
+                id,
+                type_name: self.component_names.get(&id).cloned().unwrap_or_default(),
+            });
+        }
+        for id in self.registered_resource_ids() {
+            entries.push(SchemaEntry {
+                kind: SchemaEntryKind::Resource,
+                id,
+                type_name: self.resource_names.get(&id).cloned().unwrap_or_default(),
+            });
+        }
+        entries
+    }
+
+    /// Returns a hash of the full set of registered components and resources, including their ids and
+    /// type names. The hash is deterministic so that a client and server can compare them to detect a
+    /// mismatch in registrations before their states desync.
+    ///
+    /// Type names come from [`std::any::type_name`] so both sides must be built from the same source
+    /// with the same compiler for the hashes to match.
+    pub fn schema_hash(&self) -> u64 {
+        let mut bytes: Vec<u8> = vec![];
+        for entry in self.schema_entries() {
+            bytes.push(match entry.kind {
+                SchemaEntryKind::Component => b'c',
+                SchemaEntryKind::Resource => b'r',
+            });
+            bytes.push(entry.id);
+            bytes.extend(entry.type_name.as_bytes());
+            bytes.push(0);
+        }
+        fnv1a_hash(&bytes)
+    }
+
+    /// Checks the given schema hash, usually received from the other side of a connection, against
+    /// this registries [`Self::schema_hash`]. Returns an error if they don't match.
+    pub fn check_schema_hash(&self, other_schema_hash: u64) -> Result<(), String> {
+        let schema_hash = self.schema_hash();
+        if schema_hash != other_schema_hash {
+            return Err(format!(
+                "client/server component schema mismatch: local schema hash {} does not match remote schema hash {}",
+                schema_hash, other_schema_hash
+            ));
+        }
+        Ok(())
+    }
+
+    /// Compares the given schema entries, usually received from the other side of a connection,
+    /// against this registries [`Self::schema_entries`] and returns a description of every mismatch.
+    /// Returns an empty vec if the schemas match.
+    pub fn schema_mismatches(&self, other_entries: &[SchemaEntry]) -> Vec<String> {
+        let entries = self.schema_entries();
+        let mut mismatches: Vec<String> = vec![];
+
+        for entry in entries.iter() {
+            match other_entries
+                .iter()
+                .find(|other| other.kind == entry.kind && other.id == entry.id)
+            {
+                None => mismatches.push(format!(
+                    "{:?} {} ({}) is only registered locally",
+                    entry.kind, entry.id, entry.type_name
+                )),
+                Some(other) if other.type_name != entry.type_name => mismatches.push(format!(
+                    "{:?} {} is registered as {} locally and {} remotely",
+                    entry.kind, entry.id, entry.type_name, other.type_name
+                )),
+                Some(_) => {}
+            }
+        }
+
+        for other in other_entries.iter() {
+            if !entries
+                .iter()
+                .any(|entry| other.kind == entry.kind && other.id == entry.id)
+            {
+                mismatches.push(format!(
+                    "{:?} {} ({}) is only registered remotely",
+                    other.kind, other.id, other.type_name
+                ));
+            }
+        }
+
+        mismatches
+    }
+
     /// Adds the default registry which has all the basic Bevy_GGF components and resources
     pub fn default_registry() -> GameSerDeRegistry {
         let mut game_registry = GameSerDeRegistry::new();
@@ -95,17 +252,159 @@ impl GameSerDeRegistry {
         game_registry.register_component::<Tile>();
         game_registry.register_component::<TileTerrainInfo>();
         game_registry.register_component::<TileObjects>();
+        game_registry.register_component::<TileObjectStacks>();
         game_registry.register_component::<TileMovementCosts>();
+        game_registry.register_component::<TileEdgeCosts>();
         game_registry.register_component::<ObjectId>();
         game_registry.register_component::<ObjectGridPosition>();
         game_registry.register_component::<Object>();
         game_registry.register_component::<ObjectStackingClass>();
+        game_registry.register_component::<IgnoresStacking>();
+        game_registry.register_component::<ObjectMovement>();
+        game_registry.register_component::<ObjectTypeMovementRules>();
+        game_registry.register_component::<ObjectMovementCostModifiers>();
+        game_registry.register_component::<Tags>();
+        game_registry.register_component::<Cooldowns>();
+        game_registry.register_component::<Experience>();
         game_registry.register_component::<PlayerMarker>();
+        game_registry.register_component::<Player>();
 
         game_registry
     }
 }
 
+/// Whether a [`SchemaEntry`] describes a registered component or resource
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug, Serialize, Deserialize)]
+pub enum SchemaEntryKind {
+    Component,
+    Resource,
+}
+
+/// A single registration in a [`GameSerDeRegistry`]. Used to compare registries across a connection
+#[derive(Clone, Eq, Hash, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SchemaEntry {
+    pub kind: SchemaEntryKind,
+    pub id: u8,
+    pub type_name: String,
+}
+
+/// 64 bit FNV-1a hash. Used instead of the std hasher so that the output is guaranteed to be the same
+/// on every platform and rust version.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// An id hand assigned to commands registered in a [`GameCommandRegistry`]
+///
+/// Is simply a u16 under the type
+pub type CommandId = u16;
+
+/// A [`GameCommand`] serialized by a [`GameCommandRegistry`]. Holds the [`CommandId`] the command
+/// was registered with so that it can be deserialized back into the right type
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CommandBinaryState {
+    pub id: CommandId,
+    pub command: Vec<u8>,
+}
+
+pub type CommandSerializeFn = fn(command: &dyn GameCommand) -> Option<Vec<u8>>;
+
+pub type CommandDeserializeFn = fn(data: &[u8]) -> Option<Box<dyn GameCommand>>;
+
+/// A registry that contains serialization and deserialization functions for [`GameCommand`]s so that
+/// a `Box<dyn GameCommand>` can be saved, eg for replays of the command history, or sent to another
+/// client and turned back into the same command. Only registered commands can be serialized.
+///
+/// The registry is inserted into the game world when the game is built. Register commands with
+/// [`GameBuilder::register_command`](crate::game_core::GameBuilder::register_command). Both sides of
+/// a connection must register the same commands with the same ids.
+#[derive(Resource, Clone, Default)]
+pub struct GameCommandRegistry {
+    pub command_de_map: HashMap<CommandId, CommandDeserializeFn>,
+    pub command_se_map: HashMap<TypeId, (CommandId, CommandSerializeFn)>,
+    pub command_names: HashMap<CommandId, String>,
+}
+
+impl GameCommandRegistry {
+    pub fn new() -> GameCommandRegistry {
+        GameCommandRegistry::default()
+    }
+
+    /// Registers a command with the given id into the [`GameCommandRegistry`] for serialization and
+    /// deserialization. Panics if the id is already registered
+    pub fn register_command<C>(&mut self, id: CommandId)
+    where
+        C: GameCommand + Serialize + DeserializeOwned,
+    {
+        if self.command_de_map.contains_key(&id) {
+            panic!(
+                "GameCommandRegistry command_de_map already contains key {}",
+                id
+            )
+        }
+        self.command_de_map.insert(id, command_deserialize::<C>);
+        self.command_se_map
+            .insert(TypeId::of::<C>(), (id, command_serialize::<C>));
+        self.command_names
+            .insert(id, String::from(std::any::type_name::<C>()));
+    }
+
+    /// Returns true if the given command has been registered in the [`GameCommandRegistry`]
+    pub fn is_command_registered<C>(&self) -> bool
+    where
+        C: GameCommand,
+    {
+        self.command_se_map.contains_key(&TypeId::of::<C>())
+    }
+
+    /// Returns the [`CommandId`] the given commands type was registered with
+    pub fn command_id(&self, command: &dyn GameCommand) -> Option<CommandId> {
+        self.command_se_map
+            .get(&command.as_any().type_id())
+            .map(|(id, _)| *id)
+    }
+
+    /// Serializes the given command. Returns None if its type isn't registered or it fails to
+    /// serialize
+    pub fn serialize_command(&self, command: &dyn GameCommand) -> Option<CommandBinaryState> {
+        let (id, serialize_fn) = self.command_se_map.get(&command.as_any().type_id())?;
+        Some(CommandBinaryState {
+            id: *id,
+            command: serialize_fn(command)?,
+        })
+    }
+
+    /// Deserializes the given command state back into a command. Returns None if its id isn't
+    /// registered or it fails to deserialize
+    pub fn deserialize_command(&self, data: &CommandBinaryState) -> Option<Box<dyn GameCommand>> {
+        let deserialize_fn = self.command_de_map.get(&data.id)?;
+        deserialize_fn(&data.command)
+    }
+}
+
+/// Serializes the given command if it is of type T
+pub fn command_serialize<T>(command: &dyn GameCommand) -> Option<Vec<u8>>
+where
+    T: GameCommand + Serialize + DeserializeOwned,
+{
+    let command = command.as_any().downcast_ref::<T>()?;
+    bincode::serialize(command).ok()
+}
+
+/// Deserializes a binary command of type T
+pub fn command_deserialize<T>(data: &[u8]) -> Option<Box<dyn GameCommand>>
+where
+    T: GameCommand + Serialize + DeserializeOwned,
+{
+    let command = bincode::deserialize::<T>(data).ok()?;
+    Some(Box::new(command))
+}
+
 pub type ComponentDeserializeFn = fn(data: &Vec<u8>, entity: &mut EntityMut);
 
 /// Deserializes a binary component onto the given entity.
@@ -173,3 +472,33 @@ pub trait SaveId {
         Some((self.save_id(), data))
     }
 }
+
+#[test]
+fn test_game_command_registry_round_trip() {
+    use bevy::reflect::Reflect;
+
+    #[derive(Clone, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+    struct TestCommand {
+        value: u32,
+    }
+
+    impl GameCommand for TestCommand {
+        fn execute(&mut self, _world: &mut World) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    let mut registry = GameCommandRegistry::new();
+    registry.register_command::<TestCommand>(1);
+    assert!(registry.is_command_registered::<TestCommand>());
+
+    let command: Box<dyn GameCommand> = Box::new(TestCommand { value: 7 });
+    let command_state = registry.serialize_command(command.as_ref()).unwrap();
+    assert_eq!(command_state.id, 1);
+
+    let command = registry.deserialize_command(&command_state).unwrap();
+    assert_eq!(
+        command.as_any().downcast_ref::<TestCommand>(),
+        Some(&TestCommand { value: 7 })
+    );
+}
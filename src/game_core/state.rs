@@ -10,7 +10,9 @@ use bevy::utils::HashMap;
 use bevy_ecs_tilemap::tiles::TilePos;
 use serde::{Deserialize, Serialize};
 
-use super::saving::{ComponentBinaryState, ResourceId, SaveId};
+use super::saving::{
+    BinaryComponentId, ComponentBinaryState, GameSerDeRegistry, ResourceId, SaveId,
+};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
 pub enum StateSystems {
@@ -18,9 +20,17 @@ pub enum StateSystems {
     State,
 }
 
+/// The id that an omniscient spectator is registered as in [`Changed`] when using
+/// [`GameStateHandler::get_state_diff_full`]. No player can have this id
+pub const SPECTATOR_ID: usize = usize::MAX;
+
 #[derive(Default)]
 pub struct GameStateHandler {
     state_events: StateEvents,
+    /// Whether [`GameStateHandler::get_state_diff_full`] has been used. Once it has
+    /// [`GameStateHandler::clear_changed`] also waits for the spectator to see a change before
+    /// clearing it
+    has_spectator: bool,
 }
 
 // Should be able to call get_state to get the entire game state, and then get state diff to get only
@@ -36,13 +46,13 @@ impl GameStateHandler {
             despawned_objects: vec![],
         };
 
-        let mut query = world.query_filtered::<(
+        let mut query = world.query::<(
             &dyn SaveId,
             Option<&Tile>,
             Option<&TilePos>,
             Option<&ObjectId>,
             Option<&ObjectGridPosition>,
-        ), With<Changed>>();
+        )>();
 
         for (saveable_components, opt_tile, opt_tilepos, opt_object_id, opt_object_grid_pos) in
             query.iter_mut(world)
@@ -82,14 +92,120 @@ impl GameStateHandler {
                         object_id: *object_id,
                         components,
                         object_grid_position: *tile_pos,
+                        revealed: false,
                     })
                 }
             }
         }
+
+        let mut player_query = world.query::<(&dyn SaveId, &Player)>();
+        for (saveable_components, player) in player_query.iter(world) {
+            let mut components: Vec<ComponentBinaryState> = vec![];
+            for component in saveable_components.iter() {
+                if let Some((id, binary)) = component.save() {
+                    components.push(ComponentBinaryState {
+                        id,
+                        component: binary,
+                    });
+                }
+            }
+
+            state.players.push(PlayerState {
+                player_id: *player,
+                components,
+            })
+        }
+
+        if let Some(registry) = world.get_resource::<GameSerDeRegistry>().cloned() {
+            let resource_ids: Vec<ComponentId> = registry.resource_se_map.keys().copied().collect();
+            for resource_id in resource_ids.iter() {
+                if let Some(resource_state) = registry.serialize_resource(resource_id, world) {
+                    state.resources.push(resource_state);
+                }
+            }
+        }
+
         state
     }
 
+    /// Returns the entire game state for the given player to resync from, eg when a client reconnects
+    /// or detects a desync. Every pending change is registered as seen by the player so that
+    /// following calls to [`GameStateHandler::get_state_diff`] only return changes made after this
+    /// resync point.
+    pub fn full_state_for(&mut self, world: &mut World, player_id: usize) -> StateEvents {
+        let state = self.get_entire_state(world);
+
+        let mut changed_query = world.query::<(&mut Changed, Option<&mut PendingReveal>)>();
+        for (mut changed, pending_reveal) in changed_query.iter_mut(world) {
+            changed.check_and_register_seen(player_id);
+            if let Some(mut pending_reveal) = pending_reveal {
+                pending_reveal.take_player(player_id);
+            }
+        }
+        if let Some(mut despawned_objects) = world.get_resource_mut::<DespawnedObjects>() {
+            for (_, changed) in despawned_objects.despawned_objects.iter_mut() {
+                changed.check_and_register_seen(player_id);
+            }
+        }
+        if let Some(mut resource_change_tracking) =
+            world.get_resource_mut::<ResourceChangeTracking>()
+        {
+            for (_, changed) in resource_change_tracking.resources.iter_mut() {
+                changed.check_and_register_seen(player_id);
+            }
+        }
+
+        state
+    }
+
+    /// Returns the current state of a single object with every component that has a [`SaveId`],
+    /// whether or not it has changed, eg to correct a client that thinks the object is wrong or to
+    /// dump an objects authoritative state while debugging. Returns None if there is no object with
+    /// the given id or it has no [`ObjectGridPosition`].
+    ///
+    /// Unlike [`GameStateHandler::full_state_for`] nothing is registered as seen, so the object is
+    /// still included in the next state diff if it has changed.
+    pub fn object_state(&self, world: &mut World, object_id: ObjectId) -> Option<ObjectState> {
+        let mut query = world.query::<(&dyn SaveId, &ObjectId, &ObjectGridPosition)>();
+        let (saveable_components, _, object_grid_position) =
+            query.iter(world).find(|(_, id, _)| id == &&object_id)?;
+
+        let mut components: Vec<ComponentBinaryState> = vec![];
+        for component in saveable_components.iter() {
+            if let Some((id, binary)) = component.save() {
+                components.push(ComponentBinaryState {
+                    id,
+                    component: binary,
+                });
+            }
+        }
+
+        Some(ObjectState {
+            object_id,
+            object_grid_position: *object_grid_position,
+            components,
+            revealed: false,
+        })
+    }
+
+    /// Returns the state that changed since the last time the given player got a state diff. Every
+    /// change is registered as seen by the player.
     pub fn get_state_diff(&mut self, world: &mut World, for_player_id: usize) -> StateEvents {
+        self.state_diff(world, for_player_id)
+    }
+
+    /// Returns every state that changed since the last time this was called regardless of the player,
+    /// for omniscient spectators and replay viewers.
+    ///
+    /// The spectator is registered as seeing the changes under [`SPECTATOR_ID`] and, after the first
+    /// call, [`GameStateHandler::clear_changed`] waits for the spectator to see a change as well as
+    /// the players before clearing it.
+    pub fn get_state_diff_full(&mut self, world: &mut World) -> StateEvents {
+        self.has_spectator = true;
+        self.state_diff(world, SPECTATOR_ID)
+    }
+
+    fn state_diff(&mut self, world: &mut World, for_player_id: usize) -> StateEvents {
         let mut state: StateEvents = StateEvents {
             players: vec![],
             resources: vec![],
@@ -106,6 +222,7 @@ impl GameStateHandler {
             Option<&TilePos>,
             Option<&ObjectId>,
             Option<&ObjectGridPosition>,
+            Option<&mut PendingReveal>,
         ), With<Changed>>();
 
         for (
@@ -116,6 +233,7 @@ impl GameStateHandler {
             opt_tilepos,
             opt_object_id,
             opt_object_grid_pos,
+            opt_pending_reveal,
         ) in query.iter_mut(world)
         {
             if changed.check_and_register_seen(for_player_id) {
@@ -151,11 +269,17 @@ impl GameStateHandler {
                     }
                 }
 
+                let revealed = match opt_pending_reveal {
+                    Some(mut pending_reveal) => pending_reveal.take_player(for_player_id),
+                    None => false,
+                };
+
                 if let Some(tile_pos) = opt_object_grid_pos {
                     state.objects.push(ObjectState {
                         object_id: *object_id,
                         components,
                         object_grid_position: *tile_pos,
+                        revealed,
                     })
                 }
             }
@@ -186,17 +310,43 @@ impl GameStateHandler {
             }
         });
 
+        let mut changed_resources: Vec<ComponentId> = vec![];
+        if let Some(mut resource_change_tracking) =
+            world.get_resource_mut::<ResourceChangeTracking>()
+        {
+            for (id, changed) in resource_change_tracking.resources.iter_mut() {
+                if !changed.check_and_register_seen(for_player_id) {
+                    changed_resources.push(*id);
+                }
+            }
+        }
+        if let Some(registry) = world.get_resource::<GameSerDeRegistry>().cloned() {
+            for resource_id in changed_resources.iter() {
+                if let Some(resource_state) = registry.serialize_resource(resource_id, world) {
+                    state.resources.push(resource_state);
+                }
+            }
+        }
+
         state
     }
 
     /// Simple function that will clear all changed components that have been fully seen as well as
-    /// the DespawnedObjects resource and the ResourceChangeTracking resource
+    /// the DespawnedObjects resource and the ResourceChangeTracking resource. If
+    /// [`GameStateHandler::get_state_diff_full`] has been used changes must also have been seen by
+    /// the spectator
     pub fn clear_changed(&mut self, world: &mut World, player_list: &PlayerList) {
+        let has_spectator = self.has_spectator;
+        let fully_seen = |changed: &Changed| {
+            changed.all_seen(&player_list.players)
+                && (!has_spectator || changed.players_seen.contains(&SPECTATOR_ID))
+        };
+
         let mut system_state: SystemState<(Query<(Entity, &Changed)>, Commands)> =
             SystemState::new(world);
         let (changed_query, mut commands) = system_state.get(world);
         for (entity, changed) in changed_query.iter() {
-            if changed.all_seen(&player_list.players) {
+            if fully_seen(changed) {
                 commands.entity(entity).remove::<Changed>();
             }
         }
@@ -204,7 +354,7 @@ impl GameStateHandler {
         world.resource_scope(|_world, mut despawned_objects: Mut<DespawnedObjects>| {
             let mut index_to_remove: Vec<ObjectId> = vec![];
             for (id, changed) in despawned_objects.despawned_objects.iter_mut() {
-                if changed.all_seen(&player_list.players) {
+                if fully_seen(changed) {
                     index_to_remove.push(*id);
                 }
             }
@@ -217,7 +367,7 @@ impl GameStateHandler {
             |_world, mut resource_change_tracking: Mut<ResourceChangeTracking>| {
                 let mut index_to_remove: Vec<ComponentId> = vec![];
                 for (id, changed) in resource_change_tracking.resources.iter_mut() {
-                    if changed.all_seen(&player_list.players) {
+                    if fully_seen(changed) {
                         index_to_remove.push(*id);
                     }
                 }
@@ -284,11 +434,16 @@ pub struct ResourceState {
 }
 
 /// Contains an objects state, identified via its [`ObjectId`] component
+///
+/// `revealed` is true when the object already existed but was hidden from the player, eg by fog of
+/// war, and was revealed with [`RevealObject`](crate::game_core::command::RevealObject). Clients
+/// should show the object under its existing [`ObjectId`] rather than treating it as newly spawned.
 #[derive(Debug)]
 pub struct ObjectState {
     pub object_id: ObjectId,
     pub object_grid_position: ObjectGridPosition,
     pub components: Vec<ComponentBinaryState>,
+    pub revealed: bool,
 }
 
 /// Contains the entire state of a Tile, identified by its [`TilePos`] component, and all the Objects
@@ -345,6 +500,25 @@ impl Changed {
     }
 }
 
+/// Component on an object that has been revealed to players it was hidden from. The next state diff
+/// for each of the players marks the object as `revealed` in its [`ObjectState`] and removes the
+/// player from the list
+#[derive(Default, Clone, Eq, Debug, PartialEq, Component, Reflect, FromReflect)]
+pub struct PendingReveal {
+    pub players: Vec<usize>,
+}
+
+impl PendingReveal {
+    /// Removes the given player from the pending reveal and returns true if they were in it
+    pub fn take_player(&mut self, player_id: usize) -> bool {
+        let Some(index) = self.players.iter().position(|id| id == &player_id) else {
+            return false;
+        };
+        self.players.remove(index);
+        true
+    }
+}
+
 /// Resource inserted into the world that will be used to drive sending despawned object updates
 #[derive(Clone, Eq, Debug, PartialEq, Resource, Reflect, FromReflect, Serialize, Deserialize)]
 pub struct DespawnedObjects {
@@ -356,3 +530,313 @@ pub struct DespawnedObjects {
 pub struct ResourceChangeTracking {
     pub resources: HashMap<ComponentId, Changed>,
 }
+
+/// A single difference between two [`StateEvents`] found by [`diff_states`]. Component and resource
+/// values are the serialized bytes from each state, None if the component or resource was missing from
+/// that state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateDifference {
+    /// A player is only in one of the states
+    PlayerMissing { player_id: usize, in_a: bool },
+    PlayerComponent {
+        player_id: usize,
+        component_id: BinaryComponentId,
+        a: Option<Vec<u8>>,
+        b: Option<Vec<u8>>,
+    },
+    Resource {
+        resource_id: ResourceId,
+        a: Option<Vec<u8>>,
+        b: Option<Vec<u8>>,
+    },
+    /// A tile is only in one of the states
+    TileMissing { tile_pos: TilePos, in_a: bool },
+    TileComponent {
+        tile_pos: TilePos,
+        component_id: BinaryComponentId,
+        a: Option<Vec<u8>>,
+        b: Option<Vec<u8>>,
+    },
+    /// An object is only in one of the states
+    ObjectMissing { object_id: ObjectId, in_a: bool },
+    ObjectPosition {
+        object_id: ObjectId,
+        a: ObjectGridPosition,
+        b: ObjectGridPosition,
+    },
+    ObjectComponent {
+        object_id: ObjectId,
+        component_id: BinaryComponentId,
+        a: Option<Vec<u8>>,
+        b: Option<Vec<u8>>,
+    },
+    /// An object is despawned in only one of the states
+    ObjectDespawned { object_id: ObjectId, in_a: bool },
+}
+
+impl std::fmt::Display for StateDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = |in_a: &bool| if *in_a { "a" } else { "b" };
+        match self {
+            StateDifference::PlayerMissing { player_id, in_a } => {
+                write!(f, "Player {} is only in state {}", player_id, side(in_a))
+            }
+            StateDifference::PlayerComponent {
+                player_id,
+                component_id,
+                a,
+                b,
+            } => write!(
+                f,
+                "Player {} component {} differs: a = {:?}, b = {:?}",
+                player_id, component_id, a, b
+            ),
+            StateDifference::Resource { resource_id, a, b } => write!(
+                f,
+                "Resource {} differs: a = {:?}, b = {:?}",
+                resource_id, a, b
+            ),
+            StateDifference::TileMissing { tile_pos, in_a } => write!(
+                f,
+                "Tile ({}, {}) is only in state {}",
+                tile_pos.x,
+                tile_pos.y,
+                side(in_a)
+            ),
+            StateDifference::TileComponent {
+                tile_pos,
+                component_id,
+                a,
+                b,
+            } => write!(
+                f,
+                "Tile ({}, {}) component {} differs: a = {:?}, b = {:?}",
+                tile_pos.x, tile_pos.y, component_id, a, b
+            ),
+            StateDifference::ObjectMissing { object_id, in_a } => {
+                write!(f, "Object {} is only in state {}", object_id.id, side(in_a))
+            }
+            StateDifference::ObjectPosition { object_id, a, b } => write!(
+                f,
+                "Object {} position differs: a = ({}, {}), b = ({}, {})",
+                object_id.id,
+                a.tile_position.x,
+                a.tile_position.y,
+                b.tile_position.x,
+                b.tile_position.y
+            ),
+            StateDifference::ObjectComponent {
+                object_id,
+                component_id,
+                a,
+                b,
+            } => write!(
+                f,
+                "Object {} component {} differs: a = {:?}, b = {:?}",
+                object_id.id, component_id, a, b
+            ),
+            StateDifference::ObjectDespawned { object_id, in_a } => write!(
+                f,
+                "Object {} is only despawned in state {}",
+                object_id.id,
+                side(in_a)
+            ),
+        }
+    }
+}
+
+/// Compares two [`StateEvents`], eg from a client and the server or from a replay and a live game, and
+/// returns every difference between them. Components and resources are compared by their serialized
+/// bytes so both states must come from games using the same [`GameSerDeRegistry`].
+///
+/// Differences are returned players first, then resources, tiles, objects, and despawned objects, each
+/// sorted by id, so the first difference is a good place to start looking for a desync. Tiles are
+/// identified by their [`TilePos`] alone so only compare states containing a single map. Returns an
+/// empty list if the states match.
+pub fn diff_states(a: &StateEvents, b: &StateEvents) -> Vec<StateDifference> {
+    let mut differences: Vec<StateDifference> = vec![];
+
+    let players_a: HashMap<usize, &PlayerState> = a
+        .players
+        .iter()
+        .map(|player_state| (player_state.player_id.id(), player_state))
+        .collect();
+    let players_b: HashMap<usize, &PlayerState> = b
+        .players
+        .iter()
+        .map(|player_state| (player_state.player_id.id(), player_state))
+        .collect();
+    for player_id in sorted_keys(&players_a, &players_b, |id| *id) {
+        match (players_a.get(&player_id), players_b.get(&player_id)) {
+            (Some(player_a), Some(player_b)) => {
+                for (component_id, a, b) in
+                    diff_components(&player_a.components, &player_b.components)
+                {
+                    differences.push(StateDifference::PlayerComponent {
+                        player_id,
+                        component_id,
+                        a,
+                        b,
+                    });
+                }
+            }
+            (in_a, _) => differences.push(StateDifference::PlayerMissing {
+                player_id,
+                in_a: in_a.is_some(),
+            }),
+        }
+    }
+
+    let resources_a: HashMap<ResourceId, &Vec<u8>> = a
+        .resources
+        .iter()
+        .map(|resource_state| (resource_state.resource_id, &resource_state.resource))
+        .collect();
+    let resources_b: HashMap<ResourceId, &Vec<u8>> = b
+        .resources
+        .iter()
+        .map(|resource_state| (resource_state.resource_id, &resource_state.resource))
+        .collect();
+    for resource_id in sorted_keys(&resources_a, &resources_b, |id| *id) {
+        let resource_a = resources_a.get(&resource_id);
+        let resource_b = resources_b.get(&resource_id);
+        if resource_a != resource_b {
+            differences.push(StateDifference::Resource {
+                resource_id,
+                a: resource_a.map(|resource| resource.to_vec()),
+                b: resource_b.map(|resource| resource.to_vec()),
+            });
+        }
+    }
+
+    let tiles_a: HashMap<TilePos, &TileState> = a
+        .tiles
+        .iter()
+        .map(|tile_state| (tile_state.tile_pos, tile_state))
+        .collect();
+    let tiles_b: HashMap<TilePos, &TileState> = b
+        .tiles
+        .iter()
+        .map(|tile_state| (tile_state.tile_pos, tile_state))
+        .collect();
+    for tile_pos in sorted_keys(&tiles_a, &tiles_b, |tile_pos| (tile_pos.y, tile_pos.x)) {
+        match (tiles_a.get(&tile_pos), tiles_b.get(&tile_pos)) {
+            (Some(tile_a), Some(tile_b)) => {
+                for (component_id, a, b) in diff_components(&tile_a.components, &tile_b.components)
+                {
+                    differences.push(StateDifference::TileComponent {
+                        tile_pos,
+                        component_id,
+                        a,
+                        b,
+                    });
+                }
+            }
+            (in_a, _) => differences.push(StateDifference::TileMissing {
+                tile_pos,
+                in_a: in_a.is_some(),
+            }),
+        }
+    }
+
+    let objects_a: HashMap<ObjectId, &ObjectState> = a
+        .objects
+        .iter()
+        .map(|object_state| (object_state.object_id, object_state))
+        .collect();
+    let objects_b: HashMap<ObjectId, &ObjectState> = b
+        .objects
+        .iter()
+        .map(|object_state| (object_state.object_id, object_state))
+        .collect();
+    for object_id in sorted_keys(&objects_a, &objects_b, |object_id| object_id.id) {
+        match (objects_a.get(&object_id), objects_b.get(&object_id)) {
+            (Some(object_a), Some(object_b)) => {
+                if object_a.object_grid_position != object_b.object_grid_position {
+                    differences.push(StateDifference::ObjectPosition {
+                        object_id,
+                        a: object_a.object_grid_position,
+                        b: object_b.object_grid_position,
+                    });
+                }
+                for (component_id, a, b) in
+                    diff_components(&object_a.components, &object_b.components)
+                {
+                    differences.push(StateDifference::ObjectComponent {
+                        object_id,
+                        component_id,
+                        a,
+                        b,
+                    });
+                }
+            }
+            (in_a, _) => differences.push(StateDifference::ObjectMissing {
+                object_id,
+                in_a: in_a.is_some(),
+            }),
+        }
+    }
+
+    let mut despawned: Vec<(ObjectId, bool)> = a
+        .despawned_objects
+        .iter()
+        .filter(|object_id| !b.despawned_objects.contains(object_id))
+        .map(|object_id| (*object_id, true))
+        .chain(
+            b.despawned_objects
+                .iter()
+                .filter(|object_id| !a.despawned_objects.contains(object_id))
+                .map(|object_id| (*object_id, false)),
+        )
+        .collect();
+    despawned.sort_by_key(|(object_id, _)| object_id.id);
+    for (object_id, in_a) in despawned {
+        differences.push(StateDifference::ObjectDespawned { object_id, in_a });
+    }
+
+    differences
+}
+
+/// Returns every key in either map, sorted by the given sort key
+fn sorted_keys<K, V, S, F>(a: &HashMap<K, V>, b: &HashMap<K, V>, sort_key: F) -> Vec<K>
+where
+    K: Copy + Eq + std::hash::Hash,
+    S: Ord,
+    F: Fn(&K) -> S,
+{
+    let mut keys: Vec<K> = a.keys().copied().collect();
+    keys.extend(b.keys().filter(|key| !a.contains_key(key)).copied());
+    keys.sort_by_key(sort_key);
+    keys
+}
+
+/// Returns the id and both values of every component that differs between the two lists, sorted by id
+fn diff_components(
+    a: &[ComponentBinaryState],
+    b: &[ComponentBinaryState],
+) -> Vec<(BinaryComponentId, Option<Vec<u8>>, Option<Vec<u8>>)> {
+    let components_a: HashMap<BinaryComponentId, &Vec<u8>> = a
+        .iter()
+        .map(|component| (component.id, &component.component))
+        .collect();
+    let components_b: HashMap<BinaryComponentId, &Vec<u8>> = b
+        .iter()
+        .map(|component| (component.id, &component.component))
+        .collect();
+
+    sorted_keys(&components_a, &components_b, |id| *id)
+        .into_iter()
+        .filter_map(|component_id| {
+            let component_a = components_a.get(&component_id);
+            let component_b = components_b.get(&component_id);
+            if component_a == component_b {
+                return None;
+            }
+            Some((
+                component_id,
+                component_a.map(|component| component.to_vec()),
+                component_b.map(|component| component.to_vec()),
+            ))
+        })
+        .collect()
+}
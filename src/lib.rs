@@ -12,13 +12,18 @@ use crate::movement::BggfMovementPlugin;
 use bevy::app::PluginGroupBuilder;
 use bevy::prelude::PluginGroup;
 
+pub mod abilities;
+pub mod camera;
 pub mod combat;
+pub mod experience;
 pub mod game_core;
 pub mod mapping;
 pub mod movement;
 pub mod object;
 pub mod pathfinding;
 pub mod player;
+pub mod selection;
+pub mod testing;
 
 pub struct BggfDefaultPlugins;
 
@@ -0,0 +1,552 @@
+//! Optional lazy tile storage for very large maps.
+//!
+//! A normal map spawns an entity for every tile up front. A chunked map instead stores the tile data
+//! in dense arrays in a [`DenseTileData`] component on the map entity and only spawns tile entities
+//! when they are needed. Use [`load_tiles_around`] to spawn the tiles near objects or the camera and
+//! [`unload_tiles_outside`] to despawn empty tiles that are no longer needed.
+//!
+//! ### Note
+//! Everything else in bevy_ggf, such as movement and the object commands, only works with spawned
+//! tiles. Make sure the tiles around any object that is moving or being placed are loaded first.
+
+use crate::game_core::command::{GameCommand, GameCommands};
+use crate::mapping::terrain::{TerrainType, TileTerrainInfo};
+use crate::mapping::tiles::{
+    BggfTileBundle, BggfTileObjectBundle, Tile, TileObjectStacks, TileObjects, TilePosition,
+};
+use crate::mapping::{Map, MapDeSpawned, MapId, MapIdProvider, MapSpawned};
+use crate::movement::{TerrainMovementCosts, TileEdgeCosts, TileMovementCosts};
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Component, DespawnRecursiveExt, Entity, Query, Reflect, With, World};
+use bevy_ecs_tilemap::prelude::{
+    TilePos, TileStorage, TilemapGridSize, TilemapId, TilemapSize, TilemapTileSize, TilemapType,
+};
+
+/// Component on the map entity of a chunked map holding the data of every tile in dense arrays,
+/// indexed using [`TilePos::to_index`]. The data of a tile is only copied into a tile entity when
+/// the tile is loaded.
+///
+/// The per tile arrays hold data that differs from the defaults for a tile, eg costs set with
+/// [`SetTileMovementCost`](crate::movement::SetTileMovementCost). A None entry means the tile uses
+/// the default: its terrains [`TerrainMovementCosts`], no [`TileEdgeCosts`], and the maps
+/// `tile_stack_rules`.
+#[derive(Clone, Component)]
+pub struct DenseTileData {
+    pub map_size: TilemapSize,
+    pub terrain_types: Vec<TerrainType>,
+    pub tile_stack_rules: TileObjectStacks,
+    pub tile_movement_costs: Vec<Option<TileMovementCosts>>,
+    pub tile_edge_costs: Vec<Option<TileEdgeCosts>>,
+    pub tile_object_stacks: Vec<Option<TileObjectStacks>>,
+}
+
+impl DenseTileData {
+    /// Creates a new DenseTileData with every tile set to the given terrain type
+    pub fn new(
+        map_size: TilemapSize,
+        terrain_type: TerrainType,
+        tile_stack_rules: TileObjectStacks,
+    ) -> DenseTileData {
+        DenseTileData {
+            map_size,
+            terrain_types: vec![terrain_type; map_size.count()],
+            tile_stack_rules,
+            tile_movement_costs: vec![None; map_size.count()],
+            tile_edge_costs: vec![None; map_size.count()],
+            tile_object_stacks: vec![None; map_size.count()],
+        }
+    }
+
+    /// Returns the index of the given tile in the dense arrays. Returns None if the tile is outside
+    /// the map
+    fn index(&self, tile_pos: &TilePos) -> Option<usize> {
+        tile_pos
+            .within_map_bounds(&self.map_size)
+            .then(|| tile_pos.to_index(&self.map_size))
+    }
+
+    /// Returns the terrain type of the given tile. Returns None if the tile is outside the map
+    pub fn terrain_type(&self, tile_pos: &TilePos) -> Option<&TerrainType> {
+        if !tile_pos.within_map_bounds(&self.map_size) {
+            return None;
+        }
+        self.terrain_types.get(tile_pos.to_index(&self.map_size))
+    }
+
+    /// Sets the terrain type of the given tile. Does nothing if the tile is outside the map
+    pub fn set_terrain_type(&mut self, tile_pos: &TilePos, terrain_type: TerrainType) {
+        if !tile_pos.within_map_bounds(&self.map_size) {
+            return;
+        }
+        let index = tile_pos.to_index(&self.map_size);
+        self.terrain_types[index] = terrain_type;
+    }
+
+    /// Returns the [`TileMovementCosts`] override of the given tile. Returns None if the tile uses
+    /// its terrains costs or is outside the map
+    pub fn tile_movement_costs(&self, tile_pos: &TilePos) -> Option<&TileMovementCosts> {
+        self.tile_movement_costs[self.index(tile_pos)?].as_ref()
+    }
+
+    /// Sets the [`TileMovementCosts`] override of the given tile, or clears it if None. Does nothing
+    /// if the tile is outside the map
+    pub fn set_tile_movement_costs(
+        &mut self,
+        tile_pos: &TilePos,
+        tile_movement_costs: Option<TileMovementCosts>,
+    ) {
+        if let Some(index) = self.index(tile_pos) {
+            self.tile_movement_costs[index] = tile_movement_costs;
+        }
+    }
+
+    /// Returns the [`TileEdgeCosts`] of the given tile. Returns None if the tile has none or is
+    /// outside the map
+    pub fn tile_edge_costs(&self, tile_pos: &TilePos) -> Option<&TileEdgeCosts> {
+        self.tile_edge_costs[self.index(tile_pos)?].as_ref()
+    }
+
+    /// Sets the [`TileEdgeCosts`] of the given tile, or clears them if None. Does nothing if the
+    /// tile is outside the map
+    pub fn set_tile_edge_costs(
+        &mut self,
+        tile_pos: &TilePos,
+        tile_edge_costs: Option<TileEdgeCosts>,
+    ) {
+        if let Some(index) = self.index(tile_pos) {
+            self.tile_edge_costs[index] = tile_edge_costs;
+        }
+    }
+
+    /// Returns the [`TileObjectStacks`] of the given tile, the maps `tile_stack_rules` unless the
+    /// tile has its own. Returns None if the tile is outside the map
+    pub fn tile_object_stacks(&self, tile_pos: &TilePos) -> Option<&TileObjectStacks> {
+        let index = self.index(tile_pos)?;
+        Some(
+            self.tile_object_stacks[index]
+                .as_ref()
+                .unwrap_or(&self.tile_stack_rules),
+        )
+    }
+
+    /// Sets the [`TileObjectStacks`] of the given tile, or returns it to the maps `tile_stack_rules`
+    /// if None. Does nothing if the tile is outside the map
+    pub fn set_tile_object_stacks(
+        &mut self,
+        tile_pos: &TilePos,
+        tile_object_stacks: Option<TileObjectStacks>,
+    ) {
+        if let Some(index) = self.index(tile_pos) {
+            self.tile_object_stacks[index] = tile_object_stacks;
+        }
+    }
+}
+
+/// An extension trait for [GameCommands] with chunked map commands.
+pub trait ChunkedMapCommandsExt {
+    fn generate_chunked_map(
+        &mut self,
+        map_id_provider: &mut MapIdProvider,
+        tile_map_size: TilemapSize,
+        tilemap_type: TilemapType,
+        tilemap_tile_size: TilemapTileSize,
+        terrain_type: TerrainType,
+        tile_stack_rules: TileObjectStacks,
+    ) -> SpawnChunkedMap;
+}
+
+impl ChunkedMapCommandsExt for GameCommands {
+    /// Spawns a chunked map filled with the given terrain type. No tile entities are spawned until
+    /// they are loaded. The maps [`MapId`] is allocated immediately from the given [`MapIdProvider`].
+    fn generate_chunked_map(
+        &mut self,
+        map_id_provider: &mut MapIdProvider,
+        tile_map_size: TilemapSize,
+        tilemap_type: TilemapType,
+        tilemap_tile_size: TilemapTileSize,
+        terrain_type: TerrainType,
+        tile_stack_rules: TileObjectStacks,
+    ) -> SpawnChunkedMap {
+        let map_id = map_id_provider.next_id_component();
+        self.queue.push(SpawnChunkedMap {
+            tile_map_size,
+            tilemap_type,
+            tilemap_tile_size,
+            terrain_type: terrain_type.clone(),
+            tile_stack_rules: tile_stack_rules.clone(),
+            map_id,
+        });
+        SpawnChunkedMap {
+            tile_map_size,
+            tilemap_type,
+            tilemap_tile_size,
+            terrain_type,
+            tile_stack_rules,
+            map_id,
+        }
+    }
+}
+
+#[derive(Clone, Reflect)]
+pub struct SpawnChunkedMap {
+    tile_map_size: TilemapSize,
+    tilemap_type: TilemapType,
+    tilemap_tile_size: TilemapTileSize,
+    terrain_type: TerrainType,
+    tile_stack_rules: TileObjectStacks,
+    map_id: MapId,
+}
+
+impl SpawnChunkedMap {
+    /// Returns the [`MapId`] of the map this command spawns
+    pub fn map_id(&self) -> MapId {
+        self.map_id
+    }
+}
+
+impl GameCommand for SpawnChunkedMap {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let map_size = self.tile_map_size;
+        let tile_size = self.tilemap_tile_size;
+        let grid_size: TilemapGridSize = tile_size.into();
+
+        world
+            .resource_mut::<MapIdProvider>()
+            .reserve_id(self.map_id.id);
+
+        let tilemap_entity = world.spawn_empty().id();
+        world
+            .entity_mut(tilemap_entity)
+            .insert((
+                grid_size,
+                self.tilemap_type,
+                map_size,
+                TileStorage::empty(map_size),
+                tile_size,
+            ))
+            .insert(Map {
+                tilemap_type: self.tilemap_type,
+                map_size,
+                tilemap_entity,
+            })
+            .insert(DenseTileData::new(
+                map_size,
+                self.terrain_type.clone(),
+                self.tile_stack_rules.clone(),
+            ))
+            .insert(self.map_id);
+
+        world.send_event::<MapSpawned>(MapSpawned {
+            map_id: self.map_id,
+        });
+
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<Query<(Entity, &MapId, &TileStorage)>> =
+            SystemState::new(world);
+        let map_query = system_state.get(world);
+
+        let Some((entity, _, tile_storage)) =
+            map_query.iter().find(|(_, id, _)| id == &&self.map_id)
+        else {
+            return Err(String::from("No entity found"));
+        };
+
+        let tile_entities: Vec<Entity> = tile_storage.iter().flatten().copied().collect();
+        for tile_entity in tile_entities {
+            world.entity_mut(tile_entity).despawn_recursive();
+        }
+        world.entity_mut(entity).despawn_recursive();
+
+        world.send_event::<MapDeSpawned>(MapDeSpawned {
+            map_id: self.map_id,
+        });
+
+        Ok(())
+    }
+}
+
+/// Returns the entity of the given tile on the given chunked map, spawning it from the maps
+/// [`DenseTileData`] if it isn't loaded. Returns None if the map isn't a chunked map or the tile is
+/// outside the map.
+///
+/// Loading a tile doesn't change the game state so the tile isn't marked
+/// [`Changed`](crate::game_core::state::Changed).
+pub fn load_tile(world: &mut World, on_map: MapId, tile_pos: TilePos) -> Option<Entity> {
+    let mut system_state: SystemState<Query<(Entity, &MapId, &TileStorage, &DenseTileData)>> =
+        SystemState::new(world);
+    let map_query = system_state.get(world);
+
+    let (map_entity, _, tile_storage, dense_tile_data) =
+        map_query.iter().find(|(_, id, _, _)| id == &&on_map)?;
+
+    if let Some(tile_entity) = tile_storage.checked_get(&tile_pos) {
+        return Some(tile_entity);
+    }
+
+    let terrain_type = dense_tile_data.terrain_type(&tile_pos)?.clone();
+    let tile_stack_rules = dense_tile_data.tile_object_stacks(&tile_pos)?.clone();
+    let tile_edge_costs = dense_tile_data.tile_edge_costs(&tile_pos).cloned();
+
+    let tile_movement_costs = match dense_tile_data.tile_movement_costs(&tile_pos) {
+        Some(tile_movement_costs) => Some(tile_movement_costs.clone()),
+        None => default_movement_costs(world, &terrain_type),
+    };
+
+    let mut tile = world.spawn(BggfTileBundle {
+        tile: Tile,
+        tile_terrain_info: TileTerrainInfo { terrain_type },
+        tile_pos,
+        tilemap_id: TilemapId(map_entity),
+    });
+    tile.insert(BggfTileObjectBundle {
+        tile_stack_rules,
+        tile_objects: TileObjects::default(),
+    })
+    .insert(TilePosition::from(tile_pos));
+    if let Some(tile_movement_costs) = tile_movement_costs {
+        tile.insert(tile_movement_costs);
+    }
+    if let Some(tile_edge_costs) = tile_edge_costs {
+        tile.insert(tile_edge_costs);
+    }
+    let tile_entity = tile.id();
+
+    world
+        .get_mut::<TileStorage>(map_entity)?
+        .set(&tile_pos, tile_entity);
+
+    Some(tile_entity)
+}
+
+/// Loads every tile within the given radius, in tiles along each axis, of the given tile
+pub fn load_tiles_around(world: &mut World, on_map: MapId, center: TilePos, radius: u32) {
+    let Some(map_size) = chunked_map_size(world, on_map) else {
+        return;
+    };
+    for tile_pos in tiles_in_square(center, radius, &map_size) {
+        load_tile(world, on_map, tile_pos);
+    }
+}
+
+/// Despawns every loaded tile that is further than the given radius, in tiles along each axis, from
+/// all of the given tiles. Tiles that contain objects are never unloaded. The terrain, movement costs,
+/// edge costs, and stacking rules of unloaded tiles are written back into the maps [`DenseTileData`]
+/// so that changes are kept.
+pub fn unload_tiles_outside(
+    world: &mut World,
+    on_map: MapId,
+    keep_around: &[TilePos],
+    radius: u32,
+) {
+    let mut system_state: SystemState<(
+        Query<(Entity, &MapId, &TileStorage), With<DenseTileData>>,
+        Query<(
+            &TilePos,
+            &TileObjects,
+            &TileTerrainInfo,
+            Option<&TileMovementCosts>,
+            Option<&TileEdgeCosts>,
+            &TileObjectStacks,
+        )>,
+    )> = SystemState::new(world);
+    let (map_query, tile_query) = system_state.get(world);
+
+    let Some((map_entity, _, tile_storage)) = map_query.iter().find(|(_, id, _)| id == &&on_map)
+    else {
+        return;
+    };
+
+    let mut to_unload: Vec<(Entity, TilePos, TerrainType, UnloadedTileData)> = vec![];
+    for tile_entity in tile_storage.iter().flatten() {
+        let Ok((
+            tile_pos,
+            tile_objects,
+            tile_terrain_info,
+            tile_movement_costs,
+            tile_edge_costs,
+            tile_object_stacks,
+        )) = tile_query.get(*tile_entity)
+        else {
+            continue;
+        };
+        if !tile_objects.entities_in_tile.is_empty() {
+            continue;
+        }
+        let is_near = keep_around.iter().any(|center| {
+            center.x.abs_diff(tile_pos.x) <= radius && center.y.abs_diff(tile_pos.y) <= radius
+        });
+        if !is_near {
+            to_unload.push((
+                *tile_entity,
+                *tile_pos,
+                tile_terrain_info.terrain_type.clone(),
+                (
+                    tile_movement_costs.cloned(),
+                    tile_edge_costs.cloned(),
+                    tile_object_stacks.clone(),
+                ),
+            ));
+        }
+    }
+
+    for (tile_entity, tile_pos, terrain_type, unloaded_tile_data) in to_unload {
+        let (tile_movement_costs, tile_edge_costs, tile_object_stacks) = unloaded_tile_data;
+        // Only keep the data that differs from the defaults so that later terrain changes still
+        // apply to the tile
+        let default_costs = default_movement_costs(world, &terrain_type);
+        let tile_movement_costs =
+            tile_movement_costs.filter(|costs| Some(costs) != default_costs.as_ref());
+        if let Some(mut dense_tile_data) = world.get_mut::<DenseTileData>(map_entity) {
+            let tile_object_stacks = (tile_object_stacks != dense_tile_data.tile_stack_rules)
+                .then_some(tile_object_stacks);
+            dense_tile_data.set_terrain_type(&tile_pos, terrain_type);
+            dense_tile_data.set_tile_movement_costs(&tile_pos, tile_movement_costs);
+            dense_tile_data.set_tile_edge_costs(&tile_pos, tile_edge_costs);
+            dense_tile_data.set_tile_object_stacks(&tile_pos, tile_object_stacks);
+        }
+        if let Some(mut tile_storage) = world.get_mut::<TileStorage>(map_entity) {
+            tile_storage.checked_remove(&tile_pos);
+        }
+        world.entity_mut(tile_entity).despawn_recursive();
+    }
+}
+
+/// The movement costs, edge costs, and stacking rules of a tile being unloaded
+type UnloadedTileData = (
+    Option<TileMovementCosts>,
+    Option<TileEdgeCosts>,
+    TileObjectStacks,
+);
+
+/// Returns the default [`TileMovementCosts`] for the given terrain from the [`TerrainMovementCosts`]
+fn default_movement_costs(world: &World, terrain_type: &TerrainType) -> Option<TileMovementCosts> {
+    world
+        .get_resource::<TerrainMovementCosts>()
+        .and_then(|terrain_movement_costs| {
+            terrain_movement_costs
+                .movement_cost_rules
+                .get(terrain_type)
+                .cloned()
+        })
+}
+
+/// Returns the size of the given map if it is a chunked map
+fn chunked_map_size(world: &mut World, on_map: MapId) -> Option<TilemapSize> {
+    let mut system_state: SystemState<Query<(&MapId, &DenseTileData)>> = SystemState::new(world);
+    let map_query = system_state.get(world);
+    map_query
+        .iter()
+        .find(|(id, _)| id == &&on_map)
+        .map(|(_, dense_tile_data)| dense_tile_data.map_size)
+}
+
+/// Returns every tile on the map within the given radius along each axis of the center tile
+fn tiles_in_square(center: TilePos, radius: u32, map_size: &TilemapSize) -> Vec<TilePos> {
+    let mut tiles: Vec<TilePos> = vec![];
+    let radius = radius as i32;
+    for x_offset in -radius..=radius {
+        for y_offset in -radius..=radius {
+            if let Some(tile_pos) = TilePos::from_i32_pair(
+                center.x as i32 + x_offset,
+                center.y as i32 + y_offset,
+                map_size,
+            ) {
+                tiles.push(tile_pos);
+            }
+        }
+    }
+    tiles
+}
+
+#[test]
+fn test_unloaded_tile_keeps_overridden_data() {
+    use crate::mapping::terrain::TerrainClass;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount};
+    use crate::movement::{MovementType, TileDirection};
+    use bevy::ecs::event::Events;
+    use bevy::utils::HashMap;
+
+    let mut world = World::new();
+    world.init_resource::<MapIdProvider>();
+    world.init_resource::<Events<MapSpawned>>();
+
+    let grassland = TerrainType {
+        name: String::from("Grassland"),
+        terrain_class: TerrainClass {
+            name: String::from("Ground"),
+        },
+    };
+    let infantry = MovementType {
+        name: String::from("Infantry"),
+    };
+    let ground = StackingClass {
+        name: String::from("Ground"),
+    };
+    world.insert_resource(TerrainMovementCosts {
+        movement_cost_rules: HashMap::from([(
+            grassland.clone(),
+            TileMovementCosts::new(vec![(infantry.clone(), 1)]),
+        )]),
+    });
+
+    let stack_rules = TileObjectStacks::new(vec![(
+        ground.clone(),
+        TileObjectStacksCount {
+            current_count: 0,
+            max_count: 1,
+        },
+    )]);
+    let mut spawn_map = SpawnChunkedMap {
+        tile_map_size: TilemapSize { x: 4, y: 4 },
+        tilemap_type: TilemapType::default(),
+        tilemap_tile_size: TilemapTileSize { x: 16.0, y: 16.0 },
+        terrain_type: grassland,
+        tile_stack_rules: stack_rules,
+        map_id: MapId { id: 1 },
+    };
+    assert!(spawn_map.execute(&mut world).is_ok());
+    let map_id = spawn_map.map_id();
+
+    let tile_pos = TilePos::new(1, 1);
+    let movement_costs = TileMovementCosts::new(vec![(infantry.clone(), 4)]);
+    let edge_costs = TileEdgeCosts::new(vec![(TileDirection::North, 2)]);
+    let object_stacks = TileObjectStacks::new(vec![(
+        ground,
+        TileObjectStacksCount {
+            current_count: 0,
+            max_count: 3,
+        },
+    )]);
+
+    let tile_entity = load_tile(&mut world, map_id, tile_pos).expect("tile should load");
+    world.entity_mut(tile_entity).insert((
+        movement_costs.clone(),
+        edge_costs.clone(),
+        object_stacks.clone(),
+    ));
+
+    unload_tiles_outside(&mut world, map_id, &[], 0);
+    assert!(world.get_entity(tile_entity).is_none());
+
+    let tile_entity = load_tile(&mut world, map_id, tile_pos).expect("tile should reload");
+    assert_eq!(
+        world.get::<TileMovementCosts>(tile_entity),
+        Some(&movement_costs)
+    );
+    assert_eq!(world.get::<TileEdgeCosts>(tile_entity), Some(&edge_costs));
+    assert!(world.get::<TileObjectStacks>(tile_entity) == Some(&object_stacks));
+
+    // A tile that was never changed still loads with the defaults
+    let default_tile = load_tile(&mut world, map_id, TilePos::new(2, 2)).expect("tile should load");
+    assert_eq!(
+        world.get::<TileMovementCosts>(default_tile),
+        Some(&TileMovementCosts::new(vec![(infantry, 1)]))
+    );
+    assert!(world.get::<TileEdgeCosts>(default_tile).is_none());
+    assert!(world
+        .get::<crate::game_core::state::Changed>(default_tile)
+        .is_none());
+}
@@ -1,6 +1,11 @@
+pub mod chunked;
 pub mod object;
+pub mod overlay;
+pub mod render;
+pub mod snapshot;
 pub mod terrain;
 pub mod tiles;
+pub mod vision;
 
 use self::tiles::TilePosition;
 use crate::game_core::command::{GameCommand, GameCommands};
@@ -11,6 +16,7 @@ use crate::mapping::tiles::{
     BggfTileBundle, BggfTileObjectBundle, Tile, TileObjectStacks, TileObjects,
 };
 use crate::movement::TerrainMovementCosts;
+use crate::object::{ObjectGridPosition, ObjectId};
 use bevy::ecs::system::SystemState;
 use bevy::math::Vec4Swizzles;
 use bevy::prelude::*;
@@ -23,7 +29,8 @@ impl Plugin for BggfMappingPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<MapSpawned>()
             .add_event::<MapDeSpawned>()
-            .insert_resource(MapIdProvider::default());
+            .insert_resource(MapIdProvider::default())
+            .add_system(overlay::update_tile_overlays);
     }
 }
 
@@ -64,14 +71,25 @@ impl MapIdProvider {
         MapId { id: self.next_id() }
     }
 
+    /// Returns a new id. Ids are never handed out twice, even after the map they were given to is
+    /// despawned or its spawn is rolled back. Saturates at `usize::MAX` rather than panicking
     pub fn next_id(&mut self) -> usize {
-        self.last_id = self.last_id.saturating_add_signed(1);
+        self.last_id = self.last_id.saturating_add(1);
         self.last_id
     }
 
+    /// Gives back the most recently handed out id so that it is handed out again
+    #[deprecated(
+        note = "Giving an id back can hand out an id that is still in use, ids are never reused now"
+    )]
     pub fn remove_last_id(&mut self) {
         self.last_id = self.last_id.saturating_sub(1);
     }
+
+    /// Marks the given id as used so that it is never handed out by [`next_id`](Self::next_id)
+    pub fn reserve_id(&mut self, id: usize) {
+        self.last_id = self.last_id.max(id);
+    }
 }
 
 #[derive(Clone, Copy, Eq, Hash, Debug, PartialEq, Component, Reflect, FromReflect)]
@@ -83,10 +101,24 @@ pub struct MapSpawned {
     map_id: MapId,
 }
 
+impl MapSpawned {
+    /// The id of the map that was spawned
+    pub fn map_id(&self) -> MapId {
+        self.map_id
+    }
+}
+
 pub struct MapDeSpawned {
     map_id: MapId,
 }
 
+impl MapDeSpawned {
+    /// The id of the map that was despawned
+    pub fn map_id(&self) -> MapId {
+        self.map_id
+    }
+}
+
 /// Map struct used to keep track of the general structure of the map. Holds a reference to the tilemap_entity
 /// that this map info applies to
 #[derive(Component)]
@@ -105,6 +137,26 @@ pub trait MapCommandsExt {
         map_terrain_vec: Vec<TerrainType>,
         tile_stack_rules: TileObjectStacks,
     ) -> SpawnRandomMap;
+
+    fn generate_random_map_with_id(
+        &mut self,
+        map_id_provider: &mut MapIdProvider,
+        tile_map_size: TilemapSize,
+        tilemap_type: TilemapType,
+        tilemap_tile_size: TilemapTileSize,
+        map_terrain_vec: Vec<TerrainType>,
+        tile_stack_rules: TileObjectStacks,
+    ) -> SpawnRandomMap;
+
+    fn generate_random_map_at(
+        &mut self,
+        map_transform: Transform,
+        tile_map_size: TilemapSize,
+        tilemap_type: TilemapType,
+        tilemap_tile_size: TilemapTileSize,
+        map_terrain_vec: Vec<TerrainType>,
+        tile_stack_rules: TileObjectStacks,
+    ) -> SpawnRandomMap;
 }
 
 impl MapCommandsExt for GameCommands {
@@ -122,6 +174,7 @@ impl MapCommandsExt for GameCommands {
             tilemap_tile_size,
             map_terrain_type_vec: map_terrain_type_vec.clone(),
             tile_stack_rules: tile_stack_rules.clone(),
+            map_transform: Transform::default(),
             spawned_map_id: None,
         });
         SpawnRandomMap {
@@ -130,6 +183,75 @@ impl MapCommandsExt for GameCommands {
             tilemap_tile_size,
             map_terrain_type_vec,
             tile_stack_rules,
+            map_transform: Transform::default(),
+            spawned_map_id: None,
+        }
+    }
+
+    /// Same as [`MapCommandsExt::generate_random_map`] but allocates the maps [`MapId`] from the given
+    /// [`MapIdProvider`] immediately instead of when the command is executed. Use
+    /// [`SpawnRandomMap::map_id`] on the returned command to get the id and use it in subsequent commands.
+    ///
+    /// Pass the games own provider, eg `game_builder.game_world.resource_mut::<MapIdProvider>()`, as
+    /// ids from any other provider can collide with the ids of other maps in the game.
+    fn generate_random_map_with_id(
+        &mut self,
+        map_id_provider: &mut MapIdProvider,
+        tile_map_size: TilemapSize,
+        tilemap_type: TilemapType,
+        tilemap_tile_size: TilemapTileSize,
+        map_terrain_type_vec: Vec<TerrainType>,
+        tile_stack_rules: TileObjectStacks,
+    ) -> SpawnRandomMap {
+        let map_id = map_id_provider.next_id_component();
+        self.queue.push(SpawnRandomMap {
+            tile_map_size,
+            tilemap_type,
+            tilemap_tile_size,
+            map_terrain_type_vec: map_terrain_type_vec.clone(),
+            tile_stack_rules: tile_stack_rules.clone(),
+            map_transform: Transform::default(),
+            spawned_map_id: Some(map_id),
+        });
+        SpawnRandomMap {
+            tile_map_size,
+            tilemap_type,
+            tilemap_tile_size,
+            map_terrain_type_vec,
+            tile_stack_rules,
+            map_transform: Transform::default(),
+            spawned_map_id: Some(map_id),
+        }
+    }
+
+    /// Same as [`MapCommandsExt::generate_random_map`] but places the map at the given [`Transform`]
+    /// instead of the origin. Give each map a different transform so that maps don't overlap in world
+    /// space
+    fn generate_random_map_at(
+        &mut self,
+        map_transform: Transform,
+        tile_map_size: TilemapSize,
+        tilemap_type: TilemapType,
+        tilemap_tile_size: TilemapTileSize,
+        map_terrain_type_vec: Vec<TerrainType>,
+        tile_stack_rules: TileObjectStacks,
+    ) -> SpawnRandomMap {
+        self.queue.push(SpawnRandomMap {
+            tile_map_size,
+            tilemap_type,
+            tilemap_tile_size,
+            map_terrain_type_vec: map_terrain_type_vec.clone(),
+            tile_stack_rules: tile_stack_rules.clone(),
+            map_transform,
+            spawned_map_id: None,
+        });
+        SpawnRandomMap {
+            tile_map_size,
+            tilemap_type,
+            tilemap_tile_size,
+            map_terrain_type_vec,
+            tile_stack_rules,
+            map_transform,
             spawned_map_id: None,
         }
     }
@@ -142,62 +264,101 @@ pub struct SpawnRandomMap {
     tilemap_tile_size: TilemapTileSize,
     map_terrain_type_vec: Vec<TerrainType>,
     tile_stack_rules: TileObjectStacks,
+    map_transform: Transform,
     spawned_map_id: Option<MapId>,
 }
 
+impl SpawnRandomMap {
+    /// Returns the [`MapId`] of the map this command spawns. Is None if the command was created
+    /// without pre-allocating an id and hasn't been executed yet
+    pub fn map_id(&self) -> Option<MapId> {
+        self.spawned_map_id
+    }
+
+    /// Returns the [`TerrainType`]s that the map is spawned with
+    pub fn terrain_types(&self) -> &[TerrainType] {
+        &self.map_terrain_type_vec
+    }
+
+    /// Returns the [`Transform`] that the map is placed at in world space
+    pub fn map_transform(&self) -> Transform {
+        self.map_transform
+    }
+}
+
 impl GameCommand for SpawnRandomMap {
     fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        // Check everything the tiles need before spawning anything so that a failed spawn doesn't
+        // leave a partial map in the world
+        let Some(terrain_type) = self.map_terrain_type_vec.first().cloned() else {
+            return Err(String::from("No TerrainTypes given to spawn the map with"));
+        };
+        let Some(terrain_movement_costs) = world.get_resource::<TerrainMovementCosts>() else {
+            return Err(String::from(
+                "No TerrainMovementCosts resource found. Call setup_movement on the GameBuilder",
+            ));
+        };
+        let Some(tile_movement_costs) = terrain_movement_costs
+            .movement_cost_rules
+            .get(&terrain_type)
+            .cloned()
+        else {
+            return Err(format!(
+                "No TileMovementCosts found in TerrainMovementCosts for TerrainType: {}",
+                terrain_type.name
+            ));
+        };
+
         let map_size = self.tile_map_size;
         let mut tile_storage = TileStorage::empty(map_size);
         let tilemap_type = self.tilemap_type;
         let tilemap_entity = world.spawn_empty().id();
-        world.resource_scope(|world, terrain_movement_costs: Mut<TerrainMovementCosts>| {
-            for x in 0..map_size.x {
-                for y in 0..map_size.y {
-                    let tile_pos = TilePos { x, y };
-                    let tile_position = TilePosition { x, y };
-                    let tile_movement_costs = terrain_movement_costs
-                        .movement_cost_rules
-                        .get(&self.map_terrain_type_vec[0])
-                        .unwrap();
-
-                    let tile_entity = world
-                        .spawn(BggfTileBundle {
-                            tile: Tile,
-                            tile_terrain_info: TileTerrainInfo {
-                                terrain_type: self.map_terrain_type_vec[0].clone(),
-                            },
-                            tile_pos,
-                            tilemap_id: TilemapId(tilemap_entity),
-                        })
-                        .insert(BggfTileObjectBundle {
-                            tile_stack_rules: self.tile_stack_rules.clone(),
-                            tile_objects: TileObjects::default(),
-                        })
-                        .insert(tile_movement_costs.clone())
-                        .insert((crate::game_core::state::Changed::default(), tile_position))
-                        .id();
-
-                    tile_storage.set(&tile_pos, tile_entity);
-                }
+        for x in 0..map_size.x {
+            for y in 0..map_size.y {
+                let tile_pos = TilePos { x, y };
+                let tile_position = TilePosition { x, y };
+
+                let tile_entity = world
+                    .spawn(BggfTileBundle {
+                        tile: Tile,
+                        tile_terrain_info: TileTerrainInfo {
+                            terrain_type: terrain_type.clone(),
+                        },
+                        tile_pos,
+                        tilemap_id: TilemapId(tilemap_entity),
+                    })
+                    .insert(BggfTileObjectBundle {
+                        tile_stack_rules: self.tile_stack_rules.clone(),
+                        tile_objects: TileObjects::default(),
+                    })
+                    .insert(tile_movement_costs.clone())
+                    .insert((crate::game_core::state::Changed::default(), tile_position))
+                    .id();
+
+                tile_storage.set(&tile_pos, tile_entity);
             }
-        });
+        }
 
         let tile_size = self.tilemap_tile_size;
         let grid_size: TilemapGridSize = tile_size.into();
-        let map_type = TilemapType::default();
 
-        // If we have already spawned this map in then just use that
-        let id = self.spawned_map_id.unwrap_or_else(|| {
-            let mut map_id_provider = world.resource_mut::<MapIdProvider>();
-            map_id_provider.next_id_component()
-        });
+        // If we have already spawned this map in or the id was pre-allocated then just use that,
+        // making sure the provider never hands the same id out again
+        let mut map_id_provider = world.resource_mut::<MapIdProvider>();
+        let id = match self.spawned_map_id {
+            Some(id) => {
+                map_id_provider.reserve_id(id.id);
+                id
+            }
+            None => map_id_provider.next_id_component(),
+        };
 
         world.send_event::<MapSpawned>(MapSpawned { map_id: id });
 
         world
             .entity_mut(tilemap_entity)
-            .insert((grid_size, map_type, map_size, tile_storage, tile_size))
+            .insert((grid_size, tilemap_type, map_size, tile_storage, tile_size))
+            .insert(SpatialBundle::from_transform(self.map_transform))
             .insert(Map {
                 tilemap_type,
                 map_size,
@@ -234,8 +395,6 @@ impl GameCommand for SpawnRandomMap {
             map_id: self.spawned_map_id.unwrap(),
         });
 
-        world.resource_mut::<MapIdProvider>().remove_last_id();
-
         return Ok(());
     }
 }
@@ -296,6 +455,7 @@ pub fn world_pos_to_tile_pos(
     TilePos::from_world_pos(&transformed_pos, map_size, grid_size, map_type)
 }
 
+/// Returns the world position of the center of the given tile on a map placed at the given transform
 pub fn tile_pos_to_centered_map_world_pos(
     tile_pos: &TilePos,
     map_transform: &Transform,
@@ -305,10 +465,67 @@ pub fn tile_pos_to_centered_map_world_pos(
     let tile_world_pos = tile_pos.center_in_world(grid_size, map_type).extend(0.0);
 
     let transformed_pos: Vec2 = {
-        // Extend the cursor_pos vec3 by 1.0
-        let tile_pos_4 = Vec4::from((tile_world_pos, -1.0));
-        let transformed_pos = map_transform.compute_matrix().inverse() * tile_pos_4;
+        // Extend the tile_world_pos vec3 by 1.0
+        let tile_pos_4 = Vec4::from((tile_world_pos, 1.0));
+        let transformed_pos = map_transform.compute_matrix() * tile_pos_4;
         transformed_pos.xy()
     };
     transformed_pos
 }
+
+/// Returns the world position of the center of the given tile on the given map, using the maps own
+/// [`Transform`]. Maps without a transform are treated as being at the origin. Returns None if the
+/// map doesn't exist
+pub fn map_tile_world_pos(world: &mut World, on_map: MapId, tile_pos: TilePos) -> Option<Vec2> {
+    let mut map_query =
+        world.query::<(&MapId, &TilemapGridSize, &TilemapType, Option<&Transform>)>();
+    let (_, grid_size, map_type, map_transform) =
+        map_query.iter(world).find(|(id, ..)| id == &&on_map)?;
+
+    Some(tile_pos_to_centered_map_world_pos(
+        &tile_pos,
+        &map_transform.copied().unwrap_or_default(),
+        grid_size,
+        map_type,
+    ))
+}
+
+/// Returns the map the given object is on and the world position of the center of its tile relative
+/// to that maps [`Transform`], so that objects on different maps are positioned relative to their own
+/// map. Returns None if the object isn't in a tile on any map
+pub fn object_world_pos(world: &mut World, object_id: ObjectId) -> Option<(MapId, Vec2)> {
+    let mut object_query = world.query::<(&ObjectId, &ObjectGridPosition)>();
+    let (_, object_grid_position) = object_query.iter(world).find(|(id, _)| id == &&object_id)?;
+    let tile_pos: TilePos = object_grid_position.tile_position.into();
+
+    let mut system_state: SystemState<(
+        Query<(
+            &MapId,
+            &TileStorage,
+            &TilemapGridSize,
+            &TilemapType,
+            Option<&Transform>,
+        )>,
+        Query<&TileObjects>,
+    )> = SystemState::new(world);
+    let (map_query, tile_query) = system_state.get(world);
+
+    map_query.iter().find_map(
+        |(map_id, tile_storage, grid_size, map_type, map_transform)| {
+            let tile_entity = tile_storage.checked_get(&tile_pos)?;
+            let tile_objects = tile_query.get(tile_entity).ok()?;
+            if !tile_objects.entities_in_tile.contains(&object_id) {
+                return None;
+            }
+            Some((
+                *map_id,
+                tile_pos_to_centered_map_world_pos(
+                    &tile_pos,
+                    &map_transform.copied().unwrap_or_default(),
+                    grid_size,
+                    map_type,
+                ),
+            ))
+        },
+    )
+}
@@ -0,0 +1,123 @@
+//! Overlays draw a highlight over a set of tiles - move ranges, attack ranges, paths, etc. A
+//! [`TileOverlay`] lives on its own tilemap entity, layered over the visual map, and
+//! [`update_tile_overlays`] keeps that tilemaps tiles in sync with [`TileOverlay::tiles`].
+//!
+//! Updating is diffed against the tiles that are already spawned, only tiles that were added or
+//! removed since the last update are spawned or despawned.
+//!
+//! ```rust
+//! use bevy::prelude::Query;
+//! use bevy_ecs_tilemap::prelude::TilePos;
+//! use bevy_ggf::mapping::overlay::TileOverlay;
+//!
+//! fn show_moves(mut overlay_query: Query<&mut TileOverlay>) {
+//!     let mut overlay = overlay_query.single_mut();
+//!     overlay.set_tiles(vec![TilePos::new(1, 1), TilePos::new(1, 2)]);
+//! }
+//! ```
+
+use bevy::prelude::{
+    BuildChildren, Commands, Component, DespawnRecursiveExt, Entity, Query, Transform,
+};
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_tilemap::prelude::{TileBundle, TilePos, TileStorage, TileTextureIndex, TilemapId};
+
+/// Component placed on an overlay tilemap entity, alongside its tilemap components, that holds
+/// the tiles to draw the overlay on.
+///
+/// The overlay tilemap should be the same size and type as the map it overlays. The texture used
+/// comes from the overlay tilemaps texture, and `texture_index` selects the tile in it.
+#[derive(Clone, Debug, Component)]
+pub struct TileOverlay {
+    tiles: Vec<TilePos>,
+    pub texture_index: TileTextureIndex,
+    pub z_layer: f32,
+    spawned_tiles: HashMap<TilePos, Entity>,
+}
+
+impl TileOverlay {
+    pub fn new(texture_index: TileTextureIndex, z_layer: f32) -> TileOverlay {
+        TileOverlay {
+            tiles: vec![],
+            texture_index,
+            z_layer,
+            spawned_tiles: HashMap::default(),
+        }
+    }
+
+    /// Returns the tiles that the overlay is shown on
+    pub fn tiles(&self) -> &Vec<TilePos> {
+        &self.tiles
+    }
+
+    /// Sets the tiles that the overlay is shown on. The overlay sprites are updated the next time
+    /// [`update_tile_overlays`] runs
+    pub fn set_tiles(&mut self, tiles: Vec<TilePos>) {
+        self.tiles = tiles;
+    }
+
+    /// Clears the overlay from every tile
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+}
+
+/// Spawns and despawns the tiles of every changed [`TileOverlay`] so that its tilemap matches
+/// [`TileOverlay::tiles`]. Tiles that were already spawned and are still in the overlay are kept
+/// and have their texture index updated.
+pub fn update_tile_overlays(
+    mut commands: Commands,
+    mut overlay_query: Query<
+        (Entity, &mut TileOverlay, &mut TileStorage, &mut Transform),
+        bevy::prelude::Changed<TileOverlay>,
+    >,
+    mut texture_query: Query<&mut TileTextureIndex>,
+) {
+    for (tilemap_entity, mut overlay, mut tile_storage, mut transform) in overlay_query.iter_mut() {
+        let overlay = overlay.as_mut();
+        if transform.translation.z != overlay.z_layer {
+            transform.translation.z = overlay.z_layer;
+        }
+
+        let wanted_tiles: HashSet<TilePos> = overlay
+            .tiles
+            .iter()
+            .filter(|tile_pos| tile_pos.within_map_bounds(&tile_storage.size))
+            .copied()
+            .collect();
+
+        overlay.spawned_tiles.retain(|tile_pos, tile_entity| {
+            if wanted_tiles.contains(tile_pos) {
+                return true;
+            }
+            tile_storage.checked_remove(tile_pos);
+            commands.entity(*tile_entity).despawn_recursive();
+            false
+        });
+
+        for tile_pos in wanted_tiles.iter() {
+            match overlay.spawned_tiles.get(tile_pos) {
+                Some(tile_entity) => {
+                    if let Ok(mut texture_index) = texture_query.get_mut(*tile_entity) {
+                        if *texture_index != overlay.texture_index {
+                            *texture_index = overlay.texture_index;
+                        }
+                    }
+                }
+                None => {
+                    let tile_entity = commands
+                        .spawn(TileBundle {
+                            position: *tile_pos,
+                            texture_index: overlay.texture_index,
+                            tilemap_id: TilemapId(tilemap_entity),
+                            ..Default::default()
+                        })
+                        .id();
+                    commands.entity(tilemap_entity).add_child(tile_entity);
+                    tile_storage.set(tile_pos, tile_entity);
+                    overlay.spawned_tiles.insert(*tile_pos, tile_entity);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Presentation side mapping data. Logical terrain is render agnostic - a [`TerrainType`] has no
+//! idea what it looks like. The [`TerrainRenderMap`] resource maps each terrain type to the index of
+//! its texture in a tilemaps texture so that rendering code can look up how a tile should be drawn
+//! without the logical map knowing anything about rendering.
+//!
+//! ```rust
+//! use bevy_ggf::mapping::render::TerrainRenderMap;
+//! use bevy_ggf::mapping::terrain::{TerrainClass, TerrainType};
+//!
+//! let grassland = TerrainType {
+//!     name: String::from("Grassland"),
+//!     terrain_class: TerrainClass {
+//!         name: String::from("Ground"),
+//!     },
+//! };
+//! let terrain_render_map = TerrainRenderMap::from_vec(None, vec![(grassland.clone(), 0)]);
+//! assert_eq!(terrain_render_map.texture_index(&grassland).unwrap().0, 0);
+//! ```
+//!
+//! Logical maps spawned by commands such as [`SpawnRandomMap`](crate::mapping::SpawnRandomMap) can
+//! run headless and so don't have any rendering components. Use [`insert_map_textures`] on the
+//! presentation side to make a spawned map draw, or add the [`BggfMapRenderPlugin`] to have a
+//! separate render tilemap spawned in the app world for every map in the [`Game`].
+
+use crate::game_core::Game;
+use crate::mapping::terrain::{TerrainType, TileTerrainInfo};
+use crate::mapping::{MapDeSpawned, MapId, MapSpawned};
+use bevy::app::{App, Plugin};
+use bevy::ecs::event::ManualEventReader;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{
+    Entity, Events, Handle, Image, Mut, Query, Resource, SpatialBundle, Transform, World,
+};
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_tilemap::prelude::{
+    TileBundle, TilePos, TileStorage, TileTextureIndex, TileVisible, TilemapBundle,
+    TilemapGridSize, TilemapId, TilemapSize, TilemapSpacing, TilemapTexture, TilemapTileSize,
+    TilemapType,
+};
+
+/// Presentation plugin that keeps a render tilemap in the app world for every map in the [`Game`]s
+/// game world. When a [`MapSpawned`] event is sent in the game world a `bevy_ecs_tilemap` tilemap is
+/// spawned with the same size, type, and tile size as the logical map and a tile for every logical
+/// tile whose terrain has a texture in the [`TerrainRenderMap`]. When a [`MapDeSpawned`] event is
+/// sent the render tilemap and its tiles are despawned.
+///
+/// Requires the [`Game`] and a [`TerrainRenderMap`] with a texture as resources in the app world.
+/// Maps spawned while either is missing aren't rendered. The render tilemaps are tracked in
+/// [`RenderedMaps`].
+pub struct BggfMapRenderPlugin;
+
+impl Plugin for BggfMapRenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RenderedMaps>()
+            .add_system(sync_rendered_maps);
+    }
+}
+
+/// Resource in the app world holding the render tilemap entity spawned by the [`BggfMapRenderPlugin`]
+/// for each logical map
+#[derive(Default, Resource)]
+pub struct RenderedMaps {
+    pub maps: HashMap<MapId, Entity>,
+    map_spawned_reader: ManualEventReader<MapSpawned>,
+    map_despawned_reader: ManualEventReader<MapDeSpawned>,
+}
+
+/// Reads the [`MapSpawned`] and [`MapDeSpawned`] events in the game world and spawns or despawns
+/// render tilemaps to match. Every map with an event has its render tilemap rebuilt from the game
+/// world, so a map that was despawned and spawned again, eg through rollback, is drawn correctly
+/// whatever order the events were sent in.
+pub fn sync_rendered_maps(world: &mut World) {
+    if !world.contains_resource::<Game>() || !world.contains_resource::<RenderedMaps>() {
+        return;
+    }
+    let Some(terrain_render_map) = world.get_resource::<TerrainRenderMap>().cloned() else {
+        return;
+    };
+
+    world.resource_scope(|world, mut rendered_maps: Mut<RenderedMaps>| {
+        world.resource_scope(|world, mut game: Mut<Game>| {
+            let game_world = &mut game.game_world;
+            let rendered_maps = &mut *rendered_maps;
+
+            let mut changed_maps: HashSet<MapId> = HashSet::new();
+            if let Some(events) = game_world.get_resource::<Events<MapSpawned>>() {
+                changed_maps.extend(
+                    rendered_maps
+                        .map_spawned_reader
+                        .iter(events)
+                        .map(|event| event.map_id()),
+                );
+            }
+            if let Some(events) = game_world.get_resource::<Events<MapDeSpawned>>() {
+                changed_maps.extend(
+                    rendered_maps
+                        .map_despawned_reader
+                        .iter(events)
+                        .map(|event| event.map_id()),
+                );
+            }
+
+            for map_id in changed_maps {
+                if let Some(tilemap_entity) = rendered_maps.maps.remove(&map_id) {
+                    despawn_render_map(world, tilemap_entity);
+                }
+                if let Some(tilemap_entity) =
+                    spawn_render_map(world, game_world, map_id, &terrain_render_map)
+                {
+                    rendered_maps.maps.insert(map_id, tilemap_entity);
+                }
+            }
+        });
+    });
+}
+
+/// Spawns a render tilemap in the world for the given map in the game world. Returns None if the map
+/// doesn't exist in the game world or the render map has no texture
+fn spawn_render_map(
+    world: &mut World,
+    game_world: &mut World,
+    map_id: MapId,
+    terrain_render_map: &TerrainRenderMap,
+) -> Option<Entity> {
+    let texture = terrain_render_map.texture.clone()?;
+
+    let mut system_state: SystemState<(
+        Query<(
+            &MapId,
+            &TileStorage,
+            &TilemapSize,
+            &TilemapType,
+            &TilemapTileSize,
+            &TilemapGridSize,
+            Option<&Transform>,
+        )>,
+        Query<(&TilePos, &TileTerrainInfo)>,
+    )> = SystemState::new(game_world);
+    let (map_query, tile_query) = system_state.get(game_world);
+
+    let (_, tile_storage, map_size, map_type, tile_size, grid_size, map_transform) =
+        map_query.iter().find(|(id, ..)| id == &&map_id)?;
+    let map_transform = map_transform.copied().unwrap_or_default();
+    let tiles: Vec<(TilePos, TileTextureIndex)> = tile_storage
+        .iter()
+        .flatten()
+        .filter_map(|tile_entity| {
+            let (tile_pos, tile_terrain_info) = tile_query.get(*tile_entity).ok()?;
+            let texture_index =
+                terrain_render_map.texture_index(&tile_terrain_info.terrain_type)?;
+            Some((*tile_pos, texture_index))
+        })
+        .collect();
+
+    let tilemap_entity = world.spawn_empty().id();
+    let mut render_tile_storage = TileStorage::empty(*map_size);
+    for (tile_pos, texture_index) in tiles {
+        let tile_entity = world
+            .spawn(TileBundle {
+                position: tile_pos,
+                texture_index,
+                tilemap_id: TilemapId(tilemap_entity),
+                ..Default::default()
+            })
+            .id();
+        render_tile_storage.set(&tile_pos, tile_entity);
+    }
+
+    world.entity_mut(tilemap_entity).insert((
+        TilemapBundle {
+            grid_size: *grid_size,
+            map_type: *map_type,
+            size: *map_size,
+            storage: render_tile_storage,
+            texture: TilemapTexture::Single(texture),
+            tile_size: *tile_size,
+            transform: map_transform,
+            global_transform: map_transform.into(),
+            ..Default::default()
+        },
+        map_id,
+    ));
+    Some(tilemap_entity)
+}
+
+/// Despawns the given render tilemap and every tile in it
+fn despawn_render_map(world: &mut World, tilemap_entity: Entity) {
+    let tile_entities: Vec<Entity> = world
+        .get::<TileStorage>(tilemap_entity)
+        .map(|tile_storage| tile_storage.iter().flatten().copied().collect())
+        .unwrap_or_default();
+    for tile_entity in tile_entities {
+        world.despawn(tile_entity);
+    }
+    world.despawn(tilemap_entity);
+}
+
+/// Resource mapping each [`TerrainType`] to the index of its texture in the tilemap texture given
+/// by `texture`.
+#[derive(Clone, Debug, Default, Resource)]
+pub struct TerrainRenderMap {
+    /// The image holding the terrain textures, used as the tilemaps texture
+    pub texture: Option<Handle<Image>>,
+    pub terrain_texture_indexes: HashMap<TerrainType, u32>,
+}
+
+impl TerrainRenderMap {
+    pub fn from_vec(
+        texture: Option<Handle<Image>>,
+        terrain_texture_indexes: Vec<(TerrainType, u32)>,
+    ) -> TerrainRenderMap {
+        TerrainRenderMap {
+            texture,
+            terrain_texture_indexes: terrain_texture_indexes.into_iter().collect(),
+        }
+    }
+
+    /// Sets the texture index for the given terrain type, replacing any previous index
+    pub fn insert(&mut self, terrain_type: TerrainType, texture_index: u32) {
+        self.terrain_texture_indexes
+            .insert(terrain_type, texture_index);
+    }
+
+    /// Returns the texture index of the given terrain type or None if it has no texture
+    pub fn texture_index(&self, terrain_type: &TerrainType) -> Option<TileTextureIndex> {
+        self.terrain_texture_indexes
+            .get(terrain_type)
+            .map(|texture_index| TileTextureIndex(*texture_index))
+    }
+}
+
+/// Inserts the rendering components onto the tiles and tilemap of the given map so that it draws.
+///
+/// Every tile is given the [`TileTextureIndex`] of its terrain from the [`TerrainRenderMap`] and the
+/// tilemap is given the render maps texture as its [`TilemapTexture`]. Tiles whose terrain has no
+/// texture index are left without one. The tilemap is also given a [`SpatialBundle`] if it doesn't
+/// have a [`Transform`] yet.
+///
+/// Returns an error if the map can't be found or the render map has no texture.
+pub fn insert_map_textures(
+    world: &mut World,
+    map_id: MapId,
+    terrain_render_map: &TerrainRenderMap,
+) -> Result<(), String> {
+    let Some(texture) = terrain_render_map.texture.clone() else {
+        return Err(String::from("TerrainRenderMap has no texture"));
+    };
+
+    let mut system_state: SystemState<(
+        Query<(Entity, &MapId, &TileStorage, Option<&Transform>)>,
+        Query<&TileTerrainInfo>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query) = system_state.get(world);
+
+    let Some((tilemap_entity, _, tile_storage, transform)) = tile_storage_query
+        .iter()
+        .find(|(_, id, _, _)| id == &&map_id)
+    else {
+        return Err(format!("Map: {:?} not found", map_id));
+    };
+    let has_transform = transform.is_some();
+
+    let tile_textures: Vec<(Entity, TileTextureIndex)> = tile_storage
+        .iter()
+        .flatten()
+        .filter_map(|tile_entity| {
+            let tile_terrain_info = tile_query.get(*tile_entity).ok()?;
+            let texture_index =
+                terrain_render_map.texture_index(&tile_terrain_info.terrain_type)?;
+            Some((*tile_entity, texture_index))
+        })
+        .collect();
+
+    for (tile_entity, texture_index) in tile_textures {
+        world
+            .entity_mut(tile_entity)
+            .insert((texture_index, TileVisible::default()));
+    }
+
+    let mut tilemap = world.entity_mut(tilemap_entity);
+    tilemap.insert((TilemapTexture::Single(texture), TilemapSpacing::default()));
+    if !has_transform {
+        tilemap.insert(SpatialBundle::default());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! A read only snapshot of the tile data of a map. Reading tile data normally needs a lookup in the
+//! maps [`TileStorage`] and then a component lookup on the tile entity for every tile. A
+//! [`TileDataSnapshot`] gathers the data for every tile once into flat arrays, indexed using
+//! [`TilePos::to_index`], so hot loops over many tiles read from contiguous memory.
+//!
+//! ### Note
+//! A snapshot is a copy. It does not update when the world changes so take a new snapshot after
+//! running any commands that change the map.
+
+use crate::mapping::terrain::{TerrainType, TileTerrainInfo};
+use crate::mapping::tiles::TileObjects;
+use crate::mapping::MapId;
+use crate::movement::{MovementType, TileMovementCosts};
+use crate::object::ObjectId;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Entity, Query, World};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapSize};
+
+/// Flat arrays of the terrain types, movement costs, and occupancy of every tile on a map. Tiles
+/// that don't exist or are missing a component have a None entry.
+#[derive(Clone, Debug)]
+pub struct TileDataSnapshot {
+    pub map_id: MapId,
+    pub map_size: TilemapSize,
+    pub tile_entities: Vec<Option<Entity>>,
+    pub terrain_types: Vec<Option<TerrainType>>,
+    pub movement_costs: Vec<Option<TileMovementCosts>>,
+    pub objects: Vec<Vec<ObjectId>>,
+}
+
+impl TileDataSnapshot {
+    /// Gathers the tile data of every tile on the given map. Returns None if the map can't be found
+    pub fn new(world: &mut World, map_id: MapId) -> Option<TileDataSnapshot> {
+        let mut system_state: SystemState<(
+            Query<(&MapId, &TileStorage, &TilemapSize)>,
+            Query<(
+                Option<&TileTerrainInfo>,
+                Option<&TileMovementCosts>,
+                Option<&TileObjects>,
+            )>,
+        )> = SystemState::new(world);
+        let (tile_storage_query, tile_query) = system_state.get(world);
+
+        let (_, tile_storage, map_size) = tile_storage_query
+            .iter()
+            .find(|(id, _, _)| id == &&map_id)?;
+
+        let count = map_size.count();
+        let mut snapshot = TileDataSnapshot {
+            map_id,
+            map_size: *map_size,
+            tile_entities: vec![None; count],
+            terrain_types: vec![None; count],
+            movement_costs: vec![None; count],
+            objects: vec![vec![]; count],
+        };
+
+        for y in 0..map_size.y {
+            for x in 0..map_size.x {
+                let tile_pos = TilePos::new(x, y);
+                let index = tile_pos.to_index(map_size);
+                let Some(tile_entity) = tile_storage.get(&tile_pos) else {
+                    continue;
+                };
+                snapshot.tile_entities[index] = Some(tile_entity);
+
+                let Ok((tile_terrain_info, tile_movement_costs, tile_objects)) =
+                    tile_query.get(tile_entity)
+                else {
+                    continue;
+                };
+                snapshot.terrain_types[index] =
+                    tile_terrain_info.map(|info| info.terrain_type.clone());
+                snapshot.movement_costs[index] = tile_movement_costs.cloned();
+                if let Some(tile_objects) = tile_objects {
+                    snapshot.objects[index] = tile_objects.entities_in_tile.clone();
+                }
+            }
+        }
+
+        Some(snapshot)
+    }
+
+    /// Returns the index of the given tile in the snapshots arrays or None if it is outside the map
+    pub fn index(&self, tile_pos: &TilePos) -> Option<usize> {
+        if !tile_pos.within_map_bounds(&self.map_size) {
+            return None;
+        }
+        Some(tile_pos.to_index(&self.map_size))
+    }
+
+    /// Returns the entity of the given tile
+    pub fn tile_entity(&self, tile_pos: &TilePos) -> Option<Entity> {
+        self.tile_entities[self.index(tile_pos)?]
+    }
+
+    /// Returns the terrain type of the given tile
+    pub fn terrain_type(&self, tile_pos: &TilePos) -> Option<&TerrainType> {
+        self.terrain_types[self.index(tile_pos)?].as_ref()
+    }
+
+    /// Returns the cost for the given movement type to move into the given tile. Returns None if the
+    /// tile has no [`TileMovementCosts`] or no cost for the movement type.
+    pub fn movement_cost(&self, tile_pos: &TilePos, movement_type: &MovementType) -> Option<u32> {
+        self.movement_costs[self.index(tile_pos)?]
+            .as_ref()?
+            .movement_type_cost
+            .get(movement_type)
+            .copied()
+    }
+
+    /// Returns the objects in the given tile. Returns an empty slice for tiles outside the map
+    pub fn objects_in_tile(&self, tile_pos: &TilePos) -> &[ObjectId] {
+        match self.index(tile_pos) {
+            Some(index) => &self.objects[index],
+            None => &[],
+        }
+    }
+
+    /// Returns true if there are any objects in the given tile
+    pub fn is_occupied(&self, tile_pos: &TilePos) -> bool {
+        !self.objects_in_tile(tile_pos).is_empty()
+    }
+}
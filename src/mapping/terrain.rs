@@ -17,8 +17,12 @@
 //
 //
 
-use bevy::prelude::{Component, ReflectComponent};
+use crate::mapping::tiles::tile_neighbors;
+use crate::mapping::MapId;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Component, Query, ReflectComponent, World};
 use bevy::reflect::{FromReflect, Reflect};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapType};
 use serde::{Deserialize, Serialize};
 
 /// Component holding the tile terrain info needed by any built in logic.
@@ -64,3 +68,31 @@ pub struct TerrainType {
     pub name: String,
     pub terrain_class: TerrainClass,
 }
+
+/// Returns the [`TerrainClass`] of each neighbor of the given tile, in the fixed order given by
+/// [`tile_neighbors`]. Neighbors that are outside the map or have no terrain are None. Use this for
+/// autotiling borders like coastlines or for rules that depend on the surrounding terrain. Returns
+/// an empty Vec if the map can't be found.
+pub fn terrain_neighbors(
+    world: &mut World,
+    map_id: MapId,
+    tile_pos: TilePos,
+    map_type: &TilemapType,
+) -> Vec<Option<TerrainClass>> {
+    let mut system_state: SystemState<(Query<(&MapId, &TileStorage)>, Query<&TileTerrainInfo>)> =
+        SystemState::new(world);
+    let (tile_storage_query, tile_query) = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&map_id) else {
+        return vec![];
+    };
+
+    tile_neighbors(&tile_pos, &tile_storage.size, map_type)
+        .iter()
+        .map(|neighbor| {
+            let tile_entity = tile_storage.checked_get(neighbor.as_ref()?)?;
+            let tile_terrain_info = tile_query.get(tile_entity).ok()?;
+            Some(tile_terrain_info.terrain_type.terrain_class.clone())
+        })
+        .collect()
+}
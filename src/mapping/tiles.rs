@@ -8,12 +8,13 @@
 // stuff as a unit but they get that marker component/trait and it holds them in a separate spot
 
 use crate::mapping::terrain::TileTerrainInfo;
+use crate::mapping::MapId;
 use crate::object::ObjectId;
-use bevy::prelude::{Bundle, Component, ReflectComponent};
+use bevy::prelude::{Bundle, Component, Entity, Mut, ReflectComponent, World};
 use bevy::reflect::{FromReflect, Reflect};
 use bevy::utils::hashbrown::HashMap;
-use bevy_ecs_tilemap::prelude::TilemapId;
-use bevy_ecs_tilemap::tiles::TilePos;
+use bevy_ecs_tilemap::prelude::{HexCoordSystem, TilemapId, TilemapSize, TilemapType};
+use bevy_ecs_tilemap::tiles::{TilePos, TileStorage};
 use serde::{Deserialize, Serialize};
 
 /// Bundle containing all the basic tile components needed for a tile.
@@ -96,9 +97,41 @@ impl TilePosition {
 )]
 #[reflect(Component)]
 pub struct TileObjectStacks {
+    #[serde(with = "stacks_as_vec")]
     pub tile_object_stacks: HashMap<StackingClass, TileObjectStacksCount>,
 }
 
+/// Serializes the stacks of a [`TileObjectStacks`] as a list sorted by [`StackingClass`] name instead
+/// of a map. Formats such as JSON only allow string keys, and sorting means that identical stacks
+/// always serialize to identical bytes no matter the HashMaps order.
+mod stacks_as_vec {
+    use super::{StackingClass, TileObjectStacksCount};
+    use bevy::utils::hashbrown::HashMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(
+        stacks: &HashMap<StackingClass, TileObjectStacksCount>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut stacks: Vec<(&StackingClass, &TileObjectStacksCount)> = stacks.iter().collect();
+        stacks.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        stacks.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<StackingClass, TileObjectStacksCount>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let stacks: Vec<(StackingClass, TileObjectStacksCount)> = Vec::deserialize(deserializer)?;
+        Ok(stacks.into_iter().collect())
+    }
+}
+
 impl TileObjectStacks {
     pub fn new(stack_rules: Vec<(StackingClass, TileObjectStacksCount)>) -> TileObjectStacks {
         TileObjectStacks {
@@ -127,6 +160,26 @@ impl TileObjectStacks {
         };
     }
 
+    /// Returns how many objects of the given [`StackingClass`] are currently in the tile. Classes the
+    /// tile has no rule for always return 0
+    pub fn occupancy(&self, stacking_class: &StackingClass) -> u32 {
+        self.tile_object_stacks
+            .get(stacking_class)
+            .map_or(0, |tile_stack_count| tile_stack_count.current_count)
+    }
+
+    /// Returns every [`StackingClass`] that has at least one object in the tile, sorted by name
+    pub fn occupied_classes(&self) -> Vec<StackingClass> {
+        let mut occupied_classes: Vec<StackingClass> = self
+            .tile_object_stacks
+            .iter()
+            .filter(|(_, tile_stack_count)| tile_stack_count.current_count > 0)
+            .map(|(stacking_class, _)| stacking_class.clone())
+            .collect();
+        occupied_classes.sort_by(|a, b| a.name.cmp(&b.name));
+        occupied_classes
+    }
+
     pub fn increment_object_class_count(&mut self, object_class: &ObjectStackingClass) {
         if let Some(tile_stack_count_max) =
             self.tile_object_stacks.get_mut(&object_class.stack_class)
@@ -166,6 +219,46 @@ fn test_tile_object_stacks() {
     }, ))
 }
 
+#[test]
+fn test_tile_object_stacks_save_round_trip() {
+    use crate::game_core::saving::{ComponentBinaryState, GameSerDeRegistry, SaveId};
+    use bevy::prelude::World;
+
+    let stacking_class_ground: StackingClass = StackingClass {
+        name: String::from("Ground"),
+    };
+    let stacking_class_air: StackingClass = StackingClass {
+        name: String::from("Air"),
+    };
+    let mut tile_object_stacks = TileObjectStacks::new(vec![
+        (
+            stacking_class_ground.clone(),
+            TileObjectStacksCount {
+                current_count: 0,
+                max_count: 1,
+            },
+        ),
+        (
+            stacking_class_air,
+            TileObjectStacksCount {
+                current_count: 0,
+                max_count: 3,
+            },
+        ),
+    ]);
+    tile_object_stacks.increment_object_class_count(&ObjectStackingClass {
+        stack_class: stacking_class_ground,
+    });
+
+    let registry = GameSerDeRegistry::default_registry();
+    let mut world = World::new();
+    let mut entity = world.spawn_empty();
+    let (id, component) = tile_object_stacks.save().unwrap();
+    registry.deserialize_component_onto(&ComponentBinaryState { id, component }, &mut entity);
+
+    assert!(entity.get::<TileObjectStacks>() == Some(&tile_object_stacks));
+}
+
 /// A StackingClass represents what kind of stack an object belongs to in a tile. This is used internally
 /// in [`TileObjectStacks`]
 #[derive(
@@ -203,6 +296,29 @@ pub struct ObjectStackingClass {
     pub stack_class: StackingClass,
 }
 
+/// Marker component for objects that are in a tiles [`TileObjects`], so they can be selected and
+/// queried, but don't count toward the tiles [`TileObjectStacks`]. Use it for objects like spell
+/// markers, flags, or waypoints that shouldn't take up the stacking slots of real units.
+///
+/// Objects with this component are added to and removed from tiles without changing the stacking
+/// counts, are never blocked by a full tile, and still need an [`ObjectStackingClass`].
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    Hash,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[reflect(Component)]
+pub struct IgnoresStacking;
+
 /// Wraps two u32s for use in a [`TileObjectStacks`] component. Used to keep track of the current_count
 /// of objects belonging to that [`ObjectStackingClass`] in the tile and the max_count allowed in the tile.
 #[derive(
@@ -262,3 +378,342 @@ impl TileObjects {
         }
     }
 }
+
+/// A borrow of a tiles terrain, [`TileObjectStacks`], and [`TileObjects`] together. Use it in custom
+/// commands that inspect and modify everything in a tile. Adding and removing objects through it
+/// always updates both the object list and the stacking counts so that they can't get out of sync.
+/// The built in commands, eg [`AddObjectToTile`](crate::game_core::command::AddObjectToTile) and
+/// [`RemoveObjectFromTile`](crate::game_core::command::RemoveObjectFromTile), change tiles through it
+/// as well.
+///
+/// Objects with [`IgnoresStacking`] should be added and removed with `ignores_stacking` set so that
+/// they don't change the stacking counts, matching
+/// [`AddObjectToTile`](crate::game_core::command::AddObjectToTile). Changes made through the
+/// contents are picked up by change tracking the same as any other change to the components, the
+/// objects themselves aren't changed, eg their [`ObjectGridPosition`](crate::object::ObjectGridPosition)
+/// has to be updated separately.
+pub struct TileContents<'w> {
+    tile_entity: Entity,
+    tile_terrain_info: Option<&'w TileTerrainInfo>,
+    tile_object_stacks: Mut<'w, TileObjectStacks>,
+    tile_objects: Mut<'w, TileObjects>,
+}
+
+impl<'w> TileContents<'w> {
+    /// Returns the contents of the given tile entity. Returns None if the entity is missing its
+    /// [`TileObjectStacks`] or [`TileObjects`]
+    pub fn get(world: &'w mut World, tile_entity: Entity) -> Option<TileContents<'w>> {
+        let mut tile_query = world.query::<(
+            Option<&TileTerrainInfo>,
+            &mut TileObjectStacks,
+            &mut TileObjects,
+        )>();
+        let (tile_terrain_info, tile_object_stacks, tile_objects) =
+            tile_query.get_mut(world, tile_entity).ok()?;
+        Some(TileContents {
+            tile_entity,
+            tile_terrain_info,
+            tile_object_stacks,
+            tile_objects,
+        })
+    }
+
+    /// Returns the contents of the tile at the given position on the given map. Returns None if the
+    /// map or tile can't be found
+    pub fn at(world: &'w mut World, on_map: MapId, tile_pos: TilePos) -> Option<TileContents<'w>> {
+        let mut map_query = world.query::<(&MapId, &TileStorage)>();
+        let tile_entity = map_query
+            .iter(world)
+            .find(|(id, _)| id == &&on_map)
+            .and_then(|(_, tile_storage)| tile_storage.checked_get(&tile_pos))?;
+        TileContents::get(world, tile_entity)
+    }
+
+    /// The entity of the tile
+    pub fn tile_entity(&self) -> Entity {
+        self.tile_entity
+    }
+
+    /// The terrain of the tile. None if the tile has no [`TileTerrainInfo`]
+    pub fn terrain(&self) -> Option<&TileTerrainInfo> {
+        self.tile_terrain_info
+    }
+
+    /// The stacking rules and counts of the tile
+    pub fn stacks(&self) -> &TileObjectStacks {
+        &self.tile_object_stacks
+    }
+
+    /// Every object in the tile, in the order they entered it
+    pub fn objects(&self) -> &[ObjectId] {
+        &self.tile_objects.entities_in_tile
+    }
+
+    /// Returns true if the given object is in the tile
+    pub fn contains_object(&self, object_id: ObjectId) -> bool {
+        self.tile_objects.contains_object(object_id)
+    }
+
+    /// Returns true if the tile has space for another object of the given [`ObjectStackingClass`]
+    pub fn has_space(&self, object_stacking_class: &ObjectStackingClass) -> bool {
+        self.tile_object_stacks.has_space(object_stacking_class)
+    }
+
+    /// Adds the given object to the tile and increments the count of its [`ObjectStackingClass`]
+    /// unless `ignores_stacking` is set. Fails if the object is already in the tile or, when
+    /// `strict` is set, if the tile doesn't have space for it
+    pub fn add_object(
+        &mut self,
+        object_id: ObjectId,
+        object_stacking_class: &ObjectStackingClass,
+        ignores_stacking: bool,
+        strict: bool,
+    ) -> Result<(), String> {
+        if self.contains_object(object_id) {
+            return Err(format!("ObjectId: {:?} is already in the tile", object_id));
+        }
+        if strict && !ignores_stacking && !self.has_space(object_stacking_class) {
+            return Err(format!(
+                "TileFull: Tile does not have space for ObjectId: {:?}",
+                object_id
+            ));
+        }
+
+        self.tile_objects.add_object(object_id);
+        if !ignores_stacking {
+            self.tile_object_stacks
+                .increment_object_class_count(object_stacking_class);
+        }
+        Ok(())
+    }
+
+    /// Removes the given object from the tile and decrements the count of its
+    /// [`ObjectStackingClass`] unless `ignores_stacking` is set. Returns false, without changing the
+    /// counts, if the object wasn't in the tile
+    pub fn remove_object(
+        &mut self,
+        object_id: ObjectId,
+        object_stacking_class: &ObjectStackingClass,
+        ignores_stacking: bool,
+    ) -> bool {
+        if !self.tile_objects.remove_object(object_id) {
+            return false;
+        }
+        if !ignores_stacking {
+            self.tile_object_stacks
+                .decrement_object_class_count(object_stacking_class);
+        }
+        true
+    }
+}
+
+/// Returns the neighbors of the given tile in a fixed order for the given map type. Neighbors that
+/// are outside the map are None so each index always refers to the same direction.
+///
+/// - Square and isometric maps return eight neighbors in the order north, east, south, west,
+///   northwest, northeast, southeast, southwest
+/// - Hexagon maps return six neighbors, starting at the axial direction (+1, 0) and going
+///   counterclockwise
+pub fn tile_neighbors(
+    tile_pos: &TilePos,
+    map_size: &TilemapSize,
+    map_type: &TilemapType,
+) -> Vec<Option<TilePos>> {
+    let (x, y) = (tile_pos.x as i32, tile_pos.y as i32);
+    match map_type {
+        TilemapType::Square | TilemapType::Isometric(_) => [
+            (0, 1),
+            (1, 0),
+            (0, -1),
+            (-1, 0),
+            (-1, 1),
+            (1, 1),
+            (1, -1),
+            (-1, -1),
+        ]
+        .iter()
+        .map(|(offset_x, offset_y)| TilePos::from_i32_pair(x + offset_x, y + offset_y, map_size))
+        .collect(),
+        TilemapType::Hexagon(hex_coord_system) => {
+            let (q, r) = offset_to_axial(x, y, hex_coord_system);
+            [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)]
+                .iter()
+                .map(|(offset_q, offset_r)| {
+                    let (x, y) = axial_to_offset(q + offset_q, r + offset_r, hex_coord_system);
+                    TilePos::from_i32_pair(x, y, map_size)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Returns the number of steps between two tiles on a map of the given type without moving
+/// diagonally. Square and isometric maps use the Manhattan distance and hexagon maps use the cube
+/// distance for the maps [`HexCoordSystem`]. Use [`tile_distance_diagonal`] for square maps where
+/// diagonal steps are allowed
+pub fn tile_distance(a: TilePos, b: TilePos, map_type: TilemapType) -> u32 {
+    match map_type {
+        TilemapType::Square | TilemapType::Isometric(_) => a.x.abs_diff(b.x) + a.y.abs_diff(b.y),
+        TilemapType::Hexagon(hex_coord_system) => hex_distance(a, b, &hex_coord_system),
+    }
+}
+
+/// Returns the number of steps between two tiles on a map of the given type when diagonal steps are
+/// allowed. Square and isometric maps use the Chebyshev distance and hexagon maps, which have no
+/// diagonals, use the same distance as [`tile_distance`]
+pub fn tile_distance_diagonal(a: TilePos, b: TilePos, map_type: TilemapType) -> u32 {
+    match map_type {
+        TilemapType::Square | TilemapType::Isometric(_) => a.x.abs_diff(b.x).max(a.y.abs_diff(b.y)),
+        TilemapType::Hexagon(hex_coord_system) => hex_distance(a, b, &hex_coord_system),
+    }
+}
+
+/// The cube distance between two hex tiles in the given coordinate system
+fn hex_distance(a: TilePos, b: TilePos, hex_coord_system: &HexCoordSystem) -> u32 {
+    let (a_q, a_r) = offset_to_axial(a.x as i32, a.y as i32, hex_coord_system);
+    let (b_q, b_r) = offset_to_axial(b.x as i32, b.y as i32, hex_coord_system);
+    let (d_q, d_r) = (a_q - b_q, a_r - b_r);
+    (d_q.unsigned_abs() + d_r.unsigned_abs() + (d_q + d_r).unsigned_abs()) / 2
+}
+
+/// Converts a hex tile position in the given coordinate system to axial coordinates
+fn offset_to_axial(x: i32, y: i32, hex_coord_system: &HexCoordSystem) -> (i32, i32) {
+    match hex_coord_system {
+        HexCoordSystem::Row | HexCoordSystem::Column => (x, y),
+        HexCoordSystem::RowOdd => (x - (y - (y & 1)) / 2, y),
+        HexCoordSystem::RowEven => (x - (y + (y & 1)) / 2, y),
+        HexCoordSystem::ColumnOdd => (x, y - (x - (x & 1)) / 2),
+        HexCoordSystem::ColumnEven => (x, y - (x + (x & 1)) / 2),
+    }
+}
+
+/// Converts axial hex coordinates back to a tile position in the given coordinate system
+fn axial_to_offset(q: i32, r: i32, hex_coord_system: &HexCoordSystem) -> (i32, i32) {
+    match hex_coord_system {
+        HexCoordSystem::Row | HexCoordSystem::Column => (q, r),
+        HexCoordSystem::RowOdd => (q + (r - (r & 1)) / 2, r),
+        HexCoordSystem::RowEven => (q + (r + (r & 1)) / 2, r),
+        HexCoordSystem::ColumnOdd => (q, r + (q - (q & 1)) / 2),
+        HexCoordSystem::ColumnEven => (q, r + (q + (q & 1)) / 2),
+    }
+}
+
+#[test]
+fn test_tile_distance_square() {
+    let a = TilePos { x: 1, y: 1 };
+    let b = TilePos { x: 4, y: 3 };
+
+    assert_eq!(tile_distance(a, b, TilemapType::Square), 5);
+    assert_eq!(tile_distance(b, a, TilemapType::Square), 5);
+    assert_eq!(tile_distance(a, a, TilemapType::Square), 0);
+    assert_eq!(tile_distance_diagonal(a, b, TilemapType::Square), 3);
+    assert_eq!(tile_distance_diagonal(b, a, TilemapType::Square), 3);
+    assert_eq!(
+        tile_distance_diagonal(a, TilePos { x: 2, y: 2 }, TilemapType::Square),
+        1
+    );
+}
+
+#[test]
+fn test_tile_distance_hexagon() {
+    let map_size = TilemapSize { x: 10, y: 10 };
+    let hex_coord_systems = [
+        HexCoordSystem::Row,
+        HexCoordSystem::Column,
+        HexCoordSystem::RowEven,
+        HexCoordSystem::RowOdd,
+        HexCoordSystem::ColumnEven,
+        HexCoordSystem::ColumnOdd,
+    ];
+
+    for hex_coord_system in hex_coord_systems {
+        let map_type = TilemapType::Hexagon(hex_coord_system);
+        let center = TilePos { x: 4, y: 4 };
+
+        // Every neighbor is one step away and every neighbor of a neighbor is at most two
+        for neighbor in tile_neighbors(&center, &map_size, &map_type)
+            .into_iter()
+            .flatten()
+        {
+            assert_eq!(tile_distance(center, neighbor, map_type), 1);
+            assert_eq!(tile_distance_diagonal(center, neighbor, map_type), 1);
+            for second in tile_neighbors(&neighbor, &map_size, &map_type)
+                .into_iter()
+                .flatten()
+            {
+                assert!(tile_distance(center, second, map_type) <= 2);
+            }
+        }
+        assert_eq!(tile_distance(center, center, map_type), 0);
+    }
+
+    // Axial coordinates, moving diagonally along q and r at once only costs one step per tile
+    let map_type = TilemapType::Hexagon(HexCoordSystem::Row);
+    assert_eq!(
+        tile_distance(TilePos { x: 3, y: 0 }, TilePos { x: 0, y: 3 }, map_type),
+        3
+    );
+    assert_eq!(
+        tile_distance(TilePos { x: 0, y: 0 }, TilePos { x: 3, y: 3 }, map_type),
+        6
+    );
+    // Offset coordinates, three rows down in an odd-r layout is three steps
+    let map_type = TilemapType::Hexagon(HexCoordSystem::RowOdd);
+    assert_eq!(
+        tile_distance(TilePos { x: 2, y: 0 }, TilePos { x: 3, y: 3 }, map_type),
+        3
+    );
+}
+
+#[test]
+fn test_tile_contents_keeps_stacks_in_sync() {
+    use crate::mapping::terrain::TerrainType;
+
+    let stacking_class = ObjectStackingClass {
+        stack_class: StackingClass {
+            name: String::from("Ground"),
+        },
+    };
+    let mut world = World::new();
+    let tile_entity = world
+        .spawn((
+            TileTerrainInfo {
+                terrain_type: TerrainType::default(),
+            },
+            TileObjectStacks::new(vec![(
+                stacking_class.stack_class.clone(),
+                TileObjectStacksCount {
+                    current_count: 0,
+                    max_count: 1,
+                },
+            )]),
+            TileObjects::default(),
+        ))
+        .id();
+
+    let ground = stacking_class.stack_class.clone();
+    let mut tile_contents = TileContents::get(&mut world, tile_entity).unwrap();
+    assert!(tile_contents
+        .add_object(ObjectId { id: 1 }, &stacking_class, false, true)
+        .is_ok());
+    // The tile is full but objects that ignore stacking still fit
+    assert!(tile_contents
+        .add_object(ObjectId { id: 2 }, &stacking_class, false, true)
+        .is_err());
+    assert!(tile_contents
+        .add_object(ObjectId { id: 3 }, &stacking_class, true, true)
+        .is_ok());
+    assert_eq!(
+        tile_contents.objects(),
+        &[ObjectId { id: 1 }, ObjectId { id: 3 }]
+    );
+    assert_eq!(tile_contents.stacks().occupancy(&ground), 1);
+
+    assert!(tile_contents.remove_object(ObjectId { id: 3 }, &stacking_class, true));
+    assert_eq!(tile_contents.stacks().occupancy(&ground), 1);
+    assert!(!tile_contents.remove_object(ObjectId { id: 2 }, &stacking_class, false));
+    assert_eq!(tile_contents.stacks().occupancy(&ground), 1);
+    assert!(tile_contents.remove_object(ObjectId { id: 1 }, &stacking_class, false));
+    assert_eq!(tile_contents.stacks().occupancy(&ground), 0);
+    assert!(tile_contents.objects().is_empty());
+}
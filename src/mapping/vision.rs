@@ -0,0 +1,172 @@
+//! Utilities for checking what can be seen across the tile grid. Add the [`BlocksVision`] marker to
+//! tiles or objects that should block line of sight, like mountains or walls.
+
+use crate::mapping::tiles::TileObjects;
+use crate::mapping::{Map, MapId};
+use crate::object::ObjectId;
+use crate::player::PlayerMarker;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{
+    Component, Entity, FromReflect, Query, Reflect, ReflectComponent, With, World,
+};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapType};
+use serde::{Deserialize, Serialize};
+
+/// Marker component for tiles and objects that block line of sight. A tile blocks vision if it has
+/// this component or if any object in it has this component.
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Eq,
+    Hash,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Component)]
+pub struct BlocksVision;
+
+/// Returns true if there is a clear line of sight between the two tiles on the given map. Walks every
+/// tile between `from` and `to` and returns false if any of them block vision. The `from` and `to`
+/// tiles themselves never block.
+///
+/// Returns false if the map can't be found.
+pub fn line_of_sight(world: &mut World, on_map: MapId, from: TilePos, to: TilePos) -> bool {
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage, &Map)>,
+        Query<(Option<&TileObjects>, Option<&BlocksVision>)>,
+        Query<&ObjectId, With<BlocksVision>>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, blocking_object_query) = system_state.get(world);
+
+    let Some((_, tile_storage, map)) = tile_storage_query.iter().find(|(id, _, _)| id == &&on_map)
+    else {
+        return false;
+    };
+
+    let line = tiles_in_line(map.tilemap_type, from, to);
+    for tile_pos in line
+        .iter()
+        .filter(|tile_pos| *tile_pos != &from && *tile_pos != &to)
+    {
+        let Some(tile_entity) = tile_storage.checked_get(tile_pos) else {
+            continue;
+        };
+        if tile_blocks_vision(tile_entity, &tile_query, &blocking_object_query) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns true if any object belonging to the given player on the given map has a line of sight to
+/// the given tile, or if the player has an object in the tile. Used to hide enemy objects from
+/// selection and targeting when [`GameRules::fog_of_war`](crate::game_core::rules::GameRules) is
+/// enabled.
+///
+/// Returns false if the map can't be found.
+pub fn visible_to_player(
+    world: &mut World,
+    on_map: MapId,
+    tile_pos: TilePos,
+    player_id: usize,
+) -> bool {
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage)>,
+        Query<(&TilePos, &TileObjects)>,
+        Query<(&ObjectId, &PlayerMarker)>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&on_map) else {
+        return false;
+    };
+    let player_objects: Vec<ObjectId> = object_query
+        .iter()
+        .filter(|(_, player_marker)| player_marker.id() == player_id)
+        .map(|(object_id, _)| *object_id)
+        .collect();
+    let player_tiles: Vec<TilePos> = tile_storage
+        .iter()
+        .flatten()
+        .filter_map(|tile_entity| tile_query.get(*tile_entity).ok())
+        .filter(|(_, tile_objects)| {
+            player_objects
+                .iter()
+                .any(|object_id| tile_objects.contains_object(*object_id))
+        })
+        .map(|(tile_pos, _)| *tile_pos)
+        .collect();
+
+    player_tiles
+        .into_iter()
+        .any(|from| line_of_sight(world, on_map, from, tile_pos))
+}
+
+/// Returns true if the tile has the [`BlocksVision`] component or contains an object that does
+fn tile_blocks_vision(
+    tile_entity: Entity,
+    tile_query: &Query<(Option<&TileObjects>, Option<&BlocksVision>)>,
+    blocking_object_query: &Query<&ObjectId, With<BlocksVision>>,
+) -> bool {
+    let Ok((tile_objects, blocks_vision)) = tile_query.get(tile_entity) else {
+        return false;
+    };
+    if blocks_vision.is_some() {
+        return true;
+    }
+    let Some(tile_objects) = tile_objects else {
+        return false;
+    };
+    blocking_object_query
+        .iter()
+        .any(|object_id| tile_objects.contains_object(*object_id))
+}
+
+/// Returns every tile on the line between the two tiles, including both ends, in order from `from`
+/// to `to`.
+pub fn tiles_in_line(tilemap_type: TilemapType, from: TilePos, to: TilePos) -> Vec<TilePos> {
+    match tilemap_type {
+        TilemapType::Square | TilemapType::Isometric(_) => bresenham_line(from, to),
+        // TODO: Hexagon maps need a cube coordinate line. Until then they use the square line which
+        // is only an approximation
+        TilemapType::Hexagon(_) => bresenham_line(from, to),
+    }
+}
+
+/// Bresenham line between two tiles on a square grid
+fn bresenham_line(from: TilePos, to: TilePos) -> Vec<TilePos> {
+    let mut tiles: Vec<TilePos> = vec![];
+
+    let (mut x, mut y) = (from.x as i64, from.y as i64);
+    let (end_x, end_y) = (to.x as i64, to.y as i64);
+    let delta_x = (end_x - x).abs();
+    let delta_y = -(end_y - y).abs();
+    let step_x = if x < end_x { 1 } else { -1 };
+    let step_y = if y < end_y { 1 } else { -1 };
+    let mut error = delta_x + delta_y;
+
+    loop {
+        tiles.push(TilePos::new(x as u32, y as u32));
+        if x == end_x && y == end_y {
+            break;
+        }
+        let doubled_error = 2 * error;
+        if doubled_error >= delta_y {
+            error += delta_y;
+            x += step_x;
+        }
+        if doubled_error <= delta_x {
+            error += delta_x;
+            y += step_y;
+        }
+    }
+
+    tiles
+}
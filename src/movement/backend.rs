@@ -1,4 +1,8 @@
-use crate::movement::{MoveEvent, ObjectMoved, ObjectMovement, TileMovementCosts};
+use crate::mapping::terrain::TileTerrainInfo;
+use crate::movement::{
+    AvailableMove, InvalidMoveReason, MoveEvent, ObjectMoved, ObjectMovement,
+    ObjectMovementCostModifiers, PartialMove, TileEdgeCosts, TileMovementCosts,
+};
 use crate::object::ObjectId;
 use bevy::prelude::{Commands, Entity, EventReader, Query, World};
 use bevy::utils::hashbrown::HashMap;
@@ -7,9 +11,15 @@ use bevy_ecs_tilemap::prelude::{TilePos, TilemapSize};
 /// Provided function that can be used in a [`MovementCalculator`](crate::movement::MovementCalculator) to keep track of the nodes in a pathfinding node,
 /// their associated movement costs, and which is the node that has the shortest path to that specific
 /// node. Will automatically compute all of the above.
+///
+/// The cost to move into a tile is the [`tile_entry_cost`], the tiles [`TileMovementCosts`] adjusted
+/// by any [`ObjectMovementCostModifiers`] on the moving object, plus any [`TileEdgeCosts`] on the
+/// tile being moved from toward the tile being moved into. Objects with a [`PartialMove`] can only
+/// spend their remaining move points.
 pub fn tile_movement_cost_check(
     entity_moving: Entity,
     tile_entity: Entity,
+    move_from_tile_entity: Entity,
     tile_pos: &TilePos,
     move_from_tile_pos: &TilePos,
     movement_nodes: &mut MovementNodes,
@@ -18,7 +28,11 @@ pub fn tile_movement_cost_check(
     let Some(object_movement) = world.get::<ObjectMovement>(entity_moving) else {
         return false;
     };
-    let Some(tile_movement_costs) = world.get::<TileMovementCosts>(tile_entity) else {
+    let Some(tile_entry_cost) = tile_entry_cost(entity_moving, object_movement, tile_entity, world)
+    else {
+        if let Some(tile_node) = movement_nodes.get_node_mut(tile_pos) {
+            tile_node.set_invalid_reason(InvalidMoveReason::Blocked);
+        }
         return false;
     };
 
@@ -28,47 +42,78 @@ pub fn tile_movement_cost_check(
         return false;
     };
 
+    let move_cost = move_from_tile_node.move_cost.unwrap()
+        + tile_entry_cost as i32
+        + tile_edge_cost(move_from_tile_entity, move_from_tile_pos, tile_pos, world) as i32;
+
     return if tile_node.move_cost.is_some() {
-        if (move_from_tile_node.move_cost.unwrap()
-            + *tile_movement_costs
-                .movement_type_cost
-                .get(&object_movement.movement_type)
-                .unwrap_or(&1) as i32)
-            < (tile_node.move_cost.unwrap())
-        {
-            tile_node.move_cost = Some(
-                move_from_tile_node.move_cost.unwrap()
-                    + *tile_movement_costs
-                        .movement_type_cost
-                        .get(&object_movement.movement_type)
-                        .unwrap_or(&1) as i32,
-            );
+        if move_cost < tile_node.move_cost.unwrap() {
+            tile_node.move_cost = Some(move_cost);
             tile_node.prior_node = move_from_tile_node.node_pos;
             true
         } else {
             false
         }
-    } else if (move_from_tile_node.move_cost.unwrap()
-        + *tile_movement_costs
-            .movement_type_cost
-            .get(&object_movement.movement_type)
-            .unwrap_or(&1) as i32)
-        <= object_movement.move_points
+    } else if move_cost
+        <= world
+            .get::<PartialMove>(entity_moving)
+            .map_or(object_movement.move_points, |partial_move| {
+                partial_move.remaining(object_movement)
+            })
     {
-        tile_node.move_cost = Some(
-            move_from_tile_node.move_cost.unwrap()
-                + *tile_movement_costs
-                    .movement_type_cost
-                    .get(&object_movement.movement_type)
-                    .unwrap_or(&1) as i32,
-        );
+        tile_node.move_cost = Some(move_cost);
         tile_node.prior_node = move_from_tile_node.node_pos;
         true
     } else {
+        tile_node.set_invalid_reason(InvalidMoveReason::TooFar);
         false
     };
 }
 
+/// Returns the cost for the given object to move into the tile, not counting any [`TileEdgeCosts`].
+/// This is the tiles [`TileMovementCosts`] for the objects [`MovementType`](crate::movement::MovementType),
+/// 1 if the tile has no cost for it, adjusted by the objects [`ObjectMovementCostModifiers`] if it has
+/// them. Returns None if the tile has no [`TileMovementCosts`] as it can't be moved into.
+pub fn tile_entry_cost(
+    entity_moving: Entity,
+    object_movement: &ObjectMovement,
+    tile_entity: Entity,
+    world: &World,
+) -> Option<u32> {
+    let tile_movement_costs = world.get::<TileMovementCosts>(tile_entity)?;
+    let base_cost = *tile_movement_costs
+        .movement_type_cost
+        .get(&object_movement.movement_type)
+        .unwrap_or(&1);
+
+    match world.get::<ObjectMovementCostModifiers>(entity_moving) {
+        Some(cost_modifiers) => Some(
+            cost_modifiers.apply(
+                base_cost,
+                world
+                    .get::<TileTerrainInfo>(tile_entity)
+                    .map(|tile_terrain_info| &tile_terrain_info.terrain_type.terrain_class),
+                &object_movement.movement_type,
+            ),
+        ),
+        None => Some(base_cost),
+    }
+}
+
+/// Returns the [`TileEdgeCosts`] cost of moving out of the `move_from_tile_entity` toward the tile at
+/// `tile_pos`. Returns zero if the tile has no [`TileEdgeCosts`] component.
+pub fn tile_edge_cost(
+    move_from_tile_entity: Entity,
+    move_from_tile_pos: &TilePos,
+    tile_pos: &TilePos,
+    world: &World,
+) -> u32 {
+    match world.get::<TileEdgeCosts>(move_from_tile_entity) {
+        Some(tile_edge_costs) => tile_edge_costs.edge_cost_toward(move_from_tile_pos, tile_pos),
+        None => 0,
+    }
+}
+
 /// Struct to be used in a [`MovementCalculator`](crate::movement::MovementCalculator) to hold the
 /// list of [`MoveNode`]s. This is not to be used for any other purpose than calculating movement
 /// and will be converted into an [`AvailableMove`] struct to be used outside the movement calculater
@@ -90,6 +135,8 @@ impl MovementNodes {
                 prior_node: prior_node.node_pos,
                 move_cost: None,
                 valid_move: false,
+                invalid_reason: None,
+                stops_movement: false,
             };
             self.move_nodes.insert(*tile_pos, node);
         }
@@ -190,6 +237,7 @@ impl MovementNodes {
     pub fn set_valid_move(&mut self, node_pos_to_update: &TilePos) -> Result<(), String> {
         return if let Some(node) = self.get_node_mut(node_pos_to_update) {
             node.valid_move = true;
+            node.invalid_reason = None;
             Ok(())
         } else {
             Err(String::from("Error getting node"))
@@ -197,6 +245,28 @@ impl MovementNodes {
     }
 }
 
+impl From<Vec<AvailableMove>> for MovementNodes {
+    /// Converts the output of a [`PathfindAlgorithm`](crate::pathfinding::PathfindAlgorithm) into
+    /// MovementNodes. Every node is marked as a valid move.
+    fn from(available_moves: Vec<AvailableMove>) -> Self {
+        let mut move_nodes: HashMap<TilePos, MoveNode> = HashMap::new();
+        for available_move in available_moves {
+            move_nodes.insert(
+                available_move.tile_pos,
+                MoveNode {
+                    node_pos: available_move.tile_pos,
+                    prior_node: available_move.prior_tile_pos,
+                    move_cost: Some(available_move.move_cost),
+                    valid_move: true,
+                    invalid_reason: None,
+                    stops_movement: false,
+                },
+            );
+        }
+        MovementNodes { move_nodes }
+    }
+}
+
 /// Represents a tile in a MovementNodes struct. Used to hold information relevant to movement calculation
 #[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
 pub struct MoveNode {
@@ -204,12 +274,30 @@ pub struct MoveNode {
     pub prior_node: TilePos,
     pub move_cost: Option<i32>,
     pub valid_move: bool,
+    /// Why this node isn't a valid move. None for valid moves
+    pub invalid_reason: Option<InvalidMoveReason>,
+    /// True if the object has to stop when it enters this tile, eg because of
+    /// [`MoveCheckZoneOfControl`](crate::movement::defaults::MoveCheckZoneOfControl)
+    pub stops_movement: bool,
 }
 
 impl MoveNode {
     pub fn set_cost(&mut self, new_cost: i32) {
         self.move_cost = Some(new_cost);
     }
+
+    /// Marks the node with the given reason it isn't a valid move. Does nothing if the node is
+    /// already a valid move. [`InvalidMoveReason::Blocked`] takes priority over
+    /// [`InvalidMoveReason::PassThroughOnly`] which takes priority over [`InvalidMoveReason::TooFar`]
+    /// as a blocked tile can't be reached no matter the move points
+    pub fn set_invalid_reason(&mut self, invalid_reason: InvalidMoveReason) {
+        if self.valid_move
+            || matches!(self.invalid_reason, Some(current) if current > invalid_reason)
+        {
+            return;
+        }
+        self.invalid_reason = Some(invalid_reason);
+    }
 }
 
 /// Adds the [`ObjectMoved`] component to any entity that is sent through the [`MoveEvent::MoveComplete`]
@@ -1,162 +1,199 @@
+use crate::game_core::rules::GameRules;
 use crate::mapping::terrain::TileTerrainInfo;
-use crate::mapping::tiles::{ObjectStackingClass, TileObjectStacks, TileObjects};
+use crate::mapping::tiles::{tile_neighbors, TileObjectStacks, TileObjects};
 use crate::mapping::MapId;
-use crate::movement::backend::{tile_movement_cost_check, MoveNode, MovementNodes};
+use crate::movement::backend::{MoveNode, MovementNodes};
 use crate::movement::{
-    DiagonalMovement, MovementCalculator, ObjectMovement, ObjectTypeMovementRules, TileMoveCheck,
-    TileMoveChecks,
+    DiagonalMovement, MovementCalculator, TileDirectionalRules, TileMoveCheck, TileMoveChecks,
+    TileMoveContext,
 };
-use crate::object::{ObjectGridPosition, ObjectId, ObjectInfo};
-use bevy::ecs::system::SystemState;
-use bevy::prelude::{Entity, Query, World};
+use crate::pathfinding::dijkstra::PathfindMapDijkstra;
+use crate::pathfinding::{Dijkstra, PathfindAlgorithm, PathfindCallback};
+use bevy::log::error;
+use bevy::prelude::{Entity, IVec2, World};
 use bevy::utils::hashbrown::HashMap;
-use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapSize, TilemapType};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapId, TilemapType};
 
 // BUILT IN IMPLEMENTATIONS
 
 /// Built in struct with an implementation for a [`MovementCalculator`](crate::movement::MovementCalculator) for a simple square based map.
-/// The pathfinding algorithm is an implementation of Djikstras.
+/// The pathfinding algorithm is an implementation of Djikstras provided by the [`pathfinding`](crate::pathfinding)
+/// module's [`Dijkstra`].
 /// Contains a field for a [`DiagonalMovement`] enum. The pathfinding algorithm will include diagonal
-/// tiles based on this enum.
+/// tiles based on this enum, unless it is overridden by [`GameRules::diagonal_movement`].
 #[derive(Clone)]
 pub struct SquareMovementCalculator {
     pub diagonal_movement: DiagonalMovement,
 }
 
-#[rustfmt::skip] // rustfmt breaking ci
 impl MovementCalculator for SquareMovementCalculator {
     fn calculate_move(
         &self,
         tile_move_checks: &TileMoveChecks,
-        map_type: TilemapType,
+        movement_callback: &mut Option<Box<dyn PathfindCallback<TilePos> + Send + Sync>>,
+        _map_type: TilemapType,
         on_map: MapId,
         object_moving: Entity,
         world: &mut World,
     ) -> MovementNodes {
-
-        let mut system_state: SystemState<
-            (Query<(Entity, &MapId, &TileStorage, &TilemapSize)>,
-            Query<&ObjectGridPosition>)
-        > = SystemState::new(world);
-        let (mut tile_storage_query, mut object_query) =
-            system_state.get_mut(world);
-        
-        
-        let Ok(object_grid_position) = object_query.get(object_moving) else{
-            return MovementNodes {
-                move_nodes: HashMap::new(),
-            };
+        let diagonals = GameRules::get(world)
+            .diagonal_movement
+            .unwrap_or(self.diagonal_movement.is_diagonal());
+        let pathfind_map = PathfindMapDijkstra {
+            map: HashMap::new(),
+            diagonals,
+            neighbor_offsets: None,
+            hex_coord_system: None,
         };
-        
-        let Some((_, _, tile_storage, tilemap_size)) = tile_storage_query
-            .iter_mut()
-            .find(|(_, id, _, _)| id == &&on_map)else{
-            return MovementNodes {
-                move_nodes: HashMap::new(),
-            };
-        };
-        
-        let tile_storage = tile_storage.clone();
-        let tilemap_size = tilemap_size.clone();
-
-        let mut move_info = MovementNodes {
-            move_nodes: HashMap::new(),
-        };
-
-        let mut available_moves: Vec<TilePos> = vec![];
 
-        // insert the starting node at the moving objects grid position
-        move_info.move_nodes.insert(
-            object_grid_position.tile_position.into(),
-            MoveNode {
-                node_pos: object_grid_position.tile_position.into(),
-                prior_node: object_grid_position.tile_position.into(),
-                move_cost: Some(0),
-                valid_move: true,
-            },
-        );
+        pathfind_move_nodes(
+            pathfind_map,
+            tile_move_checks,
+            movement_callback,
+            on_map,
+            object_moving,
+            world,
+        )
+    }
+}
 
-        // unvisited nodes
-        let mut unvisited_nodes: Vec<MoveNode> = vec![MoveNode {
-            node_pos: object_grid_position.tile_position.into(),
-            prior_node: object_grid_position.tile_position.into(),
-            move_cost: Some(0),
-            valid_move: false,
-        }];
-        let mut visited_nodes: Vec<TilePos> = vec![];
-
-        while !unvisited_nodes.is_empty() {
-            unvisited_nodes.sort_by(|x, y| {
-                x.move_cost
-                    .unwrap()
-                    .partial_cmp(&y.move_cost.unwrap())
-                    .unwrap()
-            });
-
-            let Some(current_node) = unvisited_nodes.get(0) else {
-                continue;
-            };
+/// Built in [`MovementCalculator`](crate::movement::MovementCalculator) for square maps with custom
+/// adjacency. Uses the same pathfinding as [`SquareMovementCalculator`] but a tiles neighbors are the
+/// tiles at each of the `neighbor_offsets` from it rather than the orthogonal and diagonal tiles, eg
+/// [`OffsetMovementCalculator::knight`] moves like a chess knight. Each step costs the same as
+/// moving into the tile with [`SquareMovementCalculator`], no matter how far away the neighbor is.
+#[derive(Clone)]
+pub struct OffsetMovementCalculator {
+    pub neighbor_offsets: Vec<IVec2>,
+}
 
-            let neighbor_pos = move_info.get_neighbors_tilepos(
-                current_node.node_pos,
-                self.diagonal_movement.is_diagonal(),
-                &tilemap_size,
-            );
+impl OffsetMovementCalculator {
+    /// An [`OffsetMovementCalculator`] that moves in an L shape like a chess knight, two tiles in one
+    /// direction and one tile perpendicular to it
+    pub fn knight() -> OffsetMovementCalculator {
+        OffsetMovementCalculator {
+            neighbor_offsets: vec![
+                IVec2::new(1, 2),
+                IVec2::new(2, 1),
+                IVec2::new(2, -1),
+                IVec2::new(1, -2),
+                IVec2::new(-1, -2),
+                IVec2::new(-2, -1),
+                IVec2::new(-2, 1),
+                IVec2::new(-1, 2),
+            ],
+        }
+    }
+}
 
-            let current_node = *current_node;
-            let mut neighbors: Vec<(TilePos, Entity)> = vec![];
-            for neighbor in neighbor_pos.iter(){
-                let Some(tile_entity) = tile_storage.get(neighbor) else {
-                    continue;
-                };
-                neighbors.push((*neighbor, tile_entity));
-            }
+impl MovementCalculator for OffsetMovementCalculator {
+    fn calculate_move(
+        &self,
+        tile_move_checks: &TileMoveChecks,
+        movement_callback: &mut Option<Box<dyn PathfindCallback<TilePos> + Send + Sync>>,
+        _map_type: TilemapType,
+        on_map: MapId,
+        object_moving: Entity,
+        world: &mut World,
+    ) -> MovementNodes {
+        let pathfind_map = PathfindMapDijkstra {
+            map: HashMap::new(),
+            diagonals: false,
+            neighbor_offsets: Some(self.neighbor_offsets.clone()),
+            hex_coord_system: None,
+        };
 
+        pathfind_move_nodes(
+            pathfind_map,
+            tile_move_checks,
+            movement_callback,
+            on_map,
+            object_moving,
+            world,
+        )
+    }
+}
 
-            'neighbors: for neighbor in neighbors.iter() {
-                if visited_nodes.contains(&neighbor.0) {
-                    continue;
-                }
-  
-
-                move_info.add_node(&neighbor.0, current_node);
-
-                if !tile_movement_cost_check(
-                    object_moving,
-                    neighbor.1,
-                    &neighbor.0,
-                    &current_node.node_pos,
-                    &mut move_info,
-                    world,
-                ){
-                    continue 'neighbors;
-                }
+/// Built in [`MovementCalculator`](crate::movement::MovementCalculator) for hexagon maps. Uses the
+/// same pathfinding, [`TileMovementCosts`](crate::movement::TileMovementCosts), and
+/// [`TileMoveChecks`] as [`SquareMovementCalculator`] but a tiles neighbors are the six hex tiles
+/// around it, found using the [`HexCoordSystem`](bevy_ecs_tilemap::prelude::HexCoordSystem) of
+/// the `map_type` passed into [`calculate_move`](MovementCalculator::calculate_move). Both row and
+/// column based coordinate systems are supported. If the map isn't a [`TilemapType::Hexagon`] an error
+/// is logged and there are no moves.
+#[derive(Clone, Copy, Default)]
+pub struct HexMovementCalculator;
 
-                if !tile_move_checks.check_tile_move_checks(
-                    object_moving,
-                    neighbor.1,
-                    &neighbor.0,
-                    &current_node.node_pos,
-                    world,
-                ) {
-                    continue 'neighbors;
-                }
+impl MovementCalculator for HexMovementCalculator {
+    fn calculate_move(
+        &self,
+        tile_move_checks: &TileMoveChecks,
+        movement_callback: &mut Option<Box<dyn PathfindCallback<TilePos> + Send + Sync>>,
+        map_type: TilemapType,
+        on_map: MapId,
+        object_moving: Entity,
+        world: &mut World,
+    ) -> MovementNodes {
+        let TilemapType::Hexagon(hex_coord_system) = map_type else {
+            error!(
+                "HexMovementCalculator used with a non hexagon TilemapType: {:?}",
+                map_type
+            );
+            return MovementNodes {
+                move_nodes: HashMap::new(),
+            };
+        };
+        let pathfind_map = PathfindMapDijkstra {
+            map: HashMap::new(),
+            diagonals: false,
+            neighbor_offsets: None,
+            hex_coord_system: Some(hex_coord_system),
+        };
 
+        pathfind_move_nodes(
+            pathfind_map,
+            tile_move_checks,
+            movement_callback,
+            on_map,
+            object_moving,
+            world,
+        )
+    }
+}
 
-                let _ = move_info.set_valid_move(&neighbor.0);
+/// Runs [`Dijkstra`] over the given [`PathfindMapDijkstra`] and returns every node in it as
+/// [`MovementNodes`]. Used by each of the built in calculators, which only differ in how the map finds
+/// a tiles neighbors
+fn pathfind_move_nodes(
+    mut pathfind_map: PathfindMapDijkstra,
+    tile_move_checks: &TileMoveChecks,
+    movement_callback: &mut Option<Box<dyn PathfindCallback<TilePos> + Send + Sync>>,
+    on_map: MapId,
+    object_moving: Entity,
+    world: &mut World,
+) -> MovementNodes {
+    let mut dijkstra = Dijkstra {
+        diagonals: pathfind_map.diagonals,
+        nodes: HashMap::new(),
+    };
 
-                // if none of them return false and cancel the loop then we can infer that we are able to move into that neighbor
-                // we add the neighbor to the list of unvisited nodes and then push the neighbor to the available moves list
-                unvisited_nodes.push(*move_info.get_node_mut(&neighbor.0).expect(
-                    "Is safe because we know we add the node in at the beginning of this loop",
-                )); //
-                available_moves.push(neighbor.0);
-            }
+    dijkstra.pathfind(
+        on_map,
+        object_moving,
+        world,
+        tile_move_checks,
+        movement_callback,
+        &mut pathfind_map,
+    );
 
-            unvisited_nodes.remove(0);
-            visited_nodes.push(current_node.node_pos);
-        }
-        move_info
+    // Build the nodes from the whole pathfind map rather than the pathfind output so that tiles
+    // that were explored but aren't valid moves keep their InvalidMoveReason
+    MovementNodes {
+        move_nodes: pathfind_map
+            .map
+            .iter()
+            .map(|(tile_pos, node)| (*tile_pos, MoveNode::from(*node)))
+            .collect(),
     }
 }
 
@@ -167,20 +204,154 @@ pub struct MoveCheckSpace;
 impl TileMoveCheck for MoveCheckSpace {
     fn is_valid_move(
         &self,
-        moving_entity: Entity,
+        context: &TileMoveContext,
         tile_entity: Entity,
         _checking_tile_pos: &TilePos,
         _move_from_tile_pos: &TilePos,
-        world: &mut World,
+        world: &World,
+    ) -> bool {
+        let Some(tile_objects) = world.get::<TileObjectStacks>(tile_entity) else {
+            return false;
+        };
+
+        context.has_space(tile_objects)
+    }
+}
+
+/// implements TileMoveCheck. A stacking check that lets objects move through their allies. Use it in
+/// place of [`MoveCheckSpace`].
+///
+/// # Logic
+/// - Tiles containing an object owned by another player block movement entirely
+/// - Tiles containing only objects owned by the moving objects player can always be moved through,
+///   those objects aren't counted against the tiles capacity while passing through
+/// - Any other tile, eg one with neutral objects, must have space for the moving objects
+///   [`ObjectStackingClass`](crate::mapping::tiles::ObjectStackingClass) to be moved through
+///
+/// In every case the object can only end its move in a tile that has space for its
+/// [`ObjectStackingClass`](crate::mapping::tiles::ObjectStackingClass). Tiles that can only be passed through are marked
+/// [`InvalidMoveReason::PassThroughOnly`](crate::movement::InvalidMoveReason::PassThroughOnly).
+pub struct MoveCheckStackingWithPassthrough;
+
+impl TileMoveCheck for MoveCheckStackingWithPassthrough {
+    fn is_valid_move(
+        &self,
+        context: &TileMoveContext,
+        tile_entity: Entity,
+        _tile_pos: &TilePos,
+        _last_tile_pos: &TilePos,
+        world: &World,
     ) -> bool {
-        let Some(object_stack_class) = world.get::<ObjectStackingClass>(moving_entity) else {
+        let (Some(tile_objects), Some(tile_object_stacks)) = (
+            world.get::<TileObjects>(tile_entity),
+            world.get::<TileObjectStacks>(tile_entity),
+        ) else {
             return false;
         };
+
+        let mut only_allies = true;
+        for object_id in tile_objects.entities_in_tile.iter().filter(|object_id| {
+            context.contains_object(object_id) && !context.object_ignores_stacking(object_id)
+        }) {
+            match (context.object_owner(object_id), &context.player_marker) {
+                (Some(player_marker), Some(moving_player)) if player_marker == moving_player => {}
+                (Some(_), _) => return false,
+                (None, _) => only_allies = false,
+            }
+        }
+
+        only_allies || context.has_space(tile_object_stacks)
+    }
+
+    fn is_valid_destination(
+        &self,
+        context: &TileMoveContext,
+        tile_entity: Entity,
+        _tile_pos: &TilePos,
+        world: &World,
+    ) -> bool {
         let Some(tile_objects) = world.get::<TileObjectStacks>(tile_entity) else {
             return false;
         };
 
-        tile_objects.has_space(object_stack_class)
+        context.has_space(tile_objects)
+    }
+}
+
+/// implements TileMoveCheck. Zone of control, an object that moves next to an enemy has to stop.
+///
+/// # Logic
+/// - A tile is in an enemies zone of control if any of its neighbors contains an object owned by a
+///   different player than the moving object. Neutral objects have no zone of control. The neighbors
+///   are the four orthogonal tiles on square and isometric maps and the six surrounding tiles on
+///   hexagon maps, using the [`TilemapType`] of the tiles tilemap
+/// - The object can still move into a tile in a zone of control but no tiles past it can be reached
+///   through it. Tiles are never blocked by this check, combine it with the other checks
+/// - Objects without a [`PlayerMarker`](crate::player::PlayerMarker) ignore zones of control
+///
+/// The tile being entered is checked rather than the tile being left, so an object stops in the
+/// first tile next to an enemy instead of one tile past it.
+///
+/// Neighbors are found through the tiles [`TilemapId`] so tiles must have one, as tiles spawned with
+/// a [`BggfTileBundle`](crate::mapping::tiles::BggfTileBundle) do. Tilemaps without a
+/// [`TilemapType`] are treated as square.
+pub struct MoveCheckZoneOfControl;
+
+impl TileMoveCheck for MoveCheckZoneOfControl {
+    fn is_valid_move(
+        &self,
+        _context: &TileMoveContext,
+        _tile_entity: Entity,
+        _tile_pos: &TilePos,
+        _last_tile_pos: &TilePos,
+        _world: &World,
+    ) -> bool {
+        true
+    }
+
+    fn stops_movement(
+        &self,
+        context: &TileMoveContext,
+        tile_entity: Entity,
+        tile_pos: &TilePos,
+        world: &World,
+    ) -> bool {
+        let Some(moving_player) = &context.player_marker else {
+            return false;
+        };
+        let Some(tilemap_entity) = world
+            .get::<TilemapId>(tile_entity)
+            .map(|tilemap_id| tilemap_id.0)
+        else {
+            return false;
+        };
+        let Some(tile_storage) = world.get::<TileStorage>(tilemap_entity) else {
+            return false;
+        };
+        let map_type = world
+            .get::<TilemapType>(tilemap_entity)
+            .copied()
+            .unwrap_or_default();
+
+        // The first four neighbors on a square map are the orthogonal ones, every neighbor on a hex
+        // map borders the tile
+        let bordering = match map_type {
+            TilemapType::Hexagon(_) => 6,
+            _ => 4,
+        };
+        tile_neighbors(tile_pos, &tile_storage.size, &map_type)
+            .into_iter()
+            .take(bordering)
+            .flatten()
+            .filter_map(|neighbor| tile_storage.checked_get(&neighbor))
+            .filter_map(|neighbor_entity| world.get::<TileObjects>(neighbor_entity))
+            .any(|tile_objects| {
+                tile_objects.entities_in_tile.iter().any(|object_id| {
+                    context
+                        .object_owner(object_id)
+                        .is_some_and(|owner| owner != moving_player)
+                })
+            })
     }
 }
 
@@ -191,54 +362,36 @@ pub struct MoveCheckAllowedTile;
 impl TileMoveCheck for MoveCheckAllowedTile {
     fn is_valid_move(
         &self,
-        entity_moving: Entity,
+        context: &TileMoveContext,
         tile_entity: Entity,
         _tile_pos: &TilePos,
         _last_tile_pos: &TilePos,
-        world: &mut World,
+        world: &World,
     ) -> bool {
-        let mut system_state: SystemState<(
-            Query<(
-                Entity,
-                &ObjectId,
-                Option<&ObjectTypeMovementRules>,
-                Option<&ObjectMovement>,
-                Option<&ObjectInfo>,
-            )>,
-            Query<(&TileTerrainInfo, &TileObjects)>,
-        )> = SystemState::new(world);
-        let (object_query, tile_query) = system_state.get_mut(world);
-
-        let Ok((entity, object_id, object_type_movement_rules, object_movement, object_info)) =
-            object_query.get(entity_moving)
-        else {
-            return false;
-        };
-
-        let Ok((tile_terrain_info, tile_objects)) = tile_query.get(tile_entity) else {
+        let (Some(tile_terrain_info), Some(tile_objects)) = (
+            world.get::<TileTerrainInfo>(tile_entity),
+            world.get::<TileObjects>(tile_entity),
+        ) else {
             return false;
         };
 
         // if the moving object has the optional type movement rules
-        if let Some(object_type_movement_rules) = object_type_movement_rules {
+        if let Some(object_type_movement_rules) = &context.object_type_movement_rules {
             // get the tiles object holder
             // for each object in the holder we feed its info into the ObjectTypeMovementRules
             // and return the bool if its there, else we just ignore it
             for tile_object in tile_objects.entities_in_tile.iter() {
-                let Some((_, _, _, _, object_info)) = object_query
-                    .iter()
-                    .find(|(_, id, _, _, _)| id == &tile_object)
-                else {
+                if !context.contains_object(tile_object) {
                     return true;
-                };
-                if let Some(object_info) = object_info {
+                }
+                if let Some(object_info) = context.object_info(tile_object) {
                     if let Some(bool) = object_type_movement_rules.can_move_on_tile(object_info) {
                         return bool;
                     }
                 }
             }
         };
-        if let Some(object_movement) = object_movement {
+        if let Some(object_movement) = &context.object_movement {
             object_movement
                 .object_terrain_movement_rules
                 .can_move_on_tile(tile_terrain_info)
@@ -247,3 +400,577 @@ impl TileMoveCheck for MoveCheckAllowedTile {
         }
     }
 }
+
+/// implements TileMoveCheck. A built in check for bridge like objects, objects that let other objects
+/// move onto tiles they normally couldn't, eg a bridge letting land units cross water. Use this in
+/// place of [`MoveCheckAllowedTile`] and [`MoveCheckSpace`].
+///
+/// # Logic
+/// The moving objects [`ObjectTypeMovementRules`](crate::movement::ObjectTypeMovementRules) are checked against every object in the tile:
+/// - If any rule denies the tile the move is invalid. Use this to deny water units the bridge tile
+/// - Else if any rule allows the tile the objects terrain rules are ignored, so land units can cross
+///   the bridge
+/// - Else the objects [`ObjectTerrainMovementRules`](crate::movement::ObjectTerrainMovementRules)
+///   decide if it can move onto the tiles terrain
+///
+/// Unlike [`MoveCheckAllowedTile`] stacking is always respected, the tile must also have space for the
+/// moving objects [`ObjectStackingClass`](crate::mapping::tiles::ObjectStackingClass).
+pub struct MoveCheckBridges;
+
+impl TileMoveCheck for MoveCheckBridges {
+    fn is_valid_move(
+        &self,
+        context: &TileMoveContext,
+        tile_entity: Entity,
+        _tile_pos: &TilePos,
+        _last_tile_pos: &TilePos,
+        world: &World,
+    ) -> bool {
+        let (Some(tile_terrain_info), Some(tile_objects), Some(tile_object_stacks)) = (
+            world.get::<TileTerrainInfo>(tile_entity),
+            world.get::<TileObjects>(tile_entity),
+            world.get::<TileObjectStacks>(tile_entity),
+        ) else {
+            return false;
+        };
+
+        if !context.has_space(tile_object_stacks) {
+            return false;
+        }
+
+        let mut allowed_by_object = false;
+        if let Some(object_type_movement_rules) = &context.object_type_movement_rules {
+            for object_info in tile_objects
+                .entities_in_tile
+                .iter()
+                .filter_map(|object_id| context.object_info(object_id))
+            {
+                match object_type_movement_rules.can_move_on_tile(object_info) {
+                    Some(false) => return false,
+                    Some(true) => allowed_by_object = true,
+                    None => {}
+                }
+            }
+        }
+        if allowed_by_object {
+            return true;
+        }
+
+        match &context.object_movement {
+            Some(object_movement) => object_movement
+                .object_terrain_movement_rules
+                .can_move_on_tile(tile_terrain_info),
+            None => false,
+        }
+    }
+}
+
+/// A [`TileMoveCheck`] for one way movement. Checks the [`TileDirectionalRules`] of the tile being
+/// moved into against the direction of the move from the last tile. Tiles without the component can
+/// be entered from any direction.
+///
+/// Use alongside the other checks, eg [`MoveCheckSpace`] and [`MoveCheckAllowedTile`], as it only
+/// checks the direction.
+pub struct MoveCheckDirectional;
+
+impl TileMoveCheck for MoveCheckDirectional {
+    fn is_valid_move(
+        &self,
+        _context: &TileMoveContext,
+        tile_entity: Entity,
+        tile_pos: &TilePos,
+        last_tile_pos: &TilePos,
+        world: &World,
+    ) -> bool {
+        match world.get::<TileDirectionalRules>(tile_entity) {
+            Some(tile_directional_rules) => {
+                tile_directional_rules.can_enter_from(last_tile_pos, tile_pos)
+            }
+            None => true,
+        }
+    }
+}
+
+#[test]
+fn test_move_check_bridges() {
+    use crate::mapping::terrain::{TerrainClass, TerrainType};
+    use crate::mapping::tiles::{ObjectStackingClass, StackingClass, TileObjectStacksCount};
+    use crate::movement::{
+        MovementType, ObjectMovement, ObjectTerrainMovementRules, ObjectTypeMovementRules,
+    };
+    use crate::object::{ObjectClass, ObjectGroup, ObjectId, ObjectInfo, ObjectType};
+
+    let mut world = World::new();
+
+    let water_class = TerrainClass {
+        name: String::from("Water"),
+    };
+    let ground_class = TerrainClass {
+        name: String::from("Ground"),
+    };
+    let water = TerrainType {
+        name: String::from("Ocean"),
+        terrain_class: water_class.clone(),
+    };
+    let ground_stack = StackingClass {
+        name: String::from("Ground"),
+    };
+    let building_stack = StackingClass {
+        name: String::from("Building"),
+    };
+    let bridge_type = ObjectType {
+        name: String::from("Bridge"),
+        object_group: ObjectGroup {
+            name: String::from("Structure"),
+            object_class: ObjectClass {
+                name: String::from("Building"),
+            },
+        },
+    };
+
+    let mut spawn_water_tile = |world: &mut World, objects: Vec<ObjectId>, ground_count: u32| {
+        world
+            .spawn((
+                TileTerrainInfo {
+                    terrain_type: water.clone(),
+                },
+                TileObjects {
+                    entities_in_tile: objects,
+                },
+                TileObjectStacks::new(vec![
+                    (
+                        ground_stack.clone(),
+                        TileObjectStacksCount {
+                            current_count: ground_count,
+                            max_count: 1,
+                        },
+                    ),
+                    (
+                        building_stack.clone(),
+                        TileObjectStacksCount {
+                            current_count: 1,
+                            max_count: 1,
+                        },
+                    ),
+                ]),
+            ))
+            .id()
+    };
+
+    world.spawn((
+        ObjectId { id: 0 },
+        ObjectInfo {
+            object_type: bridge_type.clone(),
+        },
+    ));
+    let bridge_tile = spawn_water_tile(&mut world, vec![ObjectId { id: 0 }], 0);
+    let full_bridge_tile = spawn_water_tile(&mut world, vec![ObjectId { id: 0 }], 1);
+    let open_water_tile = spawn_water_tile(&mut world, vec![], 0);
+
+    let land_unit = world
+        .spawn((
+            ObjectId { id: 1 },
+            ObjectStackingClass {
+                stack_class: ground_stack.clone(),
+            },
+            ObjectMovement {
+                move_points: 5,
+                movement_type: MovementType {
+                    name: String::from("Foot"),
+                },
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(
+                    vec![ground_class],
+                    vec![],
+                ),
+            },
+            ObjectTypeMovementRules::new(vec![], vec![], vec![(bridge_type.clone(), true)]),
+        ))
+        .id();
+    let water_unit = world
+        .spawn((
+            ObjectId { id: 2 },
+            ObjectStackingClass {
+                stack_class: ground_stack,
+            },
+            ObjectMovement {
+                move_points: 5,
+                movement_type: MovementType {
+                    name: String::from("Boat"),
+                },
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(
+                    vec![water_class],
+                    vec![],
+                ),
+            },
+            ObjectTypeMovementRules::new(vec![], vec![], vec![(bridge_type, false)]),
+        ))
+        .id();
+
+    let tile_pos = TilePos::new(0, 0);
+    let check = MoveCheckBridges;
+    let land_unit = TileMoveContext::new(&mut world, land_unit);
+    let water_unit = TileMoveContext::new(&mut world, water_unit);
+
+    // Land units can cross water on a bridge but not without one
+    assert!(check.is_valid_move(&land_unit, bridge_tile, &tile_pos, &tile_pos, &world));
+    assert!(!check.is_valid_move(&land_unit, open_water_tile, &tile_pos, &tile_pos, &world));
+    // The bridge doesn't let the land unit ignore stacking
+    assert!(!check.is_valid_move(&land_unit, full_bridge_tile, &tile_pos, &tile_pos, &world));
+    // Water units are denied the bridge tile even though they can move on water
+    assert!(!check.is_valid_move(&water_unit, bridge_tile, &tile_pos, &tile_pos, &world));
+    assert!(check.is_valid_move(&water_unit, open_water_tile, &tile_pos, &tile_pos, &world));
+}
+
+#[test]
+fn test_move_check_directional() {
+    use crate::movement::TileDirection;
+    use crate::object::ObjectId;
+
+    let mut world = World::new();
+    let object = world.spawn(ObjectId { id: 0 }).id();
+
+    // A cliff facing south, the top at y = 1 can't be climbed from the bottom at y = 0
+    let cliff_bottom_pos = TilePos::new(0, 0);
+    let cliff_top_pos = TilePos::new(0, 1);
+    let cliff_bottom = world.spawn(cliff_bottom_pos).id();
+    let cliff_top = world
+        .spawn((
+            cliff_top_pos,
+            TileDirectionalRules::new(vec![(TileDirection::North, false)]),
+        ))
+        .id();
+
+    let check = MoveCheckDirectional;
+    let object = TileMoveContext::new(&mut world, object);
+
+    // Moving down the cliff succeeds
+    assert!(check.is_valid_move(
+        &object,
+        cliff_bottom,
+        &cliff_bottom_pos,
+        &cliff_top_pos,
+        &world
+    ));
+    // Moving up the same edge fails
+    assert!(!check.is_valid_move(
+        &object,
+        cliff_top,
+        &cliff_top_pos,
+        &cliff_bottom_pos,
+        &world
+    ));
+    // The top can still be entered from the side
+    assert!(check.is_valid_move(
+        &object,
+        cliff_top,
+        &cliff_top_pos,
+        &TilePos::new(1, 1),
+        &world
+    ));
+}
+
+#[test]
+fn test_offset_movement_calculator_knight() {
+    use crate::movement::{
+        MovementType, ObjectMovement, ObjectTerrainMovementRules, TileMovementCosts,
+    };
+    use crate::object::ObjectGridPosition;
+    use bevy_ecs_tilemap::prelude::{TileStorage, TilemapSize};
+
+    let mut world = World::new();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+
+    let tilemap_size = TilemapSize { x: 8, y: 8 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        for y in 0..tilemap_size.y {
+            let tile_pos = TilePos::new(x, y);
+            let tile_entity = world
+                .spawn((tile_pos, TileMovementCosts::new(vec![(foot.clone(), 1)])))
+                .id();
+            tile_storage.set(&tile_pos, tile_entity);
+        }
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+
+    let start = TilePos::new(3, 3);
+    let knight = world
+        .spawn((
+            ObjectGridPosition {
+                tile_position: start.into(),
+            },
+            ObjectMovement {
+                move_points: 1,
+                movement_type: foot,
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+            },
+        ))
+        .id();
+
+    let move_nodes = OffsetMovementCalculator::knight().calculate_move(
+        &TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        &mut None,
+        TilemapType::Square,
+        map_id,
+        knight,
+        &mut world,
+    );
+
+    let mut reachable: Vec<TilePos> = move_nodes
+        .move_nodes
+        .values()
+        .filter(|move_node| move_node.valid_move && move_node.node_pos != start)
+        .map(|move_node| move_node.node_pos)
+        .collect();
+    reachable.sort_by_key(|tile_pos| (tile_pos.x, tile_pos.y));
+
+    // With a single move point the knight reaches exactly the eight L shaped tiles and nothing else
+    assert_eq!(
+        reachable,
+        vec![
+            TilePos::new(1, 2),
+            TilePos::new(1, 4),
+            TilePos::new(2, 1),
+            TilePos::new(2, 5),
+            TilePos::new(4, 1),
+            TilePos::new(4, 5),
+            TilePos::new(5, 2),
+            TilePos::new(5, 4),
+        ]
+    );
+}
+
+#[test]
+fn test_hex_movement_calculator_ring() {
+    use crate::movement::{
+        MovementType, ObjectMovement, ObjectTerrainMovementRules, TileMovementCosts,
+    };
+    use crate::object::ObjectGridPosition;
+    use bevy_ecs_tilemap::prelude::{HexCoordSystem, TileStorage, TilemapSize};
+
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let tilemap_size = TilemapSize { x: 7, y: 7 };
+    let start = TilePos::new(3, 3);
+
+    for (hex_coord_system, expected) in [
+        (
+            HexCoordSystem::Row,
+            vec![(2, 3), (2, 4), (3, 2), (3, 4), (4, 2), (4, 3)],
+        ),
+        (
+            HexCoordSystem::RowOdd,
+            vec![(2, 3), (3, 2), (3, 4), (4, 2), (4, 3), (4, 4)],
+        ),
+        (
+            HexCoordSystem::RowEven,
+            vec![(2, 2), (2, 3), (2, 4), (3, 2), (3, 4), (4, 3)],
+        ),
+        (
+            HexCoordSystem::ColumnOdd,
+            vec![(2, 3), (2, 4), (3, 2), (3, 4), (4, 3), (4, 4)],
+        ),
+        (
+            HexCoordSystem::ColumnEven,
+            vec![(2, 2), (2, 3), (3, 2), (3, 4), (4, 2), (4, 3)],
+        ),
+    ] {
+        let mut world = World::new();
+        let mut tile_storage = TileStorage::empty(tilemap_size);
+        for x in 0..tilemap_size.x {
+            for y in 0..tilemap_size.y {
+                let tile_pos = TilePos::new(x, y);
+                let tile_entity = world
+                    .spawn((tile_pos, TileMovementCosts::new(vec![(foot.clone(), 1)])))
+                    .id();
+                tile_storage.set(&tile_pos, tile_entity);
+            }
+        }
+        let map_id = MapId { id: 0 };
+        world.spawn((map_id, tile_storage, tilemap_size));
+
+        let object = world
+            .spawn((
+                ObjectGridPosition {
+                    tile_position: start.into(),
+                },
+                ObjectMovement {
+                    move_points: 1,
+                    movement_type: foot.clone(),
+                    object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+                },
+            ))
+            .id();
+
+        let move_nodes = HexMovementCalculator.calculate_move(
+            &TileMoveChecks {
+                tile_move_checks: vec![],
+            },
+            &mut None,
+            TilemapType::Hexagon(hex_coord_system),
+            map_id,
+            object,
+            &mut world,
+        );
+
+        let mut reachable: Vec<(u32, u32)> = move_nodes
+            .move_nodes
+            .values()
+            .filter(|move_node| move_node.valid_move && move_node.node_pos != start)
+            .map(|move_node| (move_node.node_pos.x, move_node.node_pos.y))
+            .collect();
+        reachable.sort();
+
+        // With a single move point the object reaches exactly the six tiles around it
+        assert_eq!(reachable, expected, "{:?}", hex_coord_system);
+
+        // A square map has no hex neighbors so there are no moves at all
+        let move_nodes = HexMovementCalculator.calculate_move(
+            &TileMoveChecks {
+                tile_move_checks: vec![],
+            },
+            &mut None,
+            TilemapType::Square,
+            map_id,
+            object,
+            &mut world,
+        );
+        assert!(move_nodes.move_nodes.is_empty());
+    }
+}
+
+#[test]
+fn test_move_check_zone_of_control() {
+    use crate::movement::{calculate_available_moves, calculate_move_nodes, DiagonalMovement};
+    use crate::movement::{
+        path_cost, MovementSystem, MovementType, ObjectMovement, ObjectTerrainMovementRules,
+        TileMoveCheckMeta, TileMovementCosts,
+    };
+    use crate::object::{ObjectGridPosition, ObjectId};
+    use crate::player::PlayerMarker;
+    use bevy_ecs_tilemap::prelude::{HexCoordSystem, TilemapSize};
+
+    let mut world = World::new();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+
+    // A corridor along y = 0 with an enemy next to it at (2, 1). The rest of y = 1 is a wall
+    let tilemap_size = TilemapSize { x: 5, y: 2 };
+    let map_entity = world.spawn_empty().id();
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        for y in 0..tilemap_size.y {
+            let tile_pos = TilePos::new(x, y);
+            let mut tile = world.spawn((tile_pos, TilemapId(map_entity), TileObjects::default()));
+            if y == 0 {
+                tile.insert(TileMovementCosts::new(vec![(foot.clone(), 1)]));
+            }
+            tile_storage.set(&tile_pos, tile.id());
+        }
+    }
+    let enemy_tile = tile_storage.get(&TilePos::new(2, 1)).unwrap();
+    world
+        .get_mut::<TileObjects>(enemy_tile)
+        .unwrap()
+        .add_object(ObjectId { id: 1 });
+    let map_id = MapId { id: 0 };
+    world
+        .entity_mut(map_entity)
+        .insert((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![TileMoveCheckMeta {
+                check: Box::new(MoveCheckZoneOfControl),
+            }],
+        },
+        movement_callback: None,
+    });
+
+    world.spawn((ObjectId { id: 1 }, PlayerMarker::new(1)));
+    let object = world
+        .spawn((
+            ObjectId { id: 0 },
+            PlayerMarker::new(0),
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectMovement {
+                move_points: 4,
+                movement_type: foot,
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+            },
+        ))
+        .id();
+
+    // Movement stops at the tile next to the enemy even though there are move points left
+    let mut reachable: Vec<TilePos> = calculate_available_moves(&mut world, map_id, object)
+        .unwrap()
+        .into_keys()
+        .collect();
+    reachable.sort_by_key(|tile_pos| (tile_pos.x, tile_pos.y));
+    assert_eq!(
+        reachable,
+        vec![TilePos::new(0, 0), TilePos::new(1, 0), TilePos::new(2, 0)]
+    );
+    let move_nodes = calculate_move_nodes(&mut world, map_id, object).unwrap();
+    assert!(move_nodes.move_nodes[&TilePos::new(2, 0)].stops_movement);
+    assert!(!move_nodes.move_nodes[&TilePos::new(1, 0)].stops_movement);
+
+    assert_eq!(
+        path_cost(
+            &mut world,
+            map_id,
+            object,
+            &[TilePos::new(0, 0), TilePos::new(1, 0), TilePos::new(2, 0)]
+        ),
+        Some(2)
+    );
+    assert_eq!(
+        path_cost(
+            &mut world,
+            map_id,
+            object,
+            &[
+                TilePos::new(0, 0),
+                TilePos::new(1, 0),
+                TilePos::new(2, 0),
+                TilePos::new(3, 0)
+            ]
+        ),
+        None
+    );
+
+    // (3, 0) only borders the enemy at (2, 1) on a hex map
+    let tile_entity = world
+        .get::<TileStorage>(map_entity)
+        .unwrap()
+        .get(&TilePos::new(3, 0))
+        .unwrap();
+    let context = crate::movement::TileMoveContext::new(&mut world, object);
+    assert!(!MoveCheckZoneOfControl.stops_movement(
+        &context,
+        tile_entity,
+        &TilePos::new(3, 0),
+        &world
+    ));
+    world
+        .entity_mut(map_entity)
+        .insert(TilemapType::Hexagon(HexCoordSystem::Row));
+    assert!(MoveCheckZoneOfControl.stops_movement(
+        &context,
+        tile_entity,
+        &TilePos::new(3, 0),
+        &world
+    ));
+}
@@ -3,13 +3,24 @@
 pub mod backend;
 pub mod defaults;
 
-use crate::game_core::command::{AddObjectToTile, GameCommand, GameCommands, RemoveObjectFromTile};
+use crate::combat::ObjectAttacked;
+use crate::game_core::command::{
+    move_with_carried, tile_entity, undo_move_with_carried, GameCommand, GameCommands,
+};
+use crate::game_core::rules::GameRules;
 use crate::game_core::runner::GameRunner;
 use crate::game_core::GameBuilder;
 use crate::mapping::terrain::{TerrainClass, TerrainType, TileTerrainInfo};
+use crate::mapping::tiles::{
+    tile_neighbors, IgnoresStacking, ObjectStackingClass, TileObjectStacks,
+};
 use crate::mapping::MapId;
-use crate::movement::backend::{MoveNode, MovementNodes};
-use crate::object::{ObjectClass, ObjectGroup, ObjectId, ObjectInfo, ObjectType};
+use crate::movement::backend::{tile_edge_cost, tile_entry_cost, MoveNode, MovementNodes};
+use crate::object::{
+    ObjectClass, ObjectGridPosition, ObjectGroup, ObjectId, ObjectInfo, ObjectType,
+};
+use crate::pathfinding::PathfindCallback;
+use crate::player::PlayerMarker;
 use bevy::ecs::system::SystemState;
 use bevy::prelude::{
     info, App, Bundle, Component, Entity, EventWriter, Events, Mut, Plugin, Query, Reflect,
@@ -17,7 +28,7 @@ use bevy::prelude::{
 };
 use bevy::reflect::FromReflect;
 use bevy::utils::HashMap;
-use bevy_ecs_tilemap::prelude::{TilePos, TilemapType};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapType};
 
 /// Core plugin for the bevy_ggf Movement System. Contains basic needed functionality.
 /// Does not contain a MovementSystem. You have to insert that yourself
@@ -46,6 +57,15 @@ pub trait GameBuilderMovementExt {
         MC: MovementCalculator,
         Self: Sized;
 
+    fn with_movement_callback<CB>(&mut self, movement_callback: CB)
+    where
+        CB: PathfindCallback<TilePos> + Send + Sync + 'static,
+        Self: Sized;
+
+    fn setup_movement_types(&mut self, movement_types: Vec<MovementType>)
+    where
+        Self: Sized;
+
     fn setup_movement(&mut self, tile_movement_costs: Vec<(TerrainType, TileMovementCosts)>)
     where
         Self: Sized;
@@ -64,13 +84,60 @@ where
         MC: MovementCalculator,
         Self: Sized,
     {
+        let pending_callback = self
+            .game_world
+            .remove_resource::<PendingMovementCallback>()
+            .map(|pending_callback| pending_callback.movement_callback);
+        let existing_callback = self
+            .game_world
+            .remove_resource::<MovementSystem>()
+            .and_then(|movement_system| movement_system.movement_callback);
+
         self.game_world.insert_resource(MovementSystem {
             movement_calculator: Box::new(movement_calculator),
             map_type,
             tile_move_checks: TileMoveChecks { tile_move_checks },
+            movement_callback: pending_callback.or(existing_callback),
         });
     }
 
+    /// Sets the callback that the [`MovementCalculator`] runs on every valid tile while calculating
+    /// movement. The callback is stored in the [`MovementSystem`]. If it is called before
+    /// [`with_movement_calculator`](GameBuilderMovementExt::with_movement_calculator) the callback is
+    /// held in a [`PendingMovementCallback`] until the [`MovementSystem`] is created
+    fn with_movement_callback<CB>(&mut self, movement_callback: CB)
+    where
+        CB: PathfindCallback<TilePos> + Send + Sync + 'static,
+        Self: Sized,
+    {
+        match self.game_world.get_resource_mut::<MovementSystem>() {
+            Some(mut movement_system) => {
+                movement_system.movement_callback = Some(Box::new(movement_callback));
+            }
+            None => self.game_world.insert_resource(PendingMovementCallback {
+                movement_callback: Box::new(movement_callback),
+            }),
+        }
+    }
+
+    /// Inserts the [`MovementTypes`] resource holding every [`MovementType`] in the game. Call this
+    /// before [`setup_movement`](GameBuilderMovementExt::setup_movement) so that the given
+    /// [`TileMovementCosts`] can be validated against it
+    fn setup_movement_types(&mut self, movement_types: Vec<MovementType>)
+    where
+        Self: Sized,
+    {
+        let mut movement_types_resource = MovementTypes {
+            movement_types: HashMap::new(),
+        };
+        movement_types_resource.insert_vec(movement_types);
+        self.game_world.insert_resource(movement_types_resource);
+    }
+
+    /// Inserts the [`TerrainMovementCosts`] resource and the movement events. Any [`MovementType`]
+    /// used in the given [`TileMovementCosts`] that isn't in the [`MovementTypes`] is reported by
+    /// [`GameBuilder::validate`] as a
+    /// [`SetupError::UnregisteredMovementType`](crate::game_core::SetupError::UnregisteredMovementType)
     fn setup_movement(&mut self, tile_movement_costs: Vec<(TerrainType, TileMovementCosts)>)
     where
         Self: Sized,
@@ -99,6 +166,35 @@ pub trait MoveCommandsExt {
         new_pos: TilePos,
         attempt: bool,
     ) -> MoveObject;
+
+    fn move_object_along_path(
+        &mut self,
+        object_moving: ObjectId,
+        on_map: MapId,
+        path: Vec<TilePos>,
+    ) -> MoveObjectAlongPath;
+
+    fn move_object_partial(
+        &mut self,
+        object_moving: ObjectId,
+        on_map: MapId,
+        new_pos: TilePos,
+    ) -> MoveObjectPartial;
+
+    fn wait_object(&mut self, object: ObjectId) -> WaitObject;
+
+    fn set_tile_movement_cost(
+        &mut self,
+        on_map: MapId,
+        tile_pos: TilePos,
+        costs: TileMovementCosts,
+    ) -> SetTileMovementCost;
+
+    fn reset_tile_movement_cost(
+        &mut self,
+        on_map: MapId,
+        tile_pos: TilePos,
+    ) -> ResetTileMovementCost;
 }
 
 impl MoveCommandsExt for GameCommands {
@@ -118,6 +214,7 @@ impl MoveCommandsExt for GameCommands {
             current_pos,
             new_pos,
             attempt,
+            carried: vec![],
         });
         MoveObject {
             object_moving,
@@ -125,10 +222,124 @@ impl MoveCommandsExt for GameCommands {
             current_pos,
             new_pos,
             attempt,
+            carried: vec![],
+        }
+    }
+
+    /// Moves an object along the given path after validating the exact path. See
+    /// [`MoveObjectAlongPath`]
+    fn move_object_along_path(
+        &mut self,
+        object_moving: ObjectId,
+        on_map: MapId,
+        path: Vec<TilePos>,
+    ) -> MoveObjectAlongPath {
+        self.queue.push(MoveObjectAlongPath {
+            object_moving,
+            on_map,
+            path: path.clone(),
+            moved: false,
+            carried: vec![],
+        });
+        MoveObjectAlongPath {
+            object_moving,
+            on_map,
+            path,
+            moved: false,
+            carried: vec![],
+        }
+    }
+
+    /// Moves an object part of its range, recording the move points spent so it can keep acting. See
+    /// [`MoveObjectPartial`]
+    fn move_object_partial(
+        &mut self,
+        object_moving: ObjectId,
+        on_map: MapId,
+        new_pos: TilePos,
+    ) -> MoveObjectPartial {
+        self.queue.push(MoveObjectPartial {
+            object_moving,
+            on_map,
+            new_pos,
+            previous_state: None,
+        });
+        MoveObjectPartial {
+            object_moving,
+            on_map,
+            new_pos,
+            previous_state: None,
+        }
+    }
+
+    /// Ends the given objects turn in place by marking it as having moved and attacked without
+    /// actually moving it
+    fn wait_object(&mut self, object: ObjectId) -> WaitObject {
+        self.queue.push(WaitObject {
+            object,
+            inserted_moved: false,
+            inserted_attacked: false,
+        });
+        WaitObject {
+            object,
+            inserted_moved: false,
+            inserted_attacked: false,
+        }
+    }
+
+    /// Overrides the movement costs of the given tile, eg after building a road on it. See
+    /// [`SetTileMovementCost`]
+    fn set_tile_movement_cost(
+        &mut self,
+        on_map: MapId,
+        tile_pos: TilePos,
+        costs: TileMovementCosts,
+    ) -> SetTileMovementCost {
+        self.queue.push(SetTileMovementCost {
+            on_map,
+            tile_pos,
+            costs: costs.clone(),
+            previous_costs: None,
+        });
+        SetTileMovementCost {
+            on_map,
+            tile_pos,
+            costs,
+            previous_costs: None,
+        }
+    }
+
+    /// Resets the movement costs of the given tile to the default for its terrain. See
+    /// [`ResetTileMovementCost`]
+    fn reset_tile_movement_cost(
+        &mut self,
+        on_map: MapId,
+        tile_pos: TilePos,
+    ) -> ResetTileMovementCost {
+        self.queue.push(ResetTileMovementCost {
+            on_map,
+            tile_pos,
+            previous_costs: None,
+        });
+        ResetTileMovementCost {
+            on_map,
+            tile_pos,
+            previous_costs: None,
         }
     }
 }
 
+/// Moves an object from its current tile to the new tile. If `attempt` is true the move is only made
+/// if the new tile is in [`calculate_available_moves`].
+///
+/// Every object attached to the moving object with
+/// [`CarriedByParent`](crate::object::CarriedByParent) that is in the same tile is moved along with
+/// it. The move fails with [`MoveError::TileOccupied`] before anything is moved if the new tile
+/// doesn't have space for the carried objects, and if moving any of them fails every object is put
+/// back in its original tile. Rollback moves the object and everything it carried back.
+///
+/// If the move fails a [`MoveEvent::MoveFailed`] is sent with a [`MoveError`] saying why, eg
+/// [`MoveError::TileOccupied`] if an attempted move targets a tile without space for the object.
 #[derive(Clone, Debug, Reflect)]
 pub struct MoveObject {
     object_moving: ObjectId,
@@ -136,115 +347,538 @@ pub struct MoveObject {
     current_pos: TilePos,
     new_pos: TilePos,
     attempt: bool,
+    #[reflect(ignore)]
+    carried: Vec<ObjectId>,
+}
+
+impl MoveObject {
+    /// Sends a [`MoveEvent::MoveFailed`] event for this move and returns the reason as a String to be
+    /// returned from the command
+    fn move_failed(&self, world: &mut World, reason: MoveError) -> String {
+        let mut system_state: SystemState<EventWriter<MoveEvent>> = SystemState::new(world);
+        let mut move_event = system_state.get_mut(world);
+
+        move_event.send(MoveEvent::MoveFailed {
+            object_moving: self.object_moving,
+            attempted_pos: self.new_pos,
+            reason: reason.clone(),
+        });
+
+        system_state.apply(world);
+        format!("{:?}", reason)
+    }
 }
 
 impl GameCommand for MoveObject {
-    fn execute(&mut self, mut world: &mut World) -> Result<(), String> {
-        let mut remove = RemoveObjectFromTile {
-            object_game_id: self.object_moving,
-            on_map: self.on_map,
-            tile_pos: self.current_pos,
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut object_query = world.query::<(Entity, &ObjectId)>();
+        let Some((entity, _)) = object_query
+            .iter(world)
+            .find(|(_, id)| id == &&self.object_moving)
+        else {
+            return Err(self.move_failed(world, MoveError::ObjectNotFound(self.object_moving)));
+        };
+        let mut map_query = world.query::<&MapId>();
+        if !map_query.iter(world).any(|id| id == &self.on_map) {
+            return Err(self.move_failed(world, MoveError::MapNotFound(self.on_map)));
+        }
+
+        if self.attempt {
+            let Some(moves) = calculate_available_moves(world, self.on_map, entity) else {
+                return Err(self.move_failed(world, MoveError::NoMovementSystem));
+            };
+
+            if !moves.contains_key(&self.new_pos) {
+                info!("Tile_pos not a valid move");
+                let reason = if tile_occupied(world, self.on_map, self.new_pos, entity) {
+                    MoveError::TileOccupied(self.new_pos)
+                } else {
+                    MoveError::TargetNotInRange {
+                        target: self.new_pos,
+                    }
+                };
+                return Err(self.move_failed(world, reason));
+            }
+        }
+
+        match move_with_carried(
+            world,
+            self.on_map,
+            self.object_moving,
+            self.current_pos,
+            self.new_pos,
+        ) {
+            Ok(carried) => self.carried = carried,
+            Err(reason) => return Err(self.move_failed(world, reason)),
+        }
+
+        let mut system_state: SystemState<EventWriter<MoveEvent>> = SystemState::new(world);
+        let mut move_event = system_state.get_mut(world);
+
+        move_event.send(MoveEvent::MoveComplete {
+            object_moved: self.object_moving,
+        });
+
+        system_state.apply(world);
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        undo_move_with_carried(
+            world,
+            self.on_map,
+            self.object_moving,
+            &std::mem::take(&mut self.carried),
+            self.current_pos,
+            self.new_pos,
+        )
+    }
+}
+
+/// Returns true if the tile at the given position doesn't have space for the given objects
+/// [`ObjectStackingClass`]. Objects with [`IgnoresStacking`] never find a tile occupied
+fn tile_occupied(world: &mut World, on_map: MapId, tile_pos: TilePos, object: Entity) -> bool {
+    if world.get::<IgnoresStacking>(object).is_some() {
+        return false;
+    }
+    let Some(object_stacking_class) = world.get::<ObjectStackingClass>(object).cloned() else {
+        return false;
+    };
+    let mut map_query = world.query::<(&MapId, &TileStorage)>();
+    let Some(tile_entity) = map_query
+        .iter(world)
+        .find(|(id, _)| id == &&on_map)
+        .and_then(|(_, tile_storage)| tile_storage.checked_get(&tile_pos))
+    else {
+        return false;
+    };
+    world
+        .get::<TileObjectStacks>(tile_entity)
+        .is_some_and(|tile_object_stacks| !tile_object_stacks.has_space(&object_stacking_class))
+}
+
+/// Moves an object along a path, eg one proposed by a client, from the first tile in the path to the
+/// last. Unlike [`MoveObject`] the whole path is validated with [`path_cost`] rather than only the
+/// destination, so a path that ends on a reachable tile but takes an impossible route is rejected.
+///
+/// If the path is illegal a [`MoveEvent::MoveFailed`] is sent with [`MoveError::InvalidPath`]. On
+/// success a [`MoveEvent::MoveComplete`] is sent. Objects carried by the moving object move with it
+/// the same as with [`MoveObject`]. Rollback moves the object and everything it carried back to the
+/// first tile in the path.
+#[derive(Clone, Debug, Reflect)]
+pub struct MoveObjectAlongPath {
+    pub object_moving: ObjectId,
+    pub on_map: MapId,
+    pub path: Vec<TilePos>,
+    moved: bool,
+    #[reflect(ignore)]
+    carried: Vec<ObjectId>,
+}
+
+impl MoveObjectAlongPath {
+    /// Sends a [`MoveEvent::MoveFailed`] event for this move and returns the reason as a String to be
+    /// returned from the command
+    fn move_failed(&self, world: &mut World, reason: MoveError) -> String {
+        let mut system_state: SystemState<EventWriter<MoveEvent>> = SystemState::new(world);
+        let mut move_event = system_state.get_mut(world);
+
+        move_event.send(MoveEvent::MoveFailed {
+            object_moving: self.object_moving,
+            attempted_pos: self.path.last().copied().unwrap_or_default(),
+            reason: reason.clone(),
+        });
+
+        system_state.apply(world);
+        format!("{:?}", reason)
+    }
+}
+
+impl GameCommand for MoveObjectAlongPath {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let (Some(start), Some(destination)) = (self.path.first(), self.path.last()) else {
+            return Err(self.move_failed(world, MoveError::InvalidPath(self.path.clone())));
         };
-        let mut add = AddObjectToTile {
-            object_game_id: self.object_moving,
-            on_map: self.on_map,
-            tile_pos: self.new_pos,
+        let (start, destination) = (*start, *destination);
+
+        let mut object_query = world.query::<(Entity, &ObjectId)>();
+        let Some((entity, _)) = object_query
+            .iter(world)
+            .find(|(_, id)| id == &&self.object_moving)
+        else {
+            return Err(self.move_failed(world, MoveError::ObjectNotFound(self.object_moving)));
         };
 
-        return match self.attempt {
-            true => {
-                let mut system_state: SystemState<Query<(Entity, &ObjectId)>> =
-                    SystemState::new(&mut world);
+        if path_cost(world, self.on_map, entity, &self.path).is_none() {
+            return Err(self.move_failed(world, MoveError::InvalidPath(self.path.clone())));
+        }
 
-                let mut object_query = system_state.get_mut(&mut world);
+        match move_with_carried(world, self.on_map, self.object_moving, start, destination) {
+            Ok(carried) => self.carried = carried,
+            Err(reason) => return Err(self.move_failed(world, reason)),
+        }
+        self.moved = true;
 
-                let Some((entity, id)) = object_query
-                    .iter_mut()
-                    .find(|(_, id)| id == &&self.object_moving)
-                else {
-                    return Err(String::from("Objet not found"));
-                };
+        let mut system_state: SystemState<EventWriter<MoveEvent>> = SystemState::new(world);
+        let mut move_event = system_state.get_mut(world);
 
-                let mut moves: HashMap<TilePos, AvailableMove> = HashMap::new();
-
-                world.resource_scope(|world, movement_system: Mut<MovementSystem>| {
-                    let moves_info = movement_system.movement_calculator.calculate_move(
-                        &movement_system.tile_move_checks,
-                        movement_system.map_type,
-                        self.on_map,
-                        entity,
-                        world,
-                    );
-
-                    for (tile_pos, move_node) in moves_info.move_nodes.iter() {
-                        if move_node.valid_move {
-                            moves.insert(*tile_pos, AvailableMove::from(*move_node));
-                        }
-                    }
-                });
+        move_event.send(MoveEvent::MoveComplete {
+            object_moved: self.object_moving,
+        });
 
-                if moves.contains_key(&self.new_pos) {
-                    remove.execute(world)?;
-                    add.execute(world)?;
+        system_state.apply(world);
+        Ok(())
+    }
 
-                    let mut system_state: SystemState<EventWriter<MoveEvent>> =
-                        SystemState::new(world);
-                    let mut move_event = system_state.get_mut(world);
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let (Some(start), Some(destination)) = (self.path.first(), self.path.last()) else {
+            return Err(String::from("Path is empty"));
+        };
+        if !self.moved {
+            return Err(String::from("Rollback can only be called after execute"));
+        }
 
-                    move_event.send(MoveEvent::MoveComplete {
-                        object_moved: self.object_moving,
-                    });
+        undo_move_with_carried(
+            world,
+            self.on_map,
+            self.object_moving,
+            &std::mem::take(&mut self.carried),
+            *start,
+            *destination,
+        )?;
+        self.moved = false;
+
+        Ok(())
+    }
+}
 
-                    system_state.apply(world);
-                    Ok(())
-                } else {
-                    info!("Tile_pos not a valid move");
-                    Err(String::from("Tile_pos not a valid move"))
+/// Component recording how many of an objects [`ObjectMovement::move_points`] it has spent this
+/// turn through [`MoveObjectPartial`]. Removed along with [`ObjectMoved`] when the objects actions are
+/// reset for a new turn.
+///
+/// The built in pathfinding only lets an object with this component reach tiles within its
+/// [`remaining`](PartialMove::remaining) move points, and an object with none left has no moves.
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Eq,
+    Hash,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[reflect(Component)]
+pub struct PartialMove {
+    pub spent: i32,
+}
+
+impl PartialMove {
+    /// Returns the move points the object has left this turn. Never less than 0
+    pub fn remaining(&self, object_movement: &ObjectMovement) -> i32 {
+        (object_movement.move_points - self.spent).max(0)
+    }
+}
+
+/// Moves an object to a tile in its range without ending its movement for the turn, eg when a player
+/// chooses to stop short and act before moving again. The destination must be one of the objects
+/// [`calculate_available_moves`] and its move cost must fit in the objects remaining move points, see
+/// [`PartialMove::remaining`].
+///
+/// The cost of the move is added to the objects [`PartialMove`], inserting it if needed. The object is
+/// only marked with [`ObjectMoved`] once it has no move points left. A [`MoveEvent::MoveComplete`] is
+/// not sent as that marks the object as moved, a [`MoveEvent::MoveFailed`] with a [`MoveError`] saying
+/// why is sent if the move is invalid, the same as [`MoveObject`]. A destination that is in range but
+/// costs more than the objects remaining move points fails with [`MoveError::TargetNotInRange`].
+///
+/// Objects carried by the moving object move with it the same as with [`MoveObject`]. Rollback moves
+/// the object and everything it carried back and restores its previous [`PartialMove`] and
+/// [`ObjectMoved`] state.
+#[derive(Clone, Debug, Reflect)]
+pub struct MoveObjectPartial {
+    pub object_moving: ObjectId,
+    pub on_map: MapId,
+    pub new_pos: TilePos,
+    #[reflect(ignore)]
+    previous_state: Option<(TilePos, Vec<ObjectId>, Option<PartialMove>, bool)>,
+}
+
+impl MoveObjectPartial {
+    /// Sends a [`MoveEvent::MoveFailed`] event for this move and returns the reason as a String to be
+    /// returned from the command
+    fn move_failed(&self, world: &mut World, reason: MoveError) -> String {
+        let mut system_state: SystemState<EventWriter<MoveEvent>> = SystemState::new(world);
+        let mut move_event = system_state.get_mut(world);
+
+        move_event.send(MoveEvent::MoveFailed {
+            object_moving: self.object_moving,
+            attempted_pos: self.new_pos,
+            reason: reason.clone(),
+        });
+
+        system_state.apply(world);
+        format!("{:?}", reason)
+    }
+}
+
+impl GameCommand for MoveObjectPartial {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut object_query =
+            world.query::<(Entity, &ObjectId, &ObjectGridPosition, &ObjectMovement)>();
+        let Some((entity, _, object_grid_position, object_movement)) = object_query
+            .iter(world)
+            .find(|(_, id, _, _)| id == &&self.object_moving)
+        else {
+            return Err(self.move_failed(world, MoveError::ObjectNotFound(self.object_moving)));
+        };
+        let current_pos: TilePos = object_grid_position.tile_position.into();
+        let object_movement = object_movement.clone();
+        let previous_partial_move = world.get::<PartialMove>(entity).copied();
+        let partial_move = previous_partial_move.unwrap_or_default();
+
+        let Some(moves) = calculate_available_moves(world, self.on_map, entity) else {
+            return Err(self.move_failed(world, MoveError::NoMovementSystem));
+        };
+        let Some(available_move) = moves.get(&self.new_pos) else {
+            let reason = if tile_occupied(world, self.on_map, self.new_pos, entity) {
+                MoveError::TileOccupied(self.new_pos)
+            } else {
+                MoveError::TargetNotInRange {
+                    target: self.new_pos,
                 }
-            }
-            false => {
-                remove.execute(world)?;
-                add.execute(world)?;
+            };
+            return Err(self.move_failed(world, reason));
+        };
+        if available_move.move_cost > partial_move.remaining(&object_movement) {
+            return Err(self.move_failed(
+                world,
+                MoveError::TargetNotInRange {
+                    target: self.new_pos,
+                },
+            ));
+        }
+        let move_cost = available_move.move_cost;
 
-                let mut system_state: SystemState<EventWriter<MoveEvent>> = SystemState::new(world);
-                let mut move_event = system_state.get_mut(world);
+        let carried = match move_with_carried(
+            world,
+            self.on_map,
+            self.object_moving,
+            current_pos,
+            self.new_pos,
+        ) {
+            Ok(carried) => carried,
+            Err(reason) => return Err(self.move_failed(world, reason)),
+        };
 
-                move_event.send(MoveEvent::MoveComplete {
-                    object_moved: self.object_moving,
-                });
+        let partial_move = PartialMove {
+            spent: partial_move.spent + move_cost,
+        };
+        let mut entity_mut = world.entity_mut(entity);
+        let had_moved = entity_mut.contains::<ObjectMoved>();
+        entity_mut.insert((partial_move, crate::game_core::state::Changed::default()));
+        if partial_move.remaining(&object_movement) == 0 {
+            entity_mut.insert(ObjectMoved);
+        }
 
-                system_state.apply(world);
-                Ok(())
+        self.previous_state = Some((current_pos, carried, previous_partial_move, had_moved));
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let Some((previous_pos, carried, previous_partial_move, had_moved)) =
+            self.previous_state.take()
+        else {
+            return Err(String::from("Rollback can only be called after execute"));
+        };
+
+        undo_move_with_carried(
+            world,
+            self.on_map,
+            self.object_moving,
+            &carried,
+            previous_pos,
+            self.new_pos,
+        )?;
+
+        let mut object_query = world.query::<(Entity, &ObjectId)>();
+        let Some((entity, _)) = object_query
+            .iter(world)
+            .find(|(_, id)| id == &&self.object_moving)
+        else {
+            return Err(String::from("Object not found"));
+        };
+        let mut entity_mut = world.entity_mut(entity);
+        match previous_partial_move {
+            Some(partial_move) => {
+                entity_mut.insert(partial_move);
             }
+            None => {
+                entity_mut.remove::<PartialMove>();
+            }
+        }
+        if !had_moved {
+            entity_mut.remove::<ObjectMoved>();
+        }
+        entity_mut.insert(crate::game_core::state::Changed::default());
+        Ok(())
+    }
+}
+
+/// Marks an object as done for the turn without moving it by inserting the [`ObjectMoved`] and
+/// [`ObjectAttacked`] markers. Rollback only removes the markers that this command inserted.
+#[derive(Clone, Debug, Reflect)]
+pub struct WaitObject {
+    pub object: ObjectId,
+    inserted_moved: bool,
+    inserted_attacked: bool,
+}
+
+impl GameCommand for WaitObject {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let mut system_state: SystemState<Query<(Entity, &ObjectId)>> = SystemState::new(world);
+        let object_query = system_state.get(world);
+
+        let Some((entity, _)) = object_query.iter().find(|(_, id)| id == &&self.object) else {
+            return Err(String::from("Objet not found"));
         };
+
+        let mut entity_mut = world.entity_mut(entity);
+        self.inserted_moved = !entity_mut.contains::<ObjectMoved>();
+        self.inserted_attacked = !entity_mut.contains::<ObjectAttacked>();
+        entity_mut.insert((ObjectMoved, ObjectAttacked));
+        Ok(())
     }
 
     fn rollback(&mut self, world: &mut World) -> Result<(), String> {
-        let mut remove = RemoveObjectFromTile {
-            object_game_id: self.object_moving,
-            on_map: self.on_map,
-            tile_pos: self.new_pos,
+        let mut system_state: SystemState<Query<(Entity, &ObjectId)>> = SystemState::new(world);
+        let object_query = system_state.get(world);
+
+        let Some((entity, _)) = object_query.iter().find(|(_, id)| id == &&self.object) else {
+            return Err(String::from("Objet not found"));
         };
-        let mut add = AddObjectToTile {
-            object_game_id: self.object_moving,
-            on_map: self.on_map,
-            tile_pos: self.current_pos,
+
+        let mut entity_mut = world.entity_mut(entity);
+        if self.inserted_moved {
+            entity_mut.remove::<ObjectMoved>();
+        }
+        if self.inserted_attacked {
+            entity_mut.remove::<ObjectAttacked>();
+        }
+        Ok(())
+    }
+}
+
+/// Overrides the [`TileMovementCosts`] of a tile independently of its terrain, eg when a road is
+/// built on the tile. The previous costs are recorded and restored on rollback. Use
+/// [`ResetTileMovementCost`] to return the tile to its terrains default costs.
+#[derive(Clone, Debug, Reflect)]
+pub struct SetTileMovementCost {
+    pub on_map: MapId,
+    pub tile_pos: TilePos,
+    pub costs: TileMovementCosts,
+    previous_costs: Option<TileMovementCosts>,
+}
+
+impl GameCommand for SetTileMovementCost {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let tile_entity = tile_entity(world, self.on_map, self.tile_pos)?;
+        self.previous_costs = world.get::<TileMovementCosts>(tile_entity).cloned();
+        set_tile_movement_costs(world, tile_entity, Some(self.costs.clone()));
+        Ok(())
+    }
+
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let tile_entity = tile_entity(world, self.on_map, self.tile_pos)?;
+        set_tile_movement_costs(world, tile_entity, self.previous_costs.take());
+        Ok(())
+    }
+}
+
+/// Resets the [`TileMovementCosts`] of a tile to the default for its terrain from the
+/// [`TerrainMovementCosts`] resource, undoing any [`SetTileMovementCost`] overrides. Fails if the
+/// tiles terrain has no default costs. Rollback restores the costs the tile had before.
+#[derive(Clone, Debug, Reflect)]
+pub struct ResetTileMovementCost {
+    pub on_map: MapId,
+    pub tile_pos: TilePos,
+    previous_costs: Option<TileMovementCosts>,
+}
+
+impl GameCommand for ResetTileMovementCost {
+    fn execute(&mut self, world: &mut World) -> Result<(), String> {
+        let tile_entity = tile_entity(world, self.on_map, self.tile_pos)?;
+        let Some(tile_terrain_info) = world.get::<TileTerrainInfo>(tile_entity) else {
+            return Err(String::from(
+                "Tile does not have a TileTerrainInfo component",
+            ));
+        };
+        let Some(terrain_costs) = world
+            .get_resource::<TerrainMovementCosts>()
+            .and_then(|terrain_movement_costs| {
+                terrain_movement_costs
+                    .movement_cost_rules
+                    .get(&tile_terrain_info.terrain_type)
+            })
+            .cloned()
+        else {
+            return Err(format!(
+                "No TerrainMovementCosts found for TerrainType: {:?}",
+                tile_terrain_info.terrain_type
+            ));
         };
 
-        remove.execute(world)?;
-        add.execute(world)?;
+        self.previous_costs = world.get::<TileMovementCosts>(tile_entity).cloned();
+        set_tile_movement_costs(world, tile_entity, Some(terrain_costs));
+        Ok(())
+    }
 
-        return Ok(());
+    fn rollback(&mut self, world: &mut World) -> Result<(), String> {
+        let tile_entity = tile_entity(world, self.on_map, self.tile_pos)?;
+        set_tile_movement_costs(world, tile_entity, self.previous_costs.take());
+        Ok(())
+    }
+}
+
+/// Inserts the given costs on the tile, or removes its [`TileMovementCosts`] if None
+fn set_tile_movement_costs(
+    world: &mut World,
+    tile_entity: Entity,
+    costs: Option<TileMovementCosts>,
+) {
+    let mut tile = world.entity_mut(tile_entity);
+    match costs {
+        Some(costs) => {
+            tile.insert(costs);
+        }
+        None => {
+            tile.remove::<TileMovementCosts>();
+        }
     }
+    tile.insert(crate::game_core::state::Changed::default());
+}
+
+/// Holds a movement callback given to
+/// [`with_movement_callback`](GameBuilderMovementExt::with_movement_callback) before there was a
+/// [`MovementSystem`]. Moved into the [`MovementSystem`] by
+/// [`with_movement_calculator`](GameBuilderMovementExt::with_movement_calculator). Reported by
+/// [`GameBuilder::validate`] if the game is built without a [`MovementSystem`]
+#[derive(Resource)]
+pub struct PendingMovementCallback {
+    pub movement_callback: Box<dyn PathfindCallback<TilePos> + Send + Sync>,
 }
 
 /// Defines a MovementSystem. This resource is used to calculate movement, define the list of checks
-/// for the [`MovementCalculator`], and holds the [`TilemapType`]
+/// for the [`MovementCalculator`], and holds the [`TilemapType`]. The optional movement_callback is
+/// run by the [`MovementCalculator`] for every valid tile it finds
 #[derive(Resource)]
 pub struct MovementSystem {
     pub movement_calculator: Box<dyn MovementCalculator>,
     pub map_type: TilemapType,
     pub tile_move_checks: TileMoveChecks,
+    pub movement_callback: Option<Box<dyn PathfindCallback<TilePos> + Send + Sync>>,
 }
 
 impl MovementSystem {
@@ -259,6 +893,7 @@ impl MovementSystem {
             movement_calculator,
             map_type,
             tile_move_checks: TileMoveChecks { tile_move_checks },
+            movement_callback: None,
         }
     }
     /// Unused currently. Kept for future reference and potential implementation
@@ -274,20 +909,320 @@ impl MovementSystem {
     }
 }
 
+/// Calculates every valid move for the given object on the given map using the [`MovementSystem`]
+/// resource. Returns None if there is no [`MovementSystem`] in the world.
+///
+/// If the object has an [`ObjectId`] a [`MoveEvent::MoveCalculated`] is sent with the tiles of every
+/// valid move, sorted by x then y, even if there are none so that UIs can clear old highlights.
+pub fn calculate_available_moves(
+    world: &mut World,
+    on_map: MapId,
+    object_moving: Entity,
+) -> Option<HashMap<TilePos, AvailableMove>> {
+    let moves_info = calculate_move_nodes(world, on_map, object_moving)?;
+
+    let mut moves: HashMap<TilePos, AvailableMove> = HashMap::new();
+    for (tile_pos, move_node) in moves_info.move_nodes.iter() {
+        if move_node.valid_move {
+            moves.insert(*tile_pos, AvailableMove::from(*move_node));
+        }
+    }
+
+    if let Some(object_id) = world.get::<ObjectId>(object_moving).copied() {
+        let mut available_moves: Vec<TilePos> = moves.keys().copied().collect();
+        available_moves.sort_by_key(|tile_pos| (tile_pos.x, tile_pos.y));
+        if let Some(mut move_events) = world.get_resource_mut::<Events<MoveEvent>>() {
+            move_events.send(MoveEvent::MoveCalculated {
+                object_moving: object_id,
+                available_moves,
+            });
+        }
+    }
+
+    Some(moves)
+}
+
+/// Previews every valid move for the given object on the given map without changing the game state,
+/// eg for an AI that evaluates many moves a frame. Returns the moves sorted by tile, x then y, or an
+/// empty Vec if there is no [`MovementSystem`] in the world or the object can't be found.
+///
+/// Unlike [`calculate_available_moves`] the [`MovementSystem`]s movement_callback isn't run, and any
+/// [`Changed`](crate::game_core::state::Changed) markers inserted or modified during the calculation,
+/// eg by a custom [`TileMoveCheck`], are reverted afterwards so that nothing shows up in the next
+/// state update.
+pub fn preview_available_moves(
+    world: &mut World,
+    on_map: MapId,
+    object_id: ObjectId,
+) -> Vec<AvailableMove> {
+    if !world.contains_resource::<MovementSystem>() {
+        return vec![];
+    }
+    let mut object_query = world.query::<(Entity, &ObjectId)>();
+    let Some((object_entity, _)) = object_query.iter(world).find(|(_, id)| id == &&object_id)
+    else {
+        return vec![];
+    };
+
+    let mut changed_query = world.query::<(Entity, &crate::game_core::state::Changed)>();
+    let changed_before: Vec<(Entity, crate::game_core::state::Changed)> = changed_query
+        .iter(world)
+        .map(|(entity, changed)| (entity, changed.clone()))
+        .collect();
+
+    let move_nodes = world.resource_scope(|world, mut movement_system: Mut<MovementSystem>| {
+        let movement_system = &mut *movement_system;
+        movement_system.movement_calculator.calculate_move(
+            &movement_system.tile_move_checks,
+            &mut None,
+            movement_system.map_type,
+            on_map,
+            object_entity,
+            world,
+        )
+    });
+
+    let changed_after: Vec<Entity> = changed_query
+        .iter(world)
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in changed_after {
+        world
+            .entity_mut(entity)
+            .remove::<crate::game_core::state::Changed>();
+    }
+    for (entity, changed) in changed_before {
+        if let Some(mut entity_mut) = world.get_entity_mut(entity) {
+            entity_mut.insert(changed);
+        }
+    }
+
+    let mut moves: Vec<AvailableMove> = move_nodes
+        .move_nodes
+        .values()
+        .filter(|move_node| move_node.valid_move)
+        .map(|move_node| AvailableMove::from(*move_node))
+        .collect();
+    moves.sort_by_key(|available_move| (available_move.tile_pos.x, available_move.tile_pos.y));
+    moves
+}
+
+/// Calculates every tile a hypothetical object could move to from the given tile on the given map,
+/// without the object existing, eg to preview where a unit could reach before spawning it. Returns
+/// None if there is no [`MovementSystem`] in the world.
+///
+/// [`MovementCalculator`]s work on an entity so a temporary entity with an [`ObjectGridPosition`] at
+/// `start`, the given [`ObjectMovement`] and [`ObjectStackingClass`], and the [`PlayerMarker`] if
+/// one is given is spawned for the calculation and despawned afterwards. The temporary entity isn't
+/// an object and isn't added to any tile, so it never shows up in state updates. Pass the
+/// [`PlayerMarker`] the object would be spawned with so that checks based on ownership, like
+/// [`MoveCheckStackingWithPassthrough`](defaults::MoveCheckStackingWithPassthrough), treat it
+/// correctly.
+pub fn calculate_hypothetical_moves(
+    world: &mut World,
+    on_map: MapId,
+    start: TilePos,
+    object_movement: ObjectMovement,
+    object_stacking_class: ObjectStackingClass,
+    player_marker: Option<PlayerMarker>,
+) -> Option<HashMap<TilePos, AvailableMove>> {
+    if !world.contains_resource::<MovementSystem>() {
+        return None;
+    }
+
+    let mut hypothetical_object = world.spawn((
+        ObjectGridPosition {
+            tile_position: start.into(),
+        },
+        object_movement,
+        object_stacking_class,
+    ));
+    if let Some(player_marker) = player_marker {
+        hypothetical_object.insert(player_marker);
+    }
+    let hypothetical_entity = hypothetical_object.id();
+
+    let moves = calculate_available_moves(world, on_map, hypothetical_entity);
+    world.despawn(hypothetical_entity);
+    moves
+}
+
+/// Calculates every tile that was explored but isn't a valid move for the given object on the given
+/// map, along with the [`InvalidMoveReason`] for each. Use this to tell tiles that are too far to
+/// reach apart from tiles that are blocked. Returns None if there is no [`MovementSystem`] in the world.
+pub fn calculate_invalid_moves(
+    world: &mut World,
+    on_map: MapId,
+    object_moving: Entity,
+) -> Option<HashMap<TilePos, InvalidMoveReason>> {
+    let moves_info = calculate_move_nodes(world, on_map, object_moving)?;
+
+    let mut moves: HashMap<TilePos, InvalidMoveReason> = HashMap::new();
+    for (tile_pos, move_node) in moves_info.move_nodes.iter() {
+        if let Some(invalid_reason) = move_node.invalid_reason {
+            moves.insert(*tile_pos, invalid_reason);
+        }
+    }
+
+    Some(moves)
+}
+
+/// Runs the [`MovementSystem`]s [`MovementCalculator`] for the given object and returns every
+/// [`MoveNode`] it explored, both valid and invalid. Returns None if there is no [`MovementSystem`]
+/// in the world.
+pub fn calculate_move_nodes(
+    world: &mut World,
+    on_map: MapId,
+    object_moving: Entity,
+) -> Option<MovementNodes> {
+    if !world.contains_resource::<MovementSystem>() {
+        return None;
+    }
+
+    Some(
+        world.resource_scope(|world, mut movement_system: Mut<MovementSystem>| {
+            let movement_system = &mut *movement_system;
+            movement_system.movement_calculator.calculate_move(
+                &movement_system.tile_move_checks,
+                &mut movement_system.movement_callback,
+                movement_system.map_type,
+                on_map,
+                object_moving,
+                world,
+            )
+        }),
+    )
+}
+
+/// Returns the total cost for the given object to move along the given path on the given map, or None
+/// if the path isn't a legal move. Use this to validate a full path proposed by a client without
+/// recalculating every available move.
+///
+/// The path must start with the tile the object is currently in and every following tile must be a
+/// neighbor of the tile before it. Each step costs the same as it does in the [`MovementCalculator`],
+/// the tiles [`TileMovementCosts`] for the objects [`MovementType`] plus any [`TileEdgeCosts`], and
+/// must pass the [`MovementSystem`]s [`TileMoveChecks`], no tile before the last may stop the objects
+/// movement, and the object must be able to end its move in the last tile. Returns None if any step
+/// is illegal, the total is more than the objects move points, or there is no [`MovementSystem`] in
+/// the world.
+///
+/// ### Note
+/// Diagonal steps on square maps are rejected only when [`GameRules::diagonal_movement`] is set to
+/// false, as otherwise the [`DiagonalMovement`] setting belongs to the [`MovementCalculator`]. Games
+/// that disable diagonal movement on the calculator should set the rule as well.
+pub fn path_cost(
+    world: &mut World,
+    on_map: MapId,
+    object_moving: Entity,
+    path: &[TilePos],
+) -> Option<u32> {
+    let (start, _) = path.split_first()?;
+    let object_grid_position = world.get::<ObjectGridPosition>(object_moving)?;
+    let object_tile_pos: TilePos = object_grid_position.tile_position.into();
+    if object_tile_pos != *start {
+        return None;
+    }
+    let object_movement = world.get::<ObjectMovement>(object_moving)?.clone();
+    let move_points = world
+        .get::<PartialMove>(object_moving)
+        .map_or(object_movement.move_points, |partial_move| {
+            partial_move.remaining(&object_movement)
+        });
+
+    let mut tile_storage_query = world.query::<(&MapId, &TileStorage)>();
+    let (_, tile_storage) = tile_storage_query
+        .iter(world)
+        .find(|(id, _)| id == &&on_map)?;
+    let tile_storage = tile_storage.clone();
+
+    if !world.contains_resource::<MovementSystem>() {
+        return None;
+    }
+    let diagonal_movement = GameRules::get(world).diagonal_movement;
+    let context = TileMoveContext::new(world, object_moving);
+    world.resource_scope(|world, movement_system: Mut<MovementSystem>| {
+        let mut total_cost: u32 = 0;
+        for step in path.windows(2) {
+            let (last_tile_pos, tile_pos) = (&step[0], &step[1]);
+            let is_neighbor =
+                tile_neighbors(last_tile_pos, &tile_storage.size, &movement_system.map_type)
+                    .contains(&Some(*tile_pos));
+            if !is_neighbor {
+                return None;
+            }
+            if movement_system.map_type == TilemapType::Square
+                && diagonal_movement == Some(false)
+                && last_tile_pos.x != tile_pos.x
+                && last_tile_pos.y != tile_pos.y
+            {
+                return None;
+            }
+
+            let last_tile_entity = tile_storage.checked_get(last_tile_pos)?;
+            let tile_entity = tile_storage.checked_get(tile_pos)?;
+            total_cost += tile_entry_cost(object_moving, &object_movement, tile_entity, world)?
+                + tile_edge_cost(last_tile_entity, last_tile_pos, tile_pos, world);
+            if total_cost as i32 > move_points {
+                return None;
+            }
+
+            if !movement_system.tile_move_checks.check_tile_move_checks(
+                &context,
+                tile_entity,
+                tile_pos,
+                last_tile_pos,
+                world,
+            ) {
+                return None;
+            }
+        }
+
+        // The object can't continue past any tile that stops its movement
+        if let [_, passed_through @ .., _] = path {
+            for tile_pos in passed_through.iter() {
+                let tile_entity = tile_storage.checked_get(tile_pos)?;
+                if movement_system.tile_move_checks.check_stops_movement(
+                    &context,
+                    tile_entity,
+                    tile_pos,
+                    world,
+                ) {
+                    return None;
+                }
+            }
+        }
+
+        let destination = path.last()?;
+        let destination_entity = tile_storage.checked_get(destination)?;
+        if !movement_system
+            .tile_move_checks
+            .check_tile_destination_checks(&context, destination_entity, destination, world)
+        {
+            return None;
+        }
+        Some(total_cost)
+    })
+}
+
 /// A trait defining a new MovementCalculator - define the [`calculate_move`](MovementCalculator::calculate_move) fn in order to control
 /// exactly how the movement works. Add this to a [`MovementSystem`] and insert that as a resource
 /// to define your movement system
 ///
 /// Bevy_GGF contains a series of default MovementCalculators, detailed in [`defaults`] including one
 /// that implements Advance Wars style movement for square based maps called [`SquareMovementCalculator`](defaults::SquareMovementCalculator)
+/// and one for hexagon maps called [`HexMovementCalculator`](defaults::HexMovementCalculator)
 pub trait MovementCalculator: 'static + Send + Sync {
     /// The main function of a [`MovementCalculator`]. This is called when a [`MoveEvent`] is received
     /// and all [`MoveNode`](MoveNode) with valid_move marked true will be
     /// pushed into the [`CurrentMovementInformation`] Resource automatically. Use
     /// this function to define your own movement algorithm.
+    ///
+    /// If the movement_callback is Some it should be run for every tile that is found to be a valid
+    /// move, after it passes the tile_move_checks.
     fn calculate_move(
         &self,
         tile_move_checks: &TileMoveChecks,
+        movement_callback: &mut Option<Box<dyn PathfindCallback<TilePos> + Send + Sync>>,
         map_type: TilemapType,
         on_map: MapId,
         object_moving: Entity,
@@ -304,15 +1239,50 @@ impl TileMoveChecks {
     /// false if any *one* was false, or true if all were true.
     pub fn check_tile_move_checks(
         &self,
-        entity_moving: Entity,
+        context: &TileMoveContext,
         tile_entity: Entity,
         tile_pos: &TilePos,
         last_tile_pos: &TilePos,
-        world: &mut World,
+        world: &World,
+    ) -> bool {
+        for i in 0..self.tile_move_checks.len() {
+            let check = self.tile_move_checks[i].check.as_ref();
+            if !check.is_valid_move(context, tile_entity, tile_pos, last_tile_pos, world) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Helper function that will loop through each [`TileMoveCheck`] in the movement system and return
+    /// true if the object has to stop in the tile according to any *one* of them.
+    pub fn check_stops_movement(
+        &self,
+        context: &TileMoveContext,
+        tile_entity: Entity,
+        tile_pos: &TilePos,
+        world: &World,
+    ) -> bool {
+        self.tile_move_checks.iter().any(|tile_move_check| {
+            tile_move_check
+                .check
+                .stops_movement(context, tile_entity, tile_pos, world)
+        })
+    }
+
+    /// Helper function that will loop through each [`TileMoveCheck`] in the movement system and return
+    /// false if the object can't end its move in the tile according to any *one* of them, or true if
+    /// it can according to all of them.
+    pub fn check_tile_destination_checks(
+        &self,
+        context: &TileMoveContext,
+        tile_entity: Entity,
+        tile_pos: &TilePos,
+        world: &World,
     ) -> bool {
         for i in 0..self.tile_move_checks.len() {
             let check = self.tile_move_checks[i].check.as_ref();
-            if !check.is_valid_move(entity_moving, tile_entity, tile_pos, last_tile_pos, world) {
+            if !check.is_valid_destination(context, tile_entity, tile_pos, world) {
                 return false;
             }
         }
@@ -320,6 +1290,105 @@ impl TileMoveChecks {
     }
 }
 
+/// The moving object and every object it might meet, read from the world once per movement
+/// calculation and passed to every [`TileMoveCheck`] so that checks don't have to query the world for
+/// them on every tile. Tile components are cheap to get directly from the world with the tile entity.
+///
+/// The context is a snapshot, it won't see changes made to the world after it was created.
+#[derive(Clone, Debug)]
+pub struct TileMoveContext {
+    pub entity_moving: Entity,
+    pub player_marker: Option<PlayerMarker>,
+    pub object_stacking_class: Option<ObjectStackingClass>,
+    pub object_movement: Option<ObjectMovement>,
+    pub object_type_movement_rules: Option<ObjectTypeMovementRules>,
+    /// Whether the moving object has [`IgnoresStacking`]
+    pub ignores_stacking: bool,
+    objects: HashMap<ObjectId, (Option<PlayerMarker>, Option<ObjectInfo>, bool)>,
+}
+
+impl TileMoveContext {
+    /// Reads the moving objects components and the owner and info of every object in the world
+    pub fn new(world: &mut World, entity_moving: Entity) -> TileMoveContext {
+        let mut system_state: SystemState<
+            Query<(
+                &ObjectId,
+                Option<&PlayerMarker>,
+                Option<&ObjectInfo>,
+                Option<&IgnoresStacking>,
+            )>,
+        > = SystemState::new(world);
+        let object_query = system_state.get(world);
+        let objects = object_query
+            .iter()
+            .map(
+                |(object_id, player_marker, object_info, ignores_stacking)| {
+                    (
+                        *object_id,
+                        (
+                            player_marker.copied(),
+                            object_info.cloned(),
+                            ignores_stacking.is_some(),
+                        ),
+                    )
+                },
+            )
+            .collect();
+
+        TileMoveContext {
+            entity_moving,
+            player_marker: world.get::<PlayerMarker>(entity_moving).copied(),
+            object_stacking_class: world.get::<ObjectStackingClass>(entity_moving).cloned(),
+            object_movement: world.get::<ObjectMovement>(entity_moving).cloned(),
+            object_type_movement_rules: world
+                .get::<ObjectTypeMovementRules>(entity_moving)
+                .cloned(),
+            ignores_stacking: world.get::<IgnoresStacking>(entity_moving).is_some(),
+            objects,
+        }
+    }
+
+    /// Returns true if the tile has space for the moving objects [`ObjectStackingClass`]. Always true
+    /// if the moving object has [`IgnoresStacking`] and always false if it has no stacking class
+    pub fn has_space(&self, tile_object_stacks: &TileObjectStacks) -> bool {
+        if self.ignores_stacking {
+            return true;
+        }
+        self.object_stacking_class
+            .as_ref()
+            .is_some_and(|object_stacking_class| {
+                tile_object_stacks.has_space(object_stacking_class)
+            })
+    }
+
+    /// Returns true if the object with the given id has [`IgnoresStacking`]
+    pub fn object_ignores_stacking(&self, object_id: &ObjectId) -> bool {
+        self.objects
+            .get(object_id)
+            .is_some_and(|(_, _, ignores_stacking)| *ignores_stacking)
+    }
+
+    /// Returns true if the object with the given id was in the world when the context was created
+    pub fn contains_object(&self, object_id: &ObjectId) -> bool {
+        self.objects.contains_key(object_id)
+    }
+
+    /// Returns the [`PlayerMarker`] of the object with the given id, None if the object is neutral or
+    /// wasn't found
+    pub fn object_owner(&self, object_id: &ObjectId) -> Option<&PlayerMarker> {
+        self.objects
+            .get(object_id)
+            .and_then(|(player_marker, _, _)| player_marker.as_ref())
+    }
+
+    /// Returns the [`ObjectInfo`] of the object with the given id, None if it has none or wasn't found
+    pub fn object_info(&self, object_id: &ObjectId) -> Option<&ObjectInfo> {
+        self.objects
+            .get(object_id)
+            .and_then(|(_, object_info, _)| object_info.as_ref())
+    }
+}
+
 pub struct TileMoveCheckMeta {
     pub check: Box<dyn TileMoveCheck + Send + Sync>,
 }
@@ -328,14 +1397,18 @@ pub struct TileMoveCheckMeta {
 /// Implement one of these traits for each separate logical check you want the MovementCalculator to
 /// do to determine if a tile is a valid move or not.
 ///
+/// Checks are given a [`TileMoveContext`] holding the moving object and every object it might meet so
+/// that they don't have to query the world for each tile, and read only access to the world for the
+/// tiles components.
+///
 /// # Example
 /// Here is an example of a simple TileMoveCheck implementation. This impl provides a check for whether
 /// or not a tile has space in the tile for the relevant objects stacking class
 /// ```rust
 /// use bevy::prelude::{Entity, World};
 /// use bevy_ecs_tilemap::prelude::TilePos;
-/// use bevy_ggf::mapping::tiles::{ObjectStackingClass, TileObjectStacks};
-/// use bevy_ggf::movement::TileMoveCheck;
+/// use bevy_ggf::mapping::tiles::TileObjectStacks;
+/// use bevy_ggf::movement::{TileMoveCheck, TileMoveContext};
 ///
 /// // Create a new struct for our TileMoveCheck
 /// pub struct MoveCheckSpace;
@@ -344,14 +1417,14 @@ pub struct TileMoveCheckMeta {
 /// impl TileMoveCheck for MoveCheckSpace {
 ///     fn is_valid_move(
 ///         &self,
-///         entity_moving: Entity,
+///         context: &TileMoveContext,
 ///         tile_entity: Entity,
 ///         tile_pos: &TilePos,
 ///         last_tile_pos: &TilePos,
-///         world: &mut World,
+///         world: &World,
 ///     ) -> bool {
-/// // Get the ObjectStackingClass component of our object that is trying to move
-///         let Some(object_stack_class) = world.get::<ObjectStackingClass>(entity_moving) else {
+/// // Get the ObjectStackingClass component of our object that is trying to move from the context
+///         let Some(object_stack_class) = &context.object_stacking_class else {
 /// // If the object doesnt have a stack class then we want to return false as this object should not be able to move
 ///             return false;
 ///         };
@@ -366,15 +1439,61 @@ pub struct TileMoveCheckMeta {
 ///     }
 /// }
 /// ```
+///
+/// [`is_valid_move`](TileMoveCheck::is_valid_move) decides if the object can move into or through the
+/// tile. Override [`is_valid_destination`](TileMoveCheck::is_valid_destination) for checks where an
+/// object can pass through a tile without being able to end its move there, see
+/// [`MoveCheckStackingWithPassthrough`](defaults::MoveCheckStackingWithPassthrough).
 pub trait TileMoveCheck {
     fn is_valid_move(
         &self,
-        entity_moving: Entity,
+        context: &TileMoveContext,
         tile_entity: Entity,
         tile_pos: &TilePos,
         last_tile_pos: &TilePos,
-        world: &mut World,
+        world: &World,
     ) -> bool;
+
+    /// Returns true if the object can end its move in the tile. Only called for tiles that passed
+    /// [`is_valid_move`](TileMoveCheck::is_valid_move). Defaults to true so any tile that can be moved
+    /// into can also be stopped in
+    fn is_valid_destination(
+        &self,
+        _context: &TileMoveContext,
+        _tile_entity: Entity,
+        _tile_pos: &TilePos,
+        _world: &World,
+    ) -> bool {
+        true
+    }
+
+    /// Returns true if the object has to stop when it moves into the tile, so no tiles past it can be
+    /// reached through it. The tile itself is still a valid move if it passes the other checks. Only
+    /// called for tiles that passed [`is_valid_move`](TileMoveCheck::is_valid_move). Defaults to
+    /// false, see [`MoveCheckZoneOfControl`](defaults::MoveCheckZoneOfControl)
+    fn stops_movement(
+        &self,
+        _context: &TileMoveContext,
+        _tile_entity: Entity,
+        _tile_pos: &TilePos,
+        _world: &World,
+    ) -> bool {
+        false
+    }
+}
+
+/// Why a tile that was explored while calculating a move isn't a valid move
+/// - [Self::TooFar] means the tile could be moved into but the object doesn't have enough move points
+/// to reach it
+/// - [Self::PassThroughOnly] means the tile can be moved through but the object can't end its move
+/// in it, eg a tile full of allied objects
+/// - [Self::Blocked] means the tile can't be moved into at all - it has no movement cost for the
+/// object or failed one of the [`TileMoveCheck`]s (impassable terrain, occupied, etc)
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Hash, Debug)]
+pub enum InvalidMoveReason {
+    TooFar,
+    PassThroughOnly,
+    Blocked,
 }
 
 #[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Debug)]
@@ -399,13 +1518,15 @@ impl From<MoveNode> for AvailableMove {
 /// A move event. Used to conduct actions related to object movement
 /// - [Self::MoveBegin] represents starting a move. By default, this will run the [`handle_move_begin_events`]
 /// which will calculate the available moves for the given unit.
-/// - [Self::MoveCalculated] is intended to run after a move has been calculated with the current units
-/// available moves. __This is not used currently. Instead available moves are pushed straight to the
-/// [`CurrentMovementInformation`] resource.__
+/// - [Self::MoveCalculated] is sent by [`calculate_available_moves`] every time an objects moves are
+/// calculated, containing the object that is moving and the tiles of its available moves. It is sent
+/// even when there are no available moves.
 /// - [Self::TryMoveObject] is sent when you want to try to move an object to a specific tile. Send
 /// the object thats trying to move and the tile you want it to move to. By default is handles by
 /// [`handle_try_move_events`]
 /// - [Self::MoveComplete] is sent if the [Self::TryMoveObject] event was successful.
+/// - [Self::MoveFailed] is sent if a [`MoveObject`] command fails, containing the position the object
+/// tried to move to and the reason it failed.
 #[derive(Clone, Eq, Hash, PartialEq)]
 pub enum MoveEvent {
     MoveBegin {
@@ -413,6 +1534,7 @@ pub enum MoveEvent {
         on_map: MapId,
     },
     MoveCalculated {
+        object_moving: ObjectId,
         available_moves: Vec<TilePos>,
     },
     TryMoveObject {
@@ -422,17 +1544,38 @@ pub enum MoveEvent {
     MoveComplete {
         object_moved: ObjectId,
     },
+    MoveFailed {
+        object_moving: ObjectId,
+        attempted_pos: TilePos,
+        reason: MoveError,
+    },
 }
 
 /// An error that represents any MoveErrors
+/// - [Self::ObjectNotFound] is an object that doesn't exist in the world
+/// - [Self::TargetNotInRange] is a target tile that isn't one of the objects available moves
+/// - [Self::TileOccupied] is a target tile that doesn't have space for the objects
+/// [`ObjectStackingClass`]
+/// - [Self::NoMovementSystem] is a move that needed a [`MovementSystem`] to check it when there isn't
+/// one in the world
+/// - [Self::MapNotFound] is a map that doesn't exist in the world
+/// - [Self::InvalidPath] is a path given to [`MoveObjectAlongPath`] that isn't a legal move, see
+/// [`path_cost`]
+/// - [Self::Other] is a move that failed for any other reason
 #[derive(Clone, Eq, Hash, PartialEq, Debug)]
 pub enum MoveError {
-    InvalidMove(String),
+    ObjectNotFound(ObjectId),
+    TargetNotInRange { target: TilePos },
+    TileOccupied(TilePos),
+    NoMovementSystem,
+    MapNotFound(MapId),
+    InvalidPath(Vec<TilePos>),
+    Other(String),
 }
 
 impl Default for MoveError {
     fn default() -> Self {
-        MoveError::InvalidMove(String::from("Invalid Move"))
+        MoveError::Other(String::from("Invalid Move"))
     }
 }
 
@@ -473,6 +1616,11 @@ impl MovementTypes {
                 .insert(movement_type.name.clone(), movement_type.clone());
         }
     }
+
+    /// Returns true if the given [`MovementType`] has been registered
+    pub fn contains(&self, movement_type: &MovementType) -> bool {
+        self.movement_types.get(&movement_type.name) == Some(movement_type)
+    }
 }
 
 /// Struct used to define a new [`MovementType`]. MovementType represents how a unit moves and is used
@@ -481,22 +1629,156 @@ impl MovementTypes {
     Default,
     Clone,
     Eq,
-    Hash,
+    Hash,
+    PartialEq,
+    Debug,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+pub struct MovementType {
+    pub name: String,
+}
+
+/// Component that must be added to a tile in order to define that tiles movement cost.
+///
+/// Contains a hashmap that holds a reference to a [`MovementType`] as a key and a u32 as the value. The u32 is used
+/// in pathfinding as the cost to move into that tile.
+#[derive(
+    Default,
+    Clone,
+    Eq,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[reflect(Component)]
+pub struct TileMovementCosts {
+    pub movement_type_cost: HashMap<MovementType, u32>,
+}
+
+impl TileMovementCosts {
+    /// Helper function to create a hashmap of TerrainType rules for Object Movement.
+    pub fn new(rules: Vec<(MovementType, u32)>) -> TileMovementCosts {
+        let mut hashmap: HashMap<MovementType, u32> = HashMap::new();
+        for rule in rules.iter() {
+            hashmap.insert(rule.0.clone(), rule.1);
+        }
+        TileMovementCosts {
+            movement_type_cost: hashmap,
+        }
+    }
+    pub fn calculate_unit_move_cost(&self) {}
+}
+
+/// The direction from a tile to one of its neighbors. North is +y and East is +x, matching the
+/// neighbors returned by [`MovementNodes::get_neighbors_tilepos`].
+#[derive(
+    Clone,
+    Copy,
+    Eq,
+    Hash,
+    PartialEq,
+    Debug,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+pub enum TileDirection {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl TileDirection {
+    /// Returns the direction from the `from` tile to the `to` tile. Returns None if the tiles are not
+    /// neighbors
+    pub fn between(from: &TilePos, to: &TilePos) -> Option<TileDirection> {
+        let x_offset = to.x as i64 - from.x as i64;
+        let y_offset = to.y as i64 - from.y as i64;
+        match (x_offset, y_offset) {
+            (0, 1) => Some(TileDirection::North),
+            (1, 1) => Some(TileDirection::NorthEast),
+            (1, 0) => Some(TileDirection::East),
+            (1, -1) => Some(TileDirection::SouthEast),
+            (0, -1) => Some(TileDirection::South),
+            (-1, -1) => Some(TileDirection::SouthWest),
+            (-1, 0) => Some(TileDirection::West),
+            (-1, 1) => Some(TileDirection::NorthWest),
+            _ => None,
+        }
+    }
+}
+
+/// Optional component that can be added to a tile to define an extra cost to move out of that tile in
+/// a specific direction. Used for mechanics like rivers that cost extra to cross or cliffs that can
+/// only be climbed one way.
+///
+/// The cost to move from tile A to tile B is B's [`TileMovementCosts`] plus the edge cost on A
+/// toward B. Tiles without this component, or directions without an entry, have an edge cost of zero.
+#[derive(
+    Default,
+    Clone,
+    Eq,
     PartialEq,
     Debug,
+    Component,
     Reflect,
     FromReflect,
     serde::Deserialize,
     serde::Serialize,
 )]
-pub struct MovementType {
-    pub name: String,
+#[reflect(Component)]
+pub struct TileEdgeCosts {
+    pub edge_costs: HashMap<TileDirection, u32>,
 }
 
-/// Component that must be added to a tile in order to define that tiles movement cost.
+impl TileEdgeCosts {
+    /// Helper function to create a new TileEdgeCosts from a vec of directions and costs
+    pub fn new(edge_costs: Vec<(TileDirection, u32)>) -> TileEdgeCosts {
+        let mut hashmap: HashMap<TileDirection, u32> = HashMap::new();
+        for (direction, cost) in edge_costs {
+            hashmap.insert(direction, cost);
+        }
+        TileEdgeCosts {
+            edge_costs: hashmap,
+        }
+    }
+
+    /// Returns the cost to leave the tile in the given direction
+    pub fn edge_cost(&self, direction: TileDirection) -> u32 {
+        *self.edge_costs.get(&direction).unwrap_or(&0)
+    }
+
+    /// Returns the cost to leave the tile at `from` toward the tile at `to`. Returns zero if the tiles
+    /// are not neighbors
+    pub fn edge_cost_toward(&self, from: &TilePos, to: &TilePos) -> u32 {
+        match TileDirection::between(from, to) {
+            Some(direction) => self.edge_cost(direction),
+            None => 0,
+        }
+    }
+}
+
+/// Optional component that can be added to a tile to allow or deny moving into it depending on the
+/// direction the object is moving in. Used by [`MoveCheckDirectional`](defaults::MoveCheckDirectional)
+/// for one way terrain like cliffs that can be jumped down but not climbed, or rivers that can only
+/// be moved along with the current.
 ///
-/// Contains a hashmap that holds a reference to a [`MovementType`] as a key and a u32 as the value. The u32 is used
-/// in pathfinding as the cost to move into that tile.
+/// Entries are keyed by the direction of travel into the tile, eg a tile at the top of a cliff that
+/// faces south denies [`TileDirection::North`] so objects can't climb it from below. Directions without
+/// an entry are allowed.
 #[derive(
     Default,
     Clone,
@@ -510,22 +1792,36 @@ pub struct MovementType {
     serde::Serialize,
 )]
 #[reflect(Component)]
-pub struct TileMovementCosts {
-    pub movement_type_cost: HashMap<MovementType, u32>,
+pub struct TileDirectionalRules {
+    pub entry_rules: HashMap<TileDirection, bool>,
 }
 
-impl TileMovementCosts {
-    /// Helper function to create a hashmap of TerrainType rules for Object Movement.
-    pub fn new(rules: Vec<(MovementType, u32)>) -> TileMovementCosts {
-        let mut hashmap: HashMap<MovementType, u32> = HashMap::new();
-        for rule in rules.iter() {
-            hashmap.insert(rule.0.clone(), rule.1);
+impl TileDirectionalRules {
+    /// Helper function to create a new TileDirectionalRules from a vec of directions and whether
+    /// moving into the tile in that direction is allowed
+    pub fn new(entry_rules: Vec<(TileDirection, bool)>) -> TileDirectionalRules {
+        let mut hashmap: HashMap<TileDirection, bool> = HashMap::new();
+        for (direction, allowed) in entry_rules {
+            hashmap.insert(direction, allowed);
         }
-        TileMovementCosts {
-            movement_type_cost: hashmap,
+        TileDirectionalRules {
+            entry_rules: hashmap,
+        }
+    }
+
+    /// Returns true if the tile can be entered moving in the given direction
+    pub fn can_enter(&self, direction: TileDirection) -> bool {
+        *self.entry_rules.get(&direction).unwrap_or(&true)
+    }
+
+    /// Returns true if the tile at `to` can be entered from the tile at `from`. Returns true if the
+    /// tiles are not neighbors
+    pub fn can_enter_from(&self, from: &TilePos, to: &TilePos) -> bool {
+        match TileDirection::between(from, to) {
+            Some(direction) => self.can_enter(direction),
+            None => true,
         }
     }
-    pub fn calculate_unit_move_cost(&self) {}
 }
 
 /// Defines a resource that will hold all [`TileMovementCosts`] related to TerrainTypes - references to a specific TileMovementCosts
@@ -585,6 +1881,53 @@ pub struct ObjectMovement {
     pub object_terrain_movement_rules: ObjectTerrainMovementRules,
 }
 
+/// Optional component that adjusts how much it costs the object to move into tiles, eg a scout that
+/// moves through forests more easily than other units with the same [`MovementType`].
+///
+/// The cost to move into a tile is calculated in this order:
+/// 1. The base cost from the tiles [`TileMovementCosts`] for the objects [`MovementType`]
+/// 2. Multiplied by the multiplier in `per_terrain_class` for the tiles [`TerrainClass`], if there is
+///    one, and rounded to the nearest whole number
+/// 3. Plus the amount in `flat_add` for the objects [`MovementType`], if there is one. Can be negative
+/// 4. Clamped to a minimum of 1 so that no tile can be moved into for free
+///
+/// Any [`TileEdgeCosts`] are added after the modifiers and aren't affected by them.
+#[derive(
+    Default,
+    Clone,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[reflect(Component)]
+pub struct ObjectMovementCostModifiers {
+    pub per_terrain_class: HashMap<TerrainClass, f32>,
+    pub flat_add: HashMap<MovementType, i32>,
+}
+
+impl ObjectMovementCostModifiers {
+    /// Applies the modifiers to the given base cost of moving into a tile with the given
+    /// [`TerrainClass`], for an object with the given [`MovementType`]. Never returns less than 1
+    pub fn apply(
+        &self,
+        base_cost: u32,
+        terrain_class: Option<&TerrainClass>,
+        movement_type: &MovementType,
+    ) -> u32 {
+        let multiplier = terrain_class
+            .and_then(|terrain_class| self.per_terrain_class.get(terrain_class))
+            .copied()
+            .unwrap_or(1.0);
+        let cost = (base_cost as f32 * multiplier).round() as i32
+            + self.flat_add.get(movement_type).copied().unwrap_or(0);
+        cost.max(1) as u32
+    }
+}
+
 /// Optional component that can be attached to an object to define rules related to that objects movement
 /// on other objects. Eg, allowing objects to move over water using bridges. In this situation bridges
 /// would be another object.
@@ -595,9 +1938,21 @@ pub struct ObjectMovement {
 /// These rules override [`ObjectTerrainMovementRules`].
 ///
 /// If using the built in [`MoveCheckAllowedTile`](defaults::MoveCheckAllowedTile) implementation,
-/// these rules ignore [`ObjectStackingClass`](crate::mapping::tiles::ObjectStackingClass).
+/// these rules ignore [`ObjectStackingClass`](crate::mapping::tiles::ObjectStackingClass). Use
+/// [`MoveCheckBridges`](defaults::MoveCheckBridges) to respect stacking and to let a rule deny a tile.
 ///
-#[derive(Default, Clone, Eq, PartialEq, Debug, Component, Reflect, FromReflect)]
+#[derive(
+    Default,
+    Clone,
+    Eq,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    serde::Deserialize,
+    serde::Serialize,
+)]
 #[reflect(Component)]
 pub struct ObjectTypeMovementRules {
     object_class_rules: HashMap<ObjectClass, bool>,
@@ -656,6 +2011,72 @@ impl ObjectTypeMovementRules {
         None
     }
 
+    /// Adds a rule allowing the object to move onto tiles containing objects of the given
+    /// [`ObjectClass`], replacing any existing rule for it
+    pub fn allow_class(&mut self, object_class: ObjectClass) {
+        self.object_class_rules.insert(object_class, true);
+    }
+
+    /// Adds a rule denying the object from moving onto tiles containing objects of the given
+    /// [`ObjectClass`], replacing any existing rule for it
+    pub fn deny_class(&mut self, object_class: ObjectClass) {
+        self.object_class_rules.insert(object_class, false);
+    }
+
+    /// Removes the rule for the given [`ObjectClass`], returning it if there was one
+    pub fn remove_class_rule(&mut self, object_class: &ObjectClass) -> Option<bool> {
+        self.object_class_rules.remove(object_class)
+    }
+
+    /// Adds a rule allowing the object to move onto tiles containing objects of the given
+    /// [`ObjectGroup`], replacing any existing rule for it
+    pub fn allow_group(&mut self, object_group: ObjectGroup) {
+        self.object_group_rules.insert(object_group, true);
+    }
+
+    /// Adds a rule denying the object from moving onto tiles containing objects of the given
+    /// [`ObjectGroup`], replacing any existing rule for it
+    pub fn deny_group(&mut self, object_group: ObjectGroup) {
+        self.object_group_rules.insert(object_group, false);
+    }
+
+    /// Removes the rule for the given [`ObjectGroup`], returning it if there was one
+    pub fn remove_group_rule(&mut self, object_group: &ObjectGroup) -> Option<bool> {
+        self.object_group_rules.remove(object_group)
+    }
+
+    /// Adds a rule allowing the object to move onto tiles containing objects of the given
+    /// [`ObjectType`], replacing any existing rule for it
+    pub fn allow_type(&mut self, object_type: ObjectType) {
+        self.object_type_rules.insert(object_type, true);
+    }
+
+    /// Adds a rule denying the object from moving onto tiles containing objects of the given
+    /// [`ObjectType`], replacing any existing rule for it
+    pub fn deny_type(&mut self, object_type: ObjectType) {
+        self.object_type_rules.insert(object_type, false);
+    }
+
+    /// Removes the rule for the given [`ObjectType`], returning it if there was one
+    pub fn remove_type_rule(&mut self, object_type: &ObjectType) -> Option<bool> {
+        self.object_type_rules.remove(object_type)
+    }
+
+    /// Returns the rules for each [`ObjectClass`]
+    pub fn class_rules(&self) -> &HashMap<ObjectClass, bool> {
+        &self.object_class_rules
+    }
+
+    /// Returns the rules for each [`ObjectGroup`]
+    pub fn group_rules(&self) -> &HashMap<ObjectGroup, bool> {
+        &self.object_group_rules
+    }
+
+    /// Returns the rules for each [`ObjectType`]
+    pub fn type_rules(&self) -> &HashMap<ObjectType, bool> {
+        &self.object_type_rules
+    }
+
     /// Helper function to create a hashmap of [`ObjectType`] rules for Object Movement.
     pub fn new_type_rules_hashmaps(
         type_rules: Vec<(ObjectType, bool)>,
@@ -798,8 +2219,1194 @@ fn test_terrain_rules() {
     assert_eq!(movement_rules.can_move_on_tile(&tile_terrain_info), false);
 }
 
-//TODO: When we have some form of scheduling, make this go away by default at the beginning of the
-// players turn
-/// Marker component signifying that the unit has moved and cannot move anymore
+#[test]
+fn test_move_object_out_of_bounds_errors() {
+    use crate::mapping::tiles::{
+        ObjectStackingClass, StackingClass, TileObjectStacks, TileObjectStacksCount, TileObjects,
+    };
+    use crate::object::{Object, ObjectGridPosition};
+    use bevy_ecs_tilemap::prelude::{TileStorage, TilemapSize};
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let map_id = MapId { id: 0 };
+    let current_pos = TilePos::new(0, 0);
+
+    let tile_entity = world
+        .spawn((
+            TileObjectStacks::new(vec![(
+                stacking_class.clone(),
+                TileObjectStacksCount {
+                    current_count: 1,
+                    max_count: 1,
+                },
+            )]),
+            TileObjects {
+                entities_in_tile: vec![ObjectId { id: 0 }],
+            },
+        ))
+        .id();
+    let mut tile_storage = TileStorage::empty(TilemapSize { x: 1, y: 1 });
+    tile_storage.set(&current_pos, tile_entity);
+    world.spawn((map_id, tile_storage));
+
+    world.spawn((
+        Object,
+        ObjectId { id: 0 },
+        ObjectGridPosition {
+            tile_position: current_pos.into(),
+        },
+        ObjectStackingClass {
+            stack_class: stacking_class,
+        },
+    ));
+
+    let mut move_object = MoveObject {
+        object_moving: ObjectId { id: 0 },
+        on_map: map_id,
+        current_pos,
+        new_pos: TilePos::new(5, 5),
+        attempt: false,
+        carried: vec![],
+    };
+    assert!(move_object.execute(&mut world).is_err());
+
+    // The object is left where it started
+    let tile_objects = world.get::<TileObjects>(tile_entity).unwrap();
+    assert!(tile_objects.contains_object(ObjectId { id: 0 }));
+}
+
+/// Marker component signifying that the unit has moved and cannot move anymore. Cleared by the
+/// [`ResetActionsAll`](crate::game_core::command::ResetActionsAll) and
+/// [`ResetActionsForPlayer`](crate::game_core::command::ResetActionsForPlayer) commands
 #[derive(Clone, Copy, Eq, Hash, PartialEq, Component)]
 pub struct ObjectMoved;
+
+#[test]
+fn test_object_movement_save_round_trip() {
+    use crate::game_core::saving::{ComponentBinaryState, GameSerDeRegistry, SaveId};
+    use crate::object::{ObjectClass, ObjectGroup, ObjectType};
+
+    let ground = ObjectClass {
+        name: String::from("Ground"),
+    };
+    let infantry = ObjectGroup {
+        name: String::from("Infantry"),
+        object_class: ground.clone(),
+    };
+    let bridge = ObjectType {
+        name: String::from("Bridge"),
+        object_group: ObjectGroup {
+            name: String::from("Structure"),
+            object_class: ground.clone(),
+        },
+    };
+    let grassland = TerrainType {
+        name: String::from("Grassland"),
+        terrain_class: TerrainClass {
+            name: String::from("Ground"),
+        },
+    };
+
+    let object_movement = ObjectMovement {
+        move_points: 3,
+        movement_type: MovementType {
+            name: String::from("Foot"),
+        },
+        object_terrain_movement_rules: ObjectTerrainMovementRules::new(
+            vec![grassland.terrain_class.clone()],
+            vec![(grassland, true)],
+        ),
+    };
+    let object_type_movement_rules = ObjectTypeMovementRules::new(
+        vec![(ground, false)],
+        vec![(infantry, true)],
+        vec![(bridge, true)],
+    );
+
+    let registry = GameSerDeRegistry::default_registry();
+    let mut world = World::new();
+    let mut entity = world.spawn_empty();
+    for (id, component) in [
+        object_movement.save().unwrap(),
+        object_type_movement_rules.save().unwrap(),
+    ] {
+        registry.deserialize_component_onto(&ComponentBinaryState { id, component }, &mut entity);
+    }
+
+    assert_eq!(entity.get::<ObjectMovement>(), Some(&object_movement));
+    assert_eq!(
+        entity.get::<ObjectTypeMovementRules>(),
+        Some(&object_type_movement_rules)
+    );
+}
+
+#[test]
+fn test_preview_available_moves_does_not_mark_changed() {
+    use crate::game_core::state::Changed;
+    use crate::movement::defaults::SquareMovementCalculator;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    // Marks every tile it is run on as changed, like a callback that updates tile visuals would
+    struct MarkChanged;
+
+    impl PathfindCallback<TilePos> for MarkChanged {
+        fn foreach_tile(
+            &mut self,
+            _pathfinding_entity: Entity,
+            node_entity: Entity,
+            _node_pos: TilePos,
+            _node_cost: u32,
+            world: &mut World,
+        ) {
+            world.entity_mut(node_entity).insert(Changed::default());
+        }
+    }
+
+    let mut world = World::new();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let tilemap_size = TilemapSize { x: 3, y: 3 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        for y in 0..tilemap_size.y {
+            let tile_pos = TilePos::new(x, y);
+            let tile_entity = world
+                .spawn((tile_pos, TileMovementCosts::new(vec![(foot.clone(), 1)])))
+                .id();
+            tile_storage.set(&tile_pos, tile_entity);
+        }
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        movement_callback: Some(Box::new(MarkChanged)),
+    });
+
+    let object_id = ObjectId { id: 0 };
+    world.spawn((
+        object_id,
+        ObjectGridPosition {
+            tile_position: TilePos::new(1, 1).into(),
+        },
+        ObjectMovement {
+            move_points: 1,
+            movement_type: foot,
+            object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+        },
+    ));
+
+    let moves: Vec<TilePos> = preview_available_moves(&mut world, map_id, object_id)
+        .iter()
+        .map(|available_move| available_move.tile_pos)
+        .collect();
+    assert_eq!(
+        moves,
+        vec![
+            TilePos::new(0, 1),
+            TilePos::new(1, 0),
+            TilePos::new(1, 1),
+            TilePos::new(1, 2),
+            TilePos::new(2, 1),
+        ]
+    );
+    assert_eq!(world.query::<&Changed>().iter(&world).count(), 0);
+    assert!(world
+        .resource::<MovementSystem>()
+        .movement_callback
+        .is_some());
+}
+
+#[test]
+fn test_partial_moves_shrink_range() {
+    use crate::game_core::command::AddObjectToTile;
+    use crate::mapping::tiles::{
+        StackingClass, TileObjectStacks, TileObjectStacksCount, TileObjects,
+    };
+    use crate::movement::defaults::SquareMovementCalculator;
+    use crate::object::Object;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let tilemap_size = TilemapSize { x: 8, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileMovementCosts::new(vec![(foot.clone(), 1)]),
+                TileObjectStacks::new(vec![(
+                    stacking_class.clone(),
+                    TileObjectStacksCount {
+                        current_count: 0,
+                        max_count: 1,
+                    },
+                )]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        movement_callback: None,
+    });
+
+    let object_id = ObjectId { id: 0 };
+    let object = world
+        .spawn((
+            Object,
+            object_id,
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: stacking_class,
+            },
+            ObjectMovement {
+                move_points: 5,
+                movement_type: foot,
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+            },
+        ))
+        .id();
+    AddObjectToTile {
+        object_game_id: object_id,
+        on_map: map_id,
+        tile_pos: TilePos::new(0, 0),
+        strict: false,
+    }
+    .execute(&mut world)
+    .unwrap();
+
+    let reachable = |world: &mut World| -> Vec<u32> {
+        let mut reachable: Vec<u32> = calculate_available_moves(world, map_id, object)
+            .unwrap()
+            .keys()
+            .map(|tile_pos| tile_pos.x)
+            .collect();
+        reachable.sort();
+        reachable
+    };
+    assert_eq!(reachable(&mut world), vec![0, 1, 2, 3, 4, 5]);
+
+    // Spending 3 of the 5 move points leaves a range of 2 from the new tile
+    let mut first_move = MoveObjectPartial {
+        object_moving: object_id,
+        on_map: map_id,
+        new_pos: TilePos::new(3, 0),
+        previous_state: None,
+    };
+    first_move.execute(&mut world).unwrap();
+    assert_eq!(
+        world.get::<PartialMove>(object),
+        Some(&PartialMove { spent: 3 })
+    );
+    assert_eq!(reachable(&mut world), vec![1, 2, 3, 4, 5]);
+    assert_eq!(
+        path_cost(
+            &mut world,
+            map_id,
+            object,
+            &[TilePos::new(3, 0), TilePos::new(4, 0), TilePos::new(5, 0)]
+        ),
+        Some(2)
+    );
+    assert_eq!(
+        path_cost(
+            &mut world,
+            map_id,
+            object,
+            &[
+                TilePos::new(3, 0),
+                TilePos::new(4, 0),
+                TilePos::new(5, 0),
+                TilePos::new(6, 0)
+            ]
+        ),
+        None
+    );
+
+    // A tile out of the remaining range can't be reached
+    let mut too_far = MoveObjectPartial {
+        object_moving: object_id,
+        on_map: map_id,
+        new_pos: TilePos::new(6, 0),
+        previous_state: None,
+    };
+    assert!(too_far.execute(&mut world).is_err());
+    let mut missing = MoveObjectPartial {
+        object_moving: ObjectId { id: 5 },
+        on_map: map_id,
+        new_pos: TilePos::new(4, 0),
+        previous_state: None,
+    };
+    assert!(missing.execute(&mut world).is_err());
+    let events = world.resource::<Events<MoveEvent>>();
+    let reasons: Vec<MoveError> = events
+        .get_reader()
+        .iter(events)
+        .filter_map(|event| match event {
+            MoveEvent::MoveFailed { reason, .. } => Some(reason.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            MoveError::TargetNotInRange {
+                target: TilePos::new(6, 0)
+            },
+            MoveError::ObjectNotFound(ObjectId { id: 5 })
+        ]
+    );
+
+    // Spending the rest leaves no moves at all
+    let mut second_move = MoveObjectPartial {
+        object_moving: object_id,
+        on_map: map_id,
+        new_pos: TilePos::new(5, 0),
+        previous_state: None,
+    };
+    second_move.execute(&mut world).unwrap();
+    assert!(world.get::<ObjectMoved>(object).is_some());
+    assert_eq!(reachable(&mut world), Vec::<u32>::new());
+}
+
+#[test]
+fn test_object_movement_cost_modifiers() {
+    use crate::movement::defaults::SquareMovementCalculator;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let forest = TerrainClass {
+        name: String::from("Forest"),
+    };
+
+    // Terrain base, then multiplier, then flat add, then clamped to 1
+    let scout_modifiers = ObjectMovementCostModifiers {
+        per_terrain_class: HashMap::from([(forest.clone(), 0.5)]),
+        flat_add: HashMap::new(),
+    };
+    assert_eq!(scout_modifiers.apply(3, Some(&forest), &foot), 2);
+    assert_eq!(scout_modifiers.apply(3, None, &foot), 3);
+    let free_movement = ObjectMovementCostModifiers {
+        per_terrain_class: HashMap::from([(forest.clone(), 0.0)]),
+        flat_add: HashMap::from([(foot.clone(), -5)]),
+    };
+    assert_eq!(free_movement.apply(3, Some(&forest), &foot), 1);
+
+    // A forest corridor that costs 2 to move into each tile
+    let mut world = World::new();
+    let tilemap_size = TilemapSize { x: 8, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileTerrainInfo {
+                    terrain_type: TerrainType {
+                        name: String::from("Pine Forest"),
+                        terrain_class: forest.clone(),
+                    },
+                },
+                TileMovementCosts::new(vec![(foot.clone(), 2)]),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        movement_callback: None,
+    });
+
+    let object_movement = ObjectMovement {
+        move_points: 4,
+        movement_type: foot,
+        object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+    };
+    let object_grid_position = ObjectGridPosition {
+        tile_position: TilePos::new(0, 0).into(),
+    };
+    let normal = world
+        .spawn((object_grid_position, object_movement.clone()))
+        .id();
+    let scout = world
+        .spawn((object_grid_position, object_movement, scout_modifiers))
+        .id();
+
+    let furthest = |world: &mut World, object: Entity| -> u32 {
+        calculate_available_moves(world, map_id, object)
+            .unwrap()
+            .keys()
+            .map(|tile_pos| tile_pos.x)
+            .max()
+            .unwrap()
+    };
+    assert_eq!(furthest(&mut world, normal), 2);
+    assert_eq!(furthest(&mut world, scout), 4);
+}
+
+#[test]
+fn test_move_object_failure_reasons() {
+    use crate::game_core::command::AddObjectToTile;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount, TileObjects};
+    use crate::movement::defaults::{MoveCheckSpace, SquareMovementCalculator};
+    use crate::object::Object;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let tilemap_size = TilemapSize { x: 4, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileMovementCosts::new(vec![(foot.clone(), 1)]),
+                TileObjectStacks::new(vec![(
+                    stacking_class.clone(),
+                    TileObjectStacksCount {
+                        current_count: 0,
+                        max_count: 1,
+                    },
+                )]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+
+    for (id, x) in [(0, 0), (1, 1)] {
+        world.spawn((
+            Object,
+            ObjectId { id },
+            ObjectGridPosition {
+                tile_position: TilePos::new(x, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: stacking_class.clone(),
+            },
+            ObjectMovement {
+                move_points: 1,
+                movement_type: foot.clone(),
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+            },
+        ));
+        AddObjectToTile {
+            object_game_id: ObjectId { id },
+            on_map: map_id,
+            tile_pos: TilePos::new(x, 0),
+            strict: false,
+        }
+        .execute(&mut world)
+        .unwrap();
+    }
+
+    let mut reader = world.resource::<Events<MoveEvent>>().get_reader();
+    let mut move_failed_reason =
+        |world: &mut World, object_moving: ObjectId, on_map: MapId, new_pos: TilePos, attempt| {
+            let mut move_object = MoveObject {
+                object_moving,
+                on_map,
+                current_pos: TilePos::new(0, 0),
+                new_pos,
+                attempt,
+                carried: vec![],
+            };
+            assert!(move_object.execute(world).is_err());
+            let events = world.resource::<Events<MoveEvent>>();
+            let reasons: Vec<MoveError> = reader
+                .iter(events)
+                .filter_map(|event| match event {
+                    MoveEvent::MoveFailed { reason, .. } => Some(reason.clone()),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(reasons.len(), 1);
+            reasons[0].clone()
+        };
+
+    let object_id = ObjectId { id: 0 };
+    assert_eq!(
+        move_failed_reason(
+            &mut world,
+            ObjectId { id: 5 },
+            map_id,
+            TilePos::new(1, 0),
+            false
+        ),
+        MoveError::ObjectNotFound(ObjectId { id: 5 })
+    );
+    assert_eq!(
+        move_failed_reason(
+            &mut world,
+            object_id,
+            MapId { id: 5 },
+            TilePos::new(1, 0),
+            false
+        ),
+        MoveError::MapNotFound(MapId { id: 5 })
+    );
+    assert_eq!(
+        move_failed_reason(&mut world, object_id, map_id, TilePos::new(1, 0), true),
+        MoveError::NoMovementSystem
+    );
+    assert!(matches!(
+        move_failed_reason(&mut world, object_id, map_id, TilePos::new(5, 5), false),
+        MoveError::Other(_)
+    ));
+
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![TileMoveCheckMeta {
+                check: Box::new(MoveCheckSpace),
+            }],
+        },
+        movement_callback: None,
+    });
+    assert_eq!(
+        move_failed_reason(&mut world, object_id, map_id, TilePos::new(1, 0), true),
+        MoveError::TileOccupied(TilePos::new(1, 0))
+    );
+    assert_eq!(
+        move_failed_reason(&mut world, object_id, map_id, TilePos::new(3, 0), true),
+        MoveError::TargetNotInRange {
+            target: TilePos::new(3, 0)
+        }
+    );
+}
+
+#[test]
+fn test_calculate_available_moves_sends_move_calculated() {
+    use crate::game_core::command::AddObjectToTile;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount, TileObjects};
+    use crate::movement::defaults::SquareMovementCalculator;
+    use crate::object::Object;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let tilemap_size = TilemapSize { x: 4, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileMovementCosts::new(vec![(foot.clone(), 1)]),
+                TileObjectStacks::new(vec![(
+                    stacking_class.clone(),
+                    TileObjectStacksCount {
+                        current_count: 0,
+                        max_count: 1,
+                    },
+                )]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        movement_callback: None,
+    });
+
+    let object_id = ObjectId { id: 0 };
+    let object_movement = ObjectMovement {
+        move_points: 2,
+        movement_type: foot,
+        object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+    };
+    let object = world
+        .spawn((
+            Object,
+            object_id,
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: stacking_class,
+            },
+            object_movement,
+        ))
+        .id();
+    AddObjectToTile {
+        object_game_id: object_id,
+        on_map: map_id,
+        tile_pos: TilePos::new(0, 0),
+        strict: false,
+    }
+    .execute(&mut world)
+    .unwrap();
+
+    let mut reader = world.resource::<Events<MoveEvent>>().get_reader();
+    let mut calculated_moves = |world: &mut World| -> (Vec<TilePos>, Vec<TilePos>) {
+        let mut moves: Vec<TilePos> = calculate_available_moves(world, map_id, object)
+            .unwrap()
+            .into_keys()
+            .collect();
+        moves.sort_by_key(|tile_pos| (tile_pos.x, tile_pos.y));
+
+        let events = world.resource::<Events<MoveEvent>>();
+        let calculated: Vec<(ObjectId, Vec<TilePos>)> = reader
+            .iter(events)
+            .filter_map(|event| match event {
+                MoveEvent::MoveCalculated {
+                    object_moving,
+                    available_moves,
+                } => Some((*object_moving, available_moves.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calculated.len(), 1);
+        assert_eq!(calculated[0].0, object_id);
+        (moves, calculated[0].1.clone())
+    };
+
+    let (moves, event_moves) = calculated_moves(&mut world);
+    assert_eq!(
+        moves,
+        vec![TilePos::new(0, 0), TilePos::new(1, 0), TilePos::new(2, 0)]
+    );
+    assert_eq!(event_moves, moves);
+
+    // An object with no move points left still sends the event so UIs can clear their highlights
+    world.entity_mut(object).insert(PartialMove { spent: 2 });
+    let (moves, event_moves) = calculated_moves(&mut world);
+    assert!(moves.is_empty());
+    assert_eq!(event_moves, moves);
+}
+
+#[test]
+fn test_movement_callback_before_calculator() {
+    use crate::game_core::runner::TurnBasedGameRunner;
+    use crate::game_core::SetupError;
+    use crate::movement::defaults::SquareMovementCalculator;
+    use bevy::prelude::Schedule;
+
+    struct NoopCallback;
+
+    impl PathfindCallback<TilePos> for NoopCallback {
+        fn foreach_tile(
+            &mut self,
+            _pathfinding_entity: Entity,
+            _node_entity: Entity,
+            _node_pos: TilePos,
+            _node_cost: u32,
+            _world: &mut World,
+        ) {
+        }
+    }
+
+    let mut game_builder = GameBuilder::new_game(TurnBasedGameRunner {
+        turn_schedule: Schedule::default(),
+    });
+    game_builder.with_movement_callback(NoopCallback);
+    assert!(game_builder
+        .validate()
+        .unwrap_err()
+        .contains(&SetupError::MissingMovementSystem));
+
+    game_builder.with_movement_calculator(
+        SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        },
+        vec![],
+        TilemapType::Square,
+    );
+    assert!(game_builder
+        .game_world
+        .resource::<MovementSystem>()
+        .movement_callback
+        .is_some());
+    assert!(!game_builder
+        .game_world
+        .contains_resource::<PendingMovementCallback>());
+}
+
+#[test]
+fn test_move_object_with_carried_objects() {
+    use crate::game_core::command::AddObjectToTile;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount, TileObjects};
+    use crate::object::{CarriedByParent, Object};
+    use bevy::prelude::BuildWorldChildren;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let ground = StackingClass {
+        name: String::from("Ground"),
+    };
+    let passenger = StackingClass {
+        name: String::from("Passenger"),
+    };
+    let tilemap_size = TilemapSize { x: 2, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    let mut tile_entities = vec![];
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileObjectStacks::new(vec![
+                    (
+                        ground.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                    (
+                        passenger.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                ]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+        tile_entities.push(tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+
+    let spawn_object = |world: &mut World, id: usize, stacking_class: &StackingClass| {
+        let entity = world
+            .spawn((
+                Object,
+                ObjectId { id },
+                ObjectGridPosition {
+                    tile_position: TilePos::new(0, 0).into(),
+                },
+                ObjectStackingClass {
+                    stack_class: stacking_class.clone(),
+                },
+            ))
+            .id();
+        AddObjectToTile {
+            object_game_id: ObjectId { id },
+            on_map: map_id,
+            tile_pos: TilePos::new(0, 0),
+            strict: false,
+        }
+        .execute(world)
+        .unwrap();
+        entity
+    };
+    let transport = spawn_object(&mut world, 0, &ground);
+    let first = spawn_object(&mut world, 1, &passenger);
+    let second = spawn_object(&mut world, 2, &passenger);
+    for carried in [first, second] {
+        world
+            .entity_mut(carried)
+            .set_parent(transport)
+            .insert(CarriedByParent);
+    }
+    let objects_in = |world: &World, x: usize| -> usize {
+        world
+            .get::<TileObjects>(tile_entities[x])
+            .unwrap()
+            .entities_in_tile
+            .len()
+    };
+
+    // Both passengers don't fit in the new tile so nothing moves
+    let mut move_object = MoveObject {
+        object_moving: ObjectId { id: 0 },
+        on_map: map_id,
+        current_pos: TilePos::new(0, 0),
+        new_pos: TilePos::new(1, 0),
+        attempt: false,
+        carried: vec![],
+    };
+    assert!(move_object.execute(&mut world).is_err());
+    assert_eq!(objects_in(&world, 0), 3);
+    assert_eq!(objects_in(&world, 1), 0);
+
+    // Moving the second passenger fails after the transport and first passenger have moved, so they
+    // are moved back
+    world.entity_mut(second).remove::<ObjectStackingClass>();
+    assert!(move_object.execute(&mut world).is_err());
+    assert_eq!(objects_in(&world, 0), 3);
+    assert_eq!(objects_in(&world, 1), 0);
+    assert_eq!(
+        world
+            .get::<ObjectGridPosition>(first)
+            .unwrap()
+            .tile_position,
+        TilePos::new(0, 0).into()
+    );
+
+    world
+        .entity_mut(second)
+        .remove::<CarriedByParent>()
+        .remove_parent();
+    move_object.execute(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 0), 1);
+    assert_eq!(objects_in(&world, 1), 2);
+}
+
+#[test]
+fn test_move_object_along_path_with_carried_objects() {
+    use crate::game_core::command::AddObjectToTile;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount, TileObjects};
+    use crate::movement::defaults::SquareMovementCalculator;
+    use crate::object::{CarriedByParent, Object};
+    use bevy::prelude::BuildWorldChildren;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let ground = StackingClass {
+        name: String::from("Ground"),
+    };
+    let passenger = StackingClass {
+        name: String::from("Passenger"),
+    };
+    let tilemap_size = TilemapSize { x: 3, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    let mut tile_entities = vec![];
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileMovementCosts::new(vec![(foot.clone(), 1)]),
+                TileObjectStacks::new(vec![
+                    (
+                        ground.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                    (
+                        passenger.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                ]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+        tile_entities.push(tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        movement_callback: None,
+    });
+
+    let transport = world
+        .spawn((
+            Object,
+            ObjectId { id: 0 },
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: ground,
+            },
+            ObjectMovement {
+                move_points: 2,
+                movement_type: foot,
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+            },
+        ))
+        .id();
+    let carried = world
+        .spawn((
+            Object,
+            ObjectId { id: 1 },
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: passenger,
+            },
+            CarriedByParent,
+        ))
+        .set_parent(transport)
+        .id();
+    for id in 0..2 {
+        AddObjectToTile {
+            object_game_id: ObjectId { id },
+            on_map: map_id,
+            tile_pos: TilePos::new(0, 0),
+            strict: false,
+        }
+        .execute(&mut world)
+        .unwrap();
+    }
+    let objects_in = |world: &World, x: usize| -> usize {
+        world
+            .get::<TileObjects>(tile_entities[x])
+            .unwrap()
+            .entities_in_tile
+            .len()
+    };
+
+    let mut move_along_path = MoveObjectAlongPath {
+        object_moving: ObjectId { id: 0 },
+        on_map: map_id,
+        path: vec![TilePos::new(0, 0), TilePos::new(1, 0), TilePos::new(2, 0)],
+        moved: false,
+        carried: vec![],
+    };
+    move_along_path.execute(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 0), 0);
+    assert_eq!(objects_in(&world, 2), 2);
+    assert_eq!(
+        world
+            .get::<ObjectGridPosition>(carried)
+            .unwrap()
+            .tile_position,
+        TilePos::new(2, 0).into()
+    );
+
+    move_along_path.rollback(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 0), 2);
+    assert_eq!(objects_in(&world, 2), 0);
+    assert_eq!(
+        world
+            .get::<ObjectGridPosition>(carried)
+            .unwrap()
+            .tile_position,
+        TilePos::new(0, 0).into()
+    );
+}
+
+#[test]
+fn test_move_object_partial_with_carried_objects() {
+    use crate::game_core::command::AddObjectToTile;
+    use crate::mapping::tiles::{StackingClass, TileObjectStacksCount, TileObjects};
+    use crate::movement::defaults::SquareMovementCalculator;
+    use crate::object::{CarriedByParent, Object};
+    use bevy::prelude::BuildWorldChildren;
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    world.init_resource::<Events<MoveEvent>>();
+    let foot = MovementType {
+        name: String::from("Foot"),
+    };
+    let ground = StackingClass {
+        name: String::from("Ground"),
+    };
+    let passenger = StackingClass {
+        name: String::from("Passenger"),
+    };
+    let tilemap_size = TilemapSize { x: 3, y: 1 };
+    let mut tile_storage = TileStorage::empty(tilemap_size);
+    let mut tile_entities = vec![];
+    for x in 0..tilemap_size.x {
+        let tile_pos = TilePos::new(x, 0);
+        let tile_entity = world
+            .spawn((
+                tile_pos,
+                TileMovementCosts::new(vec![(foot.clone(), 1)]),
+                TileObjectStacks::new(vec![
+                    (
+                        ground.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                    (
+                        passenger.clone(),
+                        TileObjectStacksCount {
+                            current_count: 0,
+                            max_count: 1,
+                        },
+                    ),
+                ]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+        tile_entities.push(tile_entity);
+    }
+    let map_id = MapId { id: 0 };
+    world.spawn((map_id, tile_storage, tilemap_size));
+    world.insert_resource(MovementSystem {
+        movement_calculator: Box::new(SquareMovementCalculator {
+            diagonal_movement: DiagonalMovement::Disabled,
+        }),
+        map_type: TilemapType::Square,
+        tile_move_checks: TileMoveChecks {
+            tile_move_checks: vec![],
+        },
+        movement_callback: None,
+    });
+
+    let transport = world
+        .spawn((
+            Object,
+            ObjectId { id: 0 },
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: ground,
+            },
+            ObjectMovement {
+                move_points: 3,
+                movement_type: foot,
+                object_terrain_movement_rules: ObjectTerrainMovementRules::new(vec![], vec![]),
+            },
+        ))
+        .id();
+    let carried = world
+        .spawn((
+            Object,
+            ObjectId { id: 1 },
+            ObjectGridPosition {
+                tile_position: TilePos::new(0, 0).into(),
+            },
+            ObjectStackingClass {
+                stack_class: passenger,
+            },
+            CarriedByParent,
+        ))
+        .set_parent(transport)
+        .id();
+    for id in 0..2 {
+        AddObjectToTile {
+            object_game_id: ObjectId { id },
+            on_map: map_id,
+            tile_pos: TilePos::new(0, 0),
+            strict: false,
+        }
+        .execute(&mut world)
+        .unwrap();
+    }
+    let objects_in = |world: &World, x: usize| -> usize {
+        world
+            .get::<TileObjects>(tile_entities[x])
+            .unwrap()
+            .entities_in_tile
+            .len()
+    };
+
+    let mut first_move = MoveObjectPartial {
+        object_moving: ObjectId { id: 0 },
+        on_map: map_id,
+        new_pos: TilePos::new(1, 0),
+        previous_state: None,
+    };
+    first_move.execute(&mut world).unwrap();
+    let mut second_move = MoveObjectPartial {
+        object_moving: ObjectId { id: 0 },
+        on_map: map_id,
+        new_pos: TilePos::new(2, 0),
+        previous_state: None,
+    };
+    second_move.execute(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 0), 0);
+    assert_eq!(objects_in(&world, 1), 0);
+    assert_eq!(objects_in(&world, 2), 2);
+    assert_eq!(
+        world
+            .get::<ObjectGridPosition>(carried)
+            .unwrap()
+            .tile_position,
+        TilePos::new(2, 0).into()
+    );
+
+    second_move.rollback(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 1), 2);
+    assert_eq!(objects_in(&world, 2), 0);
+    first_move.rollback(&mut world).unwrap();
+    assert_eq!(objects_in(&world, 0), 2);
+    assert_eq!(objects_in(&world, 1), 0);
+    assert_eq!(
+        world
+            .get::<ObjectGridPosition>(carried)
+            .unwrap()
+            .tile_position,
+        TilePos::new(0, 0).into()
+    );
+}
@@ -2,10 +2,18 @@
 //! a tile and resides on the map. This system is built on top of Bevy_ECS and is based on the entity
 //! component system.
 
-use crate::mapping::tiles::{ObjectStackingClass, TilePosition};
-use crate::movement::ObjectMovementBundle;
-use bevy::prelude::{Bundle, Component, ReflectComponent, Resource};
+use crate::combat::Health;
+use crate::mapping::tiles::{
+    tile_neighbors, ObjectStackingClass, StackingClass, TileObjects, TilePosition,
+};
+use crate::mapping::{Map, MapId};
+use crate::movement::{calculate_move_nodes, ObjectMovementBundle};
+use crate::player::PlayerMarker;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Bundle, Component, Entity, Query, ReflectComponent, Resource, With, World};
 use bevy::reflect::{FromReflect, Reflect};
+use bevy::utils::{HashMap, HashSet};
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage};
 use serde::{Deserialize, Serialize};
 
 // Default Components that we should have for objects
@@ -45,7 +53,7 @@ AddResourceOnTurn
 // ObjectClass -> (Ground, Air, Water, Building, etc)
 // ObjectGroup -> (Armor, Capital Ship, Helicopter)
 // ObjectType -> (Light Tank, Battleship, Infantry, Unit Barracks, Wall)
-#[derive(Bundle, Clone)]
+#[derive(Bundle, Clone, Reflect)]
 pub struct ObjectMinimalBundle {
     pub object: Object,
     pub object_info: ObjectInfo,
@@ -96,14 +104,27 @@ impl ObjectIdProvider {
         ObjectId { id: self.next_id() }
     }
 
+    /// Returns a new id. Ids are never handed out twice, even after the object they were given to
+    /// is despawned or its spawn is rolled back, so an id always refers to the same object. Commands
+    /// that recreate an object, like rolling forward a [`SpawnObject`](crate::game_core::command::SpawnObject),
+    /// reuse the id they were first given. Saturates at `usize::MAX` rather than panicking
     pub fn next_id(&mut self) -> usize {
         self.last_id = self.last_id.saturating_add(1);
         self.last_id
     }
 
+    /// Gives back the most recently handed out id so that it is handed out again
+    #[deprecated(
+        note = "Giving an id back can hand out an id that is still in use, ids are never reused now"
+    )]
     pub fn remove_last_id(&mut self) {
         self.last_id = self.last_id.saturating_sub(1);
     }
+
+    /// Marks the given id as used so that it is never handed out by [`next_id`](Self::next_id)
+    pub fn reserve_id(&mut self, id: usize) {
+        self.last_id = self.last_id.max(id);
+    }
 }
 
 /// Provides a way to track entities through potential despawns, spawns, and other shenanigans. Use
@@ -146,8 +167,25 @@ pub struct ObjectId {
 pub struct Object;
 
 impl Object {
-    // texture, tile_pos, stacking type, object type
-    pub fn spawn() {}
+    /// Builds an [`ObjectMinimalBundle`] for an object of the given type at the given tile. Pass the
+    /// bundle to [`GameCommands::spawn_object`](crate::game_core::command::GameCommands::spawn_object)
+    /// to spawn it, adding any other components the object needs to the bundle first
+    pub fn minimal_bundle(
+        object_type: ObjectType,
+        tile_pos: TilePos,
+        stacking_class: StackingClass,
+    ) -> ObjectMinimalBundle {
+        ObjectMinimalBundle {
+            object: Object,
+            object_info: ObjectInfo { object_type },
+            object_grid_position: ObjectGridPosition {
+                tile_position: tile_pos.into(),
+            },
+            object_stacking_class: ObjectStackingClass {
+                stack_class: stacking_class,
+            },
+        }
+    }
 }
 
 /// Defines a new distinct ObjectClass. ObjectClass is used to represent the base class of an Object.
@@ -283,6 +321,405 @@ pub struct ObjectInfo {
     pub object_type: ObjectType,
 }
 
+/// Returns the [`ObjectId`] of every Object whose [`ObjectInfo`] belongs to the given [`ObjectClass`]
+pub fn objects_of_class(world: &mut World, object_class: &ObjectClass) -> Vec<ObjectId> {
+    objects_matching_info(world, |object_info| {
+        &object_info.object_type.object_group.object_class == object_class
+    })
+}
+
+/// Returns the [`ObjectId`] of every Object whose [`ObjectInfo`] belongs to the given [`ObjectGroup`]
+pub fn objects_of_group(world: &mut World, object_group: &ObjectGroup) -> Vec<ObjectId> {
+    objects_matching_info(world, |object_info| {
+        &object_info.object_type.object_group == object_group
+    })
+}
+
+/// Returns the [`ObjectId`] of every Object whose [`ObjectInfo`] is the given [`ObjectType`]
+pub fn objects_of_type(world: &mut World, object_type: &ObjectType) -> Vec<ObjectId> {
+    objects_matching_info(world, |object_info| &object_info.object_type == object_type)
+}
+
+/// Returns the [`ObjectId`] of every Object whose [`ObjectInfo`] passes the given predicate
+pub fn objects_matching_info<F>(world: &mut World, predicate: F) -> Vec<ObjectId>
+where
+    F: Fn(&ObjectInfo) -> bool,
+{
+    let mut object_query = world.query_filtered::<(&ObjectId, &ObjectInfo), With<Object>>();
+    object_query
+        .iter(world)
+        .filter(|(_, object_info)| predicate(object_info))
+        .map(|(object_id, _)| *object_id)
+        .collect()
+}
+
+/// Returns every object on the given map along with the tile it is in, sorted by tile in row-major
+/// order - by y then x - and by their order in the tiles [`TileObjects`] within a tile. Every client
+/// with the same state gets the same order, so use this when all objects have to be processed in a
+/// deterministic order, eg in lockstep games. Returns an empty Vec if the map can't be found.
+pub fn objects_sorted(world: &mut World, on_map: MapId) -> Vec<(ObjectId, TilePos)> {
+    let mut system_state: SystemState<(Query<(&MapId, &TileStorage)>, Query<&TileObjects>)> =
+        SystemState::new(world);
+    let (tile_storage_query, tile_query) = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&on_map) else {
+        return vec![];
+    };
+
+    let mut objects: Vec<(ObjectId, TilePos)> = vec![];
+    for y in 0..tile_storage.size.y {
+        for x in 0..tile_storage.size.x {
+            let tile_pos = TilePos { x, y };
+            let Some(tile_objects) = tile_storage
+                .checked_get(&tile_pos)
+                .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+            else {
+                continue;
+            };
+            objects.extend(
+                tile_objects
+                    .entities_in_tile
+                    .iter()
+                    .map(|object_id| (*object_id, tile_pos)),
+            );
+        }
+    }
+    objects
+}
+
+/// A summary of an object and its most commonly needed stats. Returned by [`object_summary_at`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectSummary {
+    pub object_id: ObjectId,
+    pub entity: Entity,
+    /// The objects type from its [`ObjectInfo`], if it has one
+    pub object_type: Option<ObjectType>,
+    /// The player that owns the object, if it is owned
+    pub owner: Option<PlayerMarker>,
+    pub health: Option<Health>,
+}
+
+/// Query used to build [`ObjectSummary`]s
+type ObjectSummaryQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static ObjectId,
+        Option<&'static ObjectInfo>,
+        Option<&'static PlayerMarker>,
+        Option<&'static Health>,
+    ),
+    With<Object>,
+>;
+
+/// Returns an [`ObjectSummary`] for every object in the given tile on the given map, in the same order
+/// as the tiles [`TileObjects`]. Returns an empty Vec if the map or tile can't be found.
+pub fn object_summary_at(
+    world: &mut World,
+    on_map: MapId,
+    tile_pos: TilePos,
+) -> Vec<ObjectSummary> {
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage)>,
+        Query<&TileObjects>,
+        ObjectSummaryQuery,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&on_map) else {
+        return vec![];
+    };
+    let Some(tile_objects) = tile_storage
+        .checked_get(&tile_pos)
+        .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+    else {
+        return vec![];
+    };
+
+    summarize_tile_objects(tile_objects, &object_query)
+}
+
+/// Returns an [`ObjectSummary`] for every object in the given [`TileObjects`], in the same order
+fn summarize_tile_objects(
+    tile_objects: &TileObjects,
+    object_query: &ObjectSummaryQuery,
+) -> Vec<ObjectSummary> {
+    tile_objects
+        .entities_in_tile
+        .iter()
+        .filter_map(|object_id| {
+            object_query
+                .iter()
+                .find(|(_, id, _, _, _)| id == &object_id)
+                .map(|(entity, id, object_info, owner, health)| ObjectSummary {
+                    object_id: *id,
+                    entity,
+                    object_type: object_info.map(|object_info| object_info.object_type.clone()),
+                    owner: owner.copied(),
+                    health: health.copied(),
+                })
+        })
+        .collect()
+}
+
+/// Returns the nearest object on the given map to the `from` tile that passes the predicate, along with
+/// the tile it is in and its distance from `from`. Returns None if no object passes or the map or tile
+/// can't be found.
+///
+/// The search moves outward from `from` one ring of neighbors at a time using [`tile_neighbors`] for the
+/// maps [`TilemapType`](bevy_ecs_tilemap::prelude::TilemapType) and stops at the first ring holding a
+/// match, so the distance is the number of neighbor steps - diagonal steps count as one on square maps.
+/// Objects in `from` itself have a distance of 0. Within a ring tiles and objects are checked in a fixed
+/// order so the result is deterministic.
+///
+/// Use [`nearest_object_by_move_cost`] to measure distance by movement cost instead.
+pub fn nearest_object<F>(
+    world: &mut World,
+    on_map: MapId,
+    from: TilePos,
+    predicate: F,
+) -> Option<(ObjectId, TilePos, u32)>
+where
+    F: Fn(&ObjectSummary) -> bool,
+{
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage, &Map)>,
+        Query<&TileObjects>,
+        ObjectSummaryQuery,
+    )> = SystemState::new(world);
+    let (map_query, tile_query, object_query) = system_state.get(world);
+
+    let (_, tile_storage, map) = map_query.iter().find(|(id, _, _)| id == &&on_map)?;
+    tile_storage.checked_get(&from)?;
+
+    let mut visited: HashSet<TilePos> = HashSet::from([from]);
+    let mut ring: Vec<TilePos> = vec![from];
+    let mut distance = 0;
+    while !ring.is_empty() {
+        for tile_pos in ring.iter() {
+            let Some(tile_objects) = tile_storage
+                .checked_get(tile_pos)
+                .and_then(|tile_entity| tile_query.get(tile_entity).ok())
+            else {
+                continue;
+            };
+            if let Some(object_summary) = summarize_tile_objects(tile_objects, &object_query)
+                .into_iter()
+                .find(|object_summary| predicate(object_summary))
+            {
+                return Some((object_summary.object_id, *tile_pos, distance));
+            }
+        }
+
+        let mut next_ring: Vec<TilePos> = vec![];
+        for tile_pos in ring.iter() {
+            for neighbor in tile_neighbors(tile_pos, &tile_storage.size, &map.tilemap_type)
+                .into_iter()
+                .flatten()
+            {
+                if visited.insert(neighbor) {
+                    next_ring.push(neighbor);
+                }
+            }
+        }
+        ring = next_ring;
+        distance += 1;
+    }
+
+    None
+}
+
+/// Returns the object that passes the predicate and is the cheapest for the moving object to reach on
+/// the given map this turn, along with the tile it is in and the movement cost to reach that tile.
+/// Ties are broken by tile position so the result is deterministic.
+///
+/// Uses [`calculate_move_nodes`] so only objects in tiles the moving object can move into or through
+/// with its current move points are found - objects in tiles it can't enter, like enemies that block
+/// movement, are never returned. Use [`nearest_object`] for those. Returns None if there is no
+/// [`MovementSystem`](crate::movement::MovementSystem) in the world.
+pub fn nearest_object_by_move_cost<F>(
+    world: &mut World,
+    on_map: MapId,
+    object_moving: Entity,
+    predicate: F,
+) -> Option<(ObjectId, TilePos, u32)>
+where
+    F: Fn(&ObjectSummary) -> bool,
+{
+    let move_nodes = calculate_move_nodes(world, on_map, object_moving)?;
+    let mut reachable: Vec<(TilePos, u32)> = move_nodes
+        .move_nodes
+        .iter()
+        .filter_map(|(tile_pos, move_node)| {
+            move_node
+                .move_cost
+                .map(|move_cost| (*tile_pos, move_cost.max(0) as u32))
+        })
+        .collect();
+    reachable.sort_by_key(|(tile_pos, move_cost)| (*move_cost, tile_pos.x, tile_pos.y));
+
+    reachable.into_iter().find_map(|(tile_pos, move_cost)| {
+        object_summary_at(world, on_map, tile_pos)
+            .into_iter()
+            .find(|object_summary| {
+                object_summary.entity != object_moving && predicate(object_summary)
+            })
+            .map(|object_summary| (object_summary.object_id, tile_pos, move_cost))
+    })
+}
+
+/// Event sent in the game world when an object is spawned by
+/// [`SpawnObject`](crate::game_core::command::SpawnObject). Use it for local gameplay reactions like
+/// playing a sound, state diffs should be used to sync spawns over the network.
+#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq)]
+pub struct ObjectSpawned {
+    pub object_id: ObjectId,
+    pub tile_pos: TilePos,
+    pub map_id: MapId,
+}
+
+/// Event sent in the game world when an object is despawned by
+/// [`DespawnObject`](crate::game_core::command::DespawnObject),
+/// [`DespawnPlayerObjects`](crate::game_core::command::DespawnPlayerObjects), or destroyed in combat
+#[derive(Clone, Copy, Eq, Hash, Debug, PartialEq)]
+pub struct ObjectDespawned {
+    pub object_id: ObjectId,
+}
+
+/// Marker component on a child object that moves along with its parent object. Added by
+/// [`AttachObject`](crate::game_core::command::AttachObject) when `carried` is true. Whenever the parent
+/// is moved by a built in move command, eg [`MoveObject`](crate::movement::MoveObject),
+/// [`MoveObjectAlongPath`](crate::movement::MoveObjectAlongPath),
+/// [`MoveObjectPartial`](crate::movement::MoveObjectPartial), or
+/// [`SwapObjects`](crate::game_core::command::SwapObjects), every carried child in the parents tile
+/// is moved to the same tile.
+#[derive(
+    Default,
+    Clone,
+    Copy,
+    Eq,
+    Hash,
+    PartialEq,
+    Debug,
+    Component,
+    Reflect,
+    FromReflect,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Component)]
+pub struct CarriedByParent;
+
+/// Labels on an object that scripts and triggers can use to find it, eg "boss" or
+/// "reinforcements_a". Objects with the component are indexed in the [`TagIndex`]. Use the
+/// [`AddTag`](crate::game_core::command::AddTag) and [`RemoveTag`](crate::game_core::command::RemoveTag)
+/// commands to change the tags of a spawned object so that the index stays up to date.
+#[derive(Default, Clone, Eq, PartialEq, Debug, Component, Serialize, Deserialize)]
+pub struct Tags {
+    pub labels: HashSet<String>,
+}
+
+impl Tags {
+    pub fn new<I, S>(labels: I) -> Tags
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Tags {
+            labels: labels.into_iter().map(|label| label.into()).collect(),
+        }
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.labels.contains(label)
+    }
+}
+
+/// Resource mapping every label to the [`ObjectId`]s of the objects tagged with it. Kept up to date
+/// when objects with [`Tags`] are spawned or despawned through the built in commands and when tags
+/// are added or removed.
+#[derive(Default, Clone, Debug, Resource)]
+pub struct TagIndex {
+    objects: HashMap<String, Vec<ObjectId>>,
+}
+
+impl TagIndex {
+    /// Returns every object tagged with the given label
+    pub fn objects_with(&self, label: &str) -> &[ObjectId] {
+        match self.objects.get(label) {
+            Some(objects) => objects,
+            None => &[],
+        }
+    }
+
+    /// Returns the first object tagged with the given label. Useful for labels that are only used
+    /// once, eg "boss"
+    pub fn object_with(&self, label: &str) -> Option<ObjectId> {
+        self.objects_with(label).first().copied()
+    }
+
+    pub fn insert(&mut self, label: &str, object_id: ObjectId) {
+        let objects = self.objects.entry(label.to_string()).or_default();
+        if !objects.contains(&object_id) {
+            objects.push(object_id);
+        }
+    }
+
+    pub fn remove(&mut self, label: &str, object_id: ObjectId) {
+        let Some(objects) = self.objects.get_mut(label) else {
+            return;
+        };
+        objects.retain(|id| id != &object_id);
+        if objects.is_empty() {
+            self.objects.remove(label);
+        }
+    }
+
+    /// Removes the object from every label
+    pub fn remove_object(&mut self, object_id: ObjectId) {
+        self.objects.retain(|_, objects| {
+            objects.retain(|id| id != &object_id);
+            !objects.is_empty()
+        });
+    }
+
+    /// Rebuilds the index from the [`Tags`] of every object in the world
+    pub fn rebuild(&mut self, world: &mut World) {
+        self.objects.clear();
+        let mut object_query = world.query_filtered::<(&ObjectId, &Tags), With<Object>>();
+        for (object_id, tags) in object_query.iter(world) {
+            for label in tags.labels.iter() {
+                self.insert(label, *object_id);
+            }
+        }
+    }
+}
+
+/// Adds the [`Tags`] of the given object entity to the [`TagIndex`]. Does nothing if the entity has
+/// no tags or there is no index in the world
+pub fn index_object_tags(world: &mut World, entity: Entity) {
+    let Some(object_id) = world.get::<ObjectId>(entity).copied() else {
+        return;
+    };
+    let Some(tags) = world.get::<Tags>(entity).cloned() else {
+        return;
+    };
+    let Some(mut tag_index) = world.get_resource_mut::<TagIndex>() else {
+        return;
+    };
+    for label in tags.labels.iter() {
+        tag_index.insert(label, object_id);
+    }
+}
+
+/// Removes the given object from the [`TagIndex`]. Should be called whenever an object is despawned
+pub fn unindex_object_tags(world: &mut World, object_id: ObjectId) {
+    if let Some(mut tag_index) = world.get_resource_mut::<TagIndex>() {
+        tag_index.remove_object(object_id);
+    }
+}
+
 /// Resource holding all [`ObjectType`]s that are used in the game
 #[derive(Resource, Reflect, FromReflect)]
 #[allow(dead_code)]
@@ -320,3 +757,28 @@ pub struct ObjectGridPosition {
 struct Builder {
     pub can_build: Vec<ObjectType>,
 }
+
+#[test]
+fn test_tags_restored_from_save() {
+    use crate::game_core::saving::{ComponentBinaryState, GameSerDeRegistry, SaveId};
+
+    let object_id = ObjectId { id: 0 };
+    let tags = Tags::new(["boss"]);
+
+    let mut world = World::new();
+    world.init_resource::<TagIndex>();
+
+    let registry = GameSerDeRegistry::default_registry();
+    let mut entity = world.spawn(Object);
+    for (id, component) in [object_id.save().unwrap(), tags.save().unwrap()] {
+        registry.deserialize_component_onto(&ComponentBinaryState { id, component }, &mut entity);
+    }
+    let entity = entity.id();
+    index_object_tags(&mut world, entity);
+
+    assert_eq!(world.get::<Tags>(entity), Some(&tags));
+    assert_eq!(
+        world.resource::<TagIndex>().object_with("boss"),
+        Some(object_id)
+    );
+}
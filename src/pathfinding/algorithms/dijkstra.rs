@@ -1,12 +1,16 @@
-﻿use crate::mapping::MapId;
-use crate::movement::{AvailableMove, ObjectMovement, TileMoveChecks, TileMovementCosts};
+﻿use crate::mapping::tiles::tile_neighbors;
+use crate::mapping::MapId;
+use crate::movement::backend::{tile_edge_cost, tile_entry_cost, MoveNode};
+use crate::movement::{
+    AvailableMove, InvalidMoveReason, ObjectMovement, PartialMove, TileMoveChecks, TileMoveContext,
+};
 use crate::object::ObjectGridPosition;
 use crate::pathfinding::{MapNode, PathfindAlgorithm, PathfindCallback, PathfindMap};
 use bevy::ecs::system::SystemState;
-use bevy::prelude::{Entity, Query, World};
+use bevy::prelude::{Entity, IVec2, Query, World};
 use bevy::utils::hashbrown::HashMap;
 use bevy_ecs_tilemap::map::TilemapSize;
-use bevy_ecs_tilemap::prelude::{TilePos, TileStorage};
+use bevy_ecs_tilemap::prelude::{HexCoordSystem, TilePos, TileStorage, TilemapType};
 
 #[derive(Clone, Copy)]
 pub struct Node {
@@ -15,6 +19,36 @@ pub struct Node {
     pub move_cost: u32,
     pub valid_move: bool,
     pub calculated: bool,
+    pub invalid_reason: Option<InvalidMoveReason>,
+    /// True if the object has to stop when it enters this node so its neighbors aren't explored from it
+    pub stops_movement: bool,
+}
+
+impl Node {
+    /// Marks the node with the given reason it isn't a valid move. Does nothing if the node is
+    /// already a valid move. [`InvalidMoveReason::Blocked`] takes priority over
+    /// [`InvalidMoveReason::PassThroughOnly`] which takes priority over [`InvalidMoveReason::TooFar`]
+    pub fn set_invalid_reason(&mut self, invalid_reason: InvalidMoveReason) {
+        if self.valid_move
+            || matches!(self.invalid_reason, Some(current) if current > invalid_reason)
+        {
+            return;
+        }
+        self.invalid_reason = Some(invalid_reason);
+    }
+}
+
+impl From<Node> for MoveNode {
+    fn from(node: Node) -> Self {
+        MoveNode {
+            node_pos: node.node_pos,
+            prior_node: node.prior_node_pos,
+            move_cost: Some(node.move_cost as i32),
+            valid_move: node.valid_move,
+            invalid_reason: node.invalid_reason,
+            stops_movement: node.stops_movement,
+        }
+    }
 }
 
 impl From<Node> for AvailableMove {
@@ -50,12 +84,19 @@ impl MapNode for Node {
     }
 }
 
-pub struct DijkstraSquare {
+/// Dijkstras algorithm over the tiles of a map. Which tiles neighbor each other is decided by the
+/// [`PathfindMapDijkstra`] it is run with, so the same algorithm is used for square, offset, and hex
+/// maps
+pub struct Dijkstra {
     pub diagonals: bool,
     pub nodes: HashMap<TilePos, Node>,
 }
 
-impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for DijkstraSquare {
+/// The old name of [`Dijkstra`], from when it only pathfound on square maps
+#[deprecated(note = "Use Dijkstra, which isn't limited to square maps")]
+pub type DijkstraSquare = Dijkstra;
+
+impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for Dijkstra {
     type PathfindOutput = Vec<AvailableMove>;
 
     fn pathfind<
@@ -66,7 +107,7 @@ impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for DijkstraSquare {
         on_map: MapId,
         pathfind_entity: Entity,
         mut world: &mut World,
-        node_validity_checks: &mut TileMoveChecks,
+        node_validity_checks: &TileMoveChecks,
         pathfind_callback: &mut Option<CB>,
         pathfind_map: &mut PM,
     ) -> Self::PathfindOutput {
@@ -90,17 +131,37 @@ impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for DijkstraSquare {
         let tile_storage = tile_storage.clone();
         let tilemap_size = tilemap_size.clone();
 
-        pathfind_map.new_pathfind_map(object_grid_position.tile_position.into());
+        let start_pos: TilePos = object_grid_position.tile_position.into();
+        let context = TileMoveContext::new(world, pathfind_entity);
+
+        pathfind_map.new_pathfind_map(start_pos);
+
+        // An object that has already spent all of its move points this turn has no moves at all, not
+        // even staying where it is
+        if let (Some(object_movement), Some(partial_move)) = (
+            world.get::<ObjectMovement>(pathfind_entity),
+            world.get::<PartialMove>(pathfind_entity),
+        ) {
+            if partial_move.remaining(object_movement) <= 0 {
+                if let Some(start_node) = pathfind_map.get_node_mut(start_pos) {
+                    start_node.valid_move = false;
+                    start_node.set_invalid_reason(InvalidMoveReason::TooFar);
+                }
+                return pathfind_map.get_output();
+            }
+        }
 
         let mut available_moves: Vec<TilePos> = vec![];
 
         // unvisited nodes
         let mut unvisited_nodes: Vec<Node> = vec![Node {
-            node_pos: object_grid_position.tile_position.into(),
-            prior_node_pos: object_grid_position.tile_position.into(),
+            node_pos: start_pos,
+            prior_node_pos: start_pos,
             move_cost: 0,
             valid_move: false,
             calculated: false,
+            invalid_reason: None,
+            stops_movement: false,
         }];
         let mut visited_nodes: Vec<TilePos> = vec![];
 
@@ -114,6 +175,11 @@ impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for DijkstraSquare {
             let neighbor_pos = pathfind_map.get_neighbors(current_node.node_pos, &tilemap_size);
 
             let current_node = *current_node;
+            let Some(current_tile_entity) = tile_storage.get(&current_node.node_pos) else {
+                unvisited_nodes.remove(0);
+                visited_nodes.push(current_node.node_pos);
+                continue;
+            };
             let mut neighbors: Vec<(TilePos, Entity)> = vec![];
             for neighbor in neighbor_pos.iter() {
                 let Some(tile_entity) = tile_storage.get(neighbor) else {
@@ -132,33 +198,58 @@ impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for DijkstraSquare {
                 if !pathfind_map.node_cost_calculation(
                     pathfind_entity,
                     neighbor.1,
+                    current_tile_entity,
                     neighbor.0,
                     current_node.node_pos,
                     world,
                 ) {
-                    let _ = pathfind_map.set_calculated_node(neighbor.0);
                     continue 'neighbors;
                 }
 
+                let _ = pathfind_map.set_calculated_node(neighbor.0);
+
                 if !node_validity_checks.check_tile_move_checks(
-                    pathfind_entity,
+                    &context,
                     neighbor.1,
                     &neighbor.0,
                     &current_node.node_pos,
                     world,
                 ) {
-                    let _ = pathfind_map.set_calculated_node(neighbor.0);
+                    let _ = pathfind_map.set_blocked_node(neighbor.0);
                     continue 'neighbors;
                 }
 
-                let _ = pathfind_map.set_valid_node(neighbor.0);
-                let _ = pathfind_map.set_calculated_node(neighbor.0);
-
                 // if none of them return false and cancel the loop then we can infer that we are able to move into that neighbor
-                // we add the neighbor to the list of unvisited nodes and then push the neighbor to the available moves list
-                unvisited_nodes.push(pathfind_map.get_node_mut(neighbor.0).expect(
+                // we add the neighbor to the list of unvisited nodes. Only tiles that the object can
+                // also end its move in are valid moves, the rest can only be moved through. Tiles
+                // that stop movement are never added so nothing past them is explored
+                let node = pathfind_map.get_node_mut(neighbor.0).expect(
                     "Is safe because we know we add the node in at the beginning of this loop",
-                ).clone());
+                );
+                if node_validity_checks.check_stops_movement(
+                    &context,
+                    neighbor.1,
+                    &neighbor.0,
+                    world,
+                ) {
+                    node.stops_movement = true;
+                } else {
+                    unvisited_nodes.push(*node);
+                }
+
+                if !node_validity_checks.check_tile_destination_checks(
+                    &context,
+                    neighbor.1,
+                    &neighbor.0,
+                    world,
+                ) {
+                    if let Some(node) = pathfind_map.get_node_mut(neighbor.0) {
+                        node.set_invalid_reason(InvalidMoveReason::PassThroughOnly);
+                    }
+                    continue 'neighbors;
+                }
+
+                let _ = pathfind_map.set_valid_node(neighbor.0);
                 available_moves.push(neighbor.0);
 
                 if let Some(callback) = pathfind_callback {
@@ -183,6 +274,12 @@ impl PathfindAlgorithm<TilePos, Node, ObjectMovement> for DijkstraSquare {
 pub struct PathfindMapDijkstra {
     pub map: HashMap<TilePos, Node>,
     pub diagonals: bool,
+    /// Offsets from a tile to each of its neighbors. When Some these are used instead of the four
+    /// orthogonal tiles and `diagonals` is ignored
+    pub neighbor_offsets: Option<Vec<IVec2>>,
+    /// When Some a tiles neighbors are the six hex tiles around it in this coordinate system and both
+    /// `diagonals` and `neighbor_offsets` are ignored
+    pub hex_coord_system: Option<HexCoordSystem>,
 }
 
 impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for PathfindMapDijkstra {
@@ -198,6 +295,8 @@ impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for Pathfind
                 move_cost: 0,
                 valid_move: true,
                 calculated: false,
+                invalid_reason: None,
+                stops_movement: false,
             },
         );
 
@@ -208,6 +307,7 @@ impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for Pathfind
         &mut self,
         entity_moving: Entity,
         tile_entity: Entity,
+        move_from_tile_entity: Entity,
         tile_pos: TilePos,
         move_from_tile_pos: TilePos,
         world: &World,
@@ -215,7 +315,18 @@ impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for Pathfind
         let Some(object_movement) = world.get::<ObjectMovement>(entity_moving) else {
             return false;
         };
-        let Some(tile_movement_costs) = world.get::<TileMovementCosts>(tile_entity) else {
+        // Objects that have already moved part of their range this turn can only spend what is left
+        let move_points = world
+            .get::<PartialMove>(entity_moving)
+            .map_or(object_movement.move_points, |partial_move| {
+                partial_move.remaining(object_movement)
+            });
+        let Some(tile_entry_cost) =
+            tile_entry_cost(entity_moving, object_movement, tile_entity, world)
+        else {
+            if let Some(tile_node) = self.map.get_mut(&tile_pos) {
+                tile_node.set_invalid_reason(InvalidMoveReason::Blocked);
+            }
             return false;
         };
 
@@ -225,46 +336,54 @@ impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for Pathfind
             return false;
         };
 
+        let move_cost = move_from_tile_node.move_cost
+            + tile_entry_cost
+            + tile_edge_cost(move_from_tile_entity, &move_from_tile_pos, &tile_pos, world);
+
         return if tile_node.calculated {
-            if (move_from_tile_node.move_cost
-                + *tile_movement_costs
-                    .movement_type_cost
-                    .get(&object_movement.movement_type)
-                    .unwrap_or(&1))
-                < (tile_node.move_cost)
-            {
-                tile_node.move_cost = move_from_tile_node.move_cost
-                    + *tile_movement_costs
-                        .movement_type_cost
-                        .get(&object_movement.movement_type)
-                        .unwrap_or(&1);
+            if move_cost < tile_node.move_cost {
+                tile_node.move_cost = move_cost;
                 tile_node.prior_node_pos = move_from_tile_node.node_pos;
                 true
             } else {
                 false
             }
-        } else if (move_from_tile_node.move_cost
-            + *tile_movement_costs
-                .movement_type_cost
-                .get(&object_movement.movement_type)
-                .unwrap_or(&1))
-            <= object_movement.move_points as u32
-        {
-            tile_node.move_cost = move_from_tile_node.move_cost
-                + *tile_movement_costs
-                    .movement_type_cost
-                    .get(&object_movement.movement_type)
-                    .unwrap_or(&1);
+        } else if move_cost <= move_points as u32 {
+            tile_node.move_cost = move_cost;
             tile_node.prior_node_pos = move_from_tile_node.node_pos;
             true
         } else {
+            tile_node.set_invalid_reason(InvalidMoveReason::TooFar);
             false
         };
     }
 
     fn get_neighbors(&self, node_pos: TilePos, tilemap_size: &TilemapSize) -> Vec<TilePos> {
-        let mut neighbor_tiles: Vec<TilePos> = vec![];
         let origin_tile = node_pos;
+        if let Some(hex_coord_system) = self.hex_coord_system {
+            return tile_neighbors(
+                &origin_tile,
+                tilemap_size,
+                &TilemapType::Hexagon(hex_coord_system),
+            )
+            .into_iter()
+            .flatten()
+            .collect();
+        }
+        if let Some(neighbor_offsets) = &self.neighbor_offsets {
+            return neighbor_offsets
+                .iter()
+                .filter_map(|offset| {
+                    TilePos::from_i32_pair(
+                        origin_tile.x as i32 + offset.x,
+                        origin_tile.y as i32 + offset.y,
+                        tilemap_size,
+                    )
+                })
+                .collect();
+        }
+
+        let mut neighbor_tiles: Vec<TilePos> = vec![];
         if let Some(north) =
             TilePos::from_i32_pair(origin_tile.x as i32, origin_tile.y as i32 + 1, tilemap_size)
         {
@@ -335,6 +454,8 @@ impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for Pathfind
                 move_cost: 0,
                 valid_move: false,
                 calculated: false,
+                invalid_reason: None,
+                stops_movement: false,
             };
             self.map.insert(new_node_pos, node);
         }
@@ -343,6 +464,16 @@ impl PathfindMap<TilePos, Node, Vec<AvailableMove>, ObjectMovement> for Pathfind
     fn set_valid_node(&mut self, node_pos: TilePos) -> Result<(), String> {
         return if let Some(node) = self.get_node_mut(node_pos) {
             node.valid_move = true;
+            node.invalid_reason = None;
+            Ok(())
+        } else {
+            Err(String::from("Error getting node"))
+        };
+    }
+
+    fn set_blocked_node(&mut self, node_pos: TilePos) -> Result<(), String> {
+        return if let Some(node) = self.get_node_mut(node_pos) {
+            node.set_invalid_reason(InvalidMoveReason::Blocked);
             Ok(())
         } else {
             Err(String::from("Error getting node"))
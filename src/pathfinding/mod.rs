@@ -7,7 +7,8 @@ use bevy_ecs_tilemap::prelude::TilemapSize;
 use std::marker::PhantomData;
 
 pub use algorithms::dijkstra;
-pub use algorithms::dijkstra::DijkstraSquare;
+#[allow(deprecated)]
+pub use algorithms::dijkstra::{Dijkstra, DijkstraSquare};
 
 /// What are the main parts of a pathfinding system that we want to support
 /// 1. The actual pathfinding and generation - we need to use bevy_ecs_tilemap to access tiles and offer
@@ -70,7 +71,7 @@ where
             on_map,
             pathfind_entity,
             world,
-            &mut self.node_validity_checks,
+            &self.node_validity_checks,
             &mut self.pathfind_callback,
             &mut self.pathfind_map,
         )
@@ -91,7 +92,7 @@ pub trait PathfindAlgorithm<NodePos, MapNode, CostComponent: Component> {
         on_map: MapId,
         pathfind_entity: Entity,
         world: &mut World,
-        node_validity_checks: &mut TileMoveChecks,
+        node_validity_checks: &TileMoveChecks,
         pathfind_callback: &mut Option<CB>,
         pathfind_map: &mut PM,
     ) -> Self::PathfindOutput;
@@ -104,6 +105,7 @@ pub trait PathfindMap<NodePos, MapNode, PathfindOutput, CostComponent: Component
         &mut self,
         pathfinding_entity: Entity,
         node_entity: Entity,
+        starting_node_entity: Entity,
         node_pos: NodePos,
         starting_node_pos: NodePos,
         world: &World,
@@ -116,6 +118,8 @@ pub trait PathfindMap<NodePos, MapNode, PathfindOutput, CostComponent: Component
 
     fn new_node(&mut self, new_node_pos: NodePos, prior_node: MapNode);
     fn set_valid_node(&mut self, node_pos: NodePos) -> Result<(), String>;
+    /// Marks the node as blocked - it was reached but failed a node validity check
+    fn set_blocked_node(&mut self, node_pos: NodePos) -> Result<(), String>;
     fn set_calculated_node(&mut self, node_pos: NodePos) -> Result<(), String>;
     fn get_output(&mut self) -> PathfindOutput;
 }
@@ -146,3 +150,19 @@ pub trait PathfindCallback<NodePos> {
         world: &mut World,
     );
 }
+
+/// Allows boxed callbacks, such as the one stored in a [`MovementSystem`](crate::movement::MovementSystem),
+/// to be passed into a [`PathfindAlgorithm`]
+impl<NodePos> PathfindCallback<NodePos> for Box<dyn PathfindCallback<NodePos> + Send + Sync> {
+    fn foreach_tile(
+        &mut self,
+        pathfinding_entity: Entity,
+        node_entity: Entity,
+        node_pos: NodePos,
+        node_cost: u32,
+        world: &mut World,
+    ) {
+        self.as_mut()
+            .foreach_tile(pathfinding_entity, node_entity, node_pos, node_cost, world);
+    }
+}
@@ -1,8 +1,10 @@
-use bevy::prelude::{Component, FromReflect, Reflect, Resource};
+use bevy::prelude::{Component, Entity, FromReflect, Reflect, Resource, World};
 use serde::{Deserialize, Serialize};
 
-/// A list of all players in the game. This is copied into the game world to allow accessing it
+/// A list of all players and teams in the game. This is copied into the game world to allow accessing
+/// it and is saved and sent in state updates like any other registered resource
 #[derive(
+    Default,
     Clone,
     Eq,
     Hash,
@@ -17,6 +19,52 @@ use serde::{Deserialize, Serialize};
 )]
 pub struct PlayerList {
     pub players: Vec<Player>,
+    pub teams: Vec<Team>,
+}
+
+impl PlayerList {
+    /// Returns the player with the given id
+    pub fn get(&self, player_id: usize) -> Option<&Player> {
+        self.players.iter().find(|player| player.id() == player_id)
+    }
+
+    /// Returns the player with the given id mutably
+    pub fn get_mut(&mut self, player_id: usize) -> Option<&mut Player> {
+        self.players
+            .iter_mut()
+            .find(|player| player.id() == player_id)
+    }
+
+    /// Returns true if a player with the given id is in the list
+    pub fn contains(&self, player_id: usize) -> bool {
+        self.get(player_id).is_some()
+    }
+
+    /// Sets whether the given player needs state. Players that don't need state no longer have to see
+    /// a change before it can be cleared. Returns false if the player isn't in the list
+    pub fn set_player_needs_state(&mut self, player_id: usize, needs_state: bool) -> bool {
+        match self.get_mut(player_id) {
+            Some(player) => {
+                player.needs_state = needs_state;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns the entity in the game world holding the [`Player`] component for the given player id.
+///
+/// Every player added with [`GameBuilder::add_player`](crate::game_core::GameBuilder::add_player) is
+/// both pushed into the [`PlayerList`] and spawned as an entity with a [`Player`] component. The
+/// [`PlayerList`] is the list of who is in the game, the entity exists so that the player is tracked
+/// and sent in state updates like any other entity. Use this to go from one to the other.
+pub fn player_entity(world: &mut World, player_id: usize) -> Option<Entity> {
+    let mut player_query = world.query::<(Entity, &Player)>();
+    player_query
+        .iter(world)
+        .find(|(_, player)| player.id() == player_id)
+        .map(|(entity, _)| entity)
 }
 
 /// Represents a team of players with a custom id
@@ -38,6 +86,25 @@ pub struct Team {
     player_ids: Vec<usize>,
 }
 
+impl Team {
+    pub fn new(id: usize, player_ids: Vec<usize>) -> Team {
+        Team { id, player_ids }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn player_ids(&self) -> &Vec<usize> {
+        &self.player_ids
+    }
+
+    /// Returns true if the given player is a member of this team
+    pub fn contains_player(&self, player_id: usize) -> bool {
+        self.player_ids.contains(&player_id)
+    }
+}
+
 /// A unique player with unique information used to drive game systems
 #[derive(
     Default,
@@ -0,0 +1,326 @@
+//! Helpers for selecting objects on the map. Selecting the same tile repeatedly cycles through every
+//! object in that tile.
+//!
+//! [`next_unacted_unit`] and [`previous_unacted_unit`] step through the units a player still has to
+//! act with, and [`select_next_unacted_unit`] selects the next one and returns the
+//! [`CameraCommand`] to focus on it, eg for a "next unit" key.
+
+use crate::camera::CameraCommand;
+use crate::game_core::rules::GameRules;
+use crate::mapping::tiles::TileObjects;
+use crate::mapping::vision::visible_to_player;
+use crate::mapping::MapId;
+use crate::movement::ObjectMoved;
+use crate::object::ObjectId;
+use crate::player::PlayerMarker;
+use bevy::ecs::system::SystemState;
+use bevy::prelude::{Component, FromReflect, Query, Reflect, ReflectComponent, Resource, World};
+use bevy::utils::HashSet;
+use bevy_ecs_tilemap::prelude::{TilePos, TileStorage};
+use serde::{Deserialize, Serialize};
+
+/// Resource holding the object that is currently selected, if any. Pass the selected object into
+/// [`select_object_at_tile_pos`] so that selecting the same tile again cycles to the next object.
+#[derive(Default, Clone, Copy, Eq, PartialEq, Debug, Resource)]
+pub struct CurrentSelectedObject {
+    pub object: Option<ObjectId>,
+}
+
+/// Controls who can select an object with [`select_object_at_tile_pos`]. Objects without this
+/// component can be selected by anyone.
+#[derive(
+    Clone, Copy, Eq, Hash, PartialEq, Debug, Component, Reflect, FromReflect, Serialize, Deserialize,
+)]
+#[reflect(Component)]
+pub struct Selectable {
+    /// Only the player that owns the object, according to its [`PlayerMarker`], can select it
+    pub by_owner_only: bool,
+    /// Whether the object can be selected at all
+    pub enabled: bool,
+}
+
+impl Default for Selectable {
+    fn default() -> Self {
+        Selectable {
+            by_owner_only: false,
+            enabled: true,
+        }
+    }
+}
+
+impl Selectable {
+    /// Returns true if the given player can select an object with this component that is owned by
+    /// the given owner
+    pub fn can_select(&self, owner: Option<usize>, selecting_player: Option<usize>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        !self.by_owner_only || (owner.is_some() && owner == selecting_player)
+    }
+}
+
+/// Returns the object that should be selected at the given tile on the given map.
+///
+/// - Objects are cycled through in [`ObjectId`] order so repeated selections visit them in the same
+///   order no matter how they entered the tile.
+/// - If `current_selection` is an object in the tile then the next object in the tile is selected,
+///   wrapping back around to the first object after the last. Otherwise the first object is selected.
+/// - If `owner_filter` is set then objects whose [`PlayerMarker`] doesn't match the given player id,
+///   or neutral objects that have no [`PlayerMarker`], are skipped.
+/// - `selecting_player` is the player doing the selecting. Objects whose [`Selectable`] doesn't allow
+///   that player to select them are skipped. When [`GameRules::fog_of_war`] is enabled, objects that
+///   don't belong to the selecting player are also skipped unless the tile is
+///   [`visible_to_player`].
+///
+/// Returns None if the map or tile can't be found or if there are no selectable objects in the tile.
+pub fn select_object_at_tile_pos(
+    world: &mut World,
+    on_map: MapId,
+    tile_pos: TilePos,
+    current_selection: Option<ObjectId>,
+    owner_filter: Option<usize>,
+    selecting_player: Option<usize>,
+) -> Option<ObjectId> {
+    let hidden_by_fog = match selecting_player {
+        Some(player_id) if GameRules::get(world).fog_of_war => {
+            !visible_to_player(world, on_map, tile_pos, player_id)
+        }
+        _ => false,
+    };
+
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage)>,
+        Query<&TileObjects>,
+        Query<(&ObjectId, Option<&PlayerMarker>, Option<&Selectable>)>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let (_, tile_storage) = tile_storage_query.iter().find(|(id, _)| id == &&on_map)?;
+    let tile_entity = tile_storage.checked_get(&tile_pos)?;
+    let tile_objects = tile_query.get(tile_entity).ok()?;
+
+    let mut selectable: Vec<ObjectId> = tile_objects
+        .entities_in_tile
+        .iter()
+        .filter(|object_id| {
+            let (owner, selectable) = object_query
+                .iter()
+                .find(|(id, _, _)| id == object_id)
+                .map(|(_, player_marker, selectable)| {
+                    (
+                        player_marker.map(|player_marker| player_marker.id()),
+                        selectable,
+                    )
+                })
+                .unwrap_or((None, None));
+
+            if owner_filter.is_some() && owner != owner_filter {
+                return false;
+            }
+            if selectable.is_some_and(|selectable| !selectable.can_select(owner, selecting_player))
+            {
+                return false;
+            }
+            !hidden_by_fog || owner == selecting_player
+        })
+        .copied()
+        .collect();
+    // Cycle in ObjectId order so that selection is stable regardless of the order objects entered
+    // the tile in
+    selectable.sort_by_key(|object_id| object_id.id);
+
+    let next_index = match current_selection.and_then(|current| {
+        selectable
+            .iter()
+            .position(|object_id| object_id == &current)
+    }) {
+        Some(index) => (index + 1) % selectable.len(),
+        None => 0,
+    };
+
+    selectable.get(next_index).copied()
+}
+
+/// Returns every object on the given map owned by the given player that hasn't acted yet - that
+/// doesn't have the [`ObjectMoved`] marker - along with the tile it is in. Objects whose
+/// [`Selectable`] doesn't allow the player to select them are skipped. Sorted by [`ObjectId`] so every
+/// client gets the same order.
+pub fn unacted_units(
+    world: &mut World,
+    on_map: MapId,
+    player_id: usize,
+) -> Vec<(ObjectId, TilePos)> {
+    let mut system_state: SystemState<(
+        Query<(&MapId, &TileStorage)>,
+        Query<&TileObjects>,
+        Query<(
+            &ObjectId,
+            &PlayerMarker,
+            Option<&Selectable>,
+            Option<&ObjectMoved>,
+        )>,
+    )> = SystemState::new(world);
+    let (tile_storage_query, tile_query, object_query) = system_state.get(world);
+
+    let Some((_, tile_storage)) = tile_storage_query.iter().find(|(id, _)| id == &&on_map) else {
+        return vec![];
+    };
+
+    #[allow(clippy::unnecessary_map_or)]
+    let unacted_objects: HashSet<ObjectId> = object_query
+        .iter()
+        .filter(|(_, player_marker, selectable, moved)| {
+            player_marker.id() == player_id
+                && moved.is_none()
+                && selectable.map_or(true, |selectable| {
+                    selectable.can_select(Some(player_marker.id()), Some(player_id))
+                })
+        })
+        .map(|(object_id, _, _, _)| *object_id)
+        .collect();
+
+    // Tiles are stored by their TilePos::to_index, y * width + x
+    let map_width = tile_storage.size.x;
+    let mut unacted: Vec<(ObjectId, TilePos)> = vec![];
+    for (index, tile_entity) in tile_storage.iter().enumerate() {
+        let Some(tile_objects) =
+            tile_entity.and_then(|tile_entity| tile_query.get(tile_entity).ok())
+        else {
+            continue;
+        };
+        let tile_pos = TilePos {
+            x: index as u32 % map_width,
+            y: index as u32 / map_width,
+        };
+        unacted.extend(
+            tile_objects
+                .entities_in_tile
+                .iter()
+                .filter(|object_id| unacted_objects.contains(object_id))
+                .map(|object_id| (*object_id, tile_pos)),
+        );
+    }
+    unacted.sort_unstable_by_key(|(object_id, _)| object_id.id);
+    unacted
+}
+
+/// Returns the next unit in [`unacted_units`] after the given object, wrapping back around to the
+/// first unit after the last. If `after` is None the first unit is returned. `after` doesn't have to be
+/// in the list, so passing the unit that just acted moves on to the unit after it.
+///
+/// Returns None if the player has no units left to act with.
+pub fn next_unacted_unit(
+    world: &mut World,
+    map_id: MapId,
+    player_id: usize,
+    after: Option<ObjectId>,
+) -> Option<ObjectId> {
+    next_unacted_unit_with_pos(world, map_id, player_id, after).map(|(object_id, _)| object_id)
+}
+
+/// The same as [`next_unacted_unit`] but steps backwards, wrapping around to the last unit before
+/// the first.
+pub fn previous_unacted_unit(
+    world: &mut World,
+    map_id: MapId,
+    player_id: usize,
+    before: Option<ObjectId>,
+) -> Option<ObjectId> {
+    let unacted = unacted_units(world, map_id, player_id);
+    let previous = match before {
+        Some(before) => unacted
+            .iter()
+            .rev()
+            .find(|(object_id, _)| object_id.id < before.id)
+            .or_else(|| unacted.last()),
+        None => unacted.last(),
+    };
+    previous.map(|(object_id, _)| *object_id)
+}
+
+/// Selects the next unit in [`next_unacted_unit`] after the currently selected object and returns
+/// a [`CameraCommand::FocusOnTile`] for its tile. Send the returned command to move the camera to the
+/// unit. Leaves the selection unchanged and returns None if the player has no units left to act with.
+pub fn select_next_unacted_unit(
+    world: &mut World,
+    map_id: MapId,
+    player_id: usize,
+    current_selection: &mut CurrentSelectedObject,
+) -> Option<CameraCommand> {
+    let (object_id, tile_pos) =
+        next_unacted_unit_with_pos(world, map_id, player_id, current_selection.object)?;
+    current_selection.object = Some(object_id);
+    Some(CameraCommand::FocusOnTile(tile_pos, map_id))
+}
+
+fn next_unacted_unit_with_pos(
+    world: &mut World,
+    map_id: MapId,
+    player_id: usize,
+    after: Option<ObjectId>,
+) -> Option<(ObjectId, TilePos)> {
+    let unacted = unacted_units(world, map_id, player_id);
+    let next = match after {
+        Some(after) => unacted
+            .iter()
+            .find(|(object_id, _)| object_id.id > after.id)
+            .or_else(|| unacted.first()),
+        None => unacted.first(),
+    };
+    next.copied()
+}
+
+#[test]
+fn test_next_unacted_unit_wraps_around() {
+    use bevy_ecs_tilemap::prelude::TilemapSize;
+
+    let mut world = World::new();
+    let mut tile_storage = TileStorage::empty(TilemapSize { x: 2, y: 1 });
+    let first_tile = world
+        .spawn(TileObjects {
+            entities_in_tile: vec![ObjectId { id: 3 }, ObjectId { id: 1 }],
+        })
+        .id();
+    let second_tile = world
+        .spawn(TileObjects {
+            entities_in_tile: vec![ObjectId { id: 2 }, ObjectId { id: 4 }],
+        })
+        .id();
+    tile_storage.set(&TilePos { x: 0, y: 0 }, first_tile);
+    tile_storage.set(&TilePos { x: 1, y: 0 }, second_tile);
+    world.spawn((MapId { id: 1 }, tile_storage));
+
+    world.spawn((ObjectId { id: 1 }, PlayerMarker::new(0)));
+    world.spawn((ObjectId { id: 2 }, PlayerMarker::new(1)));
+    world.spawn((ObjectId { id: 3 }, PlayerMarker::new(0), ObjectMoved));
+    world.spawn((ObjectId { id: 4 }, PlayerMarker::new(0)));
+
+    let map_id = MapId { id: 1 };
+    // Objects belonging to other players and objects that already moved are skipped
+    assert_eq!(
+        next_unacted_unit(&mut world, map_id, 0, None),
+        Some(ObjectId { id: 1 })
+    );
+    assert_eq!(
+        next_unacted_unit(&mut world, map_id, 0, Some(ObjectId { id: 1 })),
+        Some(ObjectId { id: 4 })
+    );
+    assert_eq!(
+        next_unacted_unit(&mut world, map_id, 0, Some(ObjectId { id: 4 })),
+        Some(ObjectId { id: 1 })
+    );
+    assert_eq!(
+        previous_unacted_unit(&mut world, map_id, 0, Some(ObjectId { id: 1 })),
+        Some(ObjectId { id: 4 })
+    );
+
+    let mut current_selection = CurrentSelectedObject {
+        object: Some(ObjectId { id: 1 }),
+    };
+    assert_eq!(
+        select_next_unacted_unit(&mut world, map_id, 0, &mut current_selection),
+        Some(CameraCommand::FocusOnTile(TilePos { x: 1, y: 0 }, map_id))
+    );
+    assert_eq!(current_selection.object, Some(ObjectId { id: 4 }));
+    assert_eq!(next_unacted_unit(&mut world, map_id, 2, None), None);
+}
@@ -0,0 +1,199 @@
+//! Utilities for testing games built on Bevy_GGF.
+//!
+//! [`assert_rollback_consistent`] checks that a [`GameCommand`]s rollback exactly reverses its
+//! execute, which is easy to get wrong and otherwise only shows up as a desync much later. Use it in
+//! tests for your own commands:
+//!
+//! ```rust,ignore
+//! let mut command = MyCommand { object_id: ObjectId { id: 0 } };
+//! assert_rollback_consistent(&mut command, &mut game.game_world);
+//! ```
+//!
+//! The game state is compared using the same serialized state that is sent to clients, so only
+//! components and resources registered with the games
+//! [`GameSerDeRegistry`](crate::game_core::saving::GameSerDeRegistry) are compared. Use a world built
+//! by a [`GameBuilder`](crate::game_core::GameBuilder) with the registrations the game uses.
+
+use crate::game_core::command::GameCommand;
+use crate::game_core::state::{diff_states, GameStateHandler, StateDifference};
+use bevy::prelude::World;
+
+/// Executes the given command and then rolls it back, returning every difference between the game
+/// state before the command was executed and after it was rolled back. Entity ids are ignored since
+/// objects, tiles, and players are compared by their [`ObjectId`](crate::object::ObjectId),
+/// [`TilePos`](bevy_ecs_tilemap::prelude::TilePos), and [`Player`](crate::player::Player) ids, so a
+/// despawned entity that is respawned by rollback matches. Tiles are only identified by their
+/// [`TilePos`](bevy_ecs_tilemap::prelude::TilePos) so the world should only contain a single map.
+///
+/// Returns an Err if the command fails to execute or to rollback.
+pub fn rollback_differences(
+    command: &mut dyn GameCommand,
+    world: &mut World,
+) -> Result<Vec<StateDifference>, String> {
+    let mut game_state_handler = GameStateHandler::default();
+    let before = game_state_handler.get_entire_state(world);
+
+    command
+        .execute(world)
+        .map_err(|error| format!("{} failed to execute: {}", command.name(), error))?;
+    command
+        .rollback(world)
+        .map_err(|error| format!("{} failed to rollback: {}", command.name(), error))?;
+
+    let after = game_state_handler.get_entire_state(world);
+    Ok(diff_states(&before, &after))
+}
+
+/// Asserts that rolling back the given command after executing it returns the world to exactly the
+/// state it was in before. See [`rollback_differences`] for how the states are compared.
+///
+/// # Panics
+/// If the command fails to execute or rollback, or if any part of the game state differs, listing
+/// every difference.
+pub fn assert_rollback_consistent(command: &mut dyn GameCommand, world: &mut World) {
+    let differences = match rollback_differences(command, world) {
+        Ok(differences) => differences,
+        Err(error) => panic!("{}", error),
+    };
+
+    if !differences.is_empty() {
+        let differences: Vec<String> = differences
+            .iter()
+            .map(|difference| difference.to_string())
+            .collect();
+        panic!(
+            "Rollback of {} did not restore the game state:\n{}",
+            command.name(),
+            differences.join("\n")
+        );
+    }
+}
+
+#[test]
+fn test_built_in_commands_rollback_consistent() {
+    use crate::game_core::command::GameCommands;
+    use crate::game_core::runner::TurnBasedGameRunner;
+    use crate::game_core::{Game, GameBuilder};
+    use crate::mapping::tiles::{
+        ObjectStackingClass, StackingClass, Tile, TileObjectStacks, TileObjectStacksCount,
+        TileObjects,
+    };
+    use crate::mapping::MapId;
+    use crate::movement::{MoveCommandsExt, MoveEvent};
+    use crate::object::{Object, ObjectGridPosition, ObjectId};
+    use bevy::prelude::{Bundle, Events, Reflect, Schedule};
+    use bevy_ecs_tilemap::prelude::{TilePos, TileStorage, TilemapSize};
+
+    #[derive(Bundle, Clone, Reflect)]
+    struct TestObjectBundle {
+        object: Object,
+        object_grid_position: ObjectGridPosition,
+        object_stacking_class: ObjectStackingClass,
+    }
+
+    let stacking_class = StackingClass {
+        name: String::from("Ground"),
+    };
+    let map_id = MapId { id: 0 };
+
+    let mut game_builder = GameBuilder::new_game(TurnBasedGameRunner {
+        turn_schedule: Schedule::default(),
+    });
+    game_builder.add_default_registrations();
+    game_builder.game_world.init_resource::<Events<MoveEvent>>();
+    let map_size = TilemapSize { x: 2, y: 1 };
+    let mut tile_storage = TileStorage::empty(map_size);
+    for x in 0..map_size.x {
+        let tile_pos = TilePos { x, y: 0 };
+        let tile_entity = game_builder
+            .game_world
+            .spawn((
+                Tile,
+                tile_pos,
+                TileObjectStacks::new(vec![(
+                    stacking_class.clone(),
+                    TileObjectStacksCount {
+                        current_count: 0,
+                        max_count: 1,
+                    },
+                )]),
+                TileObjects::default(),
+            ))
+            .id();
+        tile_storage.set(&tile_pos, tile_entity);
+    }
+    game_builder.game_world.spawn((map_id, tile_storage));
+    game_builder.add_player(false);
+
+    let mut main_world = World::new();
+    game_builder.build(&mut main_world).unwrap();
+    let mut game = main_world.remove_resource::<Game>().unwrap();
+    let world = &mut game.game_world;
+
+    let mut game_commands = GameCommands::new();
+    let bundle = TestObjectBundle {
+        object: Object,
+        object_grid_position: ObjectGridPosition::default(),
+        object_stacking_class: ObjectStackingClass {
+            stack_class: stacking_class,
+        },
+    };
+    let mut spawn = game_commands.spawn_object(bundle.clone(), TilePos::new(0, 0), map_id, 0);
+    assert_rollback_consistent(&mut spawn, world);
+
+    // Leave an object on the map to move around
+    game_commands
+        .spawn_object(bundle, TilePos::new(0, 0), map_id, 0)
+        .execute(world)
+        .unwrap();
+    let object_id = world
+        .query::<&ObjectId>()
+        .iter(world)
+        .copied()
+        .next()
+        .unwrap();
+
+    let mut move_object = game_commands.move_object(
+        object_id,
+        map_id,
+        TilePos::new(0, 0),
+        TilePos::new(1, 0),
+        false,
+    );
+    assert_rollback_consistent(&mut move_object, world);
+
+    let mut set_owner = game_commands.set_object_owner(object_id, Some(1));
+    assert_rollback_consistent(&mut set_owner, world);
+}
+
+#[test]
+fn test_rollback_differences_finds_inconsistent_rollback() {
+    use crate::game_core::rules::GameRules;
+    use crate::game_core::saving::{GameSerDeRegistry, SaveId};
+    use bevy::prelude::Reflect;
+
+    // Forgets to turn friendly fire back off when rolled back
+    #[derive(Clone, Reflect)]
+    struct EnableFriendlyFire;
+
+    impl GameCommand for EnableFriendlyFire {
+        fn execute(&mut self, world: &mut World) -> Result<(), String> {
+            world.resource_mut::<GameRules>().friendly_fire = true;
+            Ok(())
+        }
+    }
+
+    let mut world = World::new();
+    world.insert_resource(GameRules::default());
+    let game_rules_id = world.components().resource_id::<GameRules>().unwrap();
+    let mut registry = GameSerDeRegistry::new();
+    registry.register_resource::<GameRules>(game_rules_id);
+    world.insert_resource(registry);
+
+    let differences = rollback_differences(&mut EnableFriendlyFire, &mut world).unwrap();
+    assert_eq!(differences.len(), 1);
+    assert!(matches!(
+        differences[0],
+        StateDifference::Resource { resource_id, .. } if resource_id == GameRules::save_id_const()
+    ));
+}